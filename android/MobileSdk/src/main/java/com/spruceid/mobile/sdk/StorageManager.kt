@@ -3,14 +3,25 @@ package com.spruceid.mobile.sdk
 import android.content.Context
 import android.util.Base64
 import com.spruceid.mobile.sdk.KeyManager
+import com.spruceid.mobile.toolkit.StorageManagerException
 import com.spruceid.mobile.toolkit.StorageManagerInterface
+import com.spruceid.mobile.toolkit.StorageOperation
+import java.io.DataInputStream
+import java.io.DataOutputStream
 import java.io.File
 import java.io.FileNotFoundException
+import java.io.FileOutputStream
 import java.security.SecureRandom
 import kotlin.coroutines.resume
 import kotlin.coroutines.suspendCoroutine
+import kotlinx.coroutines.sync.Mutex
+import kotlinx.coroutines.sync.withLock
 
 class StorageManager(val context: Context) : StorageManagerInterface {
+    /// Serializes batches with the other operations, so that none observes a
+    /// batch half-applied.
+    private val journalLock = Mutex()
+
     /// Function: add
     ///
     /// Adds a key-value pair to storage.  Should the key already exist, the value will be
@@ -21,9 +32,9 @@ class StorageManager(val context: Context) : StorageManagerInterface {
     /// value - The value to add under the key
     override suspend fun add(key: String, value: ByteArray) {
         val encryptedValue = encryptHybrid(value)
-        context.openFileOutput(filename(key), 0).use {
-            it.write(encryptedValue)
-            it.close()
+        journalLock.withLock {
+            recoverJournal()
+            write(key, encryptedValue)
         }
     }
 
@@ -35,6 +46,7 @@ class StorageManager(val context: Context) : StorageManagerInterface {
     /// key - The key to retrieve
     override suspend fun get(key: String): ByteArray? {
         val bytes: ByteArray
+        journalLock.withLock { recoverJournal() }
         try {
             context.openFileInput(filename(key)).use {
                 bytes = it.readBytes()
@@ -59,13 +71,63 @@ class StorageManager(val context: Context) : StorageManagerInterface {
     /// Arguments:
     /// key - The key to remove
     override suspend fun remove(key: String) {
-        File(context.filesDir, filename(key)).delete()
+        journalLock.withLock {
+            recoverJournal()
+            File(context.filesDir, filename(key)).delete()
+        }
+    }
+
+    /// Function: batch
+    ///
+    /// Applies several operations atomically. The encrypted operations are
+    /// first written to a journal, which is committed by renaming it, then
+    /// applied. A journal left behind by an interrupted batch is applied
+    /// again before any other operation.
+    ///
+    /// Arguments:
+    /// operations - The operations to apply, in order
+    override suspend fun batch(operations: List<StorageOperation>) {
+        // Encrypt first, so that an encryption failure leaves the store untouched.
+        val journaled = operations.map {
+            when (it) {
+                is StorageOperation.Add -> Pair(it.key, encryptHybrid(it.value))
+                is StorageOperation.Remove -> Pair(it.key, null)
+            }
+        }
+
+        journalLock.withLock {
+            recoverJournal()
+
+            val pending = File(context.filesDir, "$JOURNAL_FILENAME.tmp")
+            FileOutputStream(pending).use { file ->
+                DataOutputStream(file).let { out ->
+                    out.writeInt(journaled.size)
+                    for ((key, value) in journaled) {
+                        out.writeUTF(key)
+                        out.writeBoolean(value != null)
+                        if (value != null) {
+                            out.writeInt(value.size)
+                            out.write(value)
+                        }
+                    }
+                    out.flush()
+                }
+                file.fd.sync()
+            }
+            if (!pending.renameTo(journal())) {
+                pending.delete()
+                throw StorageManagerException.InternalException()
+            }
+
+            recoverJournal()
+        }
     }
 
     /// Function: list
     ///
     /// Lists all key-value pair in storage
     override suspend fun list(): List<String> {
+        journalLock.withLock { recoverJournal() }
         val list = context.filesDir.list() ?: throw Exception("cannot list stored objects")
 
         return list.mapNotNull {
@@ -77,6 +139,42 @@ class StorageManager(val context: Context) : StorageManagerInterface {
         }
     }
 
+    private fun journal() = File(context.filesDir, JOURNAL_FILENAME)
+
+    /// Writes an already encrypted value.
+    private fun write(key: String, encryptedValue: ByteArray) {
+        context.openFileOutput(filename(key), 0).use {
+            it.write(encryptedValue)
+            it.close()
+        }
+    }
+
+    /// Applies the committed journal, if any, then removes it. Applying it
+    /// again is harmless, so an interrupted recovery is simply resumed.
+    private fun recoverJournal() {
+        // An uncommitted journal is discarded, along with its batch.
+        File(context.filesDir, "$JOURNAL_FILENAME.tmp").delete()
+
+        val journal = journal()
+        if (!journal.exists()) {
+            return
+        }
+
+        DataInputStream(journal.inputStream().buffered()).use { input ->
+            repeat(input.readInt()) {
+                val key = input.readUTF()
+                if (input.readBoolean()) {
+                    val value = ByteArray(input.readInt())
+                    input.readFully(value)
+                    write(key, value)
+                } else {
+                    File(context.filesDir, filename(key)).delete()
+                }
+            }
+        }
+        journal.delete()
+    }
+
     companion object {
         private const val B64_FLAGS = Base64.URL_SAFE or Base64.NO_PADDING or Base64.NO_WRAP
         private const val KEY_NAME = "sprucekit/datastore"
@@ -225,6 +323,7 @@ class StorageManager(val context: Context) : StorageManagerInterface {
         }
 
         private const val FILENAME_PREFIX = "sprucekit:datastore"
+        private const val JOURNAL_FILENAME = "sprucekit:journal"
 
         private fun filename(filename: String) = "$FILENAME_PREFIX:$filename"
     }
@@ -17,6 +17,7 @@ import com.spruceid.mobile.sdk.rs.VcalmOfferedCredential
 import com.spruceid.mobile.sdk.rs.VdcCollection
 import com.spruceid.mobile.sdk.rs.Vpr
 import com.spruceid.mobile.toolkit.StorageManagerInterface
+import com.spruceid.mobile.toolkit.StorageOperation
 import kotlinx.coroutines.CoroutineScope
 import kotlinx.coroutines.Dispatchers
 import kotlinx.coroutines.launch
@@ -34,6 +35,16 @@ internal class InMemoryVdcStorage : StorageManagerInterface {
     override suspend fun get(key: String): ByteArray? = store[key]
     override suspend fun list(): List<String> = store.keys.toList()
     override suspend fun remove(key: String) { store.remove(key) }
+    override suspend fun batch(operations: List<StorageOperation>) {
+        synchronized(store) {
+            for (operation in operations) {
+                when (operation) {
+                    is StorageOperation.Add -> store[operation.key] = operation.value
+                    is StorageOperation.Remove -> store.remove(operation.key)
+                }
+            }
+        }
+    }
 }
 
 /**
@@ -28,6 +28,18 @@ final class InMemoryVdcStorage: StorageManagerInterface, @unchecked Sendable {
         lock.lock(); defer { lock.unlock() }
         store.removeValue(forKey: key)
     }
+
+    func batch(operations: [StorageOperation]) async throws {
+        lock.lock(); defer { lock.unlock() }
+        for operation in operations {
+            switch operation {
+            case let .add(key, value):
+                store[key] = value
+            case let .remove(key):
+                store.removeValue(forKey: key)
+            }
+        }
+    }
 }
 
 /// Error types for the VCALM signer.
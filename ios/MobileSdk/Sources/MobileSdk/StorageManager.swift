@@ -13,6 +13,11 @@ import SpruceIDMobileSdkRs
 /// Store and retrieve sensitive data.
 public class StorageManager: NSObject, StorageManagerInterface, @unchecked Sendable {
     let appGroupId: String?
+    /// Serializes batches with the other operations, so that none observes a batch half-applied.
+    private let journal = Journal()
+
+    /// Name of the journal of the batch being applied, stored alongside the data.
+    private static let journalName = ".sprucekit-journal"
 
     /// - Parameters:
     ///   - appGroupId: The app group under whose directory the StorageManager will place its data store.
@@ -134,10 +139,13 @@ public class StorageManager: NSObject, StorageManagerInterface, @unchecked Senda
     public func add(key: Key, value: Value) async throws {
         guard let file = await path(file: key) else { throw StorageManagerError.InternalError }
 
-        do {
-            try value.write(to: file, options: .completeFileProtection)
-        } catch {
-            throw StorageManagerError.InternalError
+        try await journal.locked {
+            try await recoverJournal()
+            do {
+                try value.write(to: file, options: .completeFileProtection)
+            } catch {
+                throw StorageManagerError.InternalError
+            }
         }
     }
 
@@ -150,6 +158,7 @@ public class StorageManager: NSObject, StorageManagerInterface, @unchecked Senda
 
     public func get(key: Key) async throws -> Value? {
         guard let file = await path(file: key) else { throw StorageManagerError.InternalError }
+        try await journal.locked { try await recoverJournal() }
 
         do {
             return try Data(contentsOf: file)
@@ -177,9 +186,12 @@ public class StorageManager: NSObject, StorageManagerInterface, @unchecked Senda
 
     public func list() async throws -> [Key] {
         guard let asdir = await path(file: "")?.path else { return [String]() }
+        try await journal.locked { try await recoverJournal() }
 
         do {
-            return try FileManager.default.contentsOfDirectory(atPath: asdir)
+            return try FileManager.default.contentsOfDirectory(atPath: asdir).filter {
+                $0 != Self.journalName && $0 != "\(Self.journalName).tmp"
+            }
         } catch {
             throw StorageManagerError.InternalError
         }
@@ -197,11 +209,105 @@ public class StorageManager: NSObject, StorageManagerInterface, @unchecked Senda
     public func remove(key: Key) async throws {
         guard let file = await path(file: key) else { return }
 
+        try await journal.locked {
+            try await recoverJournal()
+            do {
+                try FileManager.default.removeItem(at: file)
+            } catch {
+                // It's fine if the file isn't there.
+            }
+        }
+    }
+
+    /// Apply several operations atomically.
+    ///
+    /// The operations are first written to a journal, which is committed by renaming it, then applied. A journal
+    /// left behind by an interrupted batch is applied again before any other operation.
+    ///
+    /// - Parameters:
+    ///    - operations: the operations to apply, in order
+
+    public func batch(operations: [StorageOperation]) async throws {
+        guard let committed = await path(file: Self.journalName),
+              let pending = await path(file: "\(Self.journalName).tmp")
+        else { throw StorageManagerError.InternalError }
+
+        let entries = operations.map { operation -> JournalEntry in
+            switch operation {
+            case let .add(key, value):
+                return JournalEntry(key: key, value: value)
+            case let .remove(key):
+                return JournalEntry(key: key, value: nil)
+            }
+        }
+
+        try await journal.locked {
+            try await recoverJournal()
+            do {
+                try PropertyListEncoder().encode(entries)
+                    .write(to: pending, options: [.atomic, .completeFileProtection])
+                // Renaming commits the batch: from then on, it is applied even if interrupted.
+                try FileManager.default.moveItem(at: pending, to: committed)
+            } catch {
+                try? FileManager.default.removeItem(at: pending)
+                throw StorageManagerError.InternalError
+            }
+            try await recoverJournal()
+        }
+    }
+
+    /// Apply the committed journal, if any, then remove it. Applying it again is harmless, so an interrupted recovery
+    /// is simply resumed.
+    private func recoverJournal() async throws {
+        guard let committed = await path(file: Self.journalName),
+              let pending = await path(file: "\(Self.journalName).tmp")
+        else { throw StorageManagerError.InternalError }
+
+        // An uncommitted journal is discarded, along with its batch.
+        try? FileManager.default.removeItem(at: pending)
+
+        guard FileManager.default.fileExists(atPath: committed.path) else { return }
         do {
-            try FileManager.default.removeItem(at: file)
+            let entries = try PropertyListDecoder().decode([JournalEntry].self, from: Data(contentsOf: committed))
+            for entry in entries {
+                guard let file = await path(file: entry.key) else { throw StorageManagerError.InternalError }
+                if let value = entry.value {
+                    try value.write(to: file, options: .completeFileProtection)
+                } else if FileManager.default.fileExists(atPath: file.path) {
+                    try FileManager.default.removeItem(at: file)
+                }
+            }
+            try FileManager.default.removeItem(at: committed)
         } catch {
-            // It's fine if the file isn't there.
+            throw StorageManagerError.InternalError
+        }
+    }
+}
+
+/// An operation of a batch, as written to the journal.
+private struct JournalEntry: Codable {
+    let key: String
+    /// The value to write, or `nil` to remove the key.
+    let value: Data?
+}
+
+/// Runs the operations of a storage manager one at a time.
+private actor Journal {
+    private var busy = false
+    private var waiting: [CheckedContinuation<Void, Never>] = []
+
+    func locked<T>(_ operation: () async throws -> T) async throws -> T {
+        while busy {
+            await withCheckedContinuation { waiting.append($0) }
+        }
+        busy = true
+        defer {
+            busy = false
+            if !waiting.isEmpty {
+                waiting.removeFirst().resume()
+            }
         }
+        return try await operation()
     }
 }
 
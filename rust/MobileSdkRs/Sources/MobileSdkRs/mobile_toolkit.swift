@@ -1227,6 +1227,19 @@ public protocol StorageManagerInterface: AnyObject, Sendable {
      */
     func remove(key: Key) async throws 
     
+    /**
+     * Function: batch
+     *
+     * Callback function pointer to native (kotlin/swift) code for applying
+     * several operations atomically: either all of them are applied, or none
+     * of them are.  Operations are applied in order.
+     *
+     * Storage managers that cannot provide atomicity should return
+     * `StorageManagerError::Unsupported`, in which case the SDK applies the
+     * operations one by one and rolls them back on failure.
+     */
+    func batch(operations: [StorageOperation]) async throws 
+    
 }
 /**
  * Interface: StorageManagerInterface
@@ -1390,6 +1403,34 @@ open func remove(key: Key)async throws   {
         )
 }
     
+    /**
+     * Function: batch
+     *
+     * Callback function pointer to native (kotlin/swift) code for applying
+     * several operations atomically: either all of them are applied, or none
+     * of them are.  Operations are applied in order.
+     *
+     * Storage managers that cannot provide atomicity should return
+     * `StorageManagerError::Unsupported`, in which case the SDK applies the
+     * operations one by one and rolls them back on failure.
+     */
+open func batch(operations: [StorageOperation])async throws   {
+    return
+        try  await uniffiRustCallAsync(
+            rustFutureFunc: {
+                uniffi_mobile_toolkit_fn_method_storagemanagerinterface_batch(
+                    self.uniffiCloneHandle(),
+                    FfiConverterSequenceTypeStorageOperation.lower(operations)
+                )
+            },
+            pollFunc: ffi_mobile_toolkit_rust_future_poll_void,
+            completeFunc: ffi_mobile_toolkit_rust_future_complete_void,
+            freeFunc: ffi_mobile_toolkit_rust_future_free_void,
+            liftFunc: { $0 },
+            errorHandler: FfiConverterTypeStorageManagerError_lift
+        )
+}
+    
 
     
 }
@@ -1562,6 +1603,47 @@ fileprivate struct UniffiCallbackInterfaceStorageManagerInterface {
                 )
             }
 
+            let uniffiHandleSuccess = { (returnValue: ()) in
+                uniffiFutureCallback(
+                    uniffiCallbackData,
+                    UniffiForeignFutureResultVoid(
+                        callStatus: RustCallStatus()
+                    )
+                )
+            }
+            let uniffiHandleError = { (statusCode, errorBuf) in
+                uniffiFutureCallback(
+                    uniffiCallbackData,
+                    UniffiForeignFutureResultVoid(
+                        callStatus: RustCallStatus(code: statusCode, errorBuf: errorBuf)
+                    )
+                )
+            }
+            uniffiTraitInterfaceCallAsyncWithError(
+                makeCall: makeCall,
+                handleSuccess: uniffiHandleSuccess,
+                handleError: uniffiHandleError,
+                lowerError: FfiConverterTypeStorageManagerError_lower,
+                droppedCallback: uniffiOutDroppedCallback
+            )
+        },
+        batch: { (
+            uniffiHandle: UInt64,
+            operations: RustBuffer,
+            uniffiFutureCallback: @escaping UniffiForeignFutureCompleteVoid,
+            uniffiCallbackData: UInt64,
+            uniffiOutDroppedCallback: UnsafeMutablePointer<UniffiForeignFutureDroppedCallbackStruct>
+        ) in
+            let makeCall = {
+                () async throws -> () in
+                guard let uniffiObj = try? FfiConverterTypeStorageManagerInterface.handleMap.get(handle: uniffiHandle) else {
+                    throw UniffiInternalError.unexpectedStaleHandle
+                }
+                return try await uniffiObj.batch(
+                     operations: try FfiConverterSequenceTypeStorageOperation.lift(operations)
+                )
+            }
+
             let uniffiHandleSuccess = { (returnValue: ()) in
                 uniffiFutureCallback(
                     uniffiCallbackData,
@@ -2030,6 +2112,11 @@ public enum StorageManagerError: Swift.Error, Equatable, Hashable, Foundation.Lo
      * An internal problem occurred in the storage manager.
      */
     case InternalError
+    /**
+     * The storage manager does not support the requested operation.  Callers are
+     * expected to fall back to an equivalent sequence of supported operations.
+     */
+    case Unsupported
 
     
 
@@ -2064,6 +2151,7 @@ public struct FfiConverterTypeStorageManagerError: FfiConverterRustBuffer {
         case 3: return .StorageFull
         case 4: return .CouldNotMakeKey
         case 5: return .InternalError
+        case 6: return .Unsupported
 
          default: throw UniffiInternalError.unexpectedEnumCase
         }
@@ -2095,6 +2183,10 @@ public struct FfiConverterTypeStorageManagerError: FfiConverterRustBuffer {
         case .InternalError:
             writeInt(&buf, Int32(5))
         
+        
+        case .Unsupported:
+            writeInt(&buf, Int32(6))
+        
         }
     }
 }
@@ -2114,6 +2206,92 @@ public func FfiConverterTypeStorageManagerError_lower(_ value: StorageManagerErr
     return FfiConverterTypeStorageManagerError.lower(value)
 }
 
+// Note that we don't yet support `indirect` for enums.
+// See https://github.com/mozilla/uniffi-rs/issues/396 for further discussion.
+/**
+ * Enum: StorageOperation
+ *
+ * A single write operation, applied as part of a batch through
+ * [StorageManagerInterface::batch].
+ */
+
+public enum StorageOperation: Equatable, Hashable {
+    
+    /**
+     * Add a key-value pair, replacing any existing value.
+     */
+    case add(key: Key, value: Value
+    )
+    /**
+     * Remove a key, if it exists.
+     */
+    case remove(key: Key
+    )
+
+
+
+
+
+}
+
+#if compiler(>=6)
+extension StorageOperation: Sendable {}
+#endif
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public struct FfiConverterTypeStorageOperation: FfiConverterRustBuffer {
+    typealias SwiftType = StorageOperation
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> StorageOperation {
+        let variant: Int32 = try readInt(&buf)
+        switch variant {
+        
+        case 1: return .add(key: try FfiConverterTypeKey.read(from: &buf), value: try FfiConverterTypeValue.read(from: &buf)
+        )
+        
+        case 2: return .remove(key: try FfiConverterTypeKey.read(from: &buf)
+        )
+        
+        default: throw UniffiInternalError.unexpectedEnumCase
+        }
+    }
+
+    public static func write(_ value: StorageOperation, into buf: inout [UInt8]) {
+        switch value {
+        
+        
+        case let .add(key,value):
+            writeInt(&buf, Int32(1))
+            FfiConverterTypeKey.write(key, into: &buf)
+            FfiConverterTypeValue.write(value, into: &buf)
+            
+        
+        case let .remove(key):
+            writeInt(&buf, Int32(2))
+            FfiConverterTypeKey.write(key, into: &buf)
+            
+        }
+    }
+}
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeStorageOperation_lift(_ buf: RustBuffer) throws -> StorageOperation {
+    return try FfiConverterTypeStorageOperation.lift(buf)
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeStorageOperation_lower(_ value: StorageOperation) -> RustBuffer {
+    return FfiConverterTypeStorageOperation.lower(value)
+}
+
+
 #if swift(>=5.8)
 @_documentation(visibility: private)
 #endif
@@ -2138,6 +2316,31 @@ fileprivate struct FfiConverterOptionTypeValue: FfiConverterRustBuffer {
     }
 }
 
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+fileprivate struct FfiConverterSequenceTypeStorageOperation: FfiConverterRustBuffer {
+    typealias SwiftType = [StorageOperation]
+
+    public static func write(_ value: [StorageOperation], into buf: inout [UInt8]) {
+        let len = Int32(value.count)
+        writeInt(&buf, len)
+        for item in value {
+            FfiConverterTypeStorageOperation.write(item, into: &buf)
+        }
+    }
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> [StorageOperation] {
+        let len: Int32 = try readInt(&buf)
+        var seq = [StorageOperation]()
+        seq.reserveCapacity(Int(len))
+        for _ in 0 ..< len {
+            seq.append(try FfiConverterTypeStorageOperation.read(from: &buf))
+        }
+        return seq
+    }
+}
+
 #if swift(>=5.8)
 @_documentation(visibility: private)
 #endif
@@ -2498,6 +2701,9 @@ private let initializationResult: InitializationResult = {
     if (uniffi_mobile_toolkit_checksum_method_storagemanagerinterface_remove() != 2180) {
         return InitializationResult.apiChecksumMismatch
     }
+    if (uniffi_mobile_toolkit_checksum_method_storagemanagerinterface_batch() != 54076) {
+        return InitializationResult.apiChecksumMismatch
+    }
 
     uniffiCallbackInitAsyncHttpClient()
     uniffiCallbackInitKeyStore()
@@ -31,6 +31,23 @@ pub enum StorageManagerError {
     /// An internal problem occurred in the storage manager.
     #[error("Internal Error")]
     InternalError,
+
+    /// The storage manager does not support the requested operation.  Callers are
+    /// expected to fall back to an equivalent sequence of supported operations.
+    #[error("Unsupported Operation")]
+    Unsupported,
+}
+
+/// Enum: StorageOperation
+///
+/// A single write operation, applied as part of a batch through
+/// [StorageManagerInterface::batch].
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum StorageOperation {
+    /// Add a key-value pair, replacing any existing value.
+    Add { key: Key, value: Value },
+    /// Remove a key, if it exists.
+    Remove { key: Key },
 }
 
 /// Interface: StorageManagerInterface
@@ -74,4 +91,15 @@ pub trait StorageManagerInterface: Send + Sync + Debug {
     /// particular, it must treat removing a non-existent key as a normal and
     /// expected circumstance, simply returning () and not an error.
     async fn remove(&self, key: Key) -> Result<(), StorageManagerError>;
+
+    /// Function: batch
+    ///
+    /// Callback function pointer to native (kotlin/swift) code for applying
+    /// several operations atomically: either all of them are applied, or none
+    /// of them are.  Operations are applied in order.
+    ///
+    /// Storage managers that cannot provide atomicity should return
+    /// `StorageManagerError::Unsupported`, in which case the SDK applies the
+    /// operations one by one and rolls them back on failure.
+    async fn batch(&self, operations: Vec<StorageOperation>) -> Result<(), StorageManagerError>;
 }
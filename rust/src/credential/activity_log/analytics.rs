@@ -0,0 +1,135 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use uuid::Uuid;
+
+use super::{ActivityLogEntry, ActivityLogEntryType};
+
+/// Aggregate view of the attributes disclosed to verifiers, returned by
+/// [VdcCollection::activity_log_disclosure_summary](crate::vdc_collection::VdcCollection::activity_log_disclosure_summary).
+#[derive(uniffi::Record, Clone, Debug, Default)]
+pub struct DisclosureSummary {
+    /// The verifiers the attributes were disclosed to, most recent first.
+    pub verifiers: Vec<VerifierDisclosures>,
+    /// The attributes disclosed from each credential, most recent first.
+    pub credentials: Vec<CredentialDisclosures>,
+}
+
+/// What a verifier has received.
+#[derive(uniffi::Record, Clone, Debug)]
+pub struct VerifierDisclosures {
+    /// The verifier, as recorded in the `interaction_with` of the entries.
+    pub verifier: String,
+    /// Number of presentations to the verifier.
+    pub presentations: u32,
+    /// Timestamp of the last presentation to the verifier.
+    pub last_shared: u64,
+    /// The attributes received by the verifier, by credential and path.
+    pub attributes: Vec<AttributeDisclosure>,
+}
+
+/// How often and when an attribute was disclosed to a verifier.
+#[derive(uniffi::Record, Clone, Debug)]
+pub struct AttributeDisclosure {
+    pub credential_id: Uuid,
+    /// Path of the disclosed attribute.
+    pub field: String,
+    /// Number of presentations that disclosed the attribute.
+    pub count: u32,
+    /// Timestamp of the first disclosure.
+    pub first_shared: u64,
+    /// Timestamp of the last disclosure.
+    pub last_shared: u64,
+}
+
+/// The attributes ever disclosed from a credential.
+#[derive(uniffi::Record, Clone, Debug)]
+pub struct CredentialDisclosures {
+    pub credential_id: Uuid,
+    /// Paths of the disclosed attributes, sorted.
+    pub fields: Vec<String>,
+    /// The verifiers that received any of them, sorted.
+    pub verifiers: Vec<String>,
+    /// Timestamp of the last disclosure.
+    pub last_shared: u64,
+}
+
+/// Aggregate the `Shared` entries. Other entries are ignored.
+pub(crate) fn summarize(entries: &[ActivityLogEntry]) -> DisclosureSummary {
+    let mut verifiers: BTreeMap<&str, VerifierDisclosures> = BTreeMap::new();
+    let mut attributes: BTreeMap<(&str, Uuid, &str), AttributeDisclosure> = BTreeMap::new();
+    let mut credentials: BTreeMap<Uuid, (BTreeSet<&str>, BTreeSet<&str>, u64)> = BTreeMap::new();
+
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.r#type == ActivityLogEntryType::Shared)
+    {
+        let verifier = entry.interaction_with.as_str();
+        let timestamp = entry.timestamp;
+
+        let disclosures = verifiers
+            .entry(verifier)
+            .or_insert_with(|| VerifierDisclosures {
+                verifier: verifier.to_string(),
+                presentations: 0,
+                last_shared: timestamp,
+                attributes: Vec::new(),
+            });
+        disclosures.presentations += 1;
+        disclosures.last_shared = disclosures.last_shared.max(timestamp);
+
+        let (fields, credential_verifiers, last_shared) =
+            credentials.entry(entry.credential_id).or_default();
+        credential_verifiers.insert(verifier);
+        *last_shared = (*last_shared).max(timestamp);
+
+        // A field is counted once per presentation.
+        for field in entry
+            .fields
+            .iter()
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>()
+        {
+            fields.insert(field);
+
+            let attribute = attributes
+                .entry((verifier, entry.credential_id, field))
+                .or_insert_with(|| AttributeDisclosure {
+                    credential_id: entry.credential_id,
+                    field: field.to_string(),
+                    count: 0,
+                    first_shared: timestamp,
+                    last_shared: timestamp,
+                });
+            attribute.count += 1;
+            attribute.first_shared = attribute.first_shared.min(timestamp);
+            attribute.last_shared = attribute.last_shared.max(timestamp);
+        }
+    }
+
+    for ((verifier, _, _), attribute) in attributes {
+        if let Some(disclosures) = verifiers.get_mut(verifier) {
+            disclosures.attributes.push(attribute);
+        }
+    }
+
+    let mut verifiers = verifiers.into_values().collect::<Vec<_>>();
+    verifiers.sort_by(|a, b| b.last_shared.cmp(&a.last_shared));
+
+    let mut credentials = credentials
+        .into_iter()
+        .map(
+            |(credential_id, (fields, verifiers, last_shared))| CredentialDisclosures {
+                credential_id,
+                fields: fields.into_iter().map(str::to_string).collect(),
+                verifiers: verifiers.into_iter().map(str::to_string).collect(),
+                last_shared,
+            },
+        )
+        .collect::<Vec<_>>();
+    credentials.sort_by(|a, b| b.last_shared.cmp(&a.last_shared));
+
+    DisclosureSummary {
+        verifiers,
+        credentials,
+    }
+}
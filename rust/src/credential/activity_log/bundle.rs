@@ -0,0 +1,177 @@
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    chain::{entry_hash, record_hash, ChainRecord},
+    ActivityLogChainHead, ActivityLogEntry, ActivityLogError,
+};
+use crate::{
+    jwk::Jwk,
+    jws::{Jws, JwsSigner},
+};
+
+/// Media type of the signed activity log exports, set as their `typ` header.
+const EXPORT_MEDIA_TYPE: &str = "activity-log+jwt";
+
+/// Payload of a signed activity log export.
+#[derive(Serialize, Deserialize)]
+struct ExportPayload {
+    /// Timestamp of the export.
+    iat: u64,
+    credential_id: Uuid,
+    entries: Vec<ActivityLogEntry>,
+    /// Head of the hash chain at the time of the export, if enabled.
+    chain_head: Option<ActivityLogChainHead>,
+    /// Chain records of the edits made to the exported entries.
+    #[serde(default)]
+    records: Vec<ChainRecord>,
+}
+
+/// The serialized [ExportPayload], as signed.
+struct SignedPayload(Vec<u8>);
+
+impl ssi::claims::jws::JwsPayload for SignedPayload {
+    fn typ(&self) -> Option<&str> {
+        Some(EXPORT_MEDIA_TYPE)
+    }
+
+    fn payload_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+}
+
+#[derive(Deserialize)]
+struct ExportHeader {
+    alg: ssi::jwk::Algorithm,
+    kid: Option<String>,
+    typ: Option<String>,
+}
+
+/// The content of a signed activity log export, once verified.
+#[derive(uniffi::Record)]
+pub struct VerifiedActivityLogExport {
+    /// Identifier of the key that signed the export.
+    pub key_id: Option<String>,
+    /// Timestamp of the export.
+    pub issued_at: u64,
+    /// The credential the exported entries belong to.
+    pub credential_id: Uuid,
+    pub entries: Vec<Arc<ActivityLogEntry>>,
+    /// Head of the hash chain at the time of the export, if enabled.
+    pub chain_head: Option<ActivityLogChainHead>,
+}
+
+/// Sign the entries as a compact JWS.
+pub(crate) async fn sign(
+    credential_id: Uuid,
+    entries: Vec<ActivityLogEntry>,
+    chain_head: Option<ActivityLogChainHead>,
+    records: Vec<ChainRecord>,
+    signer: Arc<dyn JwsSigner>,
+) -> Result<Jws, ActivityLogError> {
+    use ssi::claims::jws::JwsSigner as _;
+
+    let payload = ExportPayload {
+        iat: chrono::Utc::now().timestamp() as u64,
+        credential_id,
+        entries,
+        chain_head,
+        records,
+    };
+
+    let payload = serde_json::to_vec(&payload)
+        .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))?;
+
+    signer
+        .as_ref()
+        .sign(SignedPayload(payload))
+        .await
+        .map(Into::into)
+        .map_err(|e| ActivityLogError::Signing(e.to_string()))
+}
+
+/// Verify an activity log export signed with
+/// [ActivityLog::export_signed](super::ActivityLog::export_signed).
+///
+/// The export is verified against the public key of the wallet, which can be
+/// looked up with the `key_id` of the export. Chained entries are also
+/// checked against their hash, or against the hash recorded by the last edit
+/// made to them through the wallet.
+#[uniffi::export]
+pub async fn verify_activity_log_export(
+    export: Jws,
+    public_key: Arc<Jwk>,
+) -> Result<VerifiedActivityLogExport, ActivityLogError> {
+    let invalid = |reason: &str| ActivityLogError::InvalidExport(reason.to_string());
+
+    let compact = String::from(export);
+    let mut parts = compact.splitn(3, '.');
+    let (Some(header), Some(payload), Some(signature)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("malformed JWS"));
+    };
+
+    let decode = |part: &str| {
+        BASE64_URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|_| invalid("malformed JWS"))
+    };
+    let header: ExportHeader =
+        serde_json::from_slice(&decode(header)?).map_err(|_| invalid("malformed header"))?;
+
+    if header.typ.as_deref() != Some(EXPORT_MEDIA_TYPE) {
+        return Err(invalid("not an activity log export"));
+    }
+
+    let key = public_key.0.read().await;
+    if key
+        .key_id
+        .as_ref()
+        .zip(header.kid.as_ref())
+        .is_some_and(|(expected, kid)| expected != kid)
+    {
+        return Err(invalid("signed by another key"));
+    }
+
+    let signing_input = &compact[..compact.len() - signature.len() - 1];
+    ssi::claims::jws::verify_bytes(
+        header.alg,
+        signing_input.as_bytes(),
+        &key,
+        &decode(signature)?,
+    )
+    .map_err(|_| invalid("invalid signature"))?;
+
+    let payload: ExportPayload =
+        serde_json::from_slice(&decode(payload)?).map_err(|_| invalid("malformed payload"))?;
+
+    let mut edited = HashMap::new();
+    let mut records = payload.records.iter().collect::<Vec<_>>();
+    records.sort_by_key(|record| record.sequence);
+    for record in records {
+        if record.hash != record_hash(record)? {
+            return Err(invalid("chain record does not match its hash"));
+        }
+        if let Some(content_hash) = &record.content_hash {
+            edited.insert(record.entry_id, content_hash);
+        }
+    }
+
+    for entry in payload.entries.iter().filter(|e| e.hash.is_some()) {
+        let expected = edited.get(&entry.id).copied().or(entry.hash.as_ref());
+        if expected != Some(&entry_hash(entry)?) {
+            return Err(invalid("entry does not match its hash"));
+        }
+    }
+
+    Ok(VerifiedActivityLogExport {
+        key_id: header.kid,
+        issued_at: payload.iat,
+        credential_id: payload.credential_id,
+        entries: payload.entries.into_iter().map(Arc::new).collect(),
+        chain_head: payload.chain_head,
+    })
+}
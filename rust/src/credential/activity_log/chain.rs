@@ -0,0 +1,552 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use futures::StreamExt;
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::MutexGuard;
+use uuid::Uuid;
+
+use super::{ActivityLogEntry, ActivityLogError};
+use crate::{
+    crypto::SigningKey,
+    storage_manager::{StorageManagerInterface, StorageOperation},
+    vdc_collection::ProfileStorage,
+    Key, Value,
+};
+
+/// Storage key of the head of the activity log hash chain.
+pub(crate) const CHAIN_HEAD_KEY: &str = "ActivityLogChainHead";
+
+/// Previous hash of the first entry of the chain.
+pub(crate) const GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Prefix of the storage keys of the chain records, followed by their
+/// position in the chain.
+pub(crate) const RECORD_KEY_PREFIX: &str = "ActivityLogChainRecord.";
+
+/// Head of the wallet-wide activity log hash chain.
+///
+/// Its presence in storage enables the chain: every entry, and every change
+/// made to an entry through the wallet afterwards, is linked to the previous
+/// one, across all credentials.
+#[derive(uniffi::Record, Clone, Debug, Serialize, Deserialize)]
+pub struct ActivityLogChainHead {
+    /// Timestamp of when the chain was enabled.
+    pub enabled_at: u64,
+    /// Number of entries and changes in the chain.
+    pub length: u64,
+    /// Hash of the last entry or change of the chain, or the genesis hash if
+    /// empty.
+    pub hash: String,
+    /// The last signed state of the chain.
+    pub checkpoint: Option<ActivityLogChainCheckpoint>,
+}
+
+/// A signed state of the activity log hash chain.
+///
+/// The signature covers `{length}.{hash}.{timestamp}`, so that any entry up to
+/// `length` cannot be edited or removed, other than through the wallet,
+/// without invalidating it.
+#[derive(uniffi::Record, Clone, Debug, Serialize, Deserialize)]
+pub struct ActivityLogChainCheckpoint {
+    /// Length of the chain when signed.
+    pub length: u64,
+    /// Hash of the last entry or change of the chain when signed.
+    pub hash: String,
+    /// Timestamp of the signature.
+    pub timestamp: u64,
+    /// Public JWK of the signing key.
+    pub jwk: String,
+    /// Base64url-encoded P-256 signature.
+    pub signature: String,
+}
+
+/// An inconsistency found by
+/// [VdcCollection::verify_activity_log_chain](crate::vdc_collection::VdcCollection::verify_activity_log_chain).
+#[derive(uniffi::Enum, Clone, Debug, PartialEq, Eq)]
+pub enum ActivityLogChainIssue {
+    /// No entry or change was found at this position of the chain.
+    Missing { sequence: u64 },
+    /// Several entries or changes claim the same position of the chain.
+    Duplicate { sequence: u64 },
+    /// The content of the entry, or of the record of a change to it, does not
+    /// match its hash.
+    Modified { entry_id: Uuid, sequence: u64 },
+    /// The entry, or the record of a change to it, is not linked to the
+    /// position preceding it.
+    BrokenLink { entry_id: Uuid, sequence: u64 },
+    /// The entry is not part of the chain.
+    Unchained { entry_id: Uuid },
+    /// The chain head does not match the last position of the chain.
+    HeadMismatch,
+    /// The signed checkpoint does not match the chain, or its signature is
+    /// invalid.
+    InvalidCheckpoint { reason: String },
+}
+
+/// Result of
+/// [VdcCollection::verify_activity_log_chain](crate::vdc_collection::VdcCollection::verify_activity_log_chain).
+#[derive(uniffi::Record, Clone, Debug)]
+pub struct ActivityLogChainReport {
+    /// Whether no issue was found.
+    pub valid: bool,
+    /// Length of the chain, according to its head.
+    pub length: u64,
+    /// Length of the chain covered by the signed checkpoint.
+    pub signed_length: u64,
+    /// Public JWK of the key that signed the checkpoint.
+    pub signed_by: String,
+    pub issues: Vec<ActivityLogChainIssue>,
+}
+
+/// A change made to a chained entry through the wallet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum ChainChange {
+    /// The entry was removed, e.g. along with its credential or by the
+    /// retention policy.
+    Removed,
+    /// The entry was moved to another profile along with its credential, and
+    /// linked to the chain of that profile.
+    Moved,
+    /// The entry was edited, e.g. hidden or cleared of its shared fields.
+    Edited,
+}
+
+/// A chained record of a change made to an entry, so that the entry can be
+/// removed or edited without breaking the chain.
+///
+/// Records are kept for as long as the chain: they only hold hashes, and no
+/// content of the entries.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ChainRecord {
+    /// Position of the record in the chain.
+    pub(crate) sequence: u64,
+    pub(crate) previous_hash: String,
+    pub(crate) timestamp: u64,
+    pub(crate) change: ChainChange,
+    pub(crate) entry_id: Uuid,
+    /// Position of the entry in the chain.
+    pub(crate) entry_sequence: u64,
+    /// Hash the entry was chained with, which the following position links
+    /// to.
+    pub(crate) entry_hash: String,
+    /// Hash of the content of the entry once edited.
+    pub(crate) content_hash: Option<String>,
+    /// Hash of this record, covering all of the above.
+    pub(crate) hash: String,
+}
+
+impl ChainRecord {
+    fn storage_key(sequence: u64) -> Key {
+        Key(format!("{RECORD_KEY_PREFIX}{sequence}"))
+    }
+}
+
+fn sha256_json(value: &impl Serialize) -> Result<String, ActivityLogError> {
+    let bytes = serde_json::to_vec(value)
+        .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))?;
+
+    Ok(hex::encode(Sha256::digest(bytes)))
+}
+
+/// Hex-encoded SHA-256 hash of every persisted field of an entry, including
+/// its link to the previous entry, but excluding its own hash.
+pub(crate) fn entry_hash(entry: &ActivityLogEntry) -> Result<String, ActivityLogError> {
+    sha256_json(&ActivityLogEntry {
+        hash: None,
+        ..entry.clone()
+    })
+}
+
+pub(crate) fn record_hash(record: &ChainRecord) -> Result<String, ActivityLogError> {
+    sha256_json(&ChainRecord {
+        hash: String::new(),
+        ..record.clone()
+    })
+}
+
+pub(crate) async fn load_head(
+    storage: &dyn StorageManagerInterface,
+) -> Result<Option<ActivityLogChainHead>, ActivityLogError> {
+    storage
+        .get(Key(CHAIN_HEAD_KEY.into()))
+        .await
+        .map_err(|e| ActivityLogError::Storage(e.to_string()))?
+        .map(|value| {
+            serde_json::from_slice(&value.0)
+                .map_err(|e| ActivityLogError::ActivityLogEntryDeserialization(e.to_string()))
+        })
+        .transpose()
+}
+
+pub(crate) fn head_operation(
+    head: &ActivityLogChainHead,
+) -> Result<StorageOperation, ActivityLogError> {
+    let value = serde_json::to_vec(head)
+        .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))?;
+
+    Ok(StorageOperation::Add {
+        key: Key(CHAIN_HEAD_KEY.into()),
+        value: Value(value),
+    })
+}
+
+/// Lock the chain head and the activity log index of the storage, until the
+/// operations updating them are written.
+pub(crate) async fn lock_chain_head(storage: &ProfileStorage) -> MutexGuard<'_, ()> {
+    storage.scope().activity_log_lock.lock().await
+}
+
+/// Link an entry to the end of the chain.
+fn link(
+    head: &mut ActivityLogChainHead,
+    entry: &mut ActivityLogEntry,
+) -> Result<(), ActivityLogError> {
+    entry.chain_sequence = Some(head.length);
+    entry.previous_hash = Some(head.hash.clone());
+    let hash = entry_hash(entry)?;
+    entry.hash = Some(hash.clone());
+
+    head.length += 1;
+    head.hash = hash;
+
+    Ok(())
+}
+
+/// The storage operations writing new entries, linking them to the hash chain
+/// when it is enabled.
+///
+/// Entries that are already part of the chain are written unchanged. The
+/// caller must hold [lock_chain_head] until the operations are written.
+pub(crate) async fn entry_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: Vec<ActivityLogEntry>,
+) -> Result<Vec<StorageOperation>, ActivityLogError> {
+    let mut head = load_head(storage).await?;
+    let mut operations = Vec::with_capacity(entries.len() + 1);
+
+    for mut entry in entries {
+        if let Some(head) = head.as_mut().filter(|_| entry.chain_sequence.is_none()) {
+            link(head, &mut entry)?;
+        }
+
+        operations.push(StorageOperation::Add {
+            key: entry.as_storage_key(),
+            value: (&entry).try_into()?,
+        });
+    }
+
+    if let Some(head) = head {
+        operations.push(head_operation(&head)?);
+    }
+
+    Ok(operations)
+}
+
+/// The storage operations enabling the chain, linking the stored entries to it
+/// from the oldest.
+pub(crate) fn enable_operations(
+    mut entries: Vec<ActivityLogEntry>,
+) -> Result<(ActivityLogChainHead, Vec<StorageOperation>), ActivityLogError> {
+    let mut head = ActivityLogChainHead {
+        enabled_at: chrono::Utc::now().timestamp() as u64,
+        length: 0,
+        hash: GENESIS_HASH.into(),
+        checkpoint: None,
+    };
+
+    entries.sort_by_key(|entry| (entry.timestamp, entry.id));
+    let mut operations = Vec::with_capacity(entries.len() + 1);
+    for mut entry in entries {
+        link(&mut head, &mut entry)?;
+        operations.push(StorageOperation::Add {
+            key: entry.as_storage_key(),
+            value: (&entry).try_into()?,
+        });
+    }
+    operations.push(head_operation(&head)?);
+
+    Ok((head, operations))
+}
+
+/// The storage operations recording changes made to entries in the hash
+/// chain, when it is enabled. Edited entries are passed as edited.
+///
+/// Entries that are not part of the chain are ignored. The caller must hold
+/// [lock_chain_head] until the operations are written, along with the
+/// changes.
+pub(crate) async fn change_operations(
+    storage: &dyn StorageManagerInterface,
+    change: ChainChange,
+    entries: &[ActivityLogEntry],
+) -> Result<Vec<StorageOperation>, ActivityLogError> {
+    let Some(mut head) = load_head(storage).await? else {
+        return Ok(Vec::new());
+    };
+
+    let mut operations = Vec::new();
+    for entry in entries {
+        let (Some(entry_sequence), Some(linked_hash)) = (entry.chain_sequence, &entry.hash) else {
+            continue;
+        };
+
+        let mut record = ChainRecord {
+            sequence: head.length,
+            previous_hash: head.hash.clone(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            change,
+            entry_id: entry.id,
+            entry_sequence,
+            entry_hash: linked_hash.clone(),
+            content_hash: match change {
+                ChainChange::Edited => Some(entry_hash(entry)?),
+                ChainChange::Removed | ChainChange::Moved => None,
+            },
+            hash: String::new(),
+        };
+        record.hash = record_hash(&record)?;
+
+        head.length += 1;
+        head.hash = record.hash.clone();
+
+        let value = serde_json::to_vec(&record)
+            .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))?;
+        operations.push(StorageOperation::Add {
+            key: ChainRecord::storage_key(record.sequence),
+            value: Value(value),
+        });
+    }
+
+    if !operations.is_empty() {
+        operations.push(head_operation(&head)?);
+    }
+    Ok(operations)
+}
+
+/// The stored chain records. Malformed records are skipped, and reported as
+/// missing by [verify].
+pub(crate) async fn stored_records(
+    storage: &dyn StorageManagerInterface,
+) -> Result<Vec<ChainRecord>, ActivityLogError> {
+    let keys = storage
+        .list()
+        .await
+        .map_err(|e| ActivityLogError::Storage(e.to_string()))?
+        .into_iter()
+        .filter(|key| key.0.starts_with(RECORD_KEY_PREFIX));
+
+    let records = futures::stream::iter(keys)
+        .filter_map(|key| async move { storage.get(key).await.ok().flatten() })
+        .filter_map(|value| async move { serde_json::from_slice(&value.0).ok() })
+        .collect::<Vec<ChainRecord>>()
+        .await;
+
+    Ok(records)
+}
+
+/// Sign the current state of the chain.
+pub(crate) fn checkpoint(
+    head: &ActivityLogChainHead,
+    signer: Arc<dyn SigningKey>,
+) -> Result<ActivityLogChainCheckpoint, ActivityLogError> {
+    let timestamp = chrono::Utc::now().timestamp() as u64;
+    let payload = checkpoint_payload(head.length, &head.hash, timestamp);
+
+    let signature = signer
+        .sign(payload)
+        .map_err(|e| ActivityLogError::Signing(e.to_string()))?;
+    let jwk = signer
+        .jwk()
+        .map_err(|e| ActivityLogError::Signing(e.to_string()))?;
+
+    Ok(ActivityLogChainCheckpoint {
+        length: head.length,
+        hash: head.hash.clone(),
+        timestamp,
+        jwk,
+        signature: BASE64_URL_SAFE_NO_PAD.encode(signature),
+    })
+}
+
+fn checkpoint_payload(length: u64, hash: &str, timestamp: u64) -> Vec<u8> {
+    format!("{length}.{hash}.{timestamp}").into_bytes()
+}
+
+/// Verify the signature of the checkpoint with the expected key. The key
+/// embedded in the checkpoint is only checked to be that same key, as anyone
+/// rewriting the chain could sign it with their own.
+fn verify_checkpoint_signature(
+    checkpoint: &ActivityLogChainCheckpoint,
+    public_jwk: &str,
+) -> Result<(), String> {
+    let key = p256::PublicKey::from_jwk_str(public_jwk)
+        .map_err(|e| format!("unsupported verification key: {e}"))?;
+    if p256::PublicKey::from_jwk_str(&checkpoint.jwk).ok() != Some(key) {
+        return Err("the checkpoint is not signed by the expected key".into());
+    }
+    let bytes = BASE64_URL_SAFE_NO_PAD
+        .decode(&checkpoint.signature)
+        .map_err(|e| format!("malformed signature: {e}"))?;
+    // Signing keys may produce either fixed-width or DER signatures.
+    let signature = Signature::from_slice(&bytes)
+        .or_else(|_| Signature::from_der(&bytes))
+        .map_err(|e| format!("malformed signature: {e}"))?;
+
+    VerifyingKey::from(&key)
+        .verify(
+            &checkpoint_payload(checkpoint.length, &checkpoint.hash, checkpoint.timestamp),
+            &signature,
+        )
+        .map_err(|_| "invalid signature".to_string())
+}
+
+/// A position of the chain.
+enum Link<'a> {
+    Entry(&'a ActivityLogEntry),
+    Record(&'a ChainRecord),
+}
+
+/// Verify the chain formed by the given entries and records against its head,
+/// and its checkpoint against the public JWK of the expected signing key.
+pub(crate) fn verify(
+    head: &ActivityLogChainHead,
+    entries: &[ActivityLogEntry],
+    records: &[ChainRecord],
+    public_jwk: &str,
+) -> Result<ActivityLogChainReport, ActivityLogError> {
+    let checkpoint = head
+        .checkpoint
+        .as_ref()
+        .ok_or(ActivityLogError::ChainNotSigned)?;
+
+    let mut issues = Vec::new();
+    let mut chain: BTreeMap<u64, Vec<Link>> = BTreeMap::new();
+
+    for entry in entries {
+        match entry.chain_sequence {
+            Some(sequence) => chain.entry(sequence).or_default().push(Link::Entry(entry)),
+            None => issues.push(ActivityLogChainIssue::Unchained { entry_id: entry.id }),
+        }
+    }
+
+    // Hash of the entries removed or moved away through the wallet, by
+    // position, and hash of the content of the entries edited through the
+    // wallet, as last recorded.
+    let mut removed: HashMap<u64, &str> = HashMap::new();
+    let mut edited: HashMap<Uuid, &str> = HashMap::new();
+    let mut sorted = records.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|record| record.sequence);
+    for record in sorted {
+        chain
+            .entry(record.sequence)
+            .or_default()
+            .push(Link::Record(record));
+
+        match (record.change, &record.content_hash) {
+            (ChainChange::Removed | ChainChange::Moved, _) => {
+                removed.insert(record.entry_sequence, &record.entry_hash);
+            }
+            (ChainChange::Edited, Some(content_hash)) => {
+                edited.insert(record.entry_id, content_hash);
+            }
+            (ChainChange::Edited, None) => {}
+        }
+    }
+
+    let stored_length = chain.keys().next_back().map(|s| s + 1).unwrap_or(0);
+    // Positions past the head are reported as a head mismatch.
+    let length = head.length.max(checkpoint.length);
+
+    // Hash of each position, when it holds a single entry or record.
+    let mut hashes: BTreeMap<u64, String> = BTreeMap::new();
+
+    for sequence in 0..length {
+        let link = match chain.get(&sequence).map(Vec::as_slice) {
+            None | Some([]) => {
+                match removed.get(&sequence) {
+                    // Entries removed through the wallet are linked to by
+                    // their recorded hash.
+                    Some(hash) => {
+                        hashes.insert(sequence, hash.to_string());
+                    }
+                    None => issues.push(ActivityLogChainIssue::Missing { sequence }),
+                }
+                continue;
+            }
+            Some([link]) => link,
+            Some(_) => {
+                issues.push(ActivityLogChainIssue::Duplicate { sequence });
+                continue;
+            }
+        };
+
+        let (entry_id, previous_hash, hash, expected) = match link {
+            Link::Entry(entry) => (
+                entry.id,
+                entry.previous_hash.as_deref(),
+                entry.hash.clone(),
+                edited
+                    .get(&entry.id)
+                    .map(|hash| hash.to_string())
+                    .or(entry.hash.clone()),
+            ),
+            Link::Record(record) => (
+                record.entry_id,
+                Some(record.previous_hash.as_str()),
+                Some(record.hash.clone()),
+                Some(record.hash.clone()),
+            ),
+        };
+
+        let content = match link {
+            Link::Entry(entry) => entry_hash(entry)?,
+            Link::Record(record) => record_hash(record)?,
+        };
+        if expected.as_ref() != Some(&content) {
+            issues.push(ActivityLogChainIssue::Modified { entry_id, sequence });
+        }
+
+        let previous = match sequence {
+            0 => Some(GENESIS_HASH),
+            _ => hashes.get(&(sequence - 1)).map(String::as_str),
+        };
+        if previous.is_some_and(|previous| previous_hash != Some(previous)) {
+            issues.push(ActivityLogChainIssue::BrokenLink { entry_id, sequence });
+        }
+
+        // The next position links to the stored hash, so that only the
+        // modified one is reported.
+        hashes.insert(sequence, hash.unwrap_or(content));
+    }
+
+    let hash_at = |length: u64| match length {
+        0 => Some(GENESIS_HASH),
+        _ => hashes.get(&(length - 1)).map(String::as_str),
+    };
+
+    if stored_length > head.length || hash_at(head.length) != Some(head.hash.as_str()) {
+        issues.push(ActivityLogChainIssue::HeadMismatch);
+    }
+
+    if hash_at(checkpoint.length) != Some(checkpoint.hash.as_str()) {
+        issues.push(ActivityLogChainIssue::InvalidCheckpoint {
+            reason: "the signed hash does not match the chain".into(),
+        });
+    }
+
+    if let Err(reason) = verify_checkpoint_signature(checkpoint, public_jwk) {
+        issues.push(ActivityLogChainIssue::InvalidCheckpoint { reason });
+    }
+
+    Ok(ActivityLogChainReport {
+        valid: issues.is_empty(),
+        length: head.length,
+        signed_length: checkpoint.length,
+        signed_by: checkpoint.jwk.clone(),
+        issues,
+    })
+}
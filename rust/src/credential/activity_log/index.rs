@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    chain::lock_chain_head, ActivityLogEntry, ActivityLogEntryType, ActivityLogError, KEY_PREFIX,
+};
+use crate::{
+    storage_manager::{StorageManagerInterface, StorageOperation},
+    vdc_collection::ProfileStorage,
+    Key, Value,
+};
+
+/// Storage key of the wallet-wide activity log index.
+pub(crate) const INDEX_KEY: &str = "ActivityLogIndex";
+
+/// Sort order of the entries returned by
+/// [VdcCollection::query_activity_log](crate::vdc_collection::VdcCollection::query_activity_log).
+#[derive(uniffi::Enum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ActivityLogSort {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// Query options for
+/// [VdcCollection::query_activity_log](crate::vdc_collection::VdcCollection::query_activity_log).
+#[derive(uniffi::Record, Clone, Default)]
+pub struct ActivityLogQuery {
+    /// Timestamp of when the logs should be filtered from
+    pub from_date: Option<u64>,
+    /// Timestamp of when the logs should be filtered to
+    pub to_date: Option<u64>,
+    /// Entry type to filter
+    pub r#type: Option<ActivityLogEntryType>,
+    /// Only return entries of these credentials. All credentials if empty.
+    pub credential_ids: Vec<Uuid>,
+    /// Case-insensitive full-text search: every word must appear in either
+    /// the description or the interaction actor of the entry.
+    pub search: Option<String>,
+    /// Sort order, by date
+    pub sort: ActivityLogSort,
+    /// Max items returned per page. All matching entries if unset.
+    pub page_size: Option<u32>,
+    /// The `next_cursor` of the previous page, to fetch the next one.
+    pub cursor: Option<String>,
+}
+
+/// A page of activity log entries.
+#[derive(uniffi::Record)]
+pub struct ActivityLogPage {
+    pub entries: Vec<Arc<ActivityLogEntry>>,
+    /// Opaque cursor to pass in [ActivityLogQuery::cursor] to fetch the next
+    /// page, if there are more entries.
+    pub next_cursor: Option<String>,
+}
+
+/// Indexed fields of an activity log entry.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ActivityLogIndexEntry {
+    pub(crate) credential_id: Uuid,
+    pub(crate) r#type: ActivityLogEntryType,
+    pub(crate) timestamp: u64,
+    pub(crate) description: String,
+    pub(crate) interaction_with: String,
+}
+
+impl From<&ActivityLogEntry> for ActivityLogIndexEntry {
+    fn from(entry: &ActivityLogEntry) -> Self {
+        Self {
+            credential_id: entry.credential_id,
+            r#type: entry.r#type.clone(),
+            timestamp: entry.timestamp,
+            description: entry.description.clone(),
+            interaction_with: entry.interaction_with.clone(),
+        }
+    }
+}
+
+/// Wallet-wide index of the activity log entries, keyed by entry ID.
+///
+/// Indexed fields never change once an entry is written, so the index is
+/// updated in the same batch as the entries it tracks are written or removed.
+/// It is only built from the stored entries when it does not exist yet, or
+/// when rebuilt with [ActivityLog::rebuild_index](super::ActivityLog::rebuild_index).
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct ActivityLogIndex {
+    pub(crate) entries: BTreeMap<Uuid, ActivityLogIndexEntry>,
+}
+
+impl ActivityLogIndex {
+    /// Load the index, building it if it does not exist yet.
+    pub(crate) async fn load(storage: &ProfileStorage) -> Result<Self, ActivityLogError> {
+        if let Some(index) = Self::stored(storage).await? {
+            return Ok(index);
+        }
+
+        let _head = lock_chain_head(storage).await;
+        match Self::stored(storage).await? {
+            Some(index) => Ok(index),
+            None => Self::rebuild(storage).await,
+        }
+    }
+
+    /// Rebuild the index from the stored entries, and store it.
+    ///
+    /// The caller must hold [lock_chain_head] until it returns.
+    pub(crate) async fn rebuild(
+        storage: &dyn StorageManagerInterface,
+    ) -> Result<Self, ActivityLogError> {
+        let index = Self {
+            entries: stored_entries(storage)
+                .await?
+                .iter()
+                .map(|entry| (entry.id, entry.into()))
+                .collect(),
+        };
+
+        storage
+            .add(Key(INDEX_KEY.into()), index.to_value()?)
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?;
+
+        Ok(index)
+    }
+
+    /// The storage operation updating the index with the entries written and
+    /// removed.
+    ///
+    /// The caller must hold [lock_chain_head] until the operation is written,
+    /// along with the entries.
+    pub(crate) async fn update_operation(
+        storage: &dyn StorageManagerInterface,
+        written: &[ActivityLogEntry],
+        removed: &[ActivityLogEntry],
+    ) -> Result<StorageOperation, ActivityLogError> {
+        let mut index = match Self::stored(storage).await? {
+            Some(index) => index,
+            None => Self::rebuild(storage).await?,
+        };
+
+        for entry in removed {
+            index.entries.remove(&entry.id);
+        }
+        for entry in written {
+            index.entries.insert(entry.id, entry.into());
+        }
+
+        Ok(StorageOperation::Add {
+            key: Key(INDEX_KEY.into()),
+            value: index.to_value()?,
+        })
+    }
+
+    async fn stored(
+        storage: &dyn StorageManagerInterface,
+    ) -> Result<Option<Self>, ActivityLogError> {
+        Ok(storage
+            .get(Key(INDEX_KEY.into()))
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?
+            // An unreadable index is rebuilt.
+            .and_then(|raw| serde_cbor::from_slice(&raw.0).ok()))
+    }
+
+    fn to_value(&self) -> Result<Value, ActivityLogError> {
+        serde_cbor::to_vec(self)
+            .map(Value)
+            .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))
+    }
+
+    /// Storage keys of the entries of a credential.
+    pub(crate) fn credential_keys(&self, credential_id: Uuid) -> Vec<Key> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.credential_id == credential_id)
+            .map(|(id, _)| ActivityLogEntry::credential_and_entry_id_to_key(credential_id, *id))
+            .collect()
+    }
+}
+
+impl ActivityLogQuery {
+    /// Returns false when the entry should be filtered out.
+    pub(crate) fn matches(&self, entry: &ActivityLogIndexEntry) -> bool {
+        if self.from_date.is_some_and(|from| from > entry.timestamp)
+            || self.to_date.is_some_and(|to| to < entry.timestamp)
+        {
+            return false;
+        }
+
+        if self.r#type.as_ref().is_some_and(|t| *t != entry.r#type) {
+            return false;
+        }
+
+        if !self.credential_ids.is_empty() && !self.credential_ids.contains(&entry.credential_id) {
+            return false;
+        }
+
+        let Some(search) = &self.search else {
+            return true;
+        };
+
+        let description = entry.description.to_lowercase();
+        let interaction_with = entry.interaction_with.to_lowercase();
+        search
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| description.contains(word) || interaction_with.contains(word))
+    }
+}
+
+/// Position of an entry in the timeline, used as pagination cursor.
+pub(crate) type Position = (u64, Uuid);
+
+pub(crate) fn encode_cursor((timestamp, id): Position) -> String {
+    format!("{timestamp}.{id}")
+}
+
+pub(crate) fn decode_cursor(cursor: &str) -> Result<Position, ActivityLogError> {
+    cursor
+        .split_once('.')
+        .and_then(|(timestamp, id)| Some((timestamp.parse().ok()?, Uuid::parse_str(id).ok()?)))
+        .ok_or_else(|| ActivityLogError::InvalidCursor(cursor.to_string()))
+}
+
+/// Read all the stored entries, of all credentials, without the index.
+pub(crate) async fn stored_entries(
+    storage: &dyn StorageManagerInterface,
+) -> Result<Vec<ActivityLogEntry>, ActivityLogError> {
+    let keys = storage
+        .list()
+        .await
+        .map_err(|e| ActivityLogError::Storage(e.to_string()))?;
+
+    let mut entries = Vec::new();
+    for (entry_id, credential_id) in keys.iter().filter_map(parse_key) {
+        let key = ActivityLogEntry::credential_and_entry_id_to_key(credential_id, entry_id);
+        if let Some(entry) = storage
+            .get(key)
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?
+            .and_then(|value| ActivityLogEntry::try_from(value).ok())
+        {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parse an activity log entry key, returning the entry and credential IDs.
+fn parse_key(key: &Key) -> Option<(Uuid, Uuid)> {
+    let rest = key.strip_prefix(KEY_PREFIX)?;
+    let (credential_id, entry_id) = rest.split_once('.')?;
+
+    Some((
+        Uuid::parse_str(entry_id).ok()?,
+        Uuid::parse_str(credential_id).ok()?,
+    ))
+}
@@ -0,0 +1,2224 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    crypto::SigningKey,
+    jws::{Jws, JwsSigner},
+    storage_manager::{apply_batch, StorageManagerInterface, StorageOperation},
+    vdc_collection::{ProfileStorage, VdcCollectionEvent},
+    Key, Value,
+};
+
+use futures::StreamExt;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize, Serializer};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+mod analytics;
+mod bundle;
+mod chain;
+mod index;
+
+pub use analytics::{
+    AttributeDisclosure, CredentialDisclosures, DisclosureSummary, VerifierDisclosures,
+};
+pub use bundle::{verify_activity_log_export, VerifiedActivityLogExport};
+pub use chain::{
+    ActivityLogChainCheckpoint, ActivityLogChainHead, ActivityLogChainIssue, ActivityLogChainReport,
+};
+pub use index::{ActivityLogPage, ActivityLogQuery, ActivityLogSort};
+
+pub(crate) use chain::lock_chain_head;
+
+use chain::ChainChange;
+
+use index::ActivityLogIndex;
+
+/// Entries are stored at the individual entry-level to
+/// ensure that storage of a complete activity log does not
+/// grow in size prohibitively. Keeping the storage at the
+/// entry level flattens the activity log size acrossed
+/// the entries in storage.
+///
+/// In practice, this means that the file identifier for the
+/// activity log must include the unique credential ID that corresponds
+/// to the activity log entry, and a unique identifier for the entry itself.
+///
+/// Ex Entry Key Identifier: `ActivityLogEntry.{credential_id}.{entry_id}`
+pub const KEY_PREFIX: &str = "ActivityLogEntry.";
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum ActivityLogError {
+    #[error("Failed to find activity log for credential: {0}")]
+    NotFound(String),
+    #[error("Failed to create an activity log entry: {0}")]
+    CreateActivityLogEntry(String),
+    #[error("Failed to add activity log entry for credential id: {0}; expected: {0}")]
+    InvalidCredentialId(Uuid, Uuid),
+    #[error("Serialization failed for activity log entry: {0}")]
+    ActivityLogEntrySerialization(String),
+    #[error("Deserialization failed for activity log entry: {0}")]
+    ActivityLogEntryDeserialization(String),
+    #[error("Storage error occured for activity log entry: {0}")]
+    Storage(String),
+    #[error("Invalid activity log cursor: {0}")]
+    InvalidCursor(String),
+    #[error("The activity log hash chain is not enabled")]
+    ChainNotEnabled,
+    #[error("The activity log hash chain has never been signed")]
+    ChainNotSigned,
+    #[error("Failed to sign the activity log: {0}")]
+    Signing(String),
+    #[error("Invalid activity log export: {0}")]
+    InvalidExport(String),
+}
+
+#[derive(uniffi::Enum, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ActivityLogEntryType {
+    Request,
+    Issued,
+    Shared,
+    Refresh,
+    Review,
+    Deleted,
+    Suspended,
+    Revoked,
+    Updated, // Add more as needed.
+}
+
+#[derive(uniffi::Record)]
+pub struct ActivityLogFilterOptions {
+    /// Timestamp of when the logs should be filtered from
+    from_date: Option<u64>,
+    /// Timestamp of when the logs should be filtered to
+    to_date: Option<u64>,
+    /// Entry type to filter
+    r#type: Option<ActivityLogEntryType>,
+    /// Filter on an interaction actor (e.g., Issuer, Verifier)
+    interacted_with: Option<String>,
+    /// Max items to be returned
+    max_items: Option<u32>,
+    /// Use cache
+    use_cache: bool,
+}
+
+impl ActivityLogFilterOptions {
+    /// Internal filtering method, returning false when the item should be filtered
+    /// from the activity log, based on the filter options.
+    fn filter(&self, entry: &ActivityLogEntry) -> bool {
+        // Check date range filters
+        if self
+            .from_date
+            .is_some_and(|from_date| from_date > entry.timestamp)
+        {
+            return false;
+        }
+
+        if self
+            .to_date
+            .is_some_and(|to_date| to_date < entry.timestamp)
+        {
+            return false;
+        }
+
+        // Check type filter
+        if self
+            .r#type
+            .as_ref()
+            .is_some_and(|filter_type| *filter_type != entry.r#type)
+        {
+            return false;
+        }
+
+        // Check interaction actor filter
+        if self
+            .interacted_with
+            .as_ref()
+            .is_some_and(|actor| !entry.interaction_with.contains(actor))
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(uniffi::Object, Clone, Serialize, Deserialize)]
+pub struct ActivityLogEntry {
+    /// Unique identifier for the entry
+    id: Uuid,
+    /// Unique identifier for the credential this activity log
+    /// entry belongs to.
+    credential_id: Uuid,
+    /// Type of activity log entry
+    r#type: ActivityLogEntryType,
+    /// Date encoded as a UNIX timestamp
+    timestamp: u64,
+    /// Date encoded as an RFC 3339 and ISO 8601 date and time string such as `1996-12-19T16:39:57-08:00`.
+    date: String,
+    /// Description shown the user of the interaction
+    description: String,
+    /// Interaction with denotes the name of the
+    /// service, issuer, verifier, etc. that the activity
+    /// corresponds to.
+    ///
+    /// For example, if ACME.gov was an issuer of a refresh
+    /// notice, then the `interaction_with` would be `ACME.gov`.
+    ///
+    /// NOTE: it's not always the case the user/holder is interacting
+    /// with an external actor, for example, if the user/holder deletes
+    /// a credential, then that activity is self referential.
+    ///
+    // TODO: determine if there is a better name for this field.
+    interaction_with: String,
+    /// Optional Call-to-action URL (either external or internal)
+    /// to route the user to the appropriate page to proceed with any
+    /// follow up details.
+    ///
+    // TODO: determine if there is a better name for this field
+    url: Option<String>,
+    /// Boolean option on whether this entry has been hidden
+    ///
+    /// This means that the UI should not show this entry,
+    /// but still shown a line item, allowing the user to
+    /// un-hide the entry, possibly using biometrics or
+    /// PIN for user control.
+    hidden: bool,
+    /// Fields that have been shared. This will be an empty
+    /// vector if there are no fields shared (i.e., when the
+    /// activity type is not `Shared`)
+    ///
+    /// Fields recorded by the presentation flows are formatted with
+    /// [field_path].
+    #[serde(
+        serialize_with = "ActivityLogEntry::serialize_fields",
+        deserialize_with = "ActivityLogEntry::deserialize_fields"
+    )]
+    fields: Vec<String>,
+    /// Position of the entry in the activity log hash chain, if it was
+    /// written while the chain was enabled.
+    chain_sequence: Option<u64>,
+    /// Hash of the previous entry of the chain.
+    previous_hash: Option<String>,
+    /// Hash of this entry, covering its link to the previous entry.
+    hash: Option<String>,
+}
+
+impl ActivityLogEntry {
+    pub fn serialize_fields<S: Serializer>(
+        fields: &[String],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let fields_string = fields.join(";");
+        serializer.serialize_str(&fields_string)
+    }
+
+    pub fn deserialize_fields<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let fields_string = String::deserialize(deserializer)?;
+        if fields_string.is_empty() {
+            Ok(Vec::new())
+        } else {
+            Ok(fields_string.split(';').map(|s| s.to_string()).collect())
+        }
+    }
+}
+
+#[uniffi::export]
+impl ActivityLogEntry {
+    #[uniffi::constructor]
+    pub fn new(
+        credential_id: Uuid,
+        r#type: ActivityLogEntryType,
+        description: String,
+        interaction_with: String,
+        fields: Option<Vec<String>>,
+        url: Option<String>,
+    ) -> Result<Self, ActivityLogError> {
+        let now = chrono::Utc::now();
+        let timestamp = now.timestamp() as u64;
+        let date = now.to_rfc3339();
+
+        let fields = fields.unwrap_or_default();
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            credential_id,
+            r#type,
+            timestamp,
+            date,
+            description,
+            interaction_with,
+            fields,
+            url,
+            hidden: false,
+            chain_sequence: None,
+            previous_hash: None,
+            hash: None,
+        })
+    }
+
+    #[uniffi::constructor]
+    fn from_json_bytes(bytes: Vec<u8>) -> Result<Self, ActivityLogError> {
+        serde_json::from_slice(&bytes)
+            .map_err(|e| ActivityLogError::ActivityLogEntryDeserialization(e.to_string()))
+    }
+
+    #[uniffi::constructor]
+    fn from_json_str(json_str: String) -> Result<Self, ActivityLogError> {
+        serde_json::from_str(&json_str)
+            .map_err(|e| ActivityLogError::ActivityLogEntryDeserialization(e.to_string()))
+    }
+
+    // Getter Methods
+
+    pub fn get_id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn get_credential_id(&self) -> Uuid {
+        self.credential_id
+    }
+
+    pub fn get_type(&self) -> ActivityLogEntryType {
+        self.r#type.clone()
+    }
+
+    pub fn get_date(&self) -> String {
+        self.date.clone()
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_description(&self) -> String {
+        self.description.clone()
+    }
+
+    pub fn get_interaction_with(&self) -> String {
+        self.interaction_with.clone()
+    }
+
+    /// The paths of the shared fields, as JSONPaths from the root of the
+    /// credential, e.g. `$.address.street_address`. The elements of an mdoc
+    /// are addressed by namespace, e.g. `$['org.iso.18013.5.1'].family_name`.
+    pub fn get_fields(&self) -> Vec<String> {
+        self.fields.clone()
+    }
+
+    pub fn get_url(&self) -> Option<String> {
+        self.url.clone()
+    }
+
+    pub fn get_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn get_chain_sequence(&self) -> Option<u64> {
+        self.chain_sequence
+    }
+
+    pub fn get_previous_hash(&self) -> Option<String> {
+        self.previous_hash.clone()
+    }
+
+    pub fn get_hash(&self) -> Option<String> {
+        self.hash.clone()
+    }
+
+    /// Serializes the activity log as a byte-encoded JSON string
+    fn to_json_bytes(&self) -> Result<Vec<u8>, ActivityLogError> {
+        serde_json::to_vec(self)
+            .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))
+    }
+
+    /// Serializes the activity log as a JSON string
+    fn to_json_string(&self) -> Result<String, ActivityLogError> {
+        serde_json::to_string(self)
+            .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))
+    }
+}
+
+impl ActivityLogEntry {
+    // Setter methods
+    pub(crate) fn set_hidden(&mut self, should_hide: bool) {
+        self.hidden = should_hide;
+    }
+
+    pub(crate) fn credential_id(&self) -> Uuid {
+        self.credential_id
+    }
+
+    pub(crate) fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Clear the shared fields, returning false if there were none.
+    pub(crate) fn clear_fields(&mut self) -> bool {
+        let had_fields = !self.fields.is_empty();
+        self.fields.clear();
+        had_fields
+    }
+
+    /// Detach the entry from the hash chain it was linked to, e.g. to link
+    /// it to the chain of another wallet.
+    pub(crate) fn unchain(&mut self) {
+        self.chain_sequence = None;
+        self.previous_hash = None;
+        self.hash = None;
+    }
+
+    pub(crate) fn credential_and_entry_id_to_key(credential_id: Uuid, entry_id: Uuid) -> Key {
+        Key(format!("{KEY_PREFIX}{credential_id}.{entry_id}"))
+    }
+
+    pub(crate) fn as_storage_key(&self) -> Key {
+        ActivityLogEntry::credential_and_entry_id_to_key(self.credential_id, self.id)
+    }
+}
+
+/// Activity Log has a 1:1 relationship with a credential
+/// and a credential ID is provided to the constructor, along
+/// with a reference to the storage manager interface to lookup
+/// the activity log details per the credential, returning this
+/// class with its accessor methods.
+#[derive(uniffi::Object)]
+pub struct ActivityLog {
+    pub(crate) credential_id: Uuid,
+    pub(crate) storage: Arc<ProfileStorage>,
+    pub(crate) cache: Mutex<HashMap<Uuid, ActivityLogEntry>>,
+}
+
+#[uniffi::export]
+impl ActivityLog {
+    /// Load activity log for the credential id.
+    ///
+    /// Requires a storage manager interface for looking up
+    /// the activity log details.
+    ///
+    /// Credential ID corresponds to the unique identifier of
+    /// the credential in the storage, which can be accessed via
+    /// the VDC collection or other storage mechanism.
+    ///
+    /// It is assumed the storage manager interface that is
+    /// passed in, is the same as the VDC collection storage manager.
+    /// Writes are only serialized with those of the collections and activity
+    /// logs opened over the same storage object, which a storage passed
+    /// through the FFI again is not: prefer
+    /// [VdcCollection::activity_log](crate::vdc_collection::VdcCollection::activity_log).
+    ///
+    // NOTE: That assumption may prove problematic, and we may wish to decouple
+    // the storage drivers further.
+    #[uniffi::constructor]
+    pub async fn load(
+        credential_id: Uuid,
+        storage: Arc<dyn StorageManagerInterface>,
+    ) -> Result<Self, ActivityLogError> {
+        Self::load_scoped(credential_id, Arc::new(ProfileStorage::new(storage, None))).await
+    }
+
+    /// Load activity log for the credential id, in the given profile.
+    ///
+    /// See [VdcCollection::with_profile](crate::vdc_collection::VdcCollection::with_profile).
+    #[uniffi::constructor]
+    pub async fn load_with_profile(
+        credential_id: Uuid,
+        storage: Arc<dyn StorageManagerInterface>,
+        profile_id: Uuid,
+    ) -> Result<Self, ActivityLogError> {
+        Self::load_scoped(
+            credential_id,
+            Arc::new(ProfileStorage::new(storage, Some(profile_id))),
+        )
+        .await
+    }
+
+    /// Adds and saved an activity log entry using the storage manager
+    /// interface provided.
+    pub async fn add(&self, entry: Arc<ActivityLogEntry>) -> Result<(), ActivityLogError> {
+        self.add_entries(vec![entry]).await
+    }
+
+    /// Adds and saves several activity log entries as a single atomic write,
+    /// using the storage manager interface provided.
+    ///
+    /// When the hash chain is enabled, the entries are linked to it.
+    pub async fn add_entries(
+        &self,
+        entries: Vec<Arc<ActivityLogEntry>>,
+    ) -> Result<(), ActivityLogError> {
+        if let Some(entry) = entries
+            .iter()
+            .find(|entry| entry.credential_id != self.credential_id)
+        {
+            return Err(ActivityLogError::InvalidCredentialId(
+                entry.credential_id,
+                self.credential_id,
+            ));
+        }
+
+        let entries = entries
+            .iter()
+            .map(|entry| entry.as_ref().to_owned())
+            .collect::<Vec<_>>();
+        let events = entries
+            .iter()
+            .map(VdcCollectionEvent::activity_logged)
+            .collect::<Vec<_>>();
+        {
+            let _head = chain::lock_chain_head(&self.storage).await;
+            let operations = entry_operations(self.storage.as_ref(), entries).await?;
+            self.write(operations).await?;
+        }
+
+        self.storage.notify(events);
+        Ok(())
+    }
+
+    pub async fn get(
+        &self,
+        entry_id: Uuid,
+    ) -> Result<Option<Arc<ActivityLogEntry>>, ActivityLogError> {
+        // Search cache first
+        {
+            let cache = self.cache.lock().await;
+            if let Some(entry) = cache.get(&entry_id) {
+                return Ok(Some(Arc::new(entry.to_owned())));
+            }
+        }
+
+        Ok(self.stored(entry_id).await?.map(Arc::new))
+    }
+
+    pub async fn set_hidden(
+        &self,
+        entry_id: Uuid,
+        should_hide: bool,
+    ) -> Result<Arc<ActivityLogEntry>, ActivityLogError> {
+        let _head = chain::lock_chain_head(&self.storage).await;
+        // Read from storage, as the cache may predate the chain.
+        let entry = self.stored(entry_id).await?;
+
+        match entry {
+            Some(mut new_entry) => {
+                new_entry.set_hidden(should_hide);
+
+                // Write the modified entry back, recording the edit in the
+                // hash chain. This also updates the cached entry.
+                let operations =
+                    edit_operations(self.storage.as_ref(), vec![new_entry.clone()]).await?;
+                self.write(operations).await?;
+
+                Ok(Arc::new(new_entry))
+            }
+            None => Err(ActivityLogError::NotFound(format!(
+                "Activity log entry for {entry_id} not found"
+            ))),
+        }
+    }
+
+    /// Remove an activity log entry given a specific entry ID.
+    pub async fn remove(&self, entry_id: Uuid) -> Result<(), ActivityLogError> {
+        let key = ActivityLogEntry::credential_and_entry_id_to_key(self.credential_id, entry_id);
+
+        let _head = chain::lock_chain_head(&self.storage).await;
+        let mut operations = match self.stored(entry_id).await? {
+            Some(entry) => purge_operations(self.storage.as_ref(), &[entry]).await?,
+            None => Vec::new(),
+        };
+        operations.push(StorageOperation::Remove { key });
+
+        apply_batch(self.storage.as_ref(), operations)
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?;
+
+        // Remove the entry from the cache
+        {
+            let mut cache = self.cache.lock().await;
+            cache.remove(&entry_id);
+        }
+
+        Ok(())
+    }
+
+    /// Remove all activity log entries belonging to the instantiated credential ID.
+    pub async fn remove_all(&self) -> Result<(), ActivityLogError> {
+        let entries = self.filter_entries(None).await?;
+
+        let _head = chain::lock_chain_head(&self.storage).await;
+        let mut operations = purge_operations(self.storage.as_ref(), &entries).await?;
+        operations.extend(
+            self.storage
+                .list()
+                .await
+                .map_err(|e| ActivityLogError::Storage(e.to_string()))?
+                .into_iter()
+                .filter(|key: &Key| {
+                    key.0
+                        .split_once(&format!("{KEY_PREFIX}{}", self.credential_id))
+                        .map(|(_, rest)| !rest.is_empty())
+                        .unwrap_or(false)
+                })
+                .map(|key| StorageOperation::Remove { key }),
+        );
+
+        apply_batch(self.storage.as_ref(), operations)
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?;
+
+        // Reset the cache
+        {
+            let mut cache = self.cache.lock().await;
+            cache.clear()
+        }
+
+        Ok(())
+    }
+
+    /// Returns a list of activity log entries matching the
+    /// `credential_id` corresponding to the activity log.
+    pub async fn entries(
+        &self,
+        filter: Option<ActivityLogFilterOptions>,
+    ) -> Result<Vec<Arc<ActivityLogEntry>>, ActivityLogError> {
+        // Return the cached entries if the filter option includes.
+        if let Some(true) = filter.as_ref().map(|f| f.use_cache) {
+            let cache = self.cache.lock().await;
+            return Ok(cache
+                .clone()
+                .values()
+                .filter(|entry| filter.as_ref().is_none_or(|opts| opts.filter(entry)))
+                .sorted_by(|a, b| Ord::cmp(&b.date, &a.date))
+                .take(
+                    filter
+                        .as_ref()
+                        .and_then(|f| f.max_items)
+                        .unwrap_or(u32::MAX) as usize,
+                )
+                .map(|entry| Arc::new(entry.to_owned()))
+                .collect());
+        }
+
+        let entries = self
+            .filter_entries(filter)
+            .await?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Returns the optionally filtered activity log entries list as a JSON encoded string for export use.
+    pub async fn export_entries(
+        &self,
+        filter: Option<ActivityLogFilterOptions>,
+    ) -> Result<String, ActivityLogError> {
+        let entries = self
+            .filter_entries(filter)
+            .await?
+            .into_iter()
+            .collect::<Vec<ActivityLogEntry>>();
+        serde_json::to_string(&entries)
+            .map_err(|e| ActivityLogError::ActivityLogEntrySerialization(e.to_string()))
+    }
+
+    /// Returns the optionally filtered activity log entries list as a signed
+    /// JWS, along with the head of the hash chain if enabled, and the records
+    /// of the edits made to the entries through the wallet.
+    ///
+    /// The JWS header carries the key identifier of the signer, so that the
+    /// recipient can verify the export with [verify_activity_log_export].
+    pub async fn export_signed(
+        &self,
+        filter: Option<ActivityLogFilterOptions>,
+        signer: Arc<dyn JwsSigner>,
+    ) -> Result<Jws, ActivityLogError> {
+        let entries = self.filter_entries(filter).await?;
+        let chain_head = chain::load_head(self.storage.as_ref()).await?;
+        let records = chain::stored_records(self.storage.as_ref())
+            .await?
+            .into_iter()
+            .filter(|record| record.change == chain::ChainChange::Edited)
+            .filter(|record| entries.iter().any(|entry| entry.id == record.entry_id))
+            .collect();
+
+        bundle::sign(self.credential_id, entries, chain_head, records, signer).await
+    }
+
+    /// Returns the optionally filtered activity log entries list as CSV encoded string for export use.
+    pub async fn export_entries_csv(
+        &self,
+        filter: Option<ActivityLogFilterOptions>,
+    ) -> Result<String, ActivityLogError> {
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(Vec::new());
+
+        wtr.write_record([
+            "Entry ID",
+            "Credential ID",
+            "Activity Type",
+            "Timestamp",
+            "Date",
+            "Description",
+            "Interaction With",
+            "URL",
+            "Hidden",
+            "Fields",
+            "Chain Sequence",
+            "Previous Hash",
+            "Hash",
+        ])
+        .map_err(|e| {
+            ActivityLogError::ActivityLogEntrySerialization(format!("Writing headers: {e}"))
+        })?;
+
+        for entry in self.filter_entries(filter).await?.into_iter() {
+            wtr.serialize(&entry).map_err(|e| {
+                ActivityLogError::ActivityLogEntrySerialization(format!("Writing entry: {e}",))
+            })?;
+        }
+
+        let bytes = wtr.into_inner().map_err(|e| {
+            ActivityLogError::ActivityLogEntrySerialization(format!("Getting as bytes: {e}",))
+        })?;
+
+        let data = String::from_utf8(bytes.to_owned()).map_err(|e| {
+            ActivityLogError::ActivityLogEntrySerialization(format!(
+                "Getting as String from bytes: {e}",
+            ))
+        })?;
+
+        Ok(data)
+    }
+
+    /// Enable the wallet-wide hash chain of the activity log entries.
+    ///
+    /// The stored entries of all credentials are linked to the chain, from
+    /// the oldest. Every entry written afterwards, for any credential, carries
+    /// the hash of the entry written before it. Does nothing if the chain is
+    /// already enabled.
+    pub async fn enable_chain(&self) -> Result<ActivityLogChainHead, ActivityLogError> {
+        let head = {
+            let _head = chain::lock_chain_head(&self.storage).await;
+            if let Some(head) = chain::load_head(self.storage.as_ref()).await? {
+                return Ok(head);
+            }
+
+            let entries = index::stored_entries(self.storage.as_ref()).await?;
+            let (head, operations) = chain::enable_operations(entries)?;
+            apply_batch(self.storage.as_ref(), operations)
+                .await
+                .map_err(|e| ActivityLogError::Storage(e.to_string()))?;
+
+            head
+        };
+
+        self.clear_cache().await?;
+        self.hydrate_cache().await?;
+
+        Ok(head)
+    }
+
+    /// Returns the head of the hash chain, or `None` if it is not enabled.
+    pub async fn chain_head(&self) -> Result<Option<ActivityLogChainHead>, ActivityLogError> {
+        chain::load_head(self.storage.as_ref()).await
+    }
+
+    /// Sign the current head of the hash chain, so that the entries written
+    /// so far cannot be edited or removed, other than through the wallet,
+    /// without it showing in
+    /// [VdcCollection::verify_activity_log_chain](crate::vdc_collection::VdcCollection::verify_activity_log_chain).
+    ///
+    /// Only P-256 signing keys can be verified.
+    pub async fn sign_chain_head(
+        &self,
+        signer: Arc<dyn SigningKey>,
+    ) -> Result<ActivityLogChainHead, ActivityLogError> {
+        let _head = chain::lock_chain_head(&self.storage).await;
+        let mut head = chain::load_head(self.storage.as_ref())
+            .await?
+            .ok_or(ActivityLogError::ChainNotEnabled)?;
+
+        head.checkpoint = Some(chain::checkpoint(&head, signer)?);
+        apply_batch(self.storage.as_ref(), vec![chain::head_operation(&head)?])
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?;
+
+        Ok(head)
+    }
+
+    /// Rebuild the wallet-wide index of the activity log entries, used by
+    /// [VdcCollection::query_activity_log](crate::vdc_collection::VdcCollection::query_activity_log),
+    /// from the stored entries.
+    ///
+    /// The index is updated along with every entry written or removed by the
+    /// wallet, so this is only needed if the storage was modified outside of
+    /// it.
+    pub async fn rebuild_index(&self) -> Result<(), ActivityLogError> {
+        {
+            let _head = chain::lock_chain_head(&self.storage).await;
+            ActivityLogIndex::rebuild(self.storage.as_ref()).await?;
+        }
+
+        self.clear_cache().await?;
+        self.hydrate_cache().await
+    }
+
+    /// hydrate the activity log cache. Sets the cache to the unfiltered
+    /// activity log entries associated with the credential. This method is
+    /// automatically called on [ActivityLog::load] method.
+    pub async fn hydrate_cache(&self) -> Result<(), ActivityLogError> {
+        let entries = self.filter_entries(None).await?;
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut cache = self.cache.lock().await;
+        *cache = entries.into_iter().map(|e| (e.id, e)).collect();
+
+        Ok(())
+    }
+
+    /// Clear the activity log cache.
+    pub async fn clear_cache(&self) -> Result<(), ActivityLogError> {
+        let mut cache = self.cache.lock().await;
+        cache.clear();
+
+        Ok(())
+    }
+}
+
+impl ActivityLog {
+    pub(crate) async fn load_scoped(
+        credential_id: Uuid,
+        storage: Arc<ProfileStorage>,
+    ) -> Result<Self, ActivityLogError> {
+        let log = Self {
+            credential_id,
+            storage,
+            cache: Mutex::new(HashMap::new()),
+        };
+
+        // Hydrate the cache of the activity log
+        log.hydrate_cache().await?;
+
+        Ok(log)
+    }
+
+    /// Read an entry of this credential from storage, bypassing the cache.
+    async fn stored(&self, entry_id: Uuid) -> Result<Option<ActivityLogEntry>, ActivityLogError> {
+        let key = ActivityLogEntry::credential_and_entry_id_to_key(self.credential_id, entry_id);
+
+        Ok(self
+            .storage
+            .get(key)
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?
+            .and_then(|value| value.try_into().ok()))
+    }
+
+    /// Apply the write operations of entries, and refresh the cached entries
+    /// of this credential.
+    async fn write(&self, operations: Vec<StorageOperation>) -> Result<(), ActivityLogError> {
+        let mut written = Vec::new();
+        for operation in &operations {
+            if let StorageOperation::Add { key, value } = operation {
+                if key.0.starts_with(KEY_PREFIX) {
+                    written.push(ActivityLogEntry::try_from(value.clone())?);
+                }
+            }
+        }
+
+        apply_batch(self.storage.as_ref(), operations)
+            .await
+            .map_err(|e| ActivityLogError::Storage(e.to_string()))?;
+
+        {
+            let mut cache = self.cache.lock().await;
+            for entry in written {
+                cache.insert(entry.id, entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a list of activity log entries matching the
+    /// `credential_id` corresponding to the activity log.
+    pub async fn filter_entries(
+        &self,
+        filter: Option<ActivityLogFilterOptions>,
+    ) -> Result<Vec<ActivityLogEntry>, ActivityLogError> {
+        let keys = ActivityLogIndex::load(self.storage.as_ref())
+            .await?
+            .credential_keys(self.credential_id);
+
+        log::info!("Found Keys for Activity Log in storage: {keys:?}");
+
+        if keys.is_empty() {
+            return Ok(Vec::with_capacity(0));
+        }
+
+        let entries = futures::stream::iter(keys)
+            .filter_map(|key| async move { self.storage.get(key).await.ok().flatten() })
+            .filter_map(|value| async move { ActivityLogEntry::try_from(value).ok() })
+            .collect::<Vec<ActivityLogEntry>>()
+            .await
+            .iter()
+            .filter(|entry| entry.credential_id == self.credential_id)
+            .filter(|entry| filter.as_ref().is_none_or(|opts| opts.filter(entry)))
+            .map(|entry| entry.to_owned())
+            // Sort by the date so the most recent activity is always first
+            .sorted_by(|a, b| Ord::cmp(&b.date, &a.date))
+            .take(
+                filter
+                    .as_ref()
+                    .and_then(|f| f.max_items)
+                    .unwrap_or(u32::MAX) as usize,
+            )
+            .collect::<Vec<ActivityLogEntry>>();
+
+        Ok(entries)
+    }
+}
+
+/// Returns a page of the activity log entries of all credentials.
+///
+/// See [VdcCollection::query_activity_log](crate::vdc_collection::VdcCollection::query_activity_log).
+pub(crate) async fn query_entries(
+    storage: &ProfileStorage,
+    query: ActivityLogQuery,
+) -> Result<ActivityLogPage, ActivityLogError> {
+    let index = ActivityLogIndex::load(storage).await?;
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(index::decode_cursor)
+        .transpose()?;
+
+    let mut positions = index
+        .entries
+        .iter()
+        .filter(|(_, entry)| query.matches(entry))
+        .map(|(id, entry)| ((entry.timestamp, *id), entry.credential_id))
+        .collect::<Vec<_>>();
+    positions.sort_by_key(|(position, _)| *position);
+    if query.sort == ActivityLogSort::NewestFirst {
+        positions.reverse();
+    }
+
+    let after_cursor = |position: &index::Position| match (cursor, query.sort) {
+        (None, _) => true,
+        (Some(cursor), ActivityLogSort::OldestFirst) => *position > cursor,
+        (Some(cursor), ActivityLogSort::NewestFirst) => *position < cursor,
+    };
+    let page_size = query.page_size.unwrap_or(u32::MAX) as usize;
+    let mut page = positions
+        .into_iter()
+        .filter(|(position, _)| after_cursor(position))
+        .take(page_size.saturating_add(1))
+        .collect::<Vec<_>>();
+
+    let next_cursor = if page.len() > page_size {
+        page.truncate(page_size);
+        page.last()
+            .map(|(position, _)| index::encode_cursor(*position))
+    } else {
+        None
+    };
+
+    let mut entries = Vec::with_capacity(page.len());
+    for ((_, entry_id), credential_id) in page {
+        // Entries removed since the index was loaded are skipped.
+        if let Some(entry) = stored_entry(storage, credential_id, entry_id).await? {
+            entries.push(Arc::new(entry));
+        }
+    }
+
+    Ok(ActivityLogPage {
+        entries,
+        next_cursor,
+    })
+}
+
+/// Verify the hash chain of the entries of all credentials.
+///
+/// See [VdcCollection::verify_activity_log_chain](crate::vdc_collection::VdcCollection::verify_activity_log_chain).
+pub(crate) async fn verify_chain(
+    storage: &ProfileStorage,
+    public_jwk: String,
+) -> Result<ActivityLogChainReport, ActivityLogError> {
+    let head = chain::load_head(storage)
+        .await?
+        .ok_or(ActivityLogError::ChainNotEnabled)?;
+
+    let entries = index::stored_entries(storage).await?;
+    let records = chain::stored_records(storage).await?;
+
+    chain::verify(&head, &entries, &records, &public_jwk)
+}
+
+/// Aggregate the attributes disclosed in the `Shared` entries of all
+/// credentials.
+///
+/// See [VdcCollection::activity_log_disclosure_summary](crate::vdc_collection::VdcCollection::activity_log_disclosure_summary).
+pub(crate) async fn disclosure_summary(
+    storage: &ProfileStorage,
+    filter: ActivityLogQuery,
+) -> Result<DisclosureSummary, ActivityLogError> {
+    let index = ActivityLogIndex::load(storage).await?;
+
+    let mut entries = Vec::new();
+    for (entry_id, indexed) in index.entries.iter().filter(|(_, e)| filter.matches(e)) {
+        // Entries removed since the index was loaded are skipped.
+        if let Some(entry) = stored_entry(storage, indexed.credential_id, *entry_id).await? {
+            entries.push(entry);
+        }
+    }
+
+    Ok(analytics::summarize(&entries))
+}
+
+async fn stored_entry(
+    storage: &ProfileStorage,
+    credential_id: Uuid,
+    entry_id: Uuid,
+) -> Result<Option<ActivityLogEntry>, ActivityLogError> {
+    let key = ActivityLogEntry::credential_and_entry_id_to_key(credential_id, entry_id);
+
+    Ok(storage
+        .get(key)
+        .await
+        .map_err(|e| ActivityLogError::Storage(e.to_string()))?
+        .and_then(|value| ActivityLogEntry::try_from(value).ok()))
+}
+
+/// The storage operations writing new entries, linked to the hash chain when
+/// it is enabled, and adding them to the index.
+///
+/// Entries that are already part of the chain are written unchanged. The
+/// caller must hold [lock_chain_head] until the operations are written.
+pub(crate) async fn entry_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: Vec<ActivityLogEntry>,
+) -> Result<Vec<StorageOperation>, ActivityLogError> {
+    let mut operations = vec![ActivityLogIndex::update_operation(storage, &entries, &[]).await?];
+    operations.extend(chain::entry_operations(storage, entries).await?);
+
+    Ok(operations)
+}
+
+/// The storage operations writing back edited entries, and recording the
+/// edits in the hash chain when it is enabled.
+///
+/// The caller must hold [lock_chain_head] until the operations are written.
+pub(crate) async fn edit_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: Vec<ActivityLogEntry>,
+) -> Result<Vec<StorageOperation>, ActivityLogError> {
+    let mut operations = chain::change_operations(storage, ChainChange::Edited, &entries).await?;
+    for entry in &entries {
+        operations.push(StorageOperation::Add {
+            key: entry.into(),
+            value: entry.try_into()?,
+        });
+    }
+
+    Ok(operations)
+}
+
+/// The storage operations recording the removal of entries: in the hash
+/// chain, when it is enabled, and from the index.
+///
+/// The caller must hold [lock_chain_head] until the operations are written,
+/// along with the removal of the entries.
+pub(crate) async fn purge_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: &[ActivityLogEntry],
+) -> Result<Vec<StorageOperation>, ActivityLogError> {
+    removal_operations(storage, ChainChange::Removed, entries).await
+}
+
+/// The storage operations recording that entries were moved to another
+/// profile: in the hash chain, when it is enabled, and from the index. In the
+/// other profile, they are written as new entries.
+///
+/// The caller must hold [lock_chain_head] until the operations are written,
+/// along with the removal of the entries.
+pub(crate) async fn move_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: &[ActivityLogEntry],
+) -> Result<Vec<StorageOperation>, ActivityLogError> {
+    removal_operations(storage, ChainChange::Moved, entries).await
+}
+
+async fn removal_operations(
+    storage: &dyn StorageManagerInterface,
+    change: ChainChange,
+    entries: &[ActivityLogEntry],
+) -> Result<Vec<StorageOperation>, ActivityLogError> {
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut operations = chain::change_operations(storage, change, entries).await?;
+    operations.push(ActivityLogIndex::update_operation(storage, &[], entries).await?);
+
+    Ok(operations)
+}
+
+/// Format the path of a shared field from its components, as returned by
+/// [ActivityLogEntry::get_fields]. Numeric components are array indexes.
+pub(crate) fn field_path<S: AsRef<str>>(components: impl IntoIterator<Item = S>) -> String {
+    let mut path = String::from("$");
+    for component in components {
+        let component = component.as_ref();
+        let is_identifier = component
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && component
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if is_identifier {
+            path.push('.');
+            path.push_str(component);
+        } else if component.parse::<usize>().is_ok() {
+            path.push_str(&format!("[{component}]"));
+        } else {
+            let escaped = component.replace('\\', "\\\\").replace('\'', "\\'");
+            path.push_str(&format!("['{escaped}']"));
+        }
+    }
+    path
+}
+
+impl From<&ActivityLogEntry> for Key {
+    fn from(entry: &ActivityLogEntry) -> Self {
+        entry.as_storage_key()
+    }
+}
+
+impl TryFrom<&ActivityLogEntry> for Value {
+    type Error = ActivityLogError;
+
+    fn try_from(entry: &ActivityLogEntry) -> Result<Self, Self::Error> {
+        entry.to_json_bytes().map(Value)
+    }
+}
+
+impl TryFrom<Value> for ActivityLogEntry {
+    type Error = ActivityLogError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Self::from_json_bytes(value.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::storage_manager::{
+        test::{DummyStorage, NamespacedDummyStorage},
+        StorageManagerError,
+    };
+    use base64::Engine;
+
+    use super::*;
+
+    /// Helper to create an ActivityLogEntry with explicit control over
+    /// timestamp and interaction_with for deterministic filter testing.
+    fn make_entry(
+        credential_id: Uuid,
+        entry_type: ActivityLogEntryType,
+        description: &str,
+        interaction_with: &str,
+        timestamp: u64,
+        fields: Vec<String>,
+    ) -> ActivityLogEntry {
+        ActivityLogEntry {
+            id: Uuid::new_v4(),
+            credential_id,
+            r#type: entry_type,
+            timestamp,
+            date: chrono::DateTime::from_timestamp(timestamp as i64, 0)
+                .unwrap()
+                .to_rfc3339(),
+            description: description.to_string(),
+            interaction_with: interaction_with.to_string(),
+            fields,
+            url: None,
+            hidden: false,
+            chain_sequence: None,
+            previous_hash: None,
+            hash: None,
+        }
+    }
+
+    /// Runs the full suite of activity log filtering assertions against
+    /// the provided storage backend so we can reuse the logic for both
+    /// the plain `DummyStorage` and the `NamespacedDummyStorage`.
+    async fn run_activity_log_filter_test(
+        storage: Arc<dyn StorageManagerInterface>,
+    ) -> Result<(), ActivityLogError> {
+        let credential_id = Uuid::new_v4();
+        let activity_log = ActivityLog::load(credential_id, storage).await?;
+
+        // ── seed data ────────────────────────────────────────────────
+        // Timestamps spaced 1 day apart starting at 2024-01-01 00:00 UTC
+        let day = 86_400u64; // seconds in a day
+        let base_ts: u64 = 1_704_067_200; // 2024-01-01T00:00:00Z
+
+        let entry_a = make_entry(
+            credential_id,
+            ActivityLogEntryType::Issued,
+            "Credential issued by ACME",
+            "ACME Corp",
+            base_ts,
+            vec!["Name".into()],
+        );
+        let entry_b = make_entry(
+            credential_id,
+            ActivityLogEntryType::Shared,
+            "Credential shared with Verifier",
+            "VerifyMe Inc",
+            base_ts + day,
+            vec!["Name".into(), "DOB".into()],
+        );
+        let entry_c = make_entry(
+            credential_id,
+            ActivityLogEntryType::Request,
+            "Credential requested by Another ACME",
+            "Another ACME Authority",
+            base_ts + 2 * day,
+            vec![],
+        );
+        let entry_d = make_entry(
+            credential_id,
+            ActivityLogEntryType::Shared,
+            "Credential shared again",
+            "VerifyMe Inc",
+            base_ts + 3 * day,
+            vec!["Age".into()],
+        );
+        let entry_e = make_entry(
+            credential_id,
+            ActivityLogEntryType::Deleted,
+            "Credential deleted",
+            "Self",
+            base_ts + 4 * day,
+            vec![],
+        );
+
+        // Save IDs before moving entries into Arcs
+        let id_a = entry_a.id;
+        let id_b = entry_b.id;
+        let id_c = entry_c.id;
+        let id_d = entry_d.id;
+        let id_e = entry_e.id;
+
+        for entry in [&entry_a, &entry_b, &entry_c, &entry_d, &entry_e] {
+            activity_log.add(Arc::new(entry.clone())).await?;
+        }
+
+        // Sanity check – all five entries present without filters
+        let all = activity_log.entries(None).await?;
+        assert_eq!(all.len(), 5, "Expected all 5 entries without any filter");
+
+        // ── 1. Filter by `interacted_with` ───────────────────────────
+
+        // Exact actor name – should match entries B and D
+        let filter_verify = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: None,
+            interacted_with: Some("VerifyMe Inc".into()),
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_verify).await?;
+        assert_eq!(
+            results.len(),
+            2,
+            "interacted_with 'VerifyMe Inc' should match 2 entries"
+        );
+        let result_ids: Vec<Uuid> = results.iter().map(|e| e.id).collect();
+        assert!(result_ids.contains(&id_b));
+        assert!(result_ids.contains(&id_d));
+
+        // Substring match – "ACME" should match both "ACME Corp" (A)
+        // and "Another ACME Authority" (C)
+        let filter_acme = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: None,
+            interacted_with: Some("ACME".into()),
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_acme).await?;
+        assert_eq!(
+            results.len(),
+            2,
+            "interacted_with 'ACME' (substring) should match entries A and C"
+        );
+        let result_ids: Vec<Uuid> = results.iter().map(|e| e.id).collect();
+        assert!(result_ids.contains(&id_a));
+        assert!(result_ids.contains(&id_c));
+
+        // No match – actor that doesn't exist
+        let filter_none = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: None,
+            interacted_with: Some("NonExistent Org".into()),
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_none).await?;
+        assert_eq!(
+            results.len(),
+            0,
+            "interacted_with 'NonExistent Org' should match nothing"
+        );
+
+        // ── 2. Filter by date range ──────────────────────────────────
+
+        // from_date only – entries from day 2 onward (C, D, E)
+        let filter_from = Some(ActivityLogFilterOptions {
+            from_date: Some(base_ts + 2 * day),
+            to_date: None,
+            r#type: None,
+            interacted_with: None,
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_from).await?;
+        assert_eq!(
+            results.len(),
+            3,
+            "from_date at day 2 should return 3 entries (C, D, E)"
+        );
+        let result_ids: Vec<Uuid> = results.iter().map(|e| e.id).collect();
+        assert!(result_ids.contains(&id_c));
+        assert!(result_ids.contains(&id_d));
+        assert!(result_ids.contains(&id_e));
+
+        // to_date only – entries up to day 1 (A, B)
+        let filter_to = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: Some(base_ts + day),
+            r#type: None,
+            interacted_with: None,
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_to).await?;
+        assert_eq!(
+            results.len(),
+            2,
+            "to_date at day 1 should return 2 entries (A, B)"
+        );
+        let result_ids: Vec<Uuid> = results.iter().map(|e| e.id).collect();
+        assert!(result_ids.contains(&id_a));
+        assert!(result_ids.contains(&id_b));
+
+        // Bounded range – from day 1 to day 3 inclusive (B, C, D)
+        let filter_range = Some(ActivityLogFilterOptions {
+            from_date: Some(base_ts + day),
+            to_date: Some(base_ts + 3 * day),
+            r#type: None,
+            interacted_with: None,
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_range).await?;
+        assert_eq!(
+            results.len(),
+            3,
+            "date range day 1..=day 3 should return 3 entries (B, C, D)"
+        );
+        let result_ids: Vec<Uuid> = results.iter().map(|e| e.id).collect();
+        assert!(result_ids.contains(&id_b));
+        assert!(result_ids.contains(&id_c));
+        assert!(result_ids.contains(&id_d));
+
+        // Empty range – from_date after all entries
+        let filter_empty_range = Some(ActivityLogFilterOptions {
+            from_date: Some(base_ts + 10 * day),
+            to_date: None,
+            r#type: None,
+            interacted_with: None,
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_empty_range).await?;
+        assert_eq!(
+            results.len(),
+            0,
+            "from_date far in the future should return 0 entries"
+        );
+
+        // ── 3. Filter by type ────────────────────────────────────────
+
+        let filter_shared = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: Some(ActivityLogEntryType::Shared),
+            interacted_with: None,
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_shared).await?;
+        assert_eq!(
+            results.len(),
+            2,
+            "type=Shared should return entries B and D"
+        );
+        let result_ids: Vec<Uuid> = results.iter().map(|e| e.id).collect();
+        assert!(result_ids.contains(&id_b));
+        assert!(result_ids.contains(&id_d));
+
+        let filter_deleted = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: Some(ActivityLogEntryType::Deleted),
+            interacted_with: None,
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_deleted).await?;
+        assert_eq!(results.len(), 1, "type=Deleted should return entry E");
+        assert_eq!(results[0].id, id_e);
+
+        // ── 4. Combined filters ──────────────────────────────────────
+
+        // type=Shared AND interacted_with="VerifyMe Inc" → B, D
+        let filter_shared_verify = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: Some(ActivityLogEntryType::Shared),
+            interacted_with: Some("VerifyMe Inc".into()),
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_shared_verify).await?;
+        assert_eq!(
+            results.len(),
+            2,
+            "type=Shared + actor=VerifyMe Inc should return B, D"
+        );
+
+        // type=Shared AND date range day 0..=day 1 → only B
+        let filter_shared_early = Some(ActivityLogFilterOptions {
+            from_date: Some(base_ts),
+            to_date: Some(base_ts + day),
+            r#type: Some(ActivityLogEntryType::Shared),
+            interacted_with: None,
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_shared_early).await?;
+        assert_eq!(
+            results.len(),
+            1,
+            "type=Shared + date range day 0..=day 1 should return only B"
+        );
+        assert_eq!(results[0].id, id_b);
+
+        // interacted_with="ACME" AND date range day 0..=day 0 → only A
+        let filter_acme_day0 = Some(ActivityLogFilterOptions {
+            from_date: Some(base_ts),
+            to_date: Some(base_ts),
+            r#type: None,
+            interacted_with: Some("ACME".into()),
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_acme_day0).await?;
+        assert_eq!(
+            results.len(),
+            1,
+            "actor=ACME + date=day 0 only should return A"
+        );
+        assert_eq!(results[0].id, id_a);
+
+        // All filters combined: type=Shared, actor=VerifyMe, day 2..=day 4 → only D
+        let filter_all = Some(ActivityLogFilterOptions {
+            from_date: Some(base_ts + 2 * day),
+            to_date: Some(base_ts + 4 * day),
+            r#type: Some(ActivityLogEntryType::Shared),
+            interacted_with: Some("VerifyMe".into()),
+            max_items: None,
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_all).await?;
+        assert_eq!(
+            results.len(),
+            1,
+            "type=Shared + actor=VerifyMe + day 2..=4 should return only D"
+        );
+        assert_eq!(results[0].id, id_d);
+
+        // ── 5. max_items ─────────────────────────────────────────────
+
+        let filter_max2 = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: None,
+            interacted_with: None,
+            max_items: Some(2),
+            use_cache: false,
+        });
+        let results = activity_log.entries(filter_max2).await?;
+        assert_eq!(
+            results.len(),
+            2,
+            "max_items=2 should return exactly 2 entries"
+        );
+
+        // ── 6. use_cache path returns same filtered results ──────────
+
+        // Hydrate the cache so the cached path has data
+        activity_log.hydrate_cache().await?;
+
+        let filter_cached_verify = Some(ActivityLogFilterOptions {
+            from_date: None,
+            to_date: None,
+            r#type: None,
+            interacted_with: Some("VerifyMe Inc".into()),
+            max_items: None,
+            use_cache: true,
+        });
+        let cached_results = activity_log.entries(filter_cached_verify).await?;
+        assert_eq!(
+            cached_results.len(),
+            2,
+            "Cached interacted_with 'VerifyMe Inc' should also return 2 entries"
+        );
+
+        let filter_cached_range = Some(ActivityLogFilterOptions {
+            from_date: Some(base_ts + day),
+            to_date: Some(base_ts + 3 * day),
+            r#type: None,
+            interacted_with: None,
+            max_items: None,
+            use_cache: true,
+        });
+        let cached_results = activity_log.entries(filter_cached_range).await?;
+        assert_eq!(
+            cached_results.len(),
+            3,
+            "Cached date range day 1..=day 3 should also return 3 entries (B, C, D)"
+        );
+
+        Ok(())
+    }
+
+    async fn run_activity_log_test(
+        storage: Arc<dyn StorageManagerInterface>,
+    ) -> Result<(), ActivityLogError> {
+        let credential_id = Uuid::new_v4();
+
+        // Load activity Log
+        let activity_log = ActivityLog::load(credential_id, storage).await?;
+
+        assert_eq!(
+            activity_log.entries(None).await?.len(),
+            0,
+            "Storage Activity log should be empty"
+        );
+
+        let entry = Arc::new(ActivityLogEntry::new(
+            credential_id,
+            ActivityLogEntryType::Request,
+            "requesting new credential issuance".into(),
+            "ISSUING AUTHORITY".into(),
+            Some(Vec::from(&["Name".into(), "Age".into()])),
+            Some("www.example.com".into()),
+        )?);
+
+        activity_log.add(entry.clone()).await?;
+
+        assert_eq!(
+            activity_log.entries(None).await?.len(),
+            1,
+            "Storage Activity log should contain an entry"
+        );
+
+        assert_eq!(entry.hidden, false, "Expect entry to NOT be hidden");
+
+        let entry = activity_log.set_hidden(entry.get_id(), true).await?;
+
+        assert_eq!(entry.hidden, true, "Expect entry to be hidden");
+
+        let csv = activity_log.export_entries_csv(None).await?;
+
+        println!("csv: {csv:?}");
+
+        activity_log.remove(entry.get_id()).await?;
+
+        assert_eq!(
+            activity_log.entries(None).await?.len(),
+            0,
+            "Storage Activity log should be empty"
+        );
+
+        Ok(())
+    }
+
+    /// Runs the wallet-wide query assertions against the provided storage
+    /// backend.
+    async fn run_activity_log_all_test(
+        storage: Arc<dyn StorageManagerInterface>,
+    ) -> Result<(), ActivityLogError> {
+        let day = 86_400u64;
+        let base_ts: u64 = 1_704_067_200; // 2024-01-01T00:00:00Z
+
+        let license = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+        let passport = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+
+        let entries = [
+            (
+                &license,
+                ActivityLogEntryType::Issued,
+                "Issued by DMV",
+                "DMV",
+            ),
+            (
+                &license,
+                ActivityLogEntryType::Shared,
+                "Shared with Bar",
+                "The Bar",
+            ),
+            (
+                &passport,
+                ActivityLogEntryType::Shared,
+                "Shared at border",
+                "Border Control",
+            ),
+            (
+                &license,
+                ActivityLogEntryType::Shared,
+                "Shared with car rental",
+                "Rent-A-Car",
+            ),
+        ];
+        let mut ids = Vec::new();
+        for (i, (log, entry_type, description, interaction_with)) in entries.into_iter().enumerate()
+        {
+            let entry = make_entry(
+                log.credential_id,
+                entry_type,
+                description,
+                interaction_with,
+                base_ts + i as u64 * day,
+                vec![],
+            );
+            ids.push(entry.id);
+            log.add(Arc::new(entry)).await?;
+        }
+
+        let ids_of = |page: &ActivityLogPage| page.entries.iter().map(|e| e.id).collect::<Vec<_>>();
+
+        // All shares in one timeline, newest first, two per page.
+        let query = ActivityLogQuery {
+            r#type: Some(ActivityLogEntryType::Shared),
+            page_size: Some(2),
+            ..Default::default()
+        };
+        let first = query_entries(&license.storage, query.clone()).await?;
+        assert_eq!(ids_of(&first), vec![ids[3], ids[2]]);
+        assert!(first.next_cursor.is_some());
+
+        let second = query_entries(
+            &license.storage,
+            ActivityLogQuery {
+                cursor: first.next_cursor,
+                ..query.clone()
+            },
+        )
+        .await?;
+        assert_eq!(ids_of(&second), vec![ids[1]]);
+        assert!(second.next_cursor.is_none());
+
+        let oldest = query_entries(
+            &passport.storage,
+            ActivityLogQuery {
+                sort: ActivityLogSort::OldestFirst,
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(ids_of(&oldest), ids);
+
+        let search = query_entries(
+            &license.storage,
+            ActivityLogQuery {
+                search: Some("SHARED border".into()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(ids_of(&search), vec![ids[2]]);
+
+        let by_credential = query_entries(
+            &license.storage,
+            ActivityLogQuery {
+                credential_ids: vec![passport.credential_id],
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(ids_of(&by_credential), vec![ids[2]]);
+
+        // Removed entries are dropped from the index.
+        passport.remove_all().await?;
+        assert_eq!(
+            query_entries(&license.storage, ActivityLogQuery::default())
+                .await?
+                .entries
+                .len(),
+            3
+        );
+
+        assert!(matches!(
+            query_entries(
+                &license.storage,
+                ActivityLogQuery {
+                    cursor: Some("not a cursor".into()),
+                    ..Default::default()
+                }
+            )
+            .await,
+            Err(ActivityLogError::InvalidCursor(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_activity_log_rebuild_index() -> Result<(), ActivityLogError> {
+        let storage = Arc::new(DummyStorage::default());
+        let log = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+        let ids_of = |page: ActivityLogPage| page.entries.iter().map(|e| e.id).collect::<Vec<_>>();
+        let entry = |description: &str, timestamp| {
+            make_entry(
+                log.credential_id,
+                ActivityLogEntryType::Shared,
+                description,
+                "The Bar",
+                timestamp,
+                vec![],
+            )
+        };
+
+        let added = entry("Shared with Bar", 1_700_000_000);
+        log.add(Arc::new(added.clone())).await?;
+
+        // Entries written behind the wallet's back are only indexed on rebuild.
+        let external = entry("Shared again with Bar", 1_700_000_100);
+        storage
+            .add(external.as_storage_key(), (&external).try_into()?)
+            .await
+            .unwrap();
+        assert_eq!(
+            ids_of(query_entries(&log.storage, ActivityLogQuery::default()).await?),
+            vec![added.id]
+        );
+
+        log.rebuild_index().await?;
+        assert_eq!(
+            ids_of(query_entries(&log.storage, ActivityLogQuery::default()).await?),
+            vec![external.id, added.id]
+        );
+
+        // A missing index is rebuilt from the stored entries.
+        storage.remove(Key(index::INDEX_KEY.into())).await.unwrap();
+        assert_eq!(
+            ids_of(query_entries(&log.storage, ActivityLogQuery::default()).await?),
+            vec![external.id, added.id]
+        );
+
+        log.remove(added.id).await?;
+        assert_eq!(
+            ids_of(query_entries(&log.storage, ActivityLogQuery::default()).await?),
+            vec![external.id]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_activity_log_chain() -> Result<(), ActivityLogError> {
+        use crate::crypto::{KeyAlias, KeyStore, RustTestKeyManager};
+
+        let storage = Arc::new(DummyStorage::default());
+        let license = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+        let passport = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+
+        let key_manager = RustTestKeyManager::default();
+        for alias in ["activity_log", "other"] {
+            key_manager
+                .generate_p256_signing_key(KeyAlias(alias.into()))
+                .await
+                .unwrap();
+        }
+        let signer = key_manager
+            .get_signing_key(KeyAlias("activity_log".into()))
+            .unwrap();
+        let public_jwk = signer.jwk().unwrap();
+
+        assert!(matches!(
+            verify_chain(&license.storage, public_jwk.clone()).await,
+            Err(ActivityLogError::ChainNotEnabled)
+        ));
+
+        // Entries written before the chain is enabled are linked to it first.
+        let base_ts: u64 = 1_704_067_200; // 2024-01-01T00:00:00Z
+        let earlier = make_entry(
+            license.credential_id,
+            ActivityLogEntryType::Issued,
+            "Issued by DMV",
+            "DMV",
+            base_ts,
+            vec![],
+        );
+        license.add(Arc::new(earlier.clone())).await?;
+
+        license.enable_chain().await?;
+
+        let new_entry = |log: &ActivityLog, interaction_with: &str| {
+            Arc::new(
+                ActivityLogEntry::new(
+                    log.credential_id,
+                    ActivityLogEntryType::Shared,
+                    format!("Shared with {interaction_with}"),
+                    interaction_with.into(),
+                    Some(vec!["$.family_name".into()]),
+                    None,
+                )
+                .unwrap(),
+            )
+        };
+        license.add(new_entry(&license, "The Bar")).await?;
+        passport.add(new_entry(&passport, "Border Control")).await?;
+        license.add(new_entry(&license, "Rent-A-Car")).await?;
+
+        let chained = query_entries(
+            &license.storage,
+            ActivityLogQuery {
+                sort: ActivityLogSort::OldestFirst,
+                ..Default::default()
+            },
+        )
+        .await?
+        .entries
+        .into_iter()
+        .filter(|e| e.chain_sequence.is_some())
+        .sorted_by_key(|e| e.chain_sequence)
+        .collect::<Vec<_>>();
+        assert_eq!(chained.len(), 4);
+        assert_eq!(chained[0].id, earlier.id);
+        assert_eq!(
+            chained[0].previous_hash.as_deref(),
+            Some(chain::GENESIS_HASH)
+        );
+        assert_eq!(chained[1].previous_hash, chained[0].hash);
+        assert_eq!(chained[2].previous_hash, chained[1].hash);
+        assert_eq!(chained[3].previous_hash, chained[2].hash);
+
+        // Editing or removing entries through the wallet is recorded in the
+        // chain.
+        license.set_hidden(chained[0].id, true).await?;
+        passport.remove(chained[2].id).await?;
+        assert_eq!(license.chain_head().await?.unwrap().length, 6);
+
+        // The chain cannot be verified until it is signed.
+        assert!(matches!(
+            verify_chain(&license.storage, public_jwk.clone()).await,
+            Err(ActivityLogError::ChainNotSigned)
+        ));
+
+        let head = license.sign_chain_head(signer.clone()).await?;
+        assert_eq!(head.length, 6);
+
+        let report = verify_chain(&passport.storage, public_jwk.clone()).await?;
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!(report.signed_length, 6);
+        assert_eq!(report.signed_by, public_jwk.clone());
+
+        // A checkpoint signed with another key, e.g. after rewriting the
+        // chain, is rejected even though it is consistent.
+        let other = key_manager
+            .get_signing_key(KeyAlias("other".into()))
+            .unwrap();
+        license.sign_chain_head(other).await?;
+        let report = verify_chain(&license.storage, public_jwk.clone()).await?;
+        assert_eq!(
+            report.issues,
+            vec![ActivityLogChainIssue::InvalidCheckpoint {
+                reason: "the checkpoint is not signed by the expected key".into()
+            }]
+        );
+        license.sign_chain_head(signer.clone()).await?;
+
+        let csv = license.export_entries_csv(None).await?;
+        assert!(csv.contains(chained[3].hash.as_deref().unwrap()));
+
+        // Hide an entry behind the log's back.
+        let mut edited = (*chained[1]).clone();
+        edited.hidden = true;
+        storage
+            .add((&edited).into(), (&edited).try_into()?)
+            .await
+            .unwrap();
+
+        // Remove an entry behind the log's back.
+        storage.remove(chained[3].as_ref().into()).await.unwrap();
+
+        // Insert an entry behind the log's back.
+        let inserted = make_entry(
+            license.credential_id,
+            ActivityLogEntryType::Shared,
+            "Shared with Bar",
+            "The Bar",
+            base_ts,
+            vec![],
+        );
+        storage
+            .add((&inserted).into(), (&inserted).try_into()?)
+            .await
+            .unwrap();
+
+        let report = verify_chain(&license.storage, public_jwk).await?;
+        assert!(!report.valid);
+        assert_eq!(
+            report.issues,
+            vec![
+                ActivityLogChainIssue::Unchained {
+                    entry_id: inserted.id
+                },
+                ActivityLogChainIssue::Modified {
+                    entry_id: edited.id,
+                    sequence: 1
+                },
+                ActivityLogChainIssue::Missing { sequence: 3 },
+            ]
+        );
+
+        Ok(())
+    }
+
+    /// Storage yielding to the other tasks before every access, so that
+    /// concurrent writes interleave.
+    #[derive(Default)]
+    struct YieldingStorage(DummyStorage);
+
+    #[async_trait::async_trait]
+    impl StorageManagerInterface for YieldingStorage {
+        async fn add(&self, key: Key, value: Value) -> Result<(), StorageManagerError> {
+            tokio::task::yield_now().await;
+            self.0.add(key, value).await
+        }
+
+        async fn get(&self, key: Key) -> Result<Option<Value>, StorageManagerError> {
+            tokio::task::yield_now().await;
+            self.0.get(key).await
+        }
+
+        async fn list(&self) -> Result<Vec<Key>, StorageManagerError> {
+            tokio::task::yield_now().await;
+            self.0.list().await
+        }
+
+        async fn remove(&self, key: Key) -> Result<(), StorageManagerError> {
+            tokio::task::yield_now().await;
+            self.0.remove(key).await
+        }
+
+        async fn batch(
+            &self,
+            operations: Vec<StorageOperation>,
+        ) -> Result<(), StorageManagerError> {
+            tokio::task::yield_now().await;
+            self.0.batch(operations).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_activity_log_chain_concurrent_add() -> Result<(), ActivityLogError> {
+        use crate::crypto::{KeyAlias, KeyStore, RustTestKeyManager};
+
+        let storage = Arc::new(YieldingStorage::default());
+        let license = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+        let passport = ActivityLog::load(Uuid::new_v4(), storage).await?;
+        license.enable_chain().await?;
+
+        let entry = |credential_id| {
+            Arc::new(
+                ActivityLogEntry::new(
+                    credential_id,
+                    ActivityLogEntryType::Shared,
+                    "Shared with Bar".into(),
+                    "The Bar".into(),
+                    None,
+                    None,
+                )
+                .unwrap(),
+            )
+        };
+        let results = futures::future::join_all((0..4).flat_map(|_| {
+            [
+                license.add(entry(license.credential_id)),
+                passport.add(entry(passport.credential_id)),
+            ]
+        }))
+        .await;
+        assert!(results.iter().all(Result::is_ok), "{results:?}");
+
+        let head = license.chain_head().await?.unwrap();
+        assert_eq!(head.length, 8);
+
+        let key_manager = RustTestKeyManager::default();
+        key_manager
+            .generate_p256_signing_key(KeyAlias("activity_log".into()))
+            .await
+            .unwrap();
+        let signer = key_manager
+            .get_signing_key(KeyAlias("activity_log".into()))
+            .unwrap();
+        license.sign_chain_head(signer.clone()).await?;
+
+        let report = verify_chain(&license.storage, signer.jwk().unwrap()).await?;
+        assert!(report.valid, "{:?}", report.issues);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_activity_log_signed_export() -> Result<(), ActivityLogError> {
+        use crate::{jwk::Jwk, tests::load_jwk};
+
+        let storage = Arc::new(DummyStorage::default());
+        let log = ActivityLog::load(Uuid::new_v4(), storage).await?;
+        log.enable_chain().await?;
+
+        let entry = Arc::new(ActivityLogEntry::new(
+            log.credential_id,
+            ActivityLogEntryType::Shared,
+            "Shared with Bar".into(),
+            "The Bar".into(),
+            Some(vec!["$.age_over_21".into()]),
+            None,
+        )?);
+        log.add(entry.clone()).await?;
+        // Edits are exported along with the entries.
+        log.set_hidden(entry.id, true).await?;
+
+        let mut key = load_jwk();
+        key.key_id = Some("wallet-key".into());
+        let public_key = || Arc::new(Jwk::from(key.to_public()));
+
+        let export = log
+            .export_signed(None, Arc::new(Jwk::from(key.clone())))
+            .await?;
+
+        let verified = verify_activity_log_export(export.clone(), public_key()).await?;
+        assert_eq!(verified.key_id.as_deref(), Some("wallet-key"));
+        assert_eq!(verified.credential_id, log.credential_id);
+        assert_eq!(verified.entries.len(), 1);
+        assert_eq!(verified.entries[0].id, entry.id);
+        assert!(verified.entries[0].hidden);
+        assert_eq!(verified.chain_head.map(|head| head.length), Some(2));
+
+        // Another key.
+        let mut other = ssi::JWK::generate_p256();
+        other.key_id = Some("wallet-key".into());
+        assert!(matches!(
+            verify_activity_log_export(export.clone(), Arc::new(Jwk::from(other.to_public())))
+                .await,
+            Err(ActivityLogError::InvalidExport(_))
+        ));
+
+        // Tampered payload.
+        let compact = String::from(export);
+        let parts = compact.split('.').collect::<Vec<_>>();
+        let payload = String::from_utf8(
+            base64::prelude::BASE64_URL_SAFE_NO_PAD
+                .decode(parts[1])
+                .unwrap(),
+        )
+        .unwrap()
+        .replace("The Bar", "The Pub");
+        let tampered = [
+            parts[0],
+            &base64::prelude::BASE64_URL_SAFE_NO_PAD.encode(payload),
+            parts[2],
+        ]
+        .join(".");
+        assert!(matches!(
+            verify_activity_log_export(tampered.try_into().unwrap(), public_key()).await,
+            Err(ActivityLogError::InvalidExport(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_disclosure_summary() -> Result<(), ActivityLogError> {
+        let storage = Arc::new(DummyStorage::default());
+        let license = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+        let passport = ActivityLog::load(Uuid::new_v4(), storage.clone()).await?;
+
+        let day = 86_400u64;
+        let base_ts: u64 = 1_704_067_200; // 2024-01-01T00:00:00Z
+        let fields = |fields: &[&str]| fields.iter().map(|f| f.to_string()).collect();
+
+        for (log, entry_type, interaction_with, timestamp, fields) in [
+            (
+                &license,
+                ActivityLogEntryType::Issued,
+                "DMV",
+                base_ts,
+                vec![],
+            ),
+            (
+                &license,
+                ActivityLogEntryType::Shared,
+                "The Bar",
+                base_ts + day,
+                fields(&["age_over_21"]),
+            ),
+            (
+                &license,
+                ActivityLogEntryType::Shared,
+                "The Bar",
+                base_ts + 3 * day,
+                fields(&["age_over_21", "portrait"]),
+            ),
+            (
+                &passport,
+                ActivityLogEntryType::Shared,
+                "Border Control",
+                base_ts + 2 * day,
+                fields(&["family_name", "nationality"]),
+            ),
+        ] {
+            let entry = make_entry(
+                log.credential_id,
+                entry_type,
+                "",
+                interaction_with,
+                timestamp,
+                fields,
+            );
+            log.add(Arc::new(entry)).await?;
+        }
+
+        let summary = disclosure_summary(&license.storage, ActivityLogQuery::default()).await?;
+
+        let verifiers = summary
+            .verifiers
+            .iter()
+            .map(|v| (v.verifier.as_str(), v.presentations, v.last_shared))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verifiers,
+            vec![
+                ("The Bar", 2, base_ts + 3 * day),
+                ("Border Control", 1, base_ts + 2 * day)
+            ]
+        );
+
+        let bar = &summary.verifiers[0].attributes;
+        assert_eq!(bar.len(), 2);
+        assert_eq!(bar[0].field, "age_over_21");
+        assert_eq!(bar[0].count, 2);
+        assert_eq!(bar[0].first_shared, base_ts + day);
+        assert_eq!(bar[0].last_shared, base_ts + 3 * day);
+        assert_eq!(bar[1].field, "portrait");
+        assert_eq!(bar[1].count, 1);
+
+        assert_eq!(summary.credentials.len(), 2);
+        assert_eq!(summary.credentials[0].credential_id, license.credential_id);
+        assert_eq!(
+            summary.credentials[0].fields,
+            vec!["age_over_21", "portrait"]
+        );
+        assert_eq!(summary.credentials[0].verifiers, vec!["The Bar"]);
+
+        let filtered = disclosure_summary(
+            &license.storage,
+            ActivityLogQuery {
+                credential_ids: vec![passport.credential_id],
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(filtered.verifiers.len(), 1);
+        assert_eq!(filtered.verifiers[0].verifier, "Border Control");
+        assert_eq!(
+            filtered.credentials[0].fields,
+            vec!["family_name", "nationality"]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_activity_log_all() -> Result<(), ActivityLogError> {
+        let storage = Arc::new(DummyStorage::default());
+        run_activity_log_all_test(storage).await
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_activity_log_all() -> Result<(), ActivityLogError> {
+        let storage: Arc<NamespacedDummyStorage> = Arc::new(NamespacedDummyStorage::default());
+        run_activity_log_all_test(storage).await
+    }
+
+    #[tokio::test]
+    async fn test_activity_log() -> Result<(), ActivityLogError> {
+        let storage = Arc::new(DummyStorage::default());
+        run_activity_log_test(storage).await
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_activity_log() -> Result<(), ActivityLogError> {
+        let storage: Arc<NamespacedDummyStorage> = Arc::new(NamespacedDummyStorage::default());
+        run_activity_log_test(storage).await
+    }
+
+    #[tokio::test]
+    async fn test_activity_log_filter() -> Result<(), ActivityLogError> {
+        let storage = Arc::new(DummyStorage::default());
+        run_activity_log_filter_test(storage).await
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_activity_log_filter() -> Result<(), ActivityLogError> {
+        let storage: Arc<NamespacedDummyStorage> = Arc::new(NamespacedDummyStorage::default());
+        run_activity_log_filter_test(storage).await
+    }
+
+    #[test]
+    fn test_field_path() {
+        assert_eq!(
+            field_path(["address", "street_address"]),
+            "$.address.street_address"
+        );
+        assert_eq!(field_path(["nationalities", "0"]), "$.nationalities[0]");
+        assert_eq!(
+            field_path(["org.iso.18013.5.1", "family_name"]),
+            "$['org.iso.18013.5.1'].family_name"
+        );
+        assert_eq!(field_path(["it's"]), "$['it\\'s']");
+    }
+}
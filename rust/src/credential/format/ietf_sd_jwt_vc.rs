@@ -9,12 +9,13 @@ use crate::{
         error::OID4VPError,
         permission_request::RequestedField,
         presentation::{CredentialPresentation, PresentationOptions},
+        transaction_data::TransactionDataHashes,
     },
     CredentialType,
 };
 
 use core::str;
-use std::sync::Arc;
+use std::{borrow::Cow, sync::Arc};
 
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 use openid4vp::core::{
@@ -23,7 +24,7 @@ use openid4vp::core::{
 };
 use ssi::{
     claims::{
-        jws::{JwsSigner, JwsSignerInfo},
+        jws::{JwsPayload, JwsSigner, JwsSignerInfo},
         jwt::AnyClaims,
         sd_jwt::{KbJwtPayload, SdAlg, SdJwtBuf},
         SignatureError,
@@ -273,6 +274,36 @@ impl JwsSigner for PresentationJwsSigner<'_> {
     }
 }
 
+/// KB-JWT payload with the `transaction_data_hashes` of the transaction data
+/// authorized by the presentation, as defined in OID4VP 1.0 §B.3.3.
+struct KbJwtWithTransactionData(Vec<u8>);
+
+impl KbJwtWithTransactionData {
+    fn new(payload: &KbJwtPayload, hashes: TransactionDataHashes) -> Result<Self, OID4VPError> {
+        let mut claims = serde_json::to_value(payload)
+            .map_err(|e| OID4VPError::VpTokenCreate(format!("invalid KB-JWT payload: {e}")))?;
+
+        claims["transaction_data_hashes"] = hashes.hashes.into();
+        if let Some(alg) = hashes.alg {
+            claims["transaction_data_hashes_alg"] = alg.into();
+        }
+
+        serde_json::to_vec(&claims)
+            .map(Self)
+            .map_err(|e| OID4VPError::VpTokenCreate(format!("invalid KB-JWT payload: {e}")))
+    }
+}
+
+impl JwsPayload for KbJwtWithTransactionData {
+    fn typ(&self) -> Option<&str> {
+        Some("kb+jwt")
+    }
+
+    fn payload_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+}
+
 impl CredentialPresentation for IetfSdJwtVc {
     type Credential = serde_json::Value;
     type CredentialFormat = ClaimFormatDesignation;
@@ -329,12 +360,9 @@ impl CredentialPresentation for IetfSdJwtVc {
         };
 
         // Create and attach Key Binding JWT (KB-JWT).
-        let aud = options
-            .audience()
-            .ok_or_else(|| {
-                OID4VPError::VpTokenCreate("missing client_id for KB-JWT audience".into())
-            })?
-            .clone();
+        let aud = options.audience().ok_or_else(|| {
+            OID4VPError::VpTokenCreate("missing client_id for KB-JWT audience".into())
+        })?;
         let nonce = options.nonce().clone();
 
         let kb_payload = KbJwtPayload::new(aud, nonce, SdAlg::Sha256, &sd_jwt);
@@ -343,10 +371,15 @@ impl CredentialPresentation for IetfSdJwtVc {
             signer: options.signer.as_ref().as_ref(),
         };
 
-        let kb_jwt = jws_signer
-            .sign(kb_payload)
-            .await
-            .map_err(|e| OID4VPError::VpTokenCreate(format!("KB-JWT signing failed: {e:?}")))?;
+        let kb_jwt = match options.transaction_data_hashes() {
+            Some(hashes) => {
+                jws_signer
+                    .sign(KbJwtWithTransactionData::new(&kb_payload, hashes)?)
+                    .await
+            }
+            None => jws_signer.sign(kb_payload).await,
+        }
+        .map_err(|e| OID4VPError::VpTokenCreate(format!("KB-JWT signing failed: {e:?}")))?;
 
         sd_jwt.set_kb(&kb_jwt);
 
@@ -505,4 +538,54 @@ mod tests {
             Some("DE")
         );
     }
+
+    #[tokio::test]
+    async fn test_kb_jwt_binds_transaction_data() {
+        use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+
+        use crate::oid4vp::{
+            holder::tests::KeySigner, permission_request::ResponseOptions,
+            presentation::PresentationSigner, transaction_data::tests::payment_request,
+        };
+
+        let vc = IetfSdJwtVc::new_from_compact_sd_jwt(
+            include_str!("../../../tests/examples/dc+sd-jwt.jwt").to_string(),
+        )
+        .unwrap();
+
+        let (request, item) = payment_request("hiid", "dc+sd-jwt");
+        let signer: Box<dyn PresentationSigner> = Box::new(KeySigner {
+            jwk: ssi::JWK::generate_p256(),
+        });
+        let response_options = ResponseOptions::default();
+        let options = PresentationOptions {
+            request: &request,
+            signer: Arc::new(signer),
+            context_map: None,
+            response_options: &response_options,
+            keystore: None,
+            transaction_data: vec![item.clone()],
+            origin: None,
+        };
+
+        let VpTokenItem::String(presentation) = vc.as_vp_token_item(&options, None).await.unwrap()
+        else {
+            panic!("expected a compact SD-JWT");
+        };
+        let kb_jwt = presentation.rsplit('~').next().unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(
+            &BASE64_URL_SAFE_NO_PAD
+                .decode(kb_jwt.split('.').nth(1).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(claims["nonce"], "nonce");
+        assert_eq!(
+            claims["transaction_data_hashes"],
+            serde_json::json!([item.hash()])
+        );
+        // The verifier did not specify the accepted hash algorithms.
+        assert!(claims.get("transaction_data_hashes_alg").is_none());
+    }
 }
@@ -5,7 +5,7 @@ use crate::{
     crypto::KeyAlias,
     oid4vp::{
         error::OID4VPError,
-        presentation::{CredentialPresentation, PresentationOptions},
+        presentation::{select_dcql_claim_values, CredentialPresentation, PresentationOptions},
         PresentationError,
     },
     CredentialType,
@@ -15,7 +15,8 @@ use std::sync::Arc;
 
 use base64::prelude::*;
 use openid4vp::core::{
-    credential_format::ClaimFormatDesignation, response::parameters::VpTokenItem,
+    credential_format::ClaimFormatDesignation, dcql_query::DcqlCredentialClaimsQueryPath,
+    response::parameters::VpTokenItem,
 };
 use ssi::{
     claims::{
@@ -195,6 +196,20 @@ impl CredentialPresentation for JwtVc {
         ClaimFormatDesignation::JwtVcJson
     }
 
+    /// Claims paths are resolved against the JWT claims, then against the
+    /// credential in the `vc` claim.
+    fn dcql_claim_values(&self, path: &[DcqlCredentialClaimsQueryPath]) -> Vec<serde_json::Value> {
+        let values = select_dcql_claim_values(&self.payload_json, path);
+        if !values.is_empty() {
+            return values;
+        }
+
+        self.payload_json
+            .get("vc")
+            .map(|vc| select_dcql_claim_values(vc, path))
+            .unwrap_or_default()
+    }
+
     /// Return the credential as a VpToken
     async fn as_vp_token_item<'a>(
         &self,
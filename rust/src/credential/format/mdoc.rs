@@ -14,8 +14,10 @@ use isomdl::{
     presentation::{device::Document, Stringify},
 };
 use openid4vp::core::{
-    credential_format::ClaimFormatDesignation, dcql_query::DcqlCredentialQuery,
-    iso_18013_7::get_encryption_jwk_thumbprint, response::parameters::VpTokenItem,
+    credential_format::ClaimFormatDesignation,
+    dcql_query::{DcqlCredentialClaimsQueryPath, DcqlCredentialQuery},
+    iso_18013_7::DcApiHandover,
+    response::parameters::VpTokenItem,
 };
 use time::format_description::well_known::Iso8601;
 use uuid::Uuid;
@@ -30,7 +32,8 @@ use crate::{
         error::OID4VPError,
         iso_18013_7::prepare_response::{build_device_response, handover_from_request},
         permission_request::RequestedField,
-        presentation::PresentationOptions,
+        presentation::{select_dcql_claim_values, PresentationOptions},
+        response_encryption::encryption_jwk_thumbprint,
     },
     storage_manager::StorageManagerInterface,
     CredentialType,
@@ -306,6 +309,38 @@ impl Mdoc {
             .collect()
     }
 
+    /// Return the value of the data element at a DCQL claims path, made of the
+    /// namespace and the element identifier. Further segments select within
+    /// the element value.
+    pub fn dcql_claim_values(
+        &self,
+        path: &[DcqlCredentialClaimsQueryPath],
+    ) -> Vec<serde_json::Value> {
+        let [namespace, element_identifier, rest @ ..] = path else {
+            return vec![];
+        };
+        let (
+            DcqlCredentialClaimsQueryPath::String(namespace),
+            DcqlCredentialClaimsQueryPath::String(element_identifier),
+        ) = (namespace, element_identifier)
+        else {
+            return vec![];
+        };
+
+        self.document()
+            .namespaces
+            .get(namespace)
+            .and_then(|elements| {
+                elements
+                    .iter()
+                    .map(|(_, element)| element.as_ref())
+                    .find(|element| element.element_identifier == *element_identifier)
+            })
+            .and_then(|element| to_json_for_display(&element.element_value))
+            .map(|value| select_dcql_claim_values(&value, rest))
+            .unwrap_or_default()
+    }
+
     /// Generate a VP Token item for OID4VP presentation.
     /// This creates a DeviceResponse with the selected fields and signs it.
     pub async fn as_vp_token_item<'a>(
@@ -378,22 +413,33 @@ impl Mdoc {
                 ))
             })?;
 
-        // Create Handover per OID4VP 1.0 §B.2.6.1 (Invocation via Redirects)
-        let jwk_thumbprint = get_encryption_jwk_thumbprint(options.request);
-        let handover =
-            handover_from_request(options.request, jwk_thumbprint.as_ref()).map_err(|e| {
-                CredentialEncodingError::VpToken(format!("Failed to create Handover: {e}"))
-            })?;
+        let jwk_thumbprint = encryption_jwk_thumbprint(options.request);
 
-        // Build and sign the DeviceResponse
-        let device_response =
-            build_device_response(keystore, self, revealed_namespaces, None, handover).map_err(
-                |e| {
-                    CredentialEncodingError::VpToken(format!(
-                        "Failed to build device response: {e}"
-                    ))
-                },
-            )?;
+        // Build and sign the DeviceResponse, with the Handover of OID4VP 1.0
+        // §B.2.6.2 (DC API) or §B.2.6.1 (Invocation via Redirects).
+        let device_response = match &options.origin {
+            Some(origin) => {
+                let handover = DcApiHandover::new(
+                    origin,
+                    options.request.nonce(),
+                    jwk_thumbprint.as_ref().map(|t| t.as_slice()),
+                )
+                .map_err(|e| {
+                    CredentialEncodingError::VpToken(format!("Failed to create Handover: {e}"))
+                })?;
+                build_device_response(keystore, self, revealed_namespaces, None, handover)
+            }
+            None => {
+                let handover = handover_from_request(options.request, jwk_thumbprint.as_ref())
+                    .map_err(|e| {
+                        CredentialEncodingError::VpToken(format!("Failed to create Handover: {e}"))
+                    })?;
+                build_device_response(keystore, self, revealed_namespaces, None, handover)
+            }
+        }
+        .map_err(|e| {
+            CredentialEncodingError::VpToken(format!("Failed to build device response: {e}"))
+        })?;
 
         // Encode as base64url
         let device_response_bytes = cbor::to_vec(&device_response).map_err(|e| {
@@ -754,4 +800,62 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_mdoc_cannot_authorize_transaction_data() {
+        use std::sync::Arc;
+
+        use crate::{
+            credential::{ParsedCredentialInner, PresentableCredential},
+            crypto::RustTestKeyManager,
+            oid4vp::{
+                error::OID4VPError,
+                holder::tests::KeySigner,
+                permission_request::{PermissionRequest, PermissionRequestError, ResponseOptions},
+                presentation::PresentationSigner,
+                transaction_data::tests::payment_request,
+            },
+        };
+
+        let key_manager = Arc::new(RustTestKeyManager::default());
+        let key_alias = KeyAlias("test_transaction_data".into());
+        key_manager
+            .generate_p256_signing_key(key_alias.clone())
+            .await
+            .unwrap();
+        let mdoc = crate::mdl::util::generate_test_mdl(key_manager.clone(), key_alias).unwrap();
+
+        let (request, _) = payment_request("mdl", "mso_mdoc");
+        let dcql_query = request.dcql_query().unwrap().unwrap();
+        let signer: Box<dyn PresentationSigner> = Box::new(KeySigner {
+            jwk: ssi::JWK::generate_p256(),
+        });
+        let credential = Arc::new(PresentableCredential {
+            inner: ParsedCredentialInner::MsoMdoc(Arc::new(mdoc)),
+            selected_fields: None,
+            credential_query_id: "mdl".into(),
+        });
+        let permission_request = PermissionRequest::new(
+            dcql_query,
+            vec![credential.clone()],
+            request,
+            Arc::new(signer),
+            None,
+            Some(key_manager),
+        );
+
+        // OID4VP defines no binding of transaction data for mdocs.
+        let error = permission_request
+            .create_permission_response(
+                vec![credential],
+                vec![vec!["$['org.iso.18013.5.1'].family_name".into()]],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            OID4VPError::PermissionRequest(PermissionRequestError::UnboundTransactionData(_))
+        ));
+    }
 }
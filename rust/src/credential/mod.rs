@@ -1,10 +1,10 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use crate::{
     crypto::KeyAlias,
     oid4vp::{
         error::OID4VPError,
-        permission_request::RequestedField,
+        permission_request::{encode_claim_path, RequestedField},
         presentation::{CredentialPresentation, PresentationError, PresentationOptions},
     },
     CredentialType,
@@ -15,7 +15,7 @@ use json_vc::{JsonVc, JsonVcEncodingError, JsonVcInitError};
 use jwt_vc::{JwtVc, JwtVcInitError};
 use mdoc::{Mdoc, MdocEncodingError, MdocInitError};
 use openid4vp::core::{
-    dcql_query::{DcqlCredentialQuery, DcqlQuery},
+    dcql_query::{DcqlCredentialClaimsQueryPath, DcqlCredentialQuery, DcqlQuery},
     response::parameters::VpTokenItem,
 };
 use optical_barcode_credential::{OpticalBarcodeCred, OpticalBarcodeCredError};
@@ -489,6 +489,8 @@ impl PresentableCredential {
         &self,
         options: &'a PresentationOptions<'a>,
     ) -> Result<VpTokenItem, OID4VPError> {
+        let options = &options.for_credential_query(&self.credential_query_id);
+
         match &self.inner {
             ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => {
                 sd_jwt
@@ -527,8 +529,12 @@ impl PresentableCredential {
 // Internal Parsed Credential methods
 impl ParsedCredential {
     /// Check if the credential satisfies a DCQL credential query.
+    ///
+    /// Besides the format-specific checks, the credential must hold the
+    /// claims of the query, or of one of its claim sets, with one of the
+    /// accepted values when restricted.
     pub fn satisfies_dcql_query(&self, credential_query: &DcqlCredentialQuery) -> bool {
-        match &self.inner {
+        let satisfies_format = match &self.inner {
             ParsedCredentialInner::JwtVcJson(vc) => vc.satisfies_dcql_query(credential_query),
             ParsedCredentialInner::JwtVcJsonLd(vc) => vc.satisfies_dcql_query(credential_query),
             ParsedCredentialInner::LdpVc(vc) => vc.satisfies_dcql_query(credential_query),
@@ -539,9 +545,40 @@ impl ParsedCredential {
             ParsedCredentialInner::MsoMdoc(mdoc) => mdoc.satisfies_dcql_query(credential_query),
             ParsedCredentialInner::Cwt(_cwt) => false,
             ParsedCredentialInner::OpticalBarcodeCredential(_) => false,
+        };
+
+        satisfies_format && self.dcql_claim_set(credential_query).is_some()
+    }
+
+    /// Return the values of the credential claim at a DCQL claims path.
+    pub fn dcql_claim_values(
+        &self,
+        path: &[DcqlCredentialClaimsQueryPath],
+    ) -> Vec<serde_json::Value> {
+        match &self.inner {
+            ParsedCredentialInner::JwtVcJson(vc) | ParsedCredentialInner::JwtVcJsonLd(vc) => {
+                vc.dcql_claim_values(path)
+            }
+            ParsedCredentialInner::LdpVc(vc) => vc.dcql_claim_values(path),
+            ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => sd_jwt.dcql_claim_values(path),
+            ParsedCredentialInner::DcSdJwt(sd_jwt) => sd_jwt.dcql_claim_values(path),
+            ParsedCredentialInner::MsoMdoc(mdoc) => mdoc.dcql_claim_values(path),
+            ParsedCredentialInner::Cwt(_) => vec![],
+            ParsedCredentialInner::OpticalBarcodeCredential(_) => vec![],
         }
     }
 
+    /// Return the indices of the claims of the DCQL credential query to
+    /// disclose, or `None` if the credential does not hold them.
+    ///
+    /// A claim restricted to `values` is only held with one of these values.
+    /// Without `claim_sets`, all the claims are required. Otherwise, the first
+    /// claim set held by the credential is selected, following the verifier's
+    /// order of preference.
+    pub fn dcql_claim_set(&self, credential_query: &DcqlCredentialQuery) -> Option<Vec<usize>> {
+        select_dcql_claim_set(credential_query, |path| self.dcql_claim_values(path))
+    }
+
     /// Return the requested fields for the credential, according to the DCQL query.
     pub fn requested_fields_dcql(
         &self,
@@ -558,7 +595,7 @@ impl ParsedCredential {
             return vec![];
         };
 
-        match &self.inner {
+        let requested_fields = match &self.inner {
             ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => {
                 sd_jwt.requested_fields_dcql(credential_query)
             }
@@ -577,10 +614,100 @@ impl ParsedCredential {
                 log::warn!("OpticalBarcodeCredential requested fields not implemented");
                 vec![]
             }
+        };
+
+        // Only request the claims of the selected claim set, matched by path:
+        // a claim may be requested as several fields, or none.
+        let selected_claims = credential_query.claims().zip(
+            credential_query
+                .claim_sets()
+                .and(self.dcql_claim_set(credential_query)),
+        );
+        match selected_claims {
+            Some((claims, claim_set)) => {
+                let paths = claim_set
+                    .iter()
+                    .filter_map(|index| claims.get(*index))
+                    .map(|claim| encode_claim_path(claim.path()))
+                    .collect::<HashSet<_>>();
+
+                requested_fields
+                    .into_iter()
+                    .filter(|field| paths.contains(&field.path))
+                    .collect()
+            }
+            None => requested_fields,
         }
     }
 }
 
+/// Return the indices of the claims of the DCQL credential query held by a
+/// credential, given the values of the credential at a claims path, or `None`
+/// if the credential does not hold them.
+///
+/// See [ParsedCredential::dcql_claim_set].
+pub(crate) fn select_dcql_claim_set<F>(
+    credential_query: &DcqlCredentialQuery,
+    claim_values: F,
+) -> Option<Vec<usize>>
+where
+    F: Fn(&[DcqlCredentialClaimsQueryPath]) -> Vec<serde_json::Value>,
+{
+    let Some(claims) = credential_query.claims() else {
+        return Some(vec![]);
+    };
+
+    let held = claims
+        .iter()
+        .map(|claim| {
+            let values = claim_values(claim.path());
+            match claim.values() {
+                Some(expected) => values.iter().any(|value| {
+                    expected
+                        .iter()
+                        .any(|expected| dcql_value_eq(expected, value))
+                }),
+                None => !values.is_empty(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let Some(claim_sets) = credential_query.claim_sets() else {
+        return held
+            .iter()
+            .all(|held| *held)
+            .then(|| (0..claims.len()).collect());
+    };
+
+    claim_sets.iter().find_map(|claim_set| {
+        claim_set
+            .iter()
+            .map(|id| {
+                claims
+                    .iter()
+                    .position(|claim| claim.id().is_some_and(|claim_id| claim_id == id))
+                    .filter(|index| held[*index])
+            })
+            .collect::<Option<Vec<_>>>()
+    })
+}
+
+/// Whether a claim value is the expected value of a DCQL claims query.
+///
+/// Numbers are compared by value, and JSON-LD value objects by their
+/// `@value`.
+fn dcql_value_eq(expected: &serde_json::Value, value: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (expected, value) {
+        (_, Value::Object(object)) if object.contains_key("@value") => {
+            dcql_value_eq(expected, &object["@value"])
+        }
+        (Value::Number(expected), Value::Number(value)) => expected.as_f64() == value.as_f64(),
+        (expected, value) => expected == value,
+    }
+}
+
 impl BitStringStatusListResolver for ParsedCredential {
     fn status_list_entry(
         &self,
@@ -781,4 +908,80 @@ mod test {
 
         assert_eq!(CredentialFormat::MsoMdoc, roundtripped);
     }
+
+    fn dcql_query(query: serde_json::Value) -> DcqlCredentialQuery {
+        serde_json::from_value(query).unwrap()
+    }
+
+    #[tokio::test]
+    async fn dcql_values_match_mdoc_elements() {
+        use crate::crypto::{KeyAlias, RustTestKeyManager};
+
+        let key_manager = RustTestKeyManager::default();
+        let key_alias = KeyAlias("test_dcql_values".to_string());
+        key_manager
+            .generate_p256_signing_key(key_alias.clone())
+            .await
+            .unwrap();
+        let mdoc = crate::mdl::util::generate_test_mdl(Arc::new(key_manager), key_alias).unwrap();
+        let credential = ParsedCredential::new_mso_mdoc(Arc::new(mdoc));
+
+        let query = |element: &str, values: serde_json::Value| {
+            dcql_query(serde_json::json!({
+                "id": "mdl",
+                "format": "mso_mdoc",
+                "meta": { "doctype_value": "org.iso.18013.5.1.mDL" },
+                "claims": [{ "path": ["org.iso.18013.5.1", element], "values": values }]
+            }))
+        };
+
+        assert!(credential.satisfies_dcql_query(&query("age_over_18", serde_json::json!([true]))));
+        assert!(!credential.satisfies_dcql_query(&query("age_over_60", serde_json::json!([true]))));
+        assert!(credential.satisfies_dcql_query(&query("family_name", serde_json::json!(["Doe"]))));
+        assert!(!credential.satisfies_dcql_query(&query("family_name", serde_json::json!(["Roe"]))));
+    }
+
+    #[test]
+    fn dcql_values_match_disclosed_sd_jwt_claims() {
+        let sd_jwt = IetfSdJwtVc::new_from_compact_sd_jwt(
+            include_str!("../../tests/examples/dc+sd-jwt.jwt").to_string(),
+        )
+        .unwrap();
+        let credential = ParsedCredential::new_dc_sd_jwt(sd_jwt);
+
+        // `affiliation_country` is only present as a disclosure of the SD-JWT.
+        let query = |values: serde_json::Value| {
+            dcql_query(serde_json::json!({
+                "id": "hiid",
+                "format": "dc+sd-jwt",
+                "meta": { "vct_values": ["eu.europa.ec.eudi.hiid.1"] },
+                "claims": [{ "path": ["affiliation_country"], "values": values }]
+            }))
+        };
+
+        assert!(credential.satisfies_dcql_query(&query(serde_json::json!(["FR", "DE"]))));
+        assert!(!credential.satisfies_dcql_query(&query(serde_json::json!(["FR"]))));
+    }
+
+    #[test]
+    fn dcql_values_match_json_ld_credentials() {
+        let json_vc =
+            JsonVc::new_from_json(include_str!("../../tests/examples/alumni_vc.json").to_string())
+                .unwrap();
+        let credential = ParsedCredential::new_ldp_vc(json_vc);
+
+        let query = |values: serde_json::Value| {
+            dcql_query(serde_json::json!({
+                "id": "alumni",
+                "format": "ldp_vc",
+                "claims": [{
+                    "path": ["credentialSubject", "alumniOf", "name"],
+                    "values": values
+                }]
+            }))
+        };
+
+        assert!(credential.satisfies_dcql_query(&query(serde_json::json!(["Example University"]))));
+        assert!(!credential.satisfies_dcql_query(&query(serde_json::json!(["Other University"]))));
+    }
 }
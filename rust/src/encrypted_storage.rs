@@ -0,0 +1,274 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use async_trait::async_trait;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::common::*;
+use crate::crypto::KeyAlias;
+use crate::storage_manager::*;
+
+/// Version tag prepended to every encrypted value, so the envelope can evolve
+/// without breaking values already written to storage.
+const ENVELOPE_VERSION_V1: u8 = 1;
+
+/// Length in bytes of the AES-GCM nonce stored in the envelope.
+const NONCE_LEN: usize = 12;
+
+/// Minimum amount of key material accepted from the host.
+const MIN_KEY_MATERIAL_LEN: usize = 32;
+
+/// HKDF `info` string binding derived keys to this storage scheme.
+const HKDF_INFO: &[u8] = b"sprucekit-mobile/encrypted-storage/v1";
+
+/// Interface: StorageKeyProvider
+///
+/// Provides the secret key material used by [EncryptedStorage]. Hosts are
+/// expected to keep this material in the platform keystore (Android Keystore,
+/// iOS Keychain) and hand it out by handle.
+#[uniffi::export(with_foreign)]
+pub trait StorageKeyProvider: Send + Sync + Debug {
+    /// Return the secret key material associated with `handle`, creating it
+    /// if it does not exist yet. The material must be at least 32 bytes long
+    /// and must be stable across application launches.
+    fn key_material(&self, handle: KeyAlias) -> Result<Vec<u8>, StorageManagerError>;
+}
+
+/// A [StorageManagerInterface] adapter that encrypts values at rest.
+///
+/// Values are sealed with AES-256-GCM using a key derived (HKDF-SHA256) from
+/// the material returned by the [StorageKeyProvider]. The storage key is
+/// authenticated as associated data, so a value cannot be moved to a
+/// different key without failing decryption.
+///
+/// Keys themselves are stored in the clear, as callers such as
+/// [VdcCollection](crate::vdc_collection::VdcCollection) rely on listing
+/// keys by prefix.
+pub struct EncryptedStorage {
+    inner: Arc<dyn StorageManagerInterface>,
+    cipher: Aes256Gcm,
+}
+
+impl Debug for EncryptedStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedStorage")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EncryptedStorage {
+    /// Wrap `inner`, encrypting values with the key identified by `key_handle`.
+    pub fn new(
+        inner: Arc<dyn StorageManagerInterface>,
+        key_provider: Arc<dyn StorageKeyProvider>,
+        key_handle: KeyAlias,
+    ) -> Result<Self, StorageManagerError> {
+        let material = Zeroizing::new(key_provider.key_material(key_handle.clone())?);
+
+        if material.len() < MIN_KEY_MATERIAL_LEN {
+            return Err(StorageManagerError::CouldNotMakeKey);
+        }
+
+        let hkdf = Hkdf::<Sha256>::new(Some(key_handle.0.as_bytes()), &material);
+        let mut key = Zeroizing::new([0u8; 32]);
+        hkdf.expand(HKDF_INFO, key.as_mut_slice())
+            .map_err(|_| StorageManagerError::CouldNotMakeKey)?;
+
+        let cipher = Aes256Gcm::new_from_slice(key.as_slice())
+            .map_err(|_| StorageManagerError::CouldNotMakeKey)?;
+
+        Ok(Self { inner, cipher })
+    }
+
+    /// Seal a value, producing `version || nonce || ciphertext`.
+    fn seal(&self, key: &Key, value: &Value) -> Result<Value, StorageManagerError> {
+        let nonce = rand::random::<[u8; NONCE_LEN]>();
+
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &value.0,
+                    aad: key.0.as_bytes(),
+                },
+            )
+            .map_err(|_| StorageManagerError::InternalError)?;
+
+        let mut sealed = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+        sealed.push(ENVELOPE_VERSION_V1);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+
+        Ok(Value(sealed))
+    }
+
+    /// Open a value previously sealed under the same storage key.
+    fn open(&self, key: &Key, value: &Value) -> Result<Value, StorageManagerError> {
+        let Some((&ENVELOPE_VERSION_V1, rest)) = value.0.split_first() else {
+            return Err(StorageManagerError::CouldNotDecryptValue);
+        };
+
+        if rest.len() < NONCE_LEN {
+            return Err(StorageManagerError::CouldNotDecryptValue);
+        }
+
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key.0.as_bytes(),
+                },
+            )
+            .map(Value)
+            .map_err(|_| StorageManagerError::CouldNotDecryptValue)
+    }
+}
+
+#[async_trait]
+impl StorageManagerInterface for EncryptedStorage {
+    async fn add(&self, key: Key, value: Value) -> Result<(), StorageManagerError> {
+        let sealed = self.seal(&key, &value)?;
+        self.inner.add(key, sealed).await
+    }
+
+    async fn get(&self, key: Key) -> Result<Option<Value>, StorageManagerError> {
+        match self.inner.get(key.clone()).await? {
+            Some(sealed) => self.open(&key, &sealed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<Key>, StorageManagerError> {
+        self.inner.list().await
+    }
+
+    async fn remove(&self, key: Key) -> Result<(), StorageManagerError> {
+        self.inner.remove(key).await
+    }
+
+    async fn batch(&self, operations: Vec<StorageOperation>) -> Result<(), StorageManagerError> {
+        let operations = operations
+            .into_iter()
+            .map(|operation| match operation {
+                StorageOperation::Add { key, value } => {
+                    let value = self.seal(&key, &value)?;
+                    Ok(StorageOperation::Add { key, value })
+                }
+                remove @ StorageOperation::Remove { .. } => Ok(remove),
+            })
+            .collect::<Result<Vec<_>, StorageManagerError>>()?;
+
+        apply_batch(self.inner.as_ref(), operations).await
+    }
+}
+
+/// Wrap a host storage manager so that every value is encrypted at rest.
+///
+/// The returned storage manager can be passed anywhere a
+/// [StorageManagerInterface] is expected, e.g. `VdcCollection::new` or
+/// `ActivityLog::load`.
+#[uniffi::export]
+pub fn encrypted_storage(
+    inner: Arc<dyn StorageManagerInterface>,
+    key_provider: Arc<dyn StorageKeyProvider>,
+    key_handle: KeyAlias,
+) -> Result<Arc<dyn StorageManagerInterface>, StorageManagerError> {
+    Ok(Arc::new(EncryptedStorage::new(
+        inner,
+        key_provider,
+        key_handle,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage_manager::test::DummyStorage;
+
+    #[derive(Debug)]
+    struct StaticKeyProvider(Vec<u8>);
+
+    impl StorageKeyProvider for StaticKeyProvider {
+        fn key_material(&self, _handle: KeyAlias) -> Result<Vec<u8>, StorageManagerError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn encrypted(inner: Arc<DummyStorage>, material: &[u8]) -> EncryptedStorage {
+        EncryptedStorage::new(
+            inner,
+            Arc::new(StaticKeyProvider(material.to_vec())),
+            KeyAlias("storage".into()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn roundtrips_and_encrypts_values() {
+        let inner = Arc::new(DummyStorage::default());
+        let storage = encrypted(inner.clone(), &[7u8; 32]);
+
+        let key = Key("Credential.1".into());
+        let value = Value(b"secret credential".to_vec());
+
+        storage.add(key.clone(), value.clone()).await.unwrap();
+
+        let raw = inner.get(key.clone()).await.unwrap().unwrap();
+        assert_ne!(raw, value);
+
+        assert_eq!(storage.get(key.clone()).await.unwrap(), Some(value));
+        assert_eq!(storage.list().await.unwrap(), vec![key.clone()]);
+
+        storage.remove(key.clone()).await.unwrap();
+        assert_eq!(storage.get(key).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_moved_values_and_wrong_keys() {
+        let inner = Arc::new(DummyStorage::default());
+        let storage = encrypted(inner.clone(), &[7u8; 32]);
+
+        let key = Key("Credential.1".into());
+        storage
+            .add(key.clone(), Value(b"secret".to_vec()))
+            .await
+            .unwrap();
+
+        // Copying the ciphertext under another key must fail authentication.
+        let raw = inner.get(key.clone()).await.unwrap().unwrap();
+        let other = Key("Credential.2".into());
+        inner.add(other.clone(), raw).await.unwrap();
+        assert!(matches!(
+            storage.get(other).await,
+            Err(StorageManagerError::CouldNotDecryptValue)
+        ));
+
+        // A different key handle cannot decrypt the value.
+        let wrong = encrypted(inner, &[8u8; 32]);
+        assert!(matches!(
+            wrong.get(key).await,
+            Err(StorageManagerError::CouldNotDecryptValue)
+        ));
+    }
+
+    #[test]
+    fn rejects_short_key_material() {
+        let result = EncryptedStorage::new(
+            Arc::new(DummyStorage::default()),
+            Arc::new(StaticKeyProvider(vec![1u8; 16])),
+            KeyAlias("storage".into()),
+        );
+        assert!(matches!(result, Err(StorageManagerError::CouldNotMakeKey)));
+    }
+}
@@ -9,6 +9,7 @@ pub mod credential;
 pub mod crypto;
 pub mod did;
 pub mod discover_protocols;
+pub mod encrypted_storage;
 pub mod haci;
 pub mod jwk;
 pub mod jws;
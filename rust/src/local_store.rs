@@ -56,4 +56,22 @@ impl StorageManagerInterface for LocalStore {
 
         Ok(())
     }
+
+    /// Apply several operations while holding the store lock.
+    async fn batch(&self, operations: Vec<StorageOperation>) -> Result<(), StorageManagerError> {
+        let mut store = self.store.lock().unwrap();
+
+        for operation in operations {
+            match operation {
+                StorageOperation::Add { key, value } => {
+                    store.insert(key, value);
+                }
+                StorageOperation::Remove { key } => {
+                    _ = store.remove(&key);
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
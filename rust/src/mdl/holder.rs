@@ -9,8 +9,9 @@
 //! will use for the BLE central client:
 //!
 
-use crate::credential::mdoc::Mdoc;
-use crate::{storage_manager::StorageManagerInterface, vdc_collection::VdcCollection};
+use crate::credential::{activity_log, mdoc::Mdoc};
+use crate::storage_manager::StorageManagerInterface;
+use crate::vdc_collection::{ActivityRecorder, VdcCollection};
 use crate::{CentralClientDetails, PeripheralServerDetails};
 use std::ops::DerefMut;
 use std::{
@@ -205,6 +206,8 @@ pub async fn initialize_mdl_presentation(
         engaged: Mutex::new(engaged_state),
         in_process: Mutex::new(None),
         ble_ident,
+        mdoc_id,
+        activity_recorder: Mutex::new(None),
     })
 }
 
@@ -313,6 +316,8 @@ pub fn initialize_mdl_presentation_from_bytes(
         engaged: Mutex::new(engaged_state),
         in_process: Mutex::new(None),
         ble_ident,
+        mdoc_id: mdoc.id(),
+        activity_recorder: Mutex::new(None),
     })
 }
 
@@ -345,6 +350,8 @@ pub struct MdlPresentationSession {
     engaged: Mutex<device::SessionManagerEngaged>,
     in_process: Mutex<Option<InProcessRecord>>,
     pub ble_ident: Vec<u8>,
+    mdoc_id: Uuid,
+    activity_recorder: Mutex<Option<Arc<ActivityRecorder>>>,
 }
 
 #[derive(uniffi::Object, Clone)]
@@ -352,6 +359,9 @@ struct InProcessRecord {
     session: device::SessionManager,
     items_request: device::RequestedItems,
     reader_common_name: Option<String>,
+    /// The paths of the items permitted in the response, as recorded in the
+    /// activity log.
+    permitted_items: Vec<String>,
 }
 
 #[uniffi::export]
@@ -418,6 +428,7 @@ impl MdlPresentationSession {
             session: session_manager,
             items_request: items_requests.items_request.clone(),
             reader_common_name: items_requests.common_name,
+            permitted_items: Vec::new(),
         });
 
         Ok(items_requests
@@ -449,6 +460,15 @@ impl MdlPresentationSession {
         &self,
         permitted_items: HashMap<String, HashMap<String, Vec<String>>>,
     ) -> Result<Vec<u8>, SignatureError> {
+        let permitted_paths = permitted_items
+            .values()
+            .flat_map(|namespaces| namespaces.iter())
+            .flat_map(|(namespace, elements)| {
+                elements
+                    .iter()
+                    .map(move |element| activity_log::field_path([namespace, element]))
+            })
+            .collect();
         let permitted = permitted_items
             .into_iter()
             .map(|(doc_type, namespaces)| {
@@ -469,6 +489,7 @@ impl MdlPresentationSession {
                 &in_process.items_request,
                 permitted,
             );
+            in_process.permitted_items = permitted_paths;
             Ok(in_process
                 .session
                 .get_next_signature_payload()
@@ -485,35 +506,29 @@ impl MdlPresentationSession {
     }
 
     pub fn submit_response(&self, signature: Vec<u8>) -> Result<Vec<u8>, SignatureError> {
-        if let Some(ref mut in_process) = self.in_process.lock().unwrap().deref_mut() {
-            let validated_signature = match in_process.session.device_auth_type() {
-                DeviceAuthType::Sign1 => p256::ecdsa::Signature::from_slice(&signature)
-                    .map_err(|e| SignatureError::InvalidSignature {
-                        value: e.to_string(),
-                    })?
-                    .to_bytes()
-                    .to_vec(),
-                DeviceAuthType::Mac0 => {
-                    // There's no good way to validate the structure of an HMAC signature (aside from length check)
-                    // We'll just let isomdl handle it, since it'll get validated later anyway
-                    signature
-                }
-            };
-            in_process
-                .session
-                .submit_next_signature(validated_signature)
-                .map_err(|e| SignatureError::Generic {
-                    value: format!("Could not submit next signature: {e:?}"),
-                })?;
-            in_process
-                .session
-                .retrieve_response()
-                .ok_or(SignatureError::TooManyDocuments)
-        } else {
-            Err(SignatureError::Generic {
-                value: "Could not get lock on session".to_string(),
-            })
+        let (response, reader, fields) = self.submit_signature(signature)?;
+
+        // Recorded once the session is released, as the recorder writes to
+        // the storage of the host.
+        let recorder = self.activity_recorder.lock().unwrap().clone();
+        if let Some(recorder) = recorder {
+            super::block_on(recorder.record_shared(
+                reader.as_deref(),
+                None,
+                vec![(self.mdoc_id, fields)],
+            ));
         }
+
+        Ok(response)
+    }
+
+    /// Record the presentation in the activity log of the mdoc once the
+    /// response is submitted, with the reader common name and the permitted
+    /// items.
+    ///
+    /// See [VdcCollection::set_activity_recording].
+    pub fn set_activity_recorder(&self, recorder: Option<Arc<ActivityRecorder>>) {
+        *self.activity_recorder.lock().unwrap() = recorder;
     }
 
     /// Terminates the mDL exchange session.
@@ -562,6 +577,51 @@ impl MdlPresentationSession {
     }
 }
 
+impl MdlPresentationSession {
+    /// Submit the signature of the response, returning the response along
+    /// with the reader common name and the permitted items, to be recorded.
+    fn submit_signature(
+        &self,
+        signature: Vec<u8>,
+    ) -> Result<(Vec<u8>, Option<String>, Vec<String>), SignatureError> {
+        if let Some(ref mut in_process) = self.in_process.lock().unwrap().deref_mut() {
+            let validated_signature = match in_process.session.device_auth_type() {
+                DeviceAuthType::Sign1 => p256::ecdsa::Signature::from_slice(&signature)
+                    .map_err(|e| SignatureError::InvalidSignature {
+                        value: e.to_string(),
+                    })?
+                    .to_bytes()
+                    .to_vec(),
+                DeviceAuthType::Mac0 => {
+                    // There's no good way to validate the structure of an HMAC signature (aside from length check)
+                    // We'll just let isomdl handle it, since it'll get validated later anyway
+                    signature
+                }
+            };
+            in_process
+                .session
+                .submit_next_signature(validated_signature)
+                .map_err(|e| SignatureError::Generic {
+                    value: format!("Could not submit next signature: {e:?}"),
+                })?;
+            let response = in_process
+                .session
+                .retrieve_response()
+                .ok_or(SignatureError::TooManyDocuments)?;
+
+            Ok((
+                response,
+                in_process.reader_common_name.clone(),
+                in_process.permitted_items.clone(),
+            ))
+        } else {
+            Err(SignatureError::Generic {
+                value: "Could not get lock on session".to_string(),
+            })
+        }
+    }
+}
+
 #[derive(uniffi::Record, Clone, Debug)]
 pub struct ItemsRequest {
     doc_type: String,
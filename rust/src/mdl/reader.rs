@@ -513,6 +513,22 @@ pub fn verify_device_response(
     session_transcript: Vec<u8>,
     ephemeral_reader_key: Vec<u8>,
     trust_anchor_registry: Option<Vec<String>>,
+) -> Result<MDLDeviceResponseVerification, MDLReaderResponseError> {
+    super::block_on(validate_device_response(
+        device_response,
+        session_transcript,
+        ephemeral_reader_key,
+        trust_anchor_registry,
+    ))
+}
+
+/// Async counterpart of [verify_device_response], for callers already running
+/// on the async runtime.
+pub(crate) async fn validate_device_response(
+    device_response: Vec<u8>,
+    session_transcript: Vec<u8>,
+    ephemeral_reader_key: Vec<u8>,
+    trust_anchor_registry: Option<Vec<String>>,
 ) -> Result<MDLDeviceResponseVerification, MDLReaderResponseError> {
     let device_response: isomdl::definitions::DeviceResponse =
         isomdl::cbor::from_slice(&device_response).map_err(|e| {
@@ -544,28 +560,28 @@ pub fn verify_device_response(
         .map(|docs| docs.iter().map(|d| d.doc_type.clone()).collect())
         .unwrap_or_default();
 
-    let validated_response =
-        super::block_on(isomdl::presentation::reader_utils::validate_response(
-            session_transcript,
-            registry,
-            x5chain,
-            document.clone(),
-            namespaces,
-            doc_types,
-            &(),
-            if ephemeral_reader_key.is_empty() {
-                [0u8; 32]
-            } else {
-                ephemeral_reader_key.try_into().map_err(|e: Vec<u8>| {
-                    MDLReaderResponseError::Generic {
-                        value: format!(
-                            "unable to parse ephemeral_reader_key: expected 32 bytes, got {}",
-                            e.len()
-                        ),
-                    }
-                })?
-            },
-        ));
+    let validated_response = isomdl::presentation::reader_utils::validate_response(
+        session_transcript,
+        registry,
+        x5chain,
+        document.clone(),
+        namespaces,
+        doc_types,
+        &(),
+        if ephemeral_reader_key.is_empty() {
+            [0u8; 32]
+        } else {
+            ephemeral_reader_key.try_into().map_err(|e: Vec<u8>| {
+                MDLReaderResponseError::Generic {
+                    value: format!(
+                        "unable to parse ephemeral_reader_key: expected 32 bytes, got {}",
+                        e.len()
+                    ),
+                }
+            })?
+        },
+    )
+    .await;
 
     let (verified_response, errors) = verified_namespaces_and_errors(&validated_response)?;
     Ok(MDLDeviceResponseVerification {
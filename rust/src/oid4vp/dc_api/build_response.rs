@@ -1,24 +1,19 @@
 use anyhow::{bail, Context, Result};
-use openid4vp::core::{
-    authorization_request::{parameters::ResponseMode, AuthorizationRequestObject},
-    iso_18013_7::compute_jwk_thumbprint,
-    jwe::{find_encryption_jwk, JweBuilder, DEFAULT_ENC},
-    object::ParsingErrorContext,
+use openid4vp::core::authorization_request::{
+    parameters::ResponseMode, AuthorizationRequestObject,
 };
 use serde_json::{json, Value as Json};
 
 use crate::oid4vp::iso_18013_7::build_response::get_state_from_request;
+use crate::oid4vp::response_encryption::ResponseEncrypter;
 
 pub enum Responder {
     Json {
         state: Option<String>,
     },
     Jwe {
-        alg: String,
-        enc: String,
-        kid: Option<String>,
+        encrypter: ResponseEncrypter,
         state: Option<String>,
-        verifier_jwk: Json,
     },
 }
 
@@ -27,44 +22,10 @@ impl Responder {
         let state = get_state_from_request(request)?;
         match request.response_mode() {
             ResponseMode::DcApi => Ok(Self::Json { state }),
-            ResponseMode::DcApiJwt => {
-                let client_metadata = request.client_metadata().parsing_error()?;
-
-                // Per OID4VP v1.0 §8.3, alg comes from the JWK's `alg` field
-                let jwks = client_metadata.jwks().parsing_error()?;
-                let keys: Vec<_> = jwks.keys.iter().collect();
-                let jwk_info = find_encryption_jwk(keys.into_iter())
-                    .context("no suitable encryption key found in client metadata")?;
-
-                // Convert to JSON for storage
-                let verifier_jwk: Json =
-                    serde_json::to_value(&jwk_info.jwk).context("failed to serialize JWK")?;
-
-                let alg = jwk_info.alg.clone();
-                if alg != "ECDH-ES" {
-                    bail!("unsupported encryption alg: {alg}")
-                }
-
-                // Per OID4VP v1.0 §8.3, enc comes from encrypted_response_enc_values_supported (default: A128GCM)
-                let enc = client_metadata
-                    .encrypted_response_enc_values_supported()
-                    .parsing_error()?
-                    .0
-                    .first()
-                    .cloned()
-                    .unwrap_or_else(|| DEFAULT_ENC.to_string());
-                if enc != DEFAULT_ENC {
-                    bail!("unsupported encryption scheme: {enc}")
-                }
-
-                Ok(Self::Jwe {
-                    alg,
-                    enc,
-                    kid: jwk_info.kid.clone(),
-                    state,
-                    verifier_jwk,
-                })
-            }
+            ResponseMode::DcApiJwt => Ok(Self::Jwe {
+                encrypter: ResponseEncrypter::from_request(request)?,
+                state,
+            }),
             mode => bail!("unsupported response mode: {mode:?}"),
         }
     }
@@ -83,14 +44,7 @@ impl Responder {
                 }
                 serde_json::to_string(&object).context("failed to serialize response")
             }
-            Self::Jwe {
-                alg,
-                enc,
-                kid,
-                state,
-                verifier_jwk,
-            } => {
-                // Build the payload
+            Self::Jwe { encrypter, state } => {
                 let mut payload = json!({
                     "vp_token": vp_token
                 });
@@ -98,18 +52,7 @@ impl Responder {
                     payload["state"] = Json::String(state.clone());
                 }
 
-                let mut builder = JweBuilder::new()
-                    .payload(payload)
-                    .recipient_key_json(verifier_jwk)
-                    .context("invalid recipient JWK")?
-                    .alg(alg)
-                    .enc(enc);
-
-                if let Some(kid) = kid {
-                    builder = builder.kid(kid);
-                }
-
-                builder.build().context("failed to build JWE")
+                encrypter.encrypt(&payload).context("failed to build JWE")
             }
         }
     }
@@ -118,7 +61,7 @@ impl Responder {
     pub fn jwk_thumbprint(&self) -> Option<[u8; 32]> {
         match self {
             Self::Json { .. } => None,
-            Self::Jwe { verifier_jwk, .. } => compute_jwk_thumbprint(verifier_jwk).ok(),
+            Self::Jwe { encrypter, .. } => Some(encrypter.jwk_thumbprint()),
         }
     }
 }
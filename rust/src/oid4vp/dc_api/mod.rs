@@ -1,5 +1,5 @@
 mod annex_c;
-mod build_response;
+pub(crate) mod build_response;
 mod ios;
 mod prepare_response;
 mod requested_values;
@@ -28,7 +28,11 @@ use prepare_response::vp_token;
 use requested_values::find_match;
 use serde_json::json;
 
-use crate::{credential::mdoc::Mdoc, crypto::KeyStore};
+use crate::{
+    credential::mdoc::Mdoc,
+    crypto::KeyStore,
+    oid4vp::trust::{VerifierTrustRecorder, VerifierTrustResult, VerifierTrustStore},
+};
 
 use super::iso_18013_7::{
     prepare_response::prepare_response,
@@ -43,12 +47,15 @@ pub struct InProgressRequestDcApi {
     responder: Responder,
     request_object: AuthorizationRequestObject,
     request_match: RequestMatch180137,
+    verifier_trust: VerifierTrustResult,
 }
 
 struct WalletActivity {
     http_client: ReqwestClient,
     origin: String,
     wallet_metadata: WalletMetadata,
+    trust_store: Arc<VerifierTrustStore>,
+    verifier_trust: VerifierTrustRecorder,
 }
 
 impl Wallet for WalletActivity {
@@ -84,8 +91,16 @@ impl RequestVerifier for WalletActivity {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_san_dns verification")?;
         self.check_expected_origins(decoded_request)?;
-        // TODO: Add trusted roots and implement chain verification in openid4vp.
-        x509_san::validate::<P256Verifier>(self.metadata(), decoded_request, request_jwt, None)
+        let trust = self.trust_store.evaluate_x509("x509_san_dns", |roots| {
+            x509_san::validate::<P256Verifier>(
+                self.metadata(),
+                decoded_request,
+                request_jwt.clone(),
+                roots,
+            )
+        })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 
     async fn x509_hash(
@@ -97,9 +112,16 @@ impl RequestVerifier for WalletActivity {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_hash verification")?;
         self.check_expected_origins(decoded_request)?;
-        // Use the x509_hash validation with P256 verifier
-        // Note: trusted_roots is None for now, meaning we don't verify the certificate chain
-        x509_hash::validate::<P256Verifier>(self.metadata(), decoded_request, request_jwt, None)
+        let trust = self.trust_store.evaluate_x509("x509_hash", |roots| {
+            x509_hash::validate::<P256Verifier>(
+                self.metadata(),
+                decoded_request,
+                request_jwt.clone(),
+                roots,
+            )
+        })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 
     async fn preregistered(
@@ -113,7 +135,14 @@ impl RequestVerifier for WalletActivity {
         // Restrict to DC API response modes so a pre-registered client cannot bypass
         // signature verification over a non-DC-API transport.
         match decoded_request.response_mode() {
-            ResponseMode::DcApi | ResponseMode::DcApiJwt => Ok(()),
+            ResponseMode::DcApi | ResponseMode::DcApiJwt => {
+                self.verifier_trust
+                    .record(self.trust_store.evaluate_unauthenticated(
+                        "origin",
+                        "the verifier is only identified by its origin",
+                    ));
+                Ok(())
+            }
             mode => bail!("unsigned requests are only accepted over the DC API, not {mode:?}"),
         }
     }
@@ -128,11 +157,35 @@ pub async fn handle_dc_api_request(
     mdoc: Arc<Mdoc>,
     origin: String,
     request_json: String,
+) -> Result<InProgressRequestDcApi, DcApiError> {
+    handle_dc_api_request_with_trust_store(
+        dcql_credential_id,
+        mdoc,
+        origin,
+        request_json,
+        VerifierTrustStore::new(),
+    )
+    .await
+}
+
+/// Handle a DC API request, evaluating its verifier against the given trust
+/// store.
+///
+/// The outcome is reported by [InProgressRequestDcApi::verifier_trust].
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn handle_dc_api_request_with_trust_store(
+    dcql_credential_id: String,
+    mdoc: Arc<Mdoc>,
+    origin: String,
+    request_json: String,
+    trust_store: Arc<VerifierTrustStore>,
 ) -> Result<InProgressRequestDcApi, DcApiError> {
     let wallet_activity = WalletActivity {
         http_client: ReqwestClient::new().map_err(DcApiError::internal_error)?,
         origin: origin.clone(),
         wallet_metadata: default_metadata(),
+        trust_store,
+        verifier_trust: Default::default(),
     };
 
     let request: AuthorizationRequest = serde_json::from_str(&request_json)
@@ -174,6 +227,7 @@ pub async fn handle_dc_api_request(
         responder,
         request_object,
         request_match,
+        verifier_trust: wallet_activity.verifier_trust.take(),
     })
 }
 
@@ -187,6 +241,10 @@ impl InProgressRequestDcApi {
         self.origin.clone()
     }
 
+    pub fn verifier_trust(&self) -> VerifierTrustResult {
+        self.verifier_trust.clone()
+    }
+
     /// Generate a response for the request.
     ///
     /// The response is either a JWE or a serialized JSON Object.
@@ -195,6 +253,10 @@ impl InProgressRequestDcApi {
         keystore: Arc<dyn KeyStore>,
         approved_fields: Vec<FieldId180137>,
     ) -> Result<String, DcApiError> {
+        self.verifier_trust
+            .ensure_answerable()
+            .map_err(DcApiError::invalid_request)?;
+
         // Per OID4VP v1.0 §B.2.6.2, the DC API Handover uses [origin, nonce, jwkThumbprint].
         // jwkThumbprint is the SHA-256 thumbprint of the verifier's encryption key,
         // or null if the response is not encrypted.
@@ -0,0 +1,465 @@
+//! Builders for DCQL queries, for verifiers to request credentials without
+//! writing the query JSON by hand.
+//!
+//! The built query is validated against the DCQL rules that JSON
+//! deserialization does not enforce (unique IDs, references of claim and
+//! credential sets), and parsed as a [DcqlQuery] before being returned.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use openid4vp::core::dcql_query::DcqlQuery;
+use serde_json::{json, Map, Value as Json};
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum DcqlQueryBuilderError {
+    #[error("Invalid identifier `{0}`: only alphanumeric characters, `_` and `-` are allowed")]
+    InvalidId(String),
+    #[error("Identifier `{0}` is used more than once")]
+    DuplicateId(String),
+    #[error("Unknown credential query `{0}`")]
+    UnknownCredential(String),
+    #[error("Unknown claim `{claim}` in credential query `{credential}`")]
+    UnknownClaim { credential: String, claim: String },
+    #[error("Invalid DCQL query: {0}")]
+    Invalid(String),
+}
+
+/// A segment of a claim path.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum DcqlClaimPathSegment {
+    /// An object key.
+    Key(String),
+    /// An array index.
+    Index(u64),
+    /// All the elements of an array.
+    Wildcard,
+}
+
+/// An accepted value of a claim.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum DcqlClaimValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+/// A claim of a credential query.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct DcqlClaim {
+    /// Required to refer to the claim from a claim set.
+    pub id: Option<String>,
+    /// Path of the claim. For mdocs, the namespace followed by the element
+    /// identifier.
+    pub path: Vec<DcqlClaimPathSegment>,
+    /// The accepted values, if the claim is restricted to some.
+    pub values: Vec<DcqlClaimValue>,
+    /// Whether the verifier intends to retain the claim. Only applies to
+    /// mdocs.
+    pub intent_to_retain: Option<bool>,
+}
+
+impl DcqlClaim {
+    fn to_json(&self) -> Json {
+        let mut claim = Map::new();
+        if let Some(id) = &self.id {
+            claim.insert("id".into(), id.clone().into());
+        }
+        claim.insert(
+            "path".into(),
+            self.path
+                .iter()
+                .map(|segment| match segment {
+                    DcqlClaimPathSegment::Key(key) => Json::from(key.clone()),
+                    DcqlClaimPathSegment::Index(index) => Json::from(*index),
+                    DcqlClaimPathSegment::Wildcard => Json::Null,
+                })
+                .collect(),
+        );
+        if !self.values.is_empty() {
+            claim.insert(
+                "values".into(),
+                self.values
+                    .iter()
+                    .map(|value| match value {
+                        DcqlClaimValue::String(value) => Json::from(value.clone()),
+                        DcqlClaimValue::Integer(value) => Json::from(*value),
+                        DcqlClaimValue::Boolean(value) => Json::from(*value),
+                    })
+                    .collect(),
+            );
+        }
+        if let Some(intent_to_retain) = self.intent_to_retain {
+            claim.insert("intent_to_retain".into(), intent_to_retain.into());
+        }
+        Json::Object(claim)
+    }
+}
+
+/// Builder of a credential query, started from the format of the requested
+/// credential.
+#[derive(Debug, Clone, uniffi::Object)]
+pub struct DcqlCredentialQueryBuilder {
+    id: String,
+    format: String,
+    meta: Json,
+    multiple: bool,
+    claims: Vec<DcqlClaim>,
+    claim_sets: Vec<Vec<String>>,
+}
+
+impl DcqlCredentialQueryBuilder {
+    fn with_format(id: String, format: &str, meta: Json) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            format: format.into(),
+            meta,
+            multiple: false,
+            claims: Vec::new(),
+            claim_sets: Vec::new(),
+        })
+    }
+
+    fn validate(&self) -> Result<(), DcqlQueryBuilderError> {
+        validate_id(&self.id)?;
+
+        let mut claim_ids = HashSet::new();
+        for id in self.claims.iter().filter_map(|claim| claim.id.as_ref()) {
+            validate_id(id)?;
+            if !claim_ids.insert(id.as_str()) {
+                return Err(DcqlQueryBuilderError::DuplicateId(id.clone()));
+            }
+        }
+
+        for claim in self.claim_sets.iter().flatten() {
+            if !claim_ids.contains(claim.as_str()) {
+                return Err(DcqlQueryBuilderError::UnknownClaim {
+                    credential: self.id.clone(),
+                    claim: claim.clone(),
+                });
+            }
+        }
+
+        if self.claims.iter().any(|claim| claim.path.is_empty()) {
+            return Err(DcqlQueryBuilderError::Invalid(format!(
+                "a claim of `{}` has an empty path",
+                self.id
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn to_json(&self) -> Json {
+        let mut query = json!({
+            "id": self.id,
+            "format": self.format,
+            "meta": self.meta,
+        });
+        if self.multiple {
+            query["multiple"] = true.into();
+        }
+        if !self.claims.is_empty() {
+            query["claims"] = self.claims.iter().map(DcqlClaim::to_json).collect();
+        }
+        if !self.claim_sets.is_empty() {
+            query["claim_sets"] = json!(self.claim_sets);
+        }
+        query
+    }
+}
+
+#[uniffi::export]
+impl DcqlCredentialQueryBuilder {
+    /// Request an `mso_mdoc` credential of the given doctype.
+    #[uniffi::constructor]
+    pub fn mso_mdoc(id: String, doctype: String) -> Arc<Self> {
+        Self::with_format(id, "mso_mdoc", json!({ "doctype_value": doctype }))
+    }
+
+    /// Request a `dc+sd-jwt` credential of one of the given types.
+    #[uniffi::constructor]
+    pub fn dc_sd_jwt(id: String, vct_values: Vec<String>) -> Arc<Self> {
+        Self::with_format(id, "dc+sd-jwt", json!({ "vct_values": vct_values }))
+    }
+
+    /// Request an `ldp_vc` credential. Each element of `type_values` is a
+    /// set of types the credential must all have.
+    #[uniffi::constructor]
+    pub fn ldp_vc(id: String, type_values: Vec<Vec<String>>) -> Arc<Self> {
+        Self::with_format(id, "ldp_vc", json!({ "type_values": type_values }))
+    }
+
+    /// Allow several credentials to be presented for the query.
+    pub fn multiple(self: Arc<Self>, multiple: bool) -> Arc<Self> {
+        Arc::new(Self {
+            multiple,
+            ..(*self).clone()
+        })
+    }
+
+    pub fn add_claim(self: Arc<Self>, claim: DcqlClaim) -> Arc<Self> {
+        let mut claims = self.claims.clone();
+        claims.push(claim);
+        Arc::new(Self {
+            claims,
+            ..(*self).clone()
+        })
+    }
+
+    /// Add an alternative set of claims, by ID, satisfying the query. The
+    /// first claim sets are preferred.
+    pub fn add_claim_set(self: Arc<Self>, claim_ids: Vec<String>) -> Arc<Self> {
+        let mut claim_sets = self.claim_sets.clone();
+        claim_sets.push(claim_ids);
+        Arc::new(Self {
+            claim_sets,
+            ..(*self).clone()
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DcqlCredentialSet {
+    options: Vec<Vec<String>>,
+    required: bool,
+}
+
+/// Builder of a DCQL query.
+#[derive(Debug, Clone, Default, uniffi::Object)]
+pub struct DcqlQueryBuilder {
+    credentials: Vec<Arc<DcqlCredentialQueryBuilder>>,
+    credential_sets: Vec<DcqlCredentialSet>,
+}
+
+#[uniffi::export]
+impl DcqlQueryBuilder {
+    #[uniffi::constructor]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_credential(
+        self: Arc<Self>,
+        credential: Arc<DcqlCredentialQueryBuilder>,
+    ) -> Arc<Self> {
+        let mut credentials = self.credentials.clone();
+        credentials.push(credential);
+        Arc::new(Self {
+            credentials,
+            ..(*self).clone()
+        })
+    }
+
+    /// Add a set of alternative combinations of credential queries, by ID.
+    ///
+    /// Once credential sets are added, credential queries are only requested
+    /// through them.
+    pub fn add_credential_set(
+        self: Arc<Self>,
+        options: Vec<Vec<String>>,
+        required: bool,
+    ) -> Arc<Self> {
+        let mut credential_sets = self.credential_sets.clone();
+        credential_sets.push(DcqlCredentialSet { options, required });
+        Arc::new(Self {
+            credential_sets,
+            ..(*self).clone()
+        })
+    }
+
+    /// Return the query as JSON, as expected in the `dcql_query` parameter of
+    /// an authorization request.
+    pub fn build(self: Arc<Self>) -> Result<String, DcqlQueryBuilderError> {
+        if self.credentials.is_empty() {
+            return Err(DcqlQueryBuilderError::Invalid(
+                "at least one credential query is required".into(),
+            ));
+        }
+
+        let mut credential_ids = HashSet::new();
+        for credential in &self.credentials {
+            credential.validate()?;
+            if !credential_ids.insert(credential.id.as_str()) {
+                return Err(DcqlQueryBuilderError::DuplicateId(credential.id.clone()));
+            }
+        }
+
+        for set in &self.credential_sets {
+            if set.options.is_empty() || set.options.iter().any(Vec::is_empty) {
+                return Err(DcqlQueryBuilderError::Invalid(
+                    "credential set options cannot be empty".into(),
+                ));
+            }
+            if let Some(id) = set
+                .options
+                .iter()
+                .flatten()
+                .find(|id| !credential_ids.contains(id.as_str()))
+            {
+                return Err(DcqlQueryBuilderError::UnknownCredential(id.clone()));
+            }
+        }
+
+        let mut query = json!({
+            "credentials": self
+                .credentials
+                .iter()
+                .map(|credential| credential.to_json())
+                .collect::<Vec<_>>(),
+        });
+        if !self.credential_sets.is_empty() {
+            query["credential_sets"] = self
+                .credential_sets
+                .iter()
+                .map(|set| json!({ "options": set.options, "required": set.required }))
+                .collect();
+        }
+
+        let query: DcqlQuery = serde_json::from_value(query)
+            .map_err(|e| DcqlQueryBuilderError::Invalid(e.to_string()))?;
+        serde_json::to_string(&query).map_err(|e| DcqlQueryBuilderError::Invalid(e.to_string()))
+    }
+}
+
+/// DCQL identifiers are non-empty strings of alphanumeric characters, `_` and
+/// `-`.
+fn validate_id(id: &str) -> Result<(), DcqlQueryBuilderError> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(DcqlQueryBuilderError::InvalidId(id.into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(id: Option<&str>, path: &[&str]) -> DcqlClaim {
+        DcqlClaim {
+            id: id.map(Into::into),
+            path: path
+                .iter()
+                .map(|segment| DcqlClaimPathSegment::Key(segment.to_string()))
+                .collect(),
+            values: vec![],
+            intent_to_retain: None,
+        }
+    }
+
+    #[test]
+    fn test_build_dcql_query() {
+        let mdl =
+            DcqlCredentialQueryBuilder::mso_mdoc("mdl".into(), "org.iso.18013.5.1.mDL".into())
+                .add_claim(DcqlClaim {
+                    intent_to_retain: Some(false),
+                    ..claim(None, &["org.iso.18013.5.1", "age_over_21"])
+                });
+
+        let pid =
+            DcqlCredentialQueryBuilder::dc_sd_jwt("pid".into(), vec!["urn:eudi:pid:1".into()])
+                .add_claim(claim(Some("name"), &["family_name"]))
+                .add_claim(claim(Some("birthdate"), &["birthdate"]))
+                .add_claim(DcqlClaim {
+                    values: vec![DcqlClaimValue::String("US".into())],
+                    ..claim(Some("nationality"), &["nationalities"])
+                })
+                .add_claim(DcqlClaim {
+                    path: vec![
+                        DcqlClaimPathSegment::Key("address".into()),
+                        DcqlClaimPathSegment::Wildcard,
+                        DcqlClaimPathSegment::Index(0),
+                    ],
+                    ..claim(Some("address"), &[])
+                })
+                .add_claim_set(vec!["name".into(), "birthdate".into()])
+                .add_claim_set(vec!["nationality".into()]);
+
+        let degree = DcqlCredentialQueryBuilder::ldp_vc(
+            "degree".into(),
+            vec![vec![
+                "VerifiableCredential".into(),
+                "UniversityDegreeCredential".into(),
+            ]],
+        )
+        .multiple(true);
+
+        let query = Arc::new(DcqlQueryBuilder::new())
+            .add_credential(mdl)
+            .add_credential(pid)
+            .add_credential(degree)
+            .add_credential_set(vec![vec!["mdl".into()], vec!["pid".into()]], true)
+            .add_credential_set(vec![vec!["degree".into()]], false)
+            .build()
+            .unwrap();
+
+        let parsed: DcqlQuery = serde_json::from_str(&query).unwrap();
+        assert_eq!(parsed.credentials().len(), 3);
+        let credential_sets = parsed.credential_sets().unwrap();
+        assert!(credential_sets[0].is_required());
+        assert!(!credential_sets[1].is_required());
+
+        let query: Json = serde_json::from_str(&query).unwrap();
+        assert_eq!(
+            query["credentials"][0]["meta"]["doctype_value"],
+            "org.iso.18013.5.1.mDL"
+        );
+        assert_eq!(
+            query["credentials"][0]["claims"][0]["intent_to_retain"],
+            false
+        );
+        assert_eq!(
+            query["credentials"][1]["claims"][2]["values"],
+            json!(["US"])
+        );
+        assert_eq!(
+            query["credentials"][1]["claims"][3]["path"],
+            json!(["address", null, 0])
+        );
+        assert_eq!(
+            query["credentials"][1]["claim_sets"],
+            json!([["name", "birthdate"], ["nationality"]])
+        );
+        assert_eq!(query["credentials"][2]["multiple"], true);
+    }
+
+    #[test]
+    fn test_build_invalid_dcql_query() {
+        let pid = || {
+            DcqlCredentialQueryBuilder::dc_sd_jwt("pid".into(), vec!["urn:eudi:pid:1".into()])
+                .add_claim(claim(Some("name"), &["family_name"]))
+        };
+
+        let result = Arc::new(DcqlQueryBuilder::new())
+            .add_credential(pid())
+            .add_credential(pid())
+            .build();
+        assert!(matches!(result, Err(DcqlQueryBuilderError::DuplicateId(id)) if id == "pid"));
+
+        let result = Arc::new(DcqlQueryBuilder::new())
+            .add_credential(pid().add_claim_set(vec!["address".into()]))
+            .build();
+        assert!(matches!(
+            result,
+            Err(DcqlQueryBuilderError::UnknownClaim { claim, .. }) if claim == "address"
+        ));
+
+        let result = Arc::new(DcqlQueryBuilder::new())
+            .add_credential(pid())
+            .add_credential_set(vec![vec!["mdl".into()]], true)
+            .build();
+        assert!(matches!(result, Err(DcqlQueryBuilderError::UnknownCredential(id)) if id == "mdl"));
+
+        let result = Arc::new(DcqlQueryBuilder::new())
+            .add_credential(DcqlCredentialQueryBuilder::dc_sd_jwt(
+                "p i d".into(),
+                vec![],
+            ))
+            .build();
+        assert!(matches!(result, Err(DcqlQueryBuilderError::InvalidId(_))));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::credential::CredentialEncodingError;
+use crate::{credential::CredentialEncodingError, oid4vp::trust::VerifierTrustError};
 
 use super::{
     permission_request::Draft18PermissionRequestError, presentation::Draft18PresentationError,
@@ -59,6 +59,8 @@ pub enum Draft18OID4VPError {
     #[error(transparent)]
     Presentation(#[from] Draft18PresentationError),
     #[error(transparent)]
+    VerifierTrust(#[from] VerifierTrustError),
+    #[error(transparent)]
     CredentialEncoding(#[from] CredentialEncodingError),
     #[error("Failed to parse JsonPath: {0}")]
     JsonPathParse(String),
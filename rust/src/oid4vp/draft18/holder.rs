@@ -5,6 +5,7 @@ use super::error::Draft18OID4VPError;
 use super::permission_request::*;
 use super::presentation::Draft18PresentationSigner;
 use crate::credential::ParsedCredential;
+use crate::oid4vp::trust::{VerifierTrustResult, VerifierTrustStore};
 use crate::vdc_collection::VdcCollection;
 
 use std::collections::HashMap;
@@ -69,7 +70,7 @@ uniffi::custom_type!(Draft18AuthRequest, String, {
 #[deprecated(
     note = "Legacy draft-18 compatibility only. Prefer OID4VP v1 APIs for new integrations; this API may be removed in a future release."
 )]
-#[derive(Debug, uniffi::Object)]
+#[derive(Debug, Clone, uniffi::Object)]
 pub struct Draft18Holder {
     /// An atomic reference to the VDC collection.
     pub(crate) vdc_collection: Option<Arc<VdcCollection>>,
@@ -80,8 +81,9 @@ pub struct Draft18Holder {
     /// HTTP Request Client
     pub(crate) client: openidvp_draft18::core::util::ReqwestClient,
 
-    /// A list of trusted DIDs.
-    pub(crate) trusted_dids: Vec<String>,
+    /// The verifiers trusted by the holder, reported with each request. Its
+    /// DIDs, if any, are the only ones allowed to sign requests.
+    pub(crate) trust_store: Arc<VerifierTrustStore>,
 
     /// Provide optional credentials to the holder instance.
     pub(crate) provided_credentials: Option<Vec<Arc<ParsedCredential>>>,
@@ -124,7 +126,7 @@ impl Draft18Holder {
             client,
             vdc_collection: Some(vdc_collection),
             metadata: Self::metadata()?,
-            trusted_dids,
+            trust_store: Arc::new(VerifierTrustStore::from_dids(trusted_dids)),
             provided_credentials: None,
             signer: Arc::new(signer),
             context_map: with_default_contexts(context_map),
@@ -150,13 +152,24 @@ impl Draft18Holder {
             client,
             vdc_collection: None,
             metadata: Self::metadata()?,
-            trusted_dids,
+            trust_store: Arc::new(VerifierTrustStore::from_dids(trusted_dids)),
             provided_credentials: Some(provided_credentials),
             signer: Arc::new(signer),
             context_map: with_default_contexts(context_map),
         }))
     }
 
+    /// Return a holder evaluating the verifiers of requests against the given
+    /// trust store, replacing the trusted DIDs it was constructed with.
+    ///
+    /// The outcome is reported by [Draft18PermissionRequest::verifier_trust].
+    pub fn with_trust_store(self: Arc<Self>, trust_store: Arc<VerifierTrustStore>) -> Arc<Self> {
+        Arc::new(Self {
+            trust_store,
+            ..(*self).clone()
+        })
+    }
+
     /// Given an authorization request URL, return a permission request,
     /// which provides a list of requested credentials and requested fields
     /// that align with the presentation definition of the request.
@@ -166,24 +179,29 @@ impl Draft18Holder {
         &self,
         req: Draft18AuthRequest,
     ) -> Result<Arc<Draft18PermissionRequest>, Draft18OID4VPError> {
-        let request = match req {
+        let (request, verifier_trust) = match req {
             Draft18AuthRequest::Url(mut url) => {
                 // NOTE: Replace the host value with an empty string to remove any
                 // leading host value before the query.
                 url.set_host(Some(""))
                     .map_err(|e| Draft18OID4VPError::RequestValidation(format!("{e:?}")))?;
 
-                self.validate_request(url)
+                let request = self
+                    .validate_request(url)
                     .await
-                    .map_err(|e| Draft18OID4VPError::RequestValidation(format!("{e:?}")))?
+                    .map_err(|e| Draft18OID4VPError::RequestValidation(format!("{e:?}")))?;
+                let verifier_trust = self.verifier_trust(&request);
+
+                (request, verifier_trust)
             }
-            Draft18AuthRequest::Request(req) => *req,
+            Draft18AuthRequest::Request(req) => (*req, VerifierTrustResult::unverified()),
         };
 
         match request.response_mode() {
-            ResponseMode::DirectPost | ResponseMode::DirectPostJwt => {
-                self.permission_request(request).await
-            }
+            ResponseMode::DirectPost | ResponseMode::DirectPostJwt => Ok(self
+                .permission_request(request)
+                .await?
+                .with_verifier_trust(verifier_trust)),
             mode => Err(Draft18OID4VPError::UnsupportedResponseMode(
                 mode.to_string(),
             )),
@@ -196,14 +214,46 @@ impl Draft18Holder {
     ) -> Result<Option<Url>, Draft18OID4VPError> {
         let auth_response = response.authorization_response()?;
 
-        self.submit_response(response.authorization_request.clone(), auth_response)
+        let url = self
+            .submit_response(response.authorization_request.clone(), auth_response)
             .await
-            .map_err(|e| Draft18OID4VPError::ResponseSubmission(format!("{e:?}")))
+            .map_err(|e| Draft18OID4VPError::ResponseSubmission(format!("{e:?}")))?;
+
+        if let Some(vdc_collection) = &self.vdc_collection {
+            let request = &response.authorization_request;
+            vdc_collection
+                .clone()
+                .activity_recorder()
+                .record_shared(
+                    request.client_id().map(|id| id.0.clone()).as_deref(),
+                    Some(request.return_uri().to_string()),
+                    response.shared_fields(),
+                )
+                .await;
+        }
+
+        Ok(url)
     }
 }
 
 // Internal methods for the Holder.
 impl Draft18Holder {
+    /// Evaluate the trust in the verifier of a validated request.
+    ///
+    /// The `did` and `redirect_uri` client ID schemes of draft 18 are
+    /// evaluated as their OID4VP 1.0 prefixes.
+    fn verifier_trust(&self, request: &AuthorizationRequestObject) -> VerifierTrustResult {
+        match request.client_id() {
+            Some(client_id) if client_id.0.starts_with("did:") => self
+                .trust_store
+                .evaluate_did("decentralized_identifier", &client_id.0),
+            _ => self.trust_store.evaluate_unauthenticated(
+                "redirect_uri",
+                "the verifier is only identified by its redirect URI",
+            ),
+        }
+    }
+
     /// Return the static metadata for the holder.
     ///
     /// This method is used to initialize the metadata for the holder.
@@ -393,16 +443,11 @@ impl RequestVerifier for Draft18Holder {
         let resolver: VerificationMethodDIDResolver<DIDWeb, AnyJwkMethod> =
             VerificationMethodDIDResolver::new(DIDWeb);
 
-        let trusted_dids = match self.trusted_dids.as_slice() {
-            [] => None,
-            dids => Some(dids),
-        };
-
         verify_with_resolver(
             &self.metadata,
             decoded_request,
             request_jwt,
-            trusted_dids,
+            self.trust_store.allowed_dids(),
             &resolver,
         )
         .await?;
@@ -423,16 +468,11 @@ impl RequestVerifier for Draft18Holder {
         let resolver: VerificationMethodDIDResolver<DIDKey, AnyJwkMethod> =
             VerificationMethodDIDResolver::new(DIDKey);
 
-        let trusted_dids = match self.trusted_dids.as_slice() {
-            [] => None,
-            dids => Some(dids),
-        };
-
         verify_with_resolver(
             &self.metadata,
             decoded_request,
             request_jwt,
-            trusted_dids,
+            self.trust_store.allowed_dids(),
             &resolver,
         )
         .await?;
@@ -6,6 +6,7 @@ use super::presentation::{
     Draft18PresentationError, Draft18PresentationOptions, Draft18PresentationSigner,
 };
 use crate::credential::{Credential, ParsedCredential};
+use crate::oid4vp::trust::VerifierTrustResult;
 
 use std::collections::HashMap;
 use std::fmt::Debug;
@@ -20,6 +21,16 @@ use openidvp_draft18::core::response::parameters::VpToken;
 use openidvp_draft18::core::response::{AuthorizationResponse, UnencodedAuthorizationResponse};
 use uuid::Uuid;
 
+/// Decode the path of a [Draft18RequestedField] into the path recorded in the
+/// activity log: the first of its alternative JSONPaths.
+fn recorded_field_path(path: &str) -> String {
+    path.split(',')
+        .next()
+        .and_then(|path| URL_SAFE.decode(path).ok())
+        .and_then(|path| String::from_utf8(path).ok())
+        .unwrap_or_else(|| path.to_string())
+}
+
 /// Type alias for mapping input descriptor ids to matching credentials
 /// stored in the VDC collection. This mapping is used to provide a
 /// shared state between native code and the rust code, to select
@@ -172,6 +183,7 @@ pub struct Draft18PermissionRequest {
     pub(crate) request: AuthorizationRequestObject,
     pub(crate) signer: Arc<Box<dyn Draft18PresentationSigner>>,
     pub(crate) context_map: Option<HashMap<String, String>>,
+    pub(crate) verifier_trust: VerifierTrustResult,
 }
 
 impl Draft18PermissionRequest {
@@ -188,6 +200,18 @@ impl Draft18PermissionRequest {
             request,
             signer,
             context_map,
+            verifier_trust: VerifierTrustResult::unverified(),
+        })
+    }
+
+    /// Set the trust in the verifier of the request, evaluated by the holder.
+    pub(crate) fn with_verifier_trust(
+        self: Arc<Self>,
+        verifier_trust: VerifierTrustResult,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            verifier_trust,
+            ..(*self).clone()
         })
     }
 }
@@ -230,11 +254,18 @@ impl Draft18PermissionRequest {
         self.request.return_uri().domain().map(ToOwned::to_owned)
     }
 
+    /// Return the trust in the verifier of the request.
+    pub fn verifier_trust(&self) -> VerifierTrustResult {
+        self.verifier_trust.clone()
+    }
+
     /// Construct a new permission response for the given credential.
     ///
     /// NOTE: `should_strip_quotes` is a non-normative setting to determine
     /// the behavior of removing extra quotations around a JSON
     /// string encoded vp_token, e.g. "'[{ @context: [...] }]'" -> '[{ @context: [...] }]'
+    ///
+    /// Fails if the verifier is [VerifierTrustResult::Invalid].
     pub async fn create_permission_response(
         &self,
         selected_credentials: Vec<Arc<Draft18PresentableCredential>>,
@@ -243,6 +274,8 @@ impl Draft18PermissionRequest {
     ) -> Result<Arc<Draft18PermissionResponse>, Draft18OID4VPError> {
         log::debug!("Creating Permission Response");
 
+        self.verifier_trust.ensure_answerable()?;
+
         // Ensure that the selected credentials are not empty.
         if selected_credentials.is_empty() {
             return Err(Draft18PermissionRequestError::InvalidSelectedCredential(
@@ -461,4 +494,23 @@ impl Draft18PermissionResponse {
             self.create_descriptor_map()?,
         ))
     }
+
+    /// The IDs of the selected credentials, with the paths of their disclosed
+    /// fields.
+    pub(crate) fn shared_fields(&self) -> Vec<(Uuid, Vec<String>)> {
+        self.selected_credentials
+            .iter()
+            .map(|credential| {
+                (
+                    credential.as_parsed_credential().id(),
+                    credential
+                        .selected_fields
+                        .iter()
+                        .flatten()
+                        .map(|path| recorded_field_path(path))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
 }
@@ -2,7 +2,11 @@
 
 use crate::credential::CredentialEncodingError;
 
-use super::{permission_request::PermissionRequestError, presentation::PresentationError};
+use super::{
+    permission_request::PermissionRequestError, presentation::PresentationError,
+    response_encryption::ResponseEncryptionError, transaction_data::TransactionDataError,
+    trust::VerifierTrustError, verifier_attestation::VerifierAttestationError,
+};
 
 /// The [OID4VPError] enum represents the errors that can occur
 /// when using the oid4vp foreign library.
@@ -59,6 +63,14 @@ pub enum OID4VPError {
     #[error(transparent)]
     Presentation(#[from] PresentationError),
     #[error(transparent)]
+    TransactionData(#[from] TransactionDataError),
+    #[error(transparent)]
+    VerifierAttestation(#[from] VerifierAttestationError),
+    #[error(transparent)]
+    VerifierTrust(#[from] VerifierTrustError),
+    #[error(transparent)]
+    ResponseEncryption(#[from] ResponseEncryptionError),
+    #[error(transparent)]
     CredentialEncoding(#[from] CredentialEncodingError),
     #[error("Failed to parse JsonPath: {0}")]
     JsonPathParse(String),
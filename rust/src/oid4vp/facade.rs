@@ -23,7 +23,9 @@ use super::permission_request::{
     PermissionRequest, PermissionRequestError, PermissionResponse, RequestedField, ResponseOptions,
 };
 use super::presentation::{PresentationError, PresentationSigner};
-use super::{select_oid4vp_version, Oid4vpVersion};
+use super::trust::{VerifierTrustResult, VerifierTrustStore};
+use super::verifier_attestation::VerifierAttestation;
+use super::{select_oid4vp_version, Oid4vpVersion, CLIENT_ID_PREFIXES};
 use crate::oid4vp::draft18::error::Draft18OID4VPError;
 use crate::oid4vp::error::OID4VPError;
 use base64::prelude::{Engine as _, BASE64_URL_SAFE_NO_PAD};
@@ -78,6 +80,10 @@ pub struct Oid4vpRequestedField {
     pub retained: bool,
     pub purpose: Option<String>,
     pub raw_fields: Vec<String>,
+    /// Whether the verifier requests the field without being entitled to it,
+    /// according to its trusted attestations. Always `false` for Draft 18 /
+    /// Draft 13 sessions.
+    pub exceeds_entitlement: bool,
 }
 
 #[deprecated(
@@ -91,7 +97,35 @@ pub struct Oid4vpRequirement {
     pub credentials: Vec<Arc<Oid4vpPresentableCredential>>,
 }
 
-#[derive(Debug)]
+/// What can be told about a request before anything is fetched, for a first
+/// confirmation by the user.
+///
+/// Parameters carried only by a request object passed by reference are
+/// unknown until it is fetched, and are `None`. Parameters read from a request
+/// object passed by value are not verified yet.
+#[deprecated(
+    note = "Compatibility facade for legacy OID4VP integrations only. Prefer the OID4VP v1 APIs for new integrations; this facade may be removed in a future release."
+)]
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Oid4vpRequestPreview {
+    /// The request as received, to pass to [Oid4vpHolder::start_previewed].
+    pub request: String,
+    pub client_id: Option<String>,
+    /// The client identifier scheme: the `client_id_scheme` parameter of
+    /// drafts, or the prefix of an OID4VP 1.0 `client_id`.
+    pub client_id_scheme: Option<String>,
+    pub response_mode: Option<String>,
+    /// Whether starting the session fetches the request object or the
+    /// presentation definition.
+    pub requires_fetch: bool,
+    pub request_uri: Option<String>,
+    pub request_uri_method: Option<String>,
+    /// The detected version, which the app may correct before starting the
+    /// session.
+    pub version: Oid4vpVersion,
+}
+
+#[derive(Debug, Clone)]
 enum Oid4vpHolderSource {
     Collection(Arc<VdcCollection>),
     Credentials(Vec<Arc<ParsedCredential>>),
@@ -100,10 +134,12 @@ enum Oid4vpHolderSource {
 #[deprecated(
     note = "Compatibility facade for legacy OID4VP integrations only. Prefer the OID4VP v1 APIs for new integrations; this facade may be removed in a future release."
 )]
-#[derive(uniffi::Object)]
+#[derive(Clone, uniffi::Object)]
 pub struct Oid4vpHolder {
     source: Oid4vpHolderSource,
     trusted_dids: Vec<String>,
+    /// Overrides the trust store built from `trusted_dids`.
+    trust_store: Option<Arc<VerifierTrustStore>>,
     signer: Arc<Box<dyn Oid4vpPresentationSigner>>,
     context_map: Option<HashMap<String, String>>,
     keystore: Option<Arc<dyn KeyStore>>,
@@ -114,6 +150,7 @@ impl std::fmt::Debug for Oid4vpHolder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Oid4vpHolder")
             .field("trusted_dids", &self.trusted_dids)
+            .field("trust_store", &self.trust_store)
             .field("context_map", &self.context_map)
             .field("keystore", &self.keystore.as_ref().map(|_| "KeyStore"))
             .field("providers", &self.providers.len())
@@ -297,6 +334,7 @@ impl Oid4vpHolder {
         Ok(Arc::new(Self {
             source: Oid4vpHolderSource::Collection(vdc_collection),
             trusted_dids,
+            trust_store: None,
             signer: Arc::new(signer),
             context_map,
             keystore,
@@ -341,6 +379,7 @@ impl Oid4vpHolder {
         Ok(Arc::new(Self {
             source: Oid4vpHolderSource::Credentials(provided_credentials),
             trusted_dids,
+            trust_store: None,
             signer: Arc::new(signer),
             context_map,
             keystore,
@@ -348,6 +387,42 @@ impl Oid4vpHolder {
         }))
     }
 
+    /// Return a holder evaluating the verifiers of requests against the given
+    /// trust store, instead of only the trusted DIDs.
+    ///
+    /// The outcome is reported by [Oid4vpSession::verifier_trust].
+    pub fn with_trust_store(self: Arc<Self>, trust_store: Arc<VerifierTrustStore>) -> Arc<Self> {
+        Arc::new(Self {
+            trust_store: Some(trust_store),
+            ..(*self).clone()
+        })
+    }
+
+    /// Describe the request without any network access, notably without
+    /// fetching a single-use `request_uri`.
+    ///
+    /// The session is then started with [Oid4vpHolder::start_previewed], once
+    /// the user confirms.
+    pub fn preview_request(
+        &self,
+        request: String,
+    ) -> Result<Oid4vpRequestPreview, Oid4vpFacadeError> {
+        preview_request(request)
+    }
+
+    /// Start a session for a previewed request, fetching and verifying it with
+    /// the version of the preview.
+    pub async fn start_previewed(
+        &self,
+        preview: Oid4vpRequestPreview,
+    ) -> Result<Arc<Oid4vpSession>, Oid4vpFacadeError> {
+        self.start_version(preview.version, &preview.request).await
+    }
+
+    /// Start a session, fetching and verifying the request right away.
+    ///
+    /// Use [Oid4vpHolder::preview_request] to confirm the request with the
+    /// user before any fetch.
     pub async fn start(&self, request: String) -> Result<Arc<Oid4vpSession>, Oid4vpFacadeError> {
         self.start_with_supported_versions(request, Vec::new())
             .await
@@ -438,17 +513,18 @@ impl Oid4vpHolder {
             signer: self.signer.clone(),
         });
 
-        match &self.source {
-            Oid4vpHolderSource::Collection(vdc_collection) => Holder::new_with_providers(
-                vdc_collection.clone(),
-                self.trusted_dids.clone(),
-                signer,
-                self.context_map.clone(),
-                self.keystore.clone(),
-                self.providers.clone(),
-            )
-            .await
-            .map_err(Into::into),
+        let holder = match &self.source {
+            Oid4vpHolderSource::Collection(vdc_collection) => {
+                Holder::new_with_providers(
+                    vdc_collection.clone(),
+                    self.trusted_dids.clone(),
+                    signer,
+                    self.context_map.clone(),
+                    self.keystore.clone(),
+                    self.providers.clone(),
+                )
+                .await?
+            }
             Oid4vpHolderSource::Credentials(credentials) => {
                 Holder::new_with_credentials_and_providers(
                     credentials.clone(),
@@ -458,10 +534,14 @@ impl Oid4vpHolder {
                     self.keystore.clone(),
                     self.providers.clone(),
                 )
-                .await
-                .map_err(Into::into)
+                .await?
             }
-        }
+        };
+
+        Ok(match &self.trust_store {
+            Some(trust_store) => holder.with_trust_store(trust_store.clone()),
+            None => holder,
+        })
     }
 
     async fn new_draft18_holder(&self) -> Result<Arc<Draft18Holder>, Oid4vpFacadeError> {
@@ -469,24 +549,31 @@ impl Oid4vpHolder {
             signer: self.signer.clone(),
         });
 
-        match &self.source {
-            Oid4vpHolderSource::Collection(vdc_collection) => Draft18Holder::new(
-                vdc_collection.clone(),
-                self.trusted_dids.clone(),
-                signer,
-                self.context_map.clone(),
-            )
-            .await
-            .map_err(Into::into),
-            Oid4vpHolderSource::Credentials(credentials) => Draft18Holder::new_with_credentials(
-                credentials.clone(),
-                self.trusted_dids.clone(),
-                signer,
-                self.context_map.clone(),
-            )
-            .await
-            .map_err(Into::into),
-        }
+        let holder = match &self.source {
+            Oid4vpHolderSource::Collection(vdc_collection) => {
+                Draft18Holder::new(
+                    vdc_collection.clone(),
+                    self.trusted_dids.clone(),
+                    signer,
+                    self.context_map.clone(),
+                )
+                .await?
+            }
+            Oid4vpHolderSource::Credentials(credentials) => {
+                Draft18Holder::new_with_credentials(
+                    credentials.clone(),
+                    self.trusted_dids.clone(),
+                    signer,
+                    self.context_map.clone(),
+                )
+                .await?
+            }
+        };
+
+        Ok(match &self.trust_store {
+            Some(trust_store) => holder.with_trust_store(trust_store.clone()),
+            None => holder,
+        })
     }
 }
 
@@ -514,6 +601,22 @@ impl Oid4vpSession {
         }
     }
 
+    /// Return the attestations about the verifier. Attestations are
+    /// OID4VP-v1 only.
+    pub fn verifier_attestations(&self) -> Vec<VerifierAttestation> {
+        match &self.inner {
+            Oid4vpSessionInner::V1 { request, .. } => request.verifier_attestations(),
+            Oid4vpSessionInner::Draft18 { .. } => vec![],
+        }
+    }
+
+    pub fn verifier_trust(&self) -> VerifierTrustResult {
+        match &self.inner {
+            Oid4vpSessionInner::V1 { request, .. } => request.verifier_trust(),
+            Oid4vpSessionInner::Draft18 { request, .. } => request.verifier_trust(),
+        }
+    }
+
     pub fn purpose(&self) -> Option<String> {
         match &self.inner {
             Oid4vpSessionInner::V1 { .. } => None,
@@ -590,8 +693,8 @@ impl Oid4vpSession {
             ) => Ok(request
                 .requested_fields(cred)
                 .into_iter()
-                .map(|field| oid4vp_requested_field_from_v1(&field))
-                .collect()),
+                .map(|field| oid4vp_requested_field_from_v1(request, &field))
+                .collect::<Result<_, _>>()?),
             (
                 Oid4vpSessionInner::Draft18 { request, .. },
                 Oid4vpPresentableCredentialInner::Draft18(cred),
@@ -825,8 +928,11 @@ impl Oid4vpPresentableCredential {
     }
 }
 
-fn oid4vp_requested_field_from_v1(field: &Arc<RequestedField>) -> Oid4vpRequestedField {
-    Oid4vpRequestedField {
+fn oid4vp_requested_field_from_v1(
+    request: &PermissionRequest,
+    field: &Arc<RequestedField>,
+) -> Result<Oid4vpRequestedField, OID4VPError> {
+    Ok(Oid4vpRequestedField {
         id: field.id(),
         match_id: field.credential_query_id(),
         name: field.name(),
@@ -835,7 +941,8 @@ fn oid4vp_requested_field_from_v1(field: &Arc<RequestedField>) -> Oid4vpRequeste
         retained: field.retained(),
         purpose: field.purpose(),
         raw_fields: field.raw_fields(),
-    }
+        exceeds_entitlement: request.exceeds_entitlement(field)?,
+    })
 }
 
 fn oid4vp_requested_field_from_draft18(field: &Arc<Draft18RequestedField>) -> Oid4vpRequestedField {
@@ -848,6 +955,7 @@ fn oid4vp_requested_field_from_draft18(field: &Arc<Draft18RequestedField>) -> Oi
         retained: field.retained(),
         purpose: field.purpose(),
         raw_fields: field.raw_fields(),
+        exceeds_entitlement: false,
     }
 }
 
@@ -886,6 +994,75 @@ fn draft18_requirements(request: &Arc<Draft18PermissionRequest>) -> Vec<Oid4vpRe
         .collect()
 }
 
+fn preview_request(request: String) -> Result<Oid4vpRequestPreview, Oid4vpFacadeError> {
+    let params = preview_params(&request)?;
+    let param = |name: &str| params.get(name).and_then(Value::as_str).map(str::to_string);
+
+    let client_id = param("client_id");
+    let client_id_scheme = param("client_id_scheme").or_else(|| {
+        client_id
+            .as_deref()
+            .and_then(|client_id| client_id.split_once(':'))
+            .map(|(prefix, _)| prefix)
+            .filter(|prefix| CLIENT_ID_PREFIXES.contains(prefix))
+            .map(str::to_string)
+    });
+    let request_uri = param("request_uri");
+
+    // The heuristics cannot see into a request object passed by value as a
+    // JWT: fall back to its decoded parameters.
+    let version = match select_oid4vp_version(&request, &[]) {
+        Oid4vpVersion::Unsupported => {
+            select_oid4vp_version(&Value::Object(params.clone()).to_string(), &[])
+        }
+        version => version,
+    };
+
+    Ok(Oid4vpRequestPreview {
+        client_id,
+        client_id_scheme,
+        response_mode: param("response_mode"),
+        requires_fetch: request_uri.is_some() || params.contains_key("presentation_definition_uri"),
+        request_uri,
+        request_uri_method: param("request_uri_method"),
+        version,
+        request,
+    })
+}
+
+/// Collect the parameters of a request without fetching anything: those of a
+/// link, or of a request object passed by value as JSON or as a JWT.
+fn preview_params(request: &str) -> Result<Map<String, Value>, Oid4vpFacadeError> {
+    let mut params = if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(request) {
+        map
+    } else if let Ok(url) = Url::parse(request) {
+        url.query_pairs()
+            .map(|(key, value)| (key.into_owned(), draft13_value(&value)))
+            .collect()
+    } else if let Some(claims) = decode_jwt_claims(request) {
+        claims
+    } else {
+        return Err(Oid4vpFacadeError::RequestParsing(
+            "unrecognized request shape".into(),
+        ));
+    };
+
+    // The parameters of a `request` object take precedence over the outer ones.
+    if let Some(Value::String(request_object)) = params.remove("request") {
+        let claims = decode_jwt_claims(&request_object)
+            .or_else(|| match serde_json::from_str::<Value>(&request_object) {
+                Ok(Value::Object(map)) => Some(map),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                Oid4vpFacadeError::RequestParsing("could not parse `request` object".into())
+            })?;
+        params.extend(claims);
+    }
+
+    Ok(params)
+}
+
 fn parse_v1_auth_request(request: &str) -> Result<AuthRequest, Oid4vpFacadeError> {
     match Url::parse(request) {
         Ok(url) => Ok(AuthRequest::Url(url)),
@@ -1016,21 +1193,32 @@ fn draft13_params_from_request_object(value: &str) -> Option<Map<String, Value>>
 
 /// Decode the claims of a compact JWS/JWT WITHOUT verifying its signature.
 /// Returns `None` if the input is not a 3-segment JWT or the payload is not a
-/// JSON object. Logs a warning when a signed (non-`alg:none`) token is decoded,
-/// because the draft-13 path does not verify request-object signatures.
-fn draft13_decode_jwt_claims(token: &str) -> Option<Map<String, Value>> {
+/// JSON object.
+fn decode_jwt_claims(token: &str) -> Option<Map<String, Value>> {
     let parts: Vec<&str> = token.trim().split('.').collect();
     if parts.len() != 3 {
         return None;
     }
     let payload = BASE64_URL_SAFE_NO_PAD.decode(parts[1]).ok()?;
-    let claims = match serde_json::from_slice::<Value>(&payload) {
-        Ok(Value::Object(map)) => map,
-        _ => return None,
-    };
+    match serde_json::from_slice::<Value>(&payload) {
+        Ok(Value::Object(map)) => Some(map),
+        _ => None,
+    }
+}
+
+/// Decode the claims of a draft-13 request object, see [decode_jwt_claims].
+/// Logs a warning when a signed (non-`alg:none`) token is decoded, because the
+/// draft-13 path does not verify request-object signatures.
+fn draft13_decode_jwt_claims(token: &str) -> Option<Map<String, Value>> {
+    let claims = decode_jwt_claims(token)?;
     // A non-empty signature segment means the verifier signed the request; the
     // draft-13 path accepts it without verification (see module comment).
-    if !parts[2].is_empty() {
+    if token
+        .trim()
+        .rsplit('.')
+        .next()
+        .is_some_and(|signature| !signature.is_empty())
+    {
         log::warn!(
             "OID4VP draft-13: accepting a signed request object WITHOUT signature \
              verification (the draft-13 compatibility path does not verify request \
@@ -1642,6 +1830,64 @@ mod tests {
         assert!(vp_token.contains("Example University"));
     }
 
+    #[test]
+    fn preview_does_not_fetch_request_uri() {
+        // The request_uri is unreachable: a fetch would fail the preview.
+        let preview = preview_request(
+            "openid4vp://?client_id=x509_san_dns%3Averifier.example&request_uri=https%3A%2F%2Fverifier.invalid%2Frequest%2Fabc&request_uri_method=post".into(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            preview.client_id.as_deref(),
+            Some("x509_san_dns:verifier.example")
+        );
+        assert_eq!(preview.client_id_scheme.as_deref(), Some("x509_san_dns"));
+        assert!(preview.requires_fetch);
+        assert_eq!(
+            preview.request_uri.as_deref(),
+            Some("https://verifier.invalid/request/abc")
+        );
+        assert_eq!(preview.request_uri_method.as_deref(), Some("post"));
+        assert_eq!(preview.response_mode, None);
+        assert_eq!(preview.version, Oid4vpVersion::V1);
+    }
+
+    #[test]
+    fn preview_reads_request_objects_passed_by_value() {
+        let claims = BASE64_URL_SAFE_NO_PAD.encode(
+            json!({
+                "client_id": "did:web:verifier.example",
+                "client_id_scheme": "did",
+                "response_mode": "direct_post",
+                "presentation_definition": { "input_descriptors": [] }
+            })
+            .to_string(),
+        );
+        let request = format!(
+            "openid4vp://?client_id=did%3Aweb%3Averifier.example&request=eyJhbGciOiJFUzI1NiJ9.{claims}.c2ln"
+        );
+
+        let preview = preview_request(request).unwrap();
+        assert_eq!(preview.client_id_scheme.as_deref(), Some("did"));
+        assert_eq!(preview.response_mode.as_deref(), Some("direct_post"));
+        assert!(!preview.requires_fetch);
+        assert_eq!(preview.version, Oid4vpVersion::Draft18);
+
+        let preview = preview_request(
+            r#"{"client_id":"redirect_uri:https://verifier.example/cb","response_mode":"direct_post.jwt","dcql_query":{"credentials":[]}}"#.into(),
+        )
+        .unwrap();
+        assert_eq!(preview.client_id_scheme.as_deref(), Some("redirect_uri"));
+        assert_eq!(preview.response_mode.as_deref(), Some("direct_post.jwt"));
+        assert_eq!(preview.version, Oid4vpVersion::V1);
+
+        assert!(matches!(
+            preview_request("not a request".into()),
+            Err(Oid4vpFacadeError::RequestParsing(_))
+        ));
+    }
+
     #[test]
     fn draft13_translation_moves_redirect_uri_and_response_mode() {
         // Unit-test the core translation: post -> direct_post, redirect_uri ->
@@ -4,6 +4,13 @@ use super::dynamic_credential::{DynamicCredentialOffer, DynamicCredentialProvide
 use super::error::OID4VPError;
 use super::permission_request::*;
 use super::presentation::PresentationSigner;
+use super::request_uri::{
+    check_wallet_nonce, fetch_request_object, generate_wallet_nonce, post_request_uri,
+    NegotiatedCapabilities,
+};
+use super::transaction_data::parse_transaction_data;
+use super::trust::{VerifierTrustRecorder, VerifierTrustResult, VerifierTrustStore};
+use super::verifier_attestation::verify_verifier_attestations;
 use crate::credential::*;
 use crate::crypto::KeyStore;
 use crate::vdc_collection::VdcCollection;
@@ -19,14 +26,15 @@ use openid4vp::core::dcql_query::DcqlQuery;
 use openid4vp::{
     core::{
         authorization_request::{
-            parameters::ResponseMode,
+            parameters::{ExpectedOrigins, ResponseMode},
             verification::{
                 did::verify_with_resolver, verifier::P256Verifier, x509_hash, x509_san,
                 RequestVerifier,
             },
-            AuthorizationRequestObject,
+            AuthorizationRequest, AuthorizationRequestObject, RequestIndirection,
         },
         metadata::WalletMetadata,
+        object::ParsingErrorContext,
     },
     wallet::Wallet as OID4VPWallet,
 };
@@ -69,7 +77,7 @@ match req {
 /// The Holder is typically the subject of the credentials, but not always.
 /// The Holder has the ability to generate Verifiable Presentations from
 /// these credentials and share them with Verifiers.
-#[derive(uniffi::Object)]
+#[derive(Clone, uniffi::Object)]
 pub struct Holder {
     /// An atomic reference to the VDC collection.
     pub(crate) vdc_collection: Option<Arc<VdcCollection>>,
@@ -80,8 +88,9 @@ pub struct Holder {
     /// HTTP Request Client
     pub(crate) client: openid4vp::core::util::ReqwestClient,
 
-    /// A list of trusted DIDs.
-    pub(crate) trusted_dids: Vec<String>,
+    /// The verifiers trusted by the holder, reported with each request. Its
+    /// DIDs, if any, are the only ones allowed to sign requests.
+    pub(crate) trust_store: Arc<VerifierTrustStore>,
 
     /// Provide optional credentials to the holder instance.
     pub(crate) provided_credentials: Option<Vec<Arc<ParsedCredential>>>,
@@ -105,7 +114,7 @@ impl std::fmt::Debug for Holder {
         f.debug_struct("Holder")
             .field("vdc_collection", &self.vdc_collection)
             .field("metadata", &self.metadata)
-            .field("trusted_dids", &self.trusted_dids)
+            .field("trust_store", &self.trust_store)
             .field("provided_credentials", &self.provided_credentials)
             .field("keystore", &self.keystore.as_ref().map(|_| "KeyStore"))
             .field("providers", &self.providers.len())
@@ -173,7 +182,7 @@ impl Holder {
             client,
             vdc_collection: Some(vdc_collection),
             metadata: Self::metadata()?,
-            trusted_dids,
+            trust_store: Arc::new(VerifierTrustStore::from_dids(trusted_dids)),
             provided_credentials: None,
             signer: Arc::new(signer),
             context_map: with_default_contexts(context_map),
@@ -228,7 +237,7 @@ impl Holder {
             client,
             vdc_collection: None,
             metadata: Self::metadata()?,
-            trusted_dids,
+            trust_store: Arc::new(VerifierTrustStore::from_dids(trusted_dids)),
             provided_credentials: Some(provided_credentials),
             signer: Arc::new(signer),
             context_map: with_default_contexts(context_map),
@@ -237,6 +246,17 @@ impl Holder {
         }))
     }
 
+    /// Return a holder evaluating the verifiers of requests against the given
+    /// trust store, replacing the trusted DIDs it was constructed with.
+    ///
+    /// The outcome is reported by [PermissionRequest::verifier_trust].
+    pub fn with_trust_store(self: Arc<Self>, trust_store: Arc<VerifierTrustStore>) -> Arc<Self> {
+        Arc::new(Self {
+            trust_store,
+            ..(*self).clone()
+        })
+    }
+
     /// Given an authorization request URL, return a permission request,
     /// which provides a list of requested credentials and requested fields
     /// that align with the presentation definition of the request.
@@ -247,37 +267,134 @@ impl Holder {
         req: AuthRequest,
         // Callback here to allow for review of untrusted DIDs.
     ) -> Result<Arc<PermissionRequest>, OID4VPError> {
-        let request = match req {
+        let (request, verifier_trust, negotiated_capabilities) = match req {
             AuthRequest::Url(mut url) => {
                 // NOTE: Replace the host value with an empty string to remove any
                 // leading host value before the query.
                 url.set_host(Some(""))
                     .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?;
 
-                self.validate_request(url)
-                    .await
-                    .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?
+                match post_request_uri(&url) {
+                    Some(request_uri) => {
+                        let (request, verifier_trust, capabilities) =
+                            self.fetch_posted_request(&url, &request_uri).await?;
+                        (request, verifier_trust, Some(capabilities))
+                    }
+                    None => {
+                        let verifier = HolderRequestVerifier::new(self, None);
+                        let request = verifier
+                            .validate_request(url)
+                            .await
+                            .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?;
+
+                        (request, verifier.trust.take(), None)
+                    }
+                }
             }
-            AuthRequest::Request(req) => *req,
+            AuthRequest::Request(req) => (*req, VerifierTrustResult::unverified(), None),
         };
 
-        match request.response_mode() {
+        let permission_request = match request.response_mode() {
             ResponseMode::DirectPost | ResponseMode::DirectPostJwt => {
-                self.permission_request(request).await
+                self.permission_request(request, verifier_trust).await?
+            }
+            _ if RedirectResponseMode::from_request(&request).is_some() => {
+                self.permission_request(request, verifier_trust).await?
             }
+            mode => return Err(OID4VPError::UnsupportedResponseMode(mode.to_string())),
+        };
+
+        Ok(match negotiated_capabilities {
+            Some(capabilities) => permission_request.with_negotiated_capabilities(capabilities),
+            None => permission_request,
+        })
+    }
+
+    /// Given a request received over the Digital Credentials API, return a
+    /// permission request.
+    ///
+    /// The `origin` is the origin of the verifier, as provided by the platform.
+    /// Signed requests must list it in their `expected_origins`, and the
+    /// presentations are bound to it.
+    ///
+    /// The response is built with [Holder::dc_api_response].
+    pub async fn dc_api_request(
+        &self,
+        request: String,
+        origin: String,
+    ) -> Result<Arc<PermissionRequest>, OID4VPError> {
+        let request: AuthorizationRequest = serde_json::from_str(&request)
+            .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?;
+
+        let verifier = HolderRequestVerifier::new(self, Some(&origin));
+        let request = request
+            .validate(&verifier)
+            .await
+            .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?;
+        let verifier_trust = verifier.trust.take();
+
+        match request.response_mode() {
+            ResponseMode::DcApi | ResponseMode::DcApiJwt => Ok(self
+                .permission_request(request, verifier_trust)
+                .await?
+                .with_origin(origin)),
             mode => Err(OID4VPError::UnsupportedResponseMode(mode.to_string())),
         }
     }
 
+    /// Return the response to a request received over the Digital Credentials
+    /// API, to hand back to the platform: a JSON object for the `dc_api`
+    /// response mode, or a JWE for `dc_api.jwt`.
+    pub async fn dc_api_response(
+        &self,
+        response: Arc<PermissionResponse>,
+    ) -> Result<String, OID4VPError> {
+        let Some(origin) = &response.origin else {
+            return Err(OID4VPError::UnsupportedResponseMode(
+                response.authorization_request.response_mode().to_string(),
+            ));
+        };
+
+        let dc_api_response = response.dc_api_response()?;
+
+        self.record_shared(&response, origin.clone()).await;
+
+        Ok(dc_api_response)
+    }
+
+    /// Submit the response to the verifier.
+    ///
+    /// For the `fragment` and `query` response modes, nothing is sent: the
+    /// returned URL redirects the user agent to the verifier with the response.
     pub async fn submit_permission_response(
         &self,
         response: Arc<PermissionResponse>,
     ) -> Result<Option<Url>, OID4VPError> {
-        let auth_response = response.authorization_response()?;
+        if response.origin.is_some() {
+            return Err(OID4VPError::ResponseSubmission(
+                "responses to Digital Credentials API requests are returned by `dc_api_response`"
+                    .into(),
+            ));
+        }
 
-        self.submit_response(response.authorization_request.clone(), auth_response)
-            .await
-            .map_err(|e| OID4VPError::ResponseSubmission(format!("{e:?}")))
+        let url = match RedirectResponseMode::from_request(&response.authorization_request) {
+            Some(mode) => Some(response.redirect_url(mode)?),
+            None => {
+                let auth_response = response.authorization_response()?;
+
+                self.submit_response(response.authorization_request.clone(), auth_response)
+                    .await
+                    .map_err(|e| OID4VPError::ResponseSubmission(format!("{e:?}")))?
+            }
+        };
+
+        self.record_shared(
+            &response,
+            response.authorization_request.return_uri().to_string(),
+        )
+        .await;
+
+        Ok(url)
     }
 }
 
@@ -341,6 +458,26 @@ impl Holder {
         Ok(metadata)
     }
 
+    /// Record the presentation in the activity log of the shared credentials.
+    async fn record_shared(&self, response: &PermissionResponse, url: String) {
+        if let Some(vdc_collection) = &self.vdc_collection {
+            vdc_collection
+                .clone()
+                .activity_recorder()
+                .record_shared(
+                    response
+                        .authorization_request
+                        .client_id()
+                        .map(|id| id.0.clone())
+                        .or_else(|| response.origin.clone())
+                        .as_deref(),
+                    Some(url),
+                    response.shared_fields(),
+                )
+                .await;
+        }
+    }
+
     /// This will return all the credentials that match the DCQL query.
     async fn search_credentials_vs_dcql_query(
         &self,
@@ -381,10 +518,54 @@ impl Holder {
         Ok(matched_credentials)
     }
 
+    /// Fetch the request object of a request with `request_uri_method=post`,
+    /// sending the holder metadata and a fresh `wallet_nonce`, and validate it.
+    async fn fetch_posted_request(
+        &self,
+        url: &Url,
+        request_uri: &str,
+    ) -> Result<
+        (
+            AuthorizationRequestObject,
+            VerifierTrustResult,
+            NegotiatedCapabilities,
+        ),
+        OID4VPError,
+    > {
+        let client_id = url
+            .query_pairs()
+            .find(|(key, _)| key == "client_id")
+            .map(|(_, value)| value.into_owned());
+
+        let wallet_nonce = generate_wallet_nonce();
+        let request_jwt =
+            fetch_request_object(&self.client, request_uri, &self.metadata, &wallet_nonce)
+                .await
+                .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?;
+
+        let verifier = HolderRequestVerifier::new(self, None);
+        let request = AuthorizationRequest {
+            client_id,
+            request_indirection: RequestIndirection::ByValue {
+                request: request_jwt.clone(),
+            },
+        }
+        .validate(&verifier)
+        .await
+        .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?;
+
+        check_wallet_nonce(&request, &wallet_nonce)
+            .map_err(|e| OID4VPError::RequestValidation(format!("{e:?}")))?;
+
+        let capabilities = NegotiatedCapabilities::new(&request, &request_jwt, wallet_nonce);
+        Ok((request, verifier.trust.take(), capabilities))
+    }
+
     // Internal method for returning the `PermissionRequest` for an oid4vp request.
     async fn permission_request(
         &self,
         request: AuthorizationRequestObject,
+        verifier_trust: VerifierTrustResult,
     ) -> Result<Arc<PermissionRequest>, OID4VPError> {
         // Resolve the DCQL query from the request.
         let dcql_query = request
@@ -396,6 +577,11 @@ impl Holder {
             })?
             .map_err(|e| OID4VPError::DcqlQueryResolution(format!("{e:?}")))?;
 
+        // Reject malformed transaction data before matching any credential.
+        parse_transaction_data(&request, &dcql_query)?;
+
+        let verifier_attestations = verify_verifier_attestations(&request, &self.trust_store)?;
+
         let matched_credentials = self.search_credentials_vs_dcql_query(&dcql_query).await?;
 
         // Stored credentials that matched the DCQL query.
@@ -446,6 +632,8 @@ impl Holder {
             self.keystore.clone(),
             dynamic_offers,
             offer_providers,
+            verifier_trust,
+            verifier_attestations,
         ))
     }
 }
@@ -467,16 +655,11 @@ impl RequestVerifier for Holder {
         let resolver: VerificationMethodDIDResolver<DIDWeb, AnyJwkMethod> =
             VerificationMethodDIDResolver::new(DIDWeb);
 
-        let trusted_dids = match self.trusted_dids.as_slice() {
-            [] => None,
-            dids => Some(dids),
-        };
-
         verify_with_resolver(
             &self.metadata,
             decoded_request,
             request_jwt,
-            trusted_dids,
+            self.trust_store.allowed_dids(),
             &resolver,
         )
         .await?;
@@ -498,16 +681,11 @@ impl RequestVerifier for Holder {
         let resolver: VerificationMethodDIDResolver<DIDKey, AnyJwkMethod> =
             VerificationMethodDIDResolver::new(DIDKey);
 
-        let trusted_dids = match self.trusted_dids.as_slice() {
-            [] => None,
-            dids => Some(dids),
-        };
-
         verify_with_resolver(
             &self.metadata,
             decoded_request,
             request_jwt,
-            trusted_dids,
+            self.trust_store.allowed_dids(),
             &resolver,
         )
         .await?;
@@ -564,13 +742,189 @@ impl OID4VPWallet for Holder {
     }
 }
 
+/// Verifies requests on behalf of a [Holder], recording the trust in their
+/// verifier.
+///
+/// Requests received over the Digital Credentials API are verified for the
+/// origin provided by the platform.
+struct HolderRequestVerifier<'a> {
+    holder: &'a Holder,
+    origin: Option<&'a str>,
+    trust: VerifierTrustRecorder,
+}
+
+impl<'a> HolderRequestVerifier<'a> {
+    fn new(holder: &'a Holder, origin: Option<&'a str>) -> Self {
+        Self {
+            holder,
+            origin,
+            trust: VerifierTrustRecorder::default(),
+        }
+    }
+
+    /// Signed requests received over the DC API must be bound to the origin,
+    /// to prevent their replay by another website (OID4VP 1.0 §A.2).
+    fn check_expected_origins(&self, request: &AuthorizationRequestObject) -> anyhow::Result<()> {
+        let Some(origin) = self.origin else {
+            return Ok(());
+        };
+
+        let expected_origins: ExpectedOrigins = request.get().parsing_error()?;
+        if !expected_origins.0.iter().any(|expected| expected == origin) {
+            anyhow::bail!("expected origin not found in request");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestVerifier for HolderRequestVerifier<'_> {
+    async fn decentralized_identifier(
+        &self,
+        decoded_request: &AuthorizationRequestObject,
+        request_jwt: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.check_expected_origins(decoded_request)?;
+        self.holder
+            .decentralized_identifier(decoded_request, request_jwt)
+            .await?;
+
+        let client_id = decoded_request
+            .client_id()
+            .context("missing client_id")?
+            .0
+            .clone();
+        let did = client_id
+            .strip_prefix(ClientIdScheme::DECENTRALIZED_IDENTIFIER)
+            .and_then(|did| did.strip_prefix(':'))
+            .unwrap_or(&client_id);
+
+        self.trust.record(
+            self.holder
+                .trust_store
+                .evaluate_did(ClientIdScheme::DECENTRALIZED_IDENTIFIER, did),
+        );
+        Ok(())
+    }
+
+    async fn redirect_uri(
+        &self,
+        decoded_request: &AuthorizationRequestObject,
+        request_jwt: Option<String>,
+    ) -> anyhow::Result<()> {
+        if self.origin.is_some() {
+            anyhow::bail!("redirect_uri requests are not accepted over the DC API");
+        }
+        self.holder
+            .redirect_uri(decoded_request, request_jwt)
+            .await?;
+
+        self.trust
+            .record(self.holder.trust_store.evaluate_unauthenticated(
+                ClientIdScheme::REDIRECT_URI,
+                "the verifier is only identified by its redirect URI",
+            ));
+        Ok(())
+    }
+
+    async fn x509_san_dns(
+        &self,
+        decoded_request: &AuthorizationRequestObject,
+        request_jwt: Option<String>,
+    ) -> anyhow::Result<()> {
+        log::debug!("Verifying x509_san_dns request.");
+
+        self.check_expected_origins(decoded_request)?;
+        let request_jwt =
+            request_jwt.context("request JWT is required for x509_san_dns verification")?;
+
+        let trust =
+            self.holder
+                .trust_store
+                .evaluate_x509(ClientIdScheme::X509_SAN_DNS, |roots| {
+                    x509_san::validate::<P256Verifier>(
+                        &self.holder.metadata,
+                        decoded_request,
+                        request_jwt.clone(),
+                        roots,
+                    )
+                })?;
+        self.trust.record(trust);
+        Ok(())
+    }
+
+    async fn x509_hash(
+        &self,
+        decoded_request: &AuthorizationRequestObject,
+        request_jwt: Option<String>,
+    ) -> anyhow::Result<()> {
+        log::debug!("Verifying x509_hash request.");
+
+        self.check_expected_origins(decoded_request)?;
+        let request_jwt =
+            request_jwt.context("request JWT is required for x509_hash verification")?;
+
+        let trust = self
+            .holder
+            .trust_store
+            .evaluate_x509(ClientIdScheme::X509_HASH, |roots| {
+                x509_hash::validate::<P256Verifier>(
+                    &self.holder.metadata,
+                    decoded_request,
+                    request_jwt.clone(),
+                    roots,
+                )
+            })?;
+        self.trust.record(trust);
+        Ok(())
+    }
+
+    /// Unsigned requests are only accepted over the DC API, where they carry
+    /// no client_id: the verifier is identified by the origin, which the
+    /// presentations are bound to.
+    async fn preregistered(
+        &self,
+        decoded_request: &AuthorizationRequestObject,
+        _request_jwt: Option<String>,
+    ) -> anyhow::Result<()> {
+        match decoded_request.response_mode() {
+            ResponseMode::DcApi | ResponseMode::DcApiJwt if self.origin.is_some() => {
+                self.trust
+                    .record(self.holder.trust_store.evaluate_unauthenticated(
+                        "origin",
+                        "the verifier is only identified by its origin",
+                    ));
+                Ok(())
+            }
+            mode => {
+                anyhow::bail!("unsigned requests are only accepted over the DC API, not {mode:?}")
+            }
+        }
+    }
+}
+
+impl OID4VPWallet for HolderRequestVerifier<'_> {
+    type HttpClient = openid4vp::core::util::ReqwestClient;
+
+    fn http_client(&self) -> &Self::HttpClient {
+        &self.holder.client
+    }
+
+    fn metadata(&self) -> &WalletMetadata {
+        &self.holder.metadata
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
     use crate::{
         context::default_ld_json_context,
         did::DidMethod,
-        oid4vp::presentation::{PresentationError, PresentationSigner},
+        oid4vp::{
+            presentation::{PresentationError, PresentationSigner},
+            trust::VerifierTrustError,
+        },
         tests::{load_jwk, load_signer},
     };
 
@@ -1133,4 +1487,547 @@ pub(crate) mod tests {
             "minted:nonce-dyn:redirect_uri:https://wallet.example/callback"
         );
     }
+
+    // ---- claim_sets and credential_sets ----
+
+    fn credential_sets_request() -> AuthorizationRequestObject {
+        let request = json!({
+            "client_id": "redirect_uri:https://wallet.example/callback",
+            "response_uri": "https://wallet.example/callback",
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "state": "state-sets",
+            "nonce": "nonce-sets",
+            "client_metadata": {
+                "vp_formats_supported": {
+                    "ldp_vc": { "proof_type_values": ["ecdsa-rdfc-2019"] }
+                }
+            },
+            "dcql_query": {
+                "credentials": [
+                    {
+                        "id": "diploma",
+                        "format": "ldp_vc",
+                        "claims": [{ "path": ["credentialSubject", "diploma"] }]
+                    },
+                    {
+                        "id": "alumni",
+                        "format": "ldp_vc",
+                        "claims": [
+                            { "id": "degree", "path": ["credentialSubject", "degree"] },
+                            { "id": "name", "path": ["credentialSubject", "alumniOf", "name"] },
+                            {
+                                "id": "identifier",
+                                "path": ["credentialSubject", "alumniOf", "identifier"]
+                            }
+                        ],
+                        "claim_sets": [["degree"], ["name"], ["name", "identifier"]]
+                    },
+                    {
+                        "id": "membership",
+                        "format": "ldp_vc"
+                    }
+                ],
+                "credential_sets": [
+                    { "options": [["diploma"], ["alumni"]] },
+                    { "options": [["membership"]], "required": false }
+                ]
+            }
+        })
+        .to_string();
+
+        serde_json::from_str(&request).expect("failed to parse credential sets test request")
+    }
+
+    #[tokio::test]
+    async fn test_claim_sets_and_credential_sets() {
+        let json_vc =
+            JsonVc::new_from_json(include_str!("../../tests/examples/alumni_vc.json").to_string())
+                .unwrap();
+
+        let holder = Holder::new_with_credentials(
+            vec![ParsedCredential::new_ldp_vc(json_vc)],
+            vec![],
+            Box::new(load_signer()),
+            Some(default_ld_json_context()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let permission_request = holder
+            .authorization_request(AuthRequest::Request(Box::new(credential_sets_request())))
+            .await
+            .unwrap();
+
+        // The credential lacks the claim of `diploma`, and only holds the
+        // second claim set of `alumni`.
+        let credentials = permission_request.credentials();
+        let query_ids = credentials
+            .iter()
+            .map(|credential| credential.credential_query_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(query_ids, ["alumni", "membership"]);
+
+        let alumni = credentials[0].clone();
+        let requested_fields = permission_request.requested_fields(&alumni);
+        assert_eq!(requested_fields.len(), 1);
+        let name_path = RequestedField::from_dcql_claims(
+            "alumni".into(),
+            vec!["credentialSubject".into(), "alumniOf".into(), "name".into()],
+            vec![],
+        )
+        .path();
+        assert_eq!(requested_fields[0].path(), name_path);
+
+        assert_eq!(
+            permission_request.selection_options(),
+            [
+                CredentialSelectionOption {
+                    credential_query_ids: vec!["alumni".into()],
+                    optional_credential_query_ids: vec![],
+                },
+                CredentialSelectionOption {
+                    credential_query_ids: vec!["alumni".into(), "membership".into()],
+                    optional_credential_query_ids: vec!["membership".into()],
+                },
+            ]
+        );
+
+        // The optional credential set alone does not satisfy the request.
+        let result = permission_request
+            .create_permission_response(
+                vec![credentials[1].clone()],
+                vec![vec![]],
+                ResponseOptions::default(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(OID4VPError::PermissionRequest(
+                PermissionRequestError::UnsatisfiedCredentialSet(_)
+            ))
+        ));
+
+        let response = permission_request
+            .create_permission_response(
+                vec![alumni],
+                vec![requested_fields.iter().map(|field| field.path()).collect()],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert!(response.vp_token().unwrap().contains("alumni"));
+    }
+
+    #[tokio::test]
+    async fn test_fragment_response_mode() {
+        let json_vc =
+            JsonVc::new_from_json(include_str!("../../tests/examples/alumni_vc.json").to_string())
+                .unwrap();
+
+        let holder = Holder::new_with_credentials(
+            vec![ParsedCredential::new_ldp_vc(json_vc)],
+            vec![],
+            Box::new(load_signer()),
+            Some(default_ld_json_context()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let request = json!({
+            "client_id": "redirect_uri:https://verifier.example/callback",
+            "redirect_uri": "https://verifier.example/callback",
+            "response_type": "vp_token",
+            "response_mode": "fragment",
+            "state": "state-fragment",
+            "nonce": "nonce-fragment",
+            "client_metadata": {
+                "vp_formats_supported": {
+                    "ldp_vc": { "proof_type_values": ["ecdsa-rdfc-2019"] }
+                }
+            },
+            "dcql_query": {
+                "credentials": [{ "id": "alumni", "format": "ldp_vc" }]
+            }
+        });
+
+        let permission_request = holder
+            .authorization_request(AuthRequest::Request(Box::new(
+                serde_json::from_value(request).unwrap(),
+            )))
+            .await
+            .unwrap();
+
+        let credentials = permission_request.credentials();
+        let response = permission_request
+            .create_permission_response(
+                credentials.clone(),
+                vec![vec![]; credentials.len()],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        // Nothing is sent: the response is carried by the redirect.
+        let url = holder
+            .submit_permission_response(response)
+            .await
+            .unwrap()
+            .expect("missing redirect URL");
+        assert_eq!(url.path(), "/callback");
+
+        let parameters: HashMap<String, String> =
+            url::form_urlencoded::parse(url.fragment().unwrap().as_bytes())
+                .into_owned()
+                .collect();
+        assert_eq!(parameters["state"], "state-fragment");
+        let vp_token: serde_json::Value = serde_json::from_str(&parameters["vp_token"]).unwrap();
+        assert!(vp_token["alumni"].is_array());
+    }
+
+    // ---- Digital Credentials API ----
+
+    use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine as _};
+    use ietf_sd_jwt_vc::IetfSdJwtVc;
+
+    const DC_API_ORIGIN: &str = "https://verifier.example";
+
+    fn dc_api_request(response_mode: &str) -> serde_json::Value {
+        json!({
+            "response_type": "vp_token",
+            "response_mode": response_mode,
+            "nonce": "nonce-dc-api",
+            "client_metadata": {
+                "vp_formats_supported": {
+                    "dc+sd-jwt": {
+                        "sd-jwt_alg_values": ["ES256"],
+                        "kb-jwt_alg_values": ["ES256"]
+                    }
+                }
+            },
+            "dcql_query": {
+                "credentials": [
+                    {
+                        "id": "hiid",
+                        "format": "dc+sd-jwt",
+                        "meta": { "vct_values": ["eu.europa.ec.eudi.hiid.1"] },
+                        "claims": [{ "path": ["health_insurance_id"] }]
+                    }
+                ]
+            }
+        })
+    }
+
+    async fn dc_api_holder() -> Arc<Holder> {
+        let sd_jwt = IetfSdJwtVc::new_from_compact_sd_jwt(
+            include_str!("../../tests/examples/dc+sd-jwt.jwt").into(),
+        )
+        .unwrap();
+
+        Holder::new_with_credentials(
+            vec![ParsedCredential::new_dc_sd_jwt(sd_jwt)],
+            vec![],
+            Box::new(KeySigner {
+                jwk: JWK::generate_p256(),
+            }),
+            None,
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Present the requested fields of the matching credentials, returning the
+    /// response to hand back to the platform.
+    async fn dc_api_present(holder: &Holder, permission_request: Arc<PermissionRequest>) -> String {
+        let credentials = permission_request.credentials();
+        assert_eq!(credentials.len(), 1);
+        let requested_fields = credentials
+            .iter()
+            .map(|credential| {
+                permission_request
+                    .requested_fields(credential)
+                    .iter()
+                    .map(|field| field.path())
+                    .collect()
+            })
+            .collect();
+
+        let response = permission_request
+            .create_permission_response(credentials, requested_fields, ResponseOptions::default())
+            .await
+            .unwrap();
+
+        // DC API responses are not submitted by the holder.
+        assert!(matches!(
+            holder.submit_permission_response(response.clone()).await,
+            Err(OID4VPError::ResponseSubmission(_))
+        ));
+
+        holder.dc_api_response(response).await.unwrap()
+    }
+
+    /// Decode the claims of the KB-JWT presenting the `hiid` credential.
+    fn kb_jwt_claims(vp_token: &serde_json::Value) -> serde_json::Value {
+        let presentation = vp_token["hiid"][0].as_str().unwrap();
+        let kb_jwt = presentation.rsplit('~').next().unwrap();
+        let payload = kb_jwt.split('.').nth(1).unwrap();
+        serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dc_api_unsigned_request() {
+        let holder = dc_api_holder().await;
+
+        let permission_request = holder
+            .dc_api_request(dc_api_request("dc_api").to_string(), DC_API_ORIGIN.into())
+            .await
+            .unwrap();
+
+        let response = dc_api_present(&holder, permission_request).await;
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+        // The presentation is bound to the origin, as the request has no
+        // client_id.
+        let claims = kb_jwt_claims(&response["vp_token"]);
+        assert_eq!(claims["aud"], format!("origin:{DC_API_ORIGIN}"));
+        assert_eq!(claims["nonce"], "nonce-dc-api");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_verifier_is_not_answered() {
+        let holder = dc_api_holder().await.with_trust_store(
+            VerifierTrustStore::new().add_client_id_prefix("x509_san_dns".into()),
+        );
+
+        let permission_request = holder
+            .dc_api_request(dc_api_request("dc_api").to_string(), DC_API_ORIGIN.into())
+            .await
+            .unwrap();
+        assert!(matches!(
+            permission_request.verifier_trust(),
+            VerifierTrustResult::Invalid { .. }
+        ));
+
+        let credentials = permission_request.credentials();
+        let result = permission_request
+            .create_permission_response(
+                credentials.clone(),
+                vec![vec![]; credentials.len()],
+                ResponseOptions::default(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(OID4VPError::VerifierTrust(
+                VerifierTrustError::InvalidVerifier(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dc_api_jwt_response() {
+        let holder = dc_api_holder().await;
+
+        let secret = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let mut public: serde_json::Value =
+            serde_json::from_str(&secret.public_key().to_jwk_string()).unwrap();
+        public["use"] = json!("enc");
+        public["kid"] = json!("enc-key");
+
+        let mut request = dc_api_request("dc_api.jwt");
+        request["client_metadata"]["jwks"] = json!({ "keys": [public] });
+        request["client_metadata"]["encrypted_response_enc_values_supported"] = json!(["A128GCM"]);
+
+        let permission_request = holder
+            .dc_api_request(request.to_string(), DC_API_ORIGIN.into())
+            .await
+            .unwrap();
+
+        let jwe = dc_api_present(&holder, permission_request).await;
+
+        let private = josekit::jwk::Jwk::from_bytes(secret.to_jwk_string().as_bytes()).unwrap();
+        let decrypter: josekit::jwe::alg::ecdh_es::EcdhEsJweDecrypter<p256::NistP256> =
+            josekit::jwe::ECDH_ES.decrypter_from_jwk(&private).unwrap();
+        let (payload, header) = josekit::jwe::deserialize_compact(&jwe, &decrypter).unwrap();
+        assert_eq!(header.key_id(), Some("enc-key"));
+        assert_eq!(header.content_encryption(), Some("A128GCM"));
+
+        let payload: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        let claims = kb_jwt_claims(&payload["vp_token"]);
+        assert_eq!(claims["aud"], format!("origin:{DC_API_ORIGIN}"));
+    }
+
+    #[tokio::test]
+    async fn test_dc_api_signed_request_expected_origins() {
+        let holder = dc_api_holder().await;
+
+        let jwk = JWK::generate_p256();
+        let verification_method = ssi::dids::DIDJWK::generate_url(&jwk.to_public()).to_string();
+        let did = verification_method.split_once('#').unwrap().0;
+
+        let mut claims = dc_api_request("dc_api");
+        claims["client_id"] = json!(format!("decentralized_identifier:{did}"));
+        claims["expected_origins"] = json!(["https://other.example"]);
+
+        let header = json!({
+            "alg": "ES256",
+            "typ": "oauth-authz-req+jwt",
+            "kid": verification_method,
+        });
+        let signing_input = format!(
+            "{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+            BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = jwk.sign_bytes(signing_input.as_bytes()).await.unwrap();
+        let request_jwt = format!(
+            "{signing_input}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(signature)
+        );
+
+        // The request was issued for another website.
+        let result = holder
+            .dc_api_request(
+                json!({ "request": request_jwt }).to_string(),
+                DC_API_ORIGIN.into(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(OID4VPError::RequestValidation(reason))
+                if reason.contains("expected origin not found in request")
+        ));
+    }
+
+    // ---- request_uri_method=post ----
+
+    use p256::{ecdsa::signature::Signer as _, pkcs8::DecodePrivateKey};
+    use sha2::{Digest, Sha256};
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+    use x509_cert::der::{DecodePem, Encode};
+
+    /// Serve at `/request` a request object signed for the `x509_hash` client
+    /// identifier of the test IACA certificate, returning the authorization
+    /// URL referencing it. The request object echoes the posted
+    /// `wallet_nonce`, unless another nonce is given.
+    async fn mount_posted_request(server: &MockServer, nonce: Option<&str>) -> Url {
+        let certificate = x509_cert::Certificate::from_pem(include_str!(
+            "../../tests/res/mdl/iaca-certificate.pem"
+        ))
+        .unwrap()
+        .to_der()
+        .unwrap();
+        let key = p256::ecdsa::SigningKey::from_pkcs8_pem(include_str!(
+            "../../tests/res/mdl/iaca-key.pem"
+        ))
+        .unwrap();
+        let client_id = format!(
+            "x509_hash:{}",
+            BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(&certificate))
+        );
+
+        let header = json!({
+            "alg": "ES256",
+            "typ": "oauth-authz-req+jwt",
+            "x5c": [base64::prelude::BASE64_STANDARD.encode(&certificate)],
+        });
+        let mut claims = dc_api_request("direct_post");
+        claims["iss"] = json!(client_id);
+        claims["aud"] = json!("https://self-issued.me/v2");
+        claims["client_id"] = json!(client_id);
+        claims["response_uri"] = json!(format!("{}/response", server.uri()));
+        claims["state"] = json!("state-post");
+
+        let nonce = nonce.map(str::to_string);
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/request"))
+            .respond_with(move |request: &wiremock::Request| {
+                let mut claims = claims.clone();
+                claims["wallet_nonce"] = match &nonce {
+                    Some(nonce) => json!(nonce),
+                    None => url::form_urlencoded::parse(&request.body)
+                        .find(|(key, _)| key == "wallet_nonce")
+                        .map(|(_, value)| json!(value))
+                        .unwrap_or_default(),
+                };
+
+                let signing_input = format!(
+                    "{}.{}",
+                    BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+                    BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+                );
+                let signature: p256::ecdsa::Signature = key.sign(signing_input.as_bytes());
+                ResponseTemplate::new(200).set_body_string(format!(
+                    "{signing_input}.{}",
+                    BASE64_URL_SAFE_NO_PAD.encode(signature.to_bytes())
+                ))
+            })
+            .expect(1)
+            .mount(server)
+            .await;
+
+        format!(
+            "openid4vp://?client_id={}&request_uri={}&request_uri_method=post",
+            urlencoding::encode(&client_id),
+            urlencoding::encode(&format!("{}/request", server.uri()))
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_posted_request_uri() {
+        let holder = dc_api_holder().await;
+        let server = MockServer::start().await;
+        let authorization_url = mount_posted_request(&server, None).await;
+
+        let permission_request = holder
+            .authorization_request(AuthRequest::Url(authorization_url))
+            .await
+            .unwrap();
+        assert_eq!(permission_request.credentials().len(), 1);
+
+        // The holder metadata and a fresh nonce were posted to the request_uri.
+        let received = server.received_requests().await.unwrap();
+        let body = url::form_urlencoded::parse(&received[0].body)
+            .into_owned()
+            .collect::<HashMap<_, _>>();
+        let wallet_metadata: serde_json::Value =
+            serde_json::from_str(&body["wallet_metadata"]).unwrap();
+        assert_eq!(
+            wallet_metadata,
+            serde_json::to_value(&holder.metadata).unwrap()
+        );
+
+        let capabilities = permission_request.negotiated_capabilities().unwrap();
+        assert_eq!(capabilities.wallet_nonce, body["wallet_nonce"]);
+        assert_eq!(capabilities.client_id_prefix.as_deref(), Some("x509_hash"));
+        assert_eq!(
+            capabilities.request_object_signing_alg.as_deref(),
+            Some("ES256")
+        );
+        assert_eq!(capabilities.response_mode, "direct_post");
+        assert_eq!(capabilities.credential_formats, ["dc+sd-jwt"]);
+        assert_eq!(capabilities.response_encryption_enc, None);
+    }
+
+    #[tokio::test]
+    async fn test_posted_request_uri_wallet_nonce_mismatch() {
+        let holder = dc_api_holder().await;
+        let server = MockServer::start().await;
+        let authorization_url = mount_posted_request(&server, Some("replayed")).await;
+
+        // The request object was not fetched with the nonce of this request.
+        let result = holder
+            .authorization_request(AuthRequest::Url(authorization_url))
+            .await;
+        assert!(matches!(
+            result,
+            Err(OID4VPError::RequestValidation(reason))
+                if reason.contains("wallet_nonce of the request object does not match")
+        ));
+    }
 }
@@ -6,11 +6,6 @@ use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
 use base64::prelude::*;
 use isomdl::{cbor, definitions::helpers::ByteStr};
-use josekit::{
-    jwe::{alg::ecdh_es::EcdhEsJweEncrypter, JweHeader},
-    jwk::Jwk,
-    jwt::{encode_with_encrypter, JwtPayload},
-};
 use openid4vp::{
     core::{
         authorization_request::{
@@ -19,7 +14,6 @@ use openid4vp::{
             AuthorizationRequest, AuthorizationRequestObject, RequestIndirection,
         },
         dcql_query::DcqlQuery,
-        iso_18013_7::compute_jwk_thumbprint,
         metadata::WalletMetadata,
         object::{ParsingErrorContext, UntypedObject},
         util::{AsyncHttpClient, ReqwestClient},
@@ -71,7 +65,14 @@ use super::{
     },
     ApprovedResponse180137,
 };
-use crate::{credential::mdoc::Mdoc, crypto::KeyStore};
+use crate::{
+    credential::mdoc::Mdoc,
+    crypto::KeyStore,
+    oid4vp::{
+        response_encryption::{encryption_jwk_thumbprint, jwks_to_json, ResponseEncrypter},
+        trust::{VerifierTrustRecorder, VerifierTrustResult, VerifierTrustStore},
+    },
+};
 
 #[deprecated(
     note = "Compatibility facade for legacy ISO 18013-7 OID4VP integrations only. Prefer the direct OID4VP v1 Annex B APIs for new integrations; this facade may be removed in a future release."
@@ -154,6 +155,9 @@ pub struct Oid4vp180137Facade {
     v1_metadata: WalletMetadata,
     draft18_http_client: Draft18ReqwestClient,
     draft18_metadata: Draft18WalletMetadata,
+    trust_store: Arc<VerifierTrustStore>,
+    /// Trust in the verifier of the request being processed.
+    verifier_trust: VerifierTrustRecorder,
 }
 
 #[deprecated(
@@ -163,6 +167,7 @@ pub struct Oid4vp180137Facade {
 pub struct Oid4vp180137Session {
     inner: Oid4vp180137SessionInner,
     handler: Oid4vp180137Facade,
+    verifier_trust: VerifierTrustResult,
 }
 
 impl fmt::Debug for Oid4vp180137Facade {
@@ -190,9 +195,22 @@ impl Oid4vp180137Facade {
             draft18_http_client: Draft18ReqwestClient::new()
                 .map_err(|e| Oid4vp180137FacadeError::InvalidRequest(format!("{e:#}")))?,
             draft18_metadata: draft18_default_metadata(),
+            trust_store: Default::default(),
+            verifier_trust: Default::default(),
         }))
     }
 
+    /// Return a facade evaluating the verifiers of requests against the given
+    /// trust store.
+    ///
+    /// The outcome is reported by [Oid4vp180137Session::verifier_trust].
+    pub fn with_trust_store(self: Arc<Self>, trust_store: Arc<VerifierTrustStore>) -> Arc<Self> {
+        Arc::new(Self {
+            trust_store,
+            ..(*self).clone()
+        })
+    }
+
     pub async fn process_request(
         &self,
         request: String,
@@ -206,26 +224,36 @@ impl Oid4vp180137Facade {
         request: String,
         compatibility_mode: Oid4vp180137CompatibilityMode,
     ) -> Result<Arc<Oid4vp180137Session>, Oid4vp180137FacadeError> {
+        // Record the trust in the verifier of this request only.
+        let handler = Self {
+            verifier_trust: Default::default(),
+            ..self.clone()
+        };
+
         let inner = match compatibility_mode {
-            Oid4vp180137CompatibilityMode::Auto => self
+            Oid4vp180137CompatibilityMode::Auto => handler
                 .process_auto_request(&request)
                 .await
                 .map_err(invalid_request)?,
             Oid4vp180137CompatibilityMode::V1 => Oid4vp180137SessionInner::V1(
-                self.process_v1_request(&request)
+                handler
+                    .process_v1_request(&request)
                     .await
                     .map_err(invalid_request)?,
             ),
             Oid4vp180137CompatibilityMode::Draft18 => Oid4vp180137SessionInner::Draft18(
-                self.process_draft18_request(&request)
+                handler
+                    .process_draft18_request(&request)
                     .await
                     .map_err(invalid_request)?,
             ),
         };
+        let verifier_trust = handler.verifier_trust.take();
 
         Ok(Arc::new(Oid4vp180137Session {
             inner,
-            handler: self.clone(),
+            handler,
+            verifier_trust,
         }))
     }
 }
@@ -236,6 +264,10 @@ impl Oid4vp180137Session {
         &self,
         approved_response: ApprovedResponse180137,
     ) -> Result<Option<Url>, Oid4vp180137FacadeError> {
+        self.verifier_trust
+            .ensure_answerable()
+            .map_err(|e| response_processing(e.into()))?;
+
         match &self.inner {
             Oid4vp180137SessionInner::V1(request) => self
                 .handler
@@ -267,9 +299,23 @@ impl Oid4vp180137Session {
             Oid4vp180137SessionInner::Draft18(request) => request.request_matches.clone(),
         }
     }
+
+    /// Returns the trust in the verifier requesting authorization.
+    pub fn verifier_trust(&self) -> VerifierTrustResult {
+        self.verifier_trust.clone()
+    }
 }
 
 impl Oid4vp180137Facade {
+    /// Record the trust in a verifier only identified by its redirect URI.
+    fn record_unauthenticated_redirect_uri(&self) {
+        self.verifier_trust
+            .record(self.trust_store.evaluate_unauthenticated(
+                "redirect_uri",
+                "the verifier is only identified by its redirect URI",
+            ));
+    }
+
     async fn process_auto_request(&self, request: &str) -> Result<Oid4vp180137SessionInner> {
         let resolved_request = self.resolve_request_once(request).await?;
         let version = detect_request_version(&resolved_request);
@@ -430,7 +476,7 @@ impl Oid4vp180137Facade {
                 .parsing_error()
                 .context("missing response_uri")?
                 .0,
-            openid4vp::core::iso_18013_7::get_encryption_jwk_thumbprint(&request.request).as_ref(),
+            encryption_jwk_thumbprint(&request.request).as_ref(),
         )
         .context("failed to generate handover")?;
 
@@ -576,6 +622,7 @@ impl RequestVerifier for Oid4vp180137Facade {
             bail!("redirect_uri requests must not use a signed request object")
         }
 
+        self.record_unauthenticated_redirect_uri();
         Ok(())
     }
 
@@ -586,12 +633,19 @@ impl RequestVerifier for Oid4vp180137Facade {
     ) -> Result<()> {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_san_dns verification")?;
-        openid4vp::core::authorization_request::verification::x509_san::validate::<P256Verifier>(
-            OpenId4vpWallet::metadata(self),
-            decoded_request,
-            request_jwt,
-            None,
-        )
+        let trust =
+            self.trust_store.evaluate_x509("x509_san_dns", |roots| {
+                openid4vp::core::authorization_request::verification::x509_san::validate::<
+                    P256Verifier,
+                >(
+                    OpenId4vpWallet::metadata(self),
+                    decoded_request,
+                    request_jwt.clone(),
+                    roots,
+                )
+            })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 
     async fn x509_hash(
@@ -601,12 +655,16 @@ impl RequestVerifier for Oid4vp180137Facade {
     ) -> Result<()> {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_hash verification")?;
-        x509_hash::validate::<P256Verifier>(
-            OpenId4vpWallet::metadata(self),
-            decoded_request,
-            request_jwt,
-            None,
-        )
+        let trust = self.trust_store.evaluate_x509("x509_hash", |roots| {
+            x509_hash::validate::<P256Verifier>(
+                OpenId4vpWallet::metadata(self),
+                decoded_request,
+                request_jwt.clone(),
+                roots,
+            )
+        })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 }
 
@@ -633,6 +691,7 @@ impl draft18_verification::RequestVerifier for Oid4vp180137Facade {
             bail!("redirect_uri requests must not use a signed request object")
         }
 
+        self.record_unauthenticated_redirect_uri();
         Ok(())
     }
 
@@ -643,13 +702,19 @@ impl draft18_verification::RequestVerifier for Oid4vp180137Facade {
     ) -> Result<()> {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_san_dns verification")?;
-        openidvp_draft18::core::authorization_request::verification::x509_san::validate::<Draft18P256>(
-            X509SanVariant::Dns,
-            Draft18Wallet::metadata(self),
-            decoded_request,
-            request_jwt,
-            None,
-        )
+        let trust = self.trust_store.evaluate_x509("x509_san_dns", |roots| {
+            openidvp_draft18::core::authorization_request::verification::x509_san::validate::<
+                Draft18P256,
+            >(
+                X509SanVariant::Dns,
+                Draft18Wallet::metadata(self),
+                decoded_request,
+                request_jwt.clone(),
+                roots,
+            )
+        })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 
     async fn x509_san_uri(
@@ -659,13 +724,19 @@ impl draft18_verification::RequestVerifier for Oid4vp180137Facade {
     ) -> Result<()> {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_san_uri verification")?;
-        openidvp_draft18::core::authorization_request::verification::x509_san::validate::<Draft18P256>(
-            X509SanVariant::Uri,
-            Draft18Wallet::metadata(self),
-            decoded_request,
-            request_jwt,
-            None,
-        )
+        let trust = self.trust_store.evaluate_x509("x509_san_uri", |roots| {
+            openidvp_draft18::core::authorization_request::verification::x509_san::validate::<
+                Draft18P256,
+            >(
+                X509SanVariant::Uri,
+                Draft18Wallet::metadata(self),
+                decoded_request,
+                request_jwt.clone(),
+                roots,
+            )
+        })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 
     async fn other(
@@ -681,7 +752,16 @@ impl draft18_verification::RequestVerifier for Oid4vp180137Facade {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_hash verification")?;
         let current_request = convert_request_object(decoded_request)?;
-        x509_hash::validate::<P256Verifier>(&self.v1_metadata, &current_request, request_jwt, None)
+        let trust = self.trust_store.evaluate_x509("x509_hash", |roots| {
+            x509_hash::validate::<P256Verifier>(
+                &self.v1_metadata,
+                &current_request,
+                request_jwt.clone(),
+                roots,
+            )
+        })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 }
 
@@ -909,38 +989,9 @@ fn build_draft18_encrypted_response(
     let client_metadata = request
         .client_metadata()
         .context("failed to resolve client_metadata")?;
-    let jwks = client_metadata.jwks().draft18_parsing_error()?;
-    let keys: Vec<_> = jwks.keys.iter().collect();
-    let jwk_info = openid4vp::core::jwe::find_encryption_jwk(keys.into_iter())
-        .context("no suitable encryption key found in client metadata")?;
-
-    let alg = client_metadata
-        .authorization_encrypted_response_alg()
-        .draft18_parsing_error()?
-        .0
-        .to_string();
-    if alg != "ECDH-ES" {
-        bail!("unsupported encryption alg: {alg}")
-    }
-
-    let enc = client_metadata
-        .authorization_encrypted_response_enc()
-        .draft18_parsing_error()?
-        .0;
-
-    let jwk_json: Json = serde_json::to_value(&jwk_info.jwk).context("failed to serialize JWK")?;
-    let mut builder = openid4vp::core::jwe::JweBuilder::new()
-        .payload(payload)
-        .recipient_key_json(&jwk_json)
-        .context("invalid recipient JWK")?
-        .alg(alg)
-        .enc(enc);
-
-    if let Some(kid) = &jwk_info.kid {
-        builder = builder.kid(kid);
-    }
-
-    let jwe = builder.build().context("failed to build JWE")?;
+    let jwe = draft18_response_encrypter(&client_metadata)?
+        .encrypt(&payload)
+        .context("failed to build JWE")?;
 
     Ok(Draft18AuthorizationResponse::Jwt(
         Draft18JwtAuthorizationResponse { response: jwe },
@@ -956,89 +1007,47 @@ fn build_draft18_annex_b_encrypted_response(
     let client_metadata = request
         .client_metadata()
         .context("failed to resolve client_metadata")?;
-    let alg = client_metadata
-        .authorization_encrypted_response_alg()
-        .draft18_parsing_error()?
-        .0;
-    if alg != "ECDH-ES" {
-        bail!("unsupported encryption alg: {alg}")
-    }
-
-    let enc = client_metadata
-        .authorization_encrypted_response_enc()
-        .draft18_parsing_error()?
-        .0;
-    if enc != "A256GCM" {
-        bail!("unsupported encryption scheme: {enc}")
-    }
-
-    let jwk = draft18_get_encryption_jwk(&client_metadata)?;
-    let mut jwt_payload = JwtPayload::new();
-    let Json::Object(map) = payload else {
+    if !payload.is_object() {
         bail!("payload must be a JSON object")
-    };
-    for (key, value) in map {
-        jwt_payload.set_claim(&key, Some(value))?;
-    }
-
-    let mut jwe_header = JweHeader::new();
-    jwe_header.set_token_type("JWT");
-    jwe_header.set_content_encryption(enc);
-    jwe_header.set_algorithm(alg);
-    jwe_header.set_agreement_partyuinfo(apu);
-    jwe_header.set_agreement_partyvinfo(apv);
-
-    if let Some(kid) = jwk.key_id() {
-        jwe_header.set_key_id(kid);
     }
 
-    let encrypter: EcdhEsJweEncrypter<p256::NistP256> =
-        josekit::jwe::ECDH_ES.encrypter_from_jwk(&jwk)?;
-    let jwe = encode_with_encrypter(&jwt_payload, &jwe_header, &encrypter)?;
+    let jwe = draft18_response_encrypter(&client_metadata)?
+        .with_agreement_info(apu, apv)
+        .with_token_type("JWT")
+        .encrypt(&payload)
+        .context("failed to build JWE")?;
 
     Ok(Draft18AuthorizationResponse::Jwt(
         Draft18JwtAuthorizationResponse { response: jwe },
     ))
 }
 
-fn draft18_get_encryption_jwk(client_metadata: &Draft18ClientMetadata) -> Result<Jwk> {
-    client_metadata
-        .jwks()
+/// Negotiate the encryption of a draft 18 response, whose metadata states
+/// the algorithms instead of the keys.
+fn draft18_response_encrypter(
+    client_metadata: &Draft18ClientMetadata,
+) -> Result<ResponseEncrypter> {
+    let jwks = client_metadata.jwks().draft18_parsing_error()?;
+    let keys = jwks_to_json(&jwks.keys)?;
+
+    let alg = client_metadata
+        .authorization_encrypted_response_alg()
         .draft18_parsing_error()?
-        .keys
-        .iter()
-        .filter_map(|jwk| {
-            let jwk = serde_json::from_value::<Jwk>(Json::Object(jwk.clone()));
-            match jwk {
-                Ok(jwk) => Some(jwk),
-                Err(e) => {
-                    tracing::warn!("unable to parse a JWK in keyset: {e}");
-                    None
-                }
-            }
-        })
-        .find(|jwk| {
-            let Some(crv) = jwk.curve() else {
-                tracing::warn!("jwk in keyset was missing 'crv'");
-                return false;
-            };
-            if let Some(use_) = jwk.key_use() {
-                crv == "P-256" && use_ == "enc"
-            } else {
-                tracing::warn!("jwk in keyset was missing 'use'");
-                crv == "P-256"
-            }
-        })
-        .context("no 'P-256' keys for use 'enc' found in JWK keyset")
+        .0
+        .to_string();
+    let enc = client_metadata
+        .authorization_encrypted_response_enc()
+        .draft18_parsing_error()?
+        .0
+        .to_string();
+
+    Ok(ResponseEncrypter::negotiate(&keys, Some(&alg), &[enc])?)
 }
 
 fn draft18_jwk_thumbprint(client_metadata: &Draft18ClientMetadata) -> Result<Option<[u8; 32]>> {
-    let jwks = client_metadata.jwks().draft18_parsing_error()?;
-    let keys: Vec<_> = jwks.keys.iter().collect();
-    let jwk_info = openid4vp::core::jwe::find_encryption_jwk(keys.into_iter())
-        .context("no suitable encryption key found in client metadata")?;
-    let jwk_json: Json = serde_json::to_value(&jwk_info.jwk).context("failed to serialize JWK")?;
-    Ok(Some(compute_jwk_thumbprint(&jwk_json)?))
+    Ok(Some(
+        draft18_response_encrypter(client_metadata)?.jwk_thumbprint(),
+    ))
 }
 
 fn convert_request_object(
@@ -32,7 +32,11 @@ use serde_json::json;
 use url::Url;
 use uuid::Uuid;
 
-use crate::{credential::mdoc::Mdoc, crypto::KeyStore};
+use crate::{
+    credential::mdoc::Mdoc,
+    crypto::KeyStore,
+    oid4vp::trust::{VerifierTrustRecorder, VerifierTrustResult, VerifierTrustStore},
+};
 
 /// Handler for OpenID4VP requests according to the profile in ISO/IEC 18013-7 Annex B.
 ///
@@ -43,6 +47,9 @@ pub struct OID4VP180137 {
     http_client: ReqwestClient,
     keystore: Arc<dyn KeyStore>,
     metadata: WalletMetadata,
+    trust_store: Arc<VerifierTrustStore>,
+    /// Trust in the verifier of the request being processed.
+    verifier_trust: VerifierTrustRecorder,
 }
 
 #[derive(uniffi::Object)]
@@ -51,6 +58,7 @@ pub struct InProgressRequest180137 {
     pub dcql_query: DcqlQuery,
     pub request_matches: Vec<Arc<RequestMatch180137>>,
     pub handler: OID4VP180137,
+    pub verifier_trust: VerifierTrustResult,
 }
 
 #[derive(Debug, uniffi::Record)]
@@ -107,6 +115,19 @@ impl OID4VP180137 {
             http_client: openid4vp::core::util::ReqwestClient::new()
                 .map_err(OID4VP180137Error::initialization)?,
             metadata: default_metadata(),
+            trust_store: Default::default(),
+            verifier_trust: Default::default(),
+        })
+    }
+
+    /// Return a handler evaluating the verifiers of requests against the
+    /// given trust store.
+    ///
+    /// The outcome is reported by [InProgressRequest180137::verifier_trust].
+    pub fn with_trust_store(self: Arc<Self>, trust_store: Arc<VerifierTrustStore>) -> Arc<Self> {
+        Arc::new(Self {
+            trust_store,
+            ..(*self).clone()
         })
     }
 
@@ -122,10 +143,17 @@ impl OID4VP180137 {
 
 impl OID4VP180137 {
     async fn process_request_inner(&self, url: Url) -> Result<InProgressRequest180137> {
-        let request = self
+        // Record the trust in the verifier of this request only.
+        let handler = Self {
+            verifier_trust: Default::default(),
+            ..self.clone()
+        };
+
+        let request = handler
             .validate_request(url)
             .await
             .context("failed to validate the request")?;
+        let verifier_trust = handler.verifier_trust.take();
 
         if request.response_mode() != &ResponseMode::DirectPostJwt {
             bail!("cannot respond to {} with a JWE", request.response_mode())
@@ -143,7 +171,8 @@ impl OID4VP180137 {
             request,
             dcql_query,
             request_matches,
-            handler: self.clone(),
+            handler,
+            verifier_trust,
         })
     }
 }
@@ -169,6 +198,11 @@ impl InProgressRequest180137 {
     pub fn matches(&self) -> Vec<Arc<RequestMatch180137>> {
         self.request_matches.clone()
     }
+
+    /// Returns the trust in the verifier requesting authorization.
+    pub fn verifier_trust(&self) -> VerifierTrustResult {
+        self.verifier_trust.clone()
+    }
 }
 
 impl InProgressRequest180137 {
@@ -176,6 +210,8 @@ impl InProgressRequest180137 {
         &self,
         approved_response: ApprovedResponse180137,
     ) -> Result<Option<Url>> {
+        self.verifier_trust.ensure_answerable()?;
+
         let credential = self
             .handler
             .credentials
@@ -239,12 +275,14 @@ impl RequestVerifier for OID4VP180137 {
     ) -> Result<()> {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_san_dns verification")?;
-        openid4vp::core::authorization_request::verification::x509_san::validate::<P256Verifier>(
-            &self.metadata,
-            decoded_request,
-            request_jwt,
-            None,
-        )
+        let trust =
+            self.trust_store.evaluate_x509("x509_san_dns", |roots| {
+                openid4vp::core::authorization_request::verification::x509_san::validate::<
+                    P256Verifier,
+                >(&self.metadata, decoded_request, request_jwt.clone(), roots)
+            })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 
     async fn x509_hash(
@@ -256,9 +294,17 @@ impl RequestVerifier for OID4VP180137 {
         let request_jwt =
             request_jwt.context("request JWT is required for x509_hash verification")?;
         // Not checking the origin like it's done for DC API as this is a redirect
-        // Use the x509_hash validation with P256 verifier
-        // Note: trusted_roots is None for now, meaning we don't verify the certificate chain
-        x509_hash::validate::<P256Verifier>(self.metadata(), decoded_request, request_jwt, None)
+        // The certificate chain is only verified against the roots of the trust store.
+        let trust = self.trust_store.evaluate_x509("x509_hash", |roots| {
+            x509_hash::validate::<P256Verifier>(
+                self.metadata(),
+                decoded_request,
+                request_jwt.clone(),
+                roots,
+            )
+        })?;
+        self.verifier_trust.record(trust);
+        Ok(())
     }
 }
 
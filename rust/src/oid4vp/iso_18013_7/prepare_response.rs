@@ -77,7 +77,11 @@ pub fn handover_from_request(
 ) -> Result<Handover> {
     let client_id = request.client_id().context("missing client_id")?.0.clone();
     let nonce = request.nonce().to_string();
-    let response_uri = request.get::<RawResponseUri>().parsing_error()?.0;
+    // The redirect_uri is used for the `fragment` and `query` response modes.
+    let response_uri = match request.get::<RawResponseUri>() {
+        Some(response_uri) => response_uri.parsing_error()?.0,
+        None => request.get::<RawRedirectUri>().parsing_error()?.0,
+    };
 
     handover_from_components(&client_id, &nonce, &response_uri, jwk_thumbprint)
 }
@@ -129,6 +133,33 @@ impl From<RawResponseUri> for Json {
     }
 }
 
+/// Unprocessed redirect_uri, used instead of the response_uri in the Handover
+/// of responses returned through a redirect.
+#[derive(Debug, Clone)]
+pub struct RawRedirectUri(pub String);
+
+impl TypedParameter for RawRedirectUri {
+    const KEY: &'static str = "redirect_uri";
+}
+
+impl TryFrom<Json> for RawRedirectUri {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Json) -> std::result::Result<Self, Self::Error> {
+        let Json::String(uri) = value else {
+            bail!("unexpected type")
+        };
+
+        Ok(Self(uri))
+    }
+}
+
+impl From<RawRedirectUri> for Json {
+    fn from(value: RawRedirectUri) -> Self {
+        Json::String(value.0)
+    }
+}
+
 /// Core function to build a DeviceResponse with device authentication.
 ///
 /// It handles DeviceAuthentication signing and DeviceResponse construction.
@@ -1,23 +1,39 @@
 pub mod dc_api;
+pub mod dcql;
 pub mod draft18;
 pub mod dynamic_credential;
 pub mod error;
 pub mod facade;
 pub mod holder;
 pub mod iso_18013_7;
+pub mod native_verifier;
 pub mod permission_request;
 pub mod presentation;
+pub mod request_signer;
+pub mod request_uri;
+pub mod response_encryption;
+pub mod transaction_data;
+pub mod trust;
 pub mod verifier;
+pub mod verifier_attestation;
+mod x5c;
 
 use serde_json::Value;
 use url::Url;
 
+pub use dcql::*;
 pub use dynamic_credential::*;
 pub use facade::*;
 pub use holder::*;
+pub use native_verifier::*;
 pub use permission_request::*;
 pub use presentation::*;
+pub use request_uri::*;
+pub use response_encryption::*;
+pub use transaction_data::*;
+pub use trust::*;
 pub use verifier::*;
+pub use verifier_attestation::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
 pub enum Oid4vpVersion {
@@ -31,6 +47,17 @@ pub enum Oid4vpVersion {
     Unsupported,
 }
 
+/// Client identifier prefixes of OID4VP 1.0 (§5.9.3).
+pub(crate) const CLIENT_ID_PREFIXES: [&str; 7] = [
+    "redirect_uri",
+    "decentralized_identifier",
+    "x509_san_dns",
+    "x509_hash",
+    "openid_federation",
+    "verifier_attestation",
+    "origin",
+];
+
 #[uniffi::export]
 pub fn get_oid4vp_version(request: String) -> Oid4vpVersion {
     select_oid4vp_version(&request, &[])
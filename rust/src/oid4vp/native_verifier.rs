@@ -0,0 +1,1573 @@
+//! Native OID4VP 1.0 verifier (relying party).
+//!
+//! Unlike the [DelegatedVerifier](super::DelegatedVerifier), which polls a
+//! remote verifier service, the [Oid4vpVerifier] builds and signs the
+//! authorization requests itself, and verifies the presentations it receives
+//! on device. DID-based issuer and holder keys are resolved with
+//! [AnyDidMethod], so verification is offline for `did:key` and `did:jwk`.
+//! Issuers signing with an X.509 certificate chain must be anchored in the
+//! trusted roots of the [Oid4vpVerifierConfig].
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use base64::prelude::*;
+use josekit::jwe::alg::ecdh_es::EcdhEsJweDecrypter;
+use openid4vp::core::{
+    dcql_query::{DcqlCredentialQuery, DcqlQuery},
+    iso_18013_7::compute_jwk_thumbprint,
+};
+use serde_json::{json, Value as Json};
+use sha2::{Digest, Sha256};
+use ssi::{
+    claims::{
+        jwt::{AnyClaims, ToDecodedJwt},
+        sd_jwt::SdJwt,
+        Jws, VerificationParameters,
+    },
+    dids::{AnyDidMethod, DIDResolver, VerificationMethodDIDResolver},
+    prelude::{AnyJsonCredential, AnyJsonPresentation, AnySuite, DataIntegrity},
+    verification_methods::AnyMethod,
+    JWK,
+};
+use tokio::sync::{Mutex, MutexGuard};
+use url::Url;
+use uuid::Uuid;
+use x509_cert::{
+    der::{oid::AssociatedOid, Decode, DecodePem},
+    ext::pkix::{name::GeneralName, SubjectAltName},
+    Certificate,
+};
+
+use super::iso_18013_7::prepare_response::{handover_from_components, OID4VPSessionTranscript};
+use super::presentation::select_dcql_claim_values;
+use super::request_signer::RequestSignerInterface;
+use super::verifier::Oid4vpVerifierError;
+use super::x5c;
+use crate::credential::{select_dcql_claim_set, CredentialFormat};
+use crate::mdl::reader::{validate_device_response, AuthenticationStatus};
+
+/// Media type of the signed request objects.
+const REQUEST_OBJECT_TYPE: &str = "oauth-authz-req+jwt";
+
+/// Media type of the SD-JWT key binding JWTs.
+const KB_JWT_TYPE: &str = "kb+jwt";
+
+/// Static audience of request objects, per OID4VP 1.0 §5.8.
+const SELF_ISSUED_AUDIENCE: &str = "https://self-issued.me/v2";
+
+/// Content encryption of `direct_post.jwt` responses.
+const RESPONSE_ENCRYPTION_ENC: &str = "A128GCM";
+
+/// Seconds a request waits for its response, after which it is discarded.
+const PENDING_REQUEST_TTL: i64 = 10 * 60;
+
+/// Tolerated difference between the clocks of the verifier and the wallet,
+/// in seconds.
+const CLOCK_SKEW: i64 = 60;
+
+/// How the verifier identifies itself, and the key material the wallet uses to
+/// authenticate its requests.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum Oid4vpVerifierClientId {
+    /// `x509_san_dns:{dns_name}`. The DNS name must be a SAN of the leaf
+    /// certificate.
+    X509SanDns {
+        dns_name: String,
+        /// DER-encoded certificates, leaf first.
+        certificate_chain: Vec<Vec<u8>>,
+    },
+    /// `x509_hash:{hash}`, where the hash is the SHA-256 of the leaf
+    /// certificate.
+    X509Hash {
+        /// DER-encoded certificates, leaf first.
+        certificate_chain: Vec<Vec<u8>>,
+    },
+    /// `decentralized_identifier:{did}`, with the request signed by the given
+    /// verification method of the DID.
+    DecentralizedIdentifier { verification_method: String },
+}
+
+impl Oid4vpVerifierClientId {
+    fn client_id(&self) -> String {
+        match self {
+            Self::X509SanDns { dns_name, .. } => format!("x509_san_dns:{dns_name}"),
+            Self::X509Hash { certificate_chain } => {
+                let leaf = certificate_chain.first().map(Vec::as_slice).unwrap_or(&[]);
+                format!(
+                    "x509_hash:{}",
+                    BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(leaf))
+                )
+            }
+            Self::DecentralizedIdentifier {
+                verification_method,
+            } => {
+                let did = verification_method
+                    .split_once('#')
+                    .map(|(did, _)| did)
+                    .unwrap_or(verification_method);
+                format!("decentralized_identifier:{did}")
+            }
+        }
+    }
+
+    /// Header parameters identifying the signing key of the request object.
+    fn header_parameters(&self) -> serde_json::Map<String, Json> {
+        let mut header = serde_json::Map::new();
+        match self {
+            Self::X509SanDns {
+                certificate_chain, ..
+            }
+            | Self::X509Hash { certificate_chain } => {
+                header.insert(
+                    "x5c".into(),
+                    certificate_chain
+                        .iter()
+                        .map(|certificate| Json::String(BASE64_STANDARD.encode(certificate)))
+                        .collect(),
+                );
+            }
+            Self::DecentralizedIdentifier {
+                verification_method,
+            } => {
+                header.insert("kid".into(), verification_method.clone().into());
+            }
+        }
+        header
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Oid4vpVerifierResponseMode {
+    /// The wallet posts the response as is.
+    DirectPost,
+    /// The wallet posts the response encrypted to an ephemeral key of the
+    /// verifier.
+    DirectPostJwt,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Oid4vpVerifierConfig {
+    pub client_id: Oid4vpVerifierClientId,
+    /// Where the wallet posts its response.
+    pub response_uri: Url,
+    pub response_mode: Oid4vpVerifierResponseMode,
+    /// PEM-encoded root certificates trusted for issuers: IACA certificates
+    /// for mdocs, and the roots of the `x5c` chains of SD-JWTs.
+    pub trusted_roots: Vec<String>,
+}
+
+/// An authorization request created by [Oid4vpVerifier::create_request].
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Oid4vpVerifierRequest {
+    /// Identifies the request, and the response to it.
+    pub state: String,
+    pub nonce: String,
+    /// The `openid4vp://` URL to present to the wallet, as a QR code or link.
+    pub authorization_url: String,
+    /// The signed request object.
+    pub request_object: String,
+}
+
+/// The verified content of an authorization response.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Oid4vpVerifiedResponse {
+    pub state: String,
+    /// Whether every presentation was verified, and every required credential
+    /// was presented.
+    pub verified: bool,
+    pub presentations: Vec<Oid4vpVerifiedPresentation>,
+    /// IDs of the required credential queries left unanswered.
+    pub missing_credential_queries: Vec<String>,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Oid4vpVerifiedPresentation {
+    pub credential_query_id: String,
+    pub format: CredentialFormat,
+    pub verified: bool,
+    /// The disclosed claims, as JSON.
+    pub claims: Option<String>,
+    /// Why the presentation could not be verified.
+    pub error: Option<String>,
+}
+
+/// A request waiting for its response.
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    /// When the request was created, as a UNIX timestamp.
+    created_at: i64,
+    nonce: String,
+    dcql_query: DcqlQuery,
+    /// The request object, until it is fetched by the wallet.
+    request_object: Option<String>,
+    /// Ephemeral key the response is encrypted to, for `direct_post.jwt`.
+    encryption_key: Option<JWK>,
+}
+
+impl PendingRequest {
+    fn encryption_key_id(&self) -> Option<&str> {
+        self.encryption_key.as_ref()?.key_id.as_deref()
+    }
+
+    fn is_expired(&self, now: i64) -> bool {
+        now - self.created_at > PENDING_REQUEST_TTL
+    }
+}
+
+/// Values the presentations are bound to.
+struct PresentationContext<'a> {
+    client_id: &'a str,
+    nonce: &'a str,
+    /// When the request was created. Key binding JWTs issued before are
+    /// rejected.
+    issued_at: i64,
+    response_uri: &'a str,
+    /// Thumbprint of the response encryption key, part of the mdoc handover.
+    jwk_thumbprint: Option<[u8; 32]>,
+    trusted_roots: &'a [String],
+}
+
+/// An OID4VP 1.0 verifier, creating authorization requests and verifying the
+/// responses to them without a verifier service.
+#[derive(uniffi::Object)]
+pub struct Oid4vpVerifier {
+    config: Oid4vpVerifierConfig,
+    signer: Arc<dyn RequestSignerInterface>,
+    /// Pending requests, by state. Expired requests are discarded whenever
+    /// they are accessed.
+    pending: Mutex<HashMap<String, PendingRequest>>,
+}
+
+impl std::fmt::Debug for Oid4vpVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Oid4vpVerifier")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl Oid4vpVerifier {
+    #[uniffi::constructor]
+    pub fn new(config: Oid4vpVerifierConfig, signer: Arc<dyn RequestSignerInterface>) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            signer,
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The client ID of the verifier, including its prefix.
+    pub fn client_id(&self) -> String {
+        self.config.client_id.client_id()
+    }
+
+    /// Create and sign an authorization request for the given DCQL query.
+    ///
+    /// The request expires if it is not answered within ten minutes.
+    ///
+    /// With a `request_uri`, the authorization URL references the request
+    /// object, which must then be served at that URI with
+    /// [Oid4vpVerifier::fetch_request_object]. Otherwise, the request object
+    /// is passed by value.
+    pub async fn create_request(
+        &self,
+        dcql_query: String,
+        request_uri: Option<Url>,
+    ) -> Result<Oid4vpVerifierRequest, Oid4vpVerifierError> {
+        let query: DcqlQuery = serde_json::from_str(&dcql_query)
+            .map_err(|e| Oid4vpVerifierError::InvalidQuery(e.to_string()))?;
+
+        let client_id = self.client_id();
+        let state = Uuid::new_v4().to_string();
+        let nonce = Uuid::new_v4().to_string();
+        let created_at = chrono::Utc::now().timestamp();
+
+        let encryption_key = match self.config.response_mode {
+            Oid4vpVerifierResponseMode::DirectPost => None,
+            Oid4vpVerifierResponseMode::DirectPostJwt => {
+                let mut key = JWK::generate_p256();
+                key.key_id = Some(Uuid::new_v4().to_string());
+                Some(key)
+            }
+        };
+
+        let mut client_metadata = json!({ "vp_formats_supported": {} });
+        if let Some(key) = &encryption_key {
+            client_metadata["jwks"] = json!({ "keys": [encryption_jwk(key)?] });
+            client_metadata["encrypted_response_enc_values_supported"] =
+                json!([RESPONSE_ENCRYPTION_ENC]);
+        }
+
+        let response_mode = match self.config.response_mode {
+            Oid4vpVerifierResponseMode::DirectPost => "direct_post",
+            Oid4vpVerifierResponseMode::DirectPostJwt => "direct_post.jwt",
+        };
+
+        let claims = json!({
+            "iss": client_id,
+            "aud": SELF_ISSUED_AUDIENCE,
+            "iat": created_at,
+            "client_id": client_id,
+            "response_type": "vp_token",
+            "response_mode": response_mode,
+            "response_uri": self.config.response_uri.to_string(),
+            "state": state,
+            "nonce": nonce,
+            "dcql_query": serde_json::to_value(&query)
+                .map_err(|e| Oid4vpVerifierError::InvalidQuery(e.to_string()))?,
+            "client_metadata": client_metadata,
+        });
+
+        let request_object = self.sign_request_object(&claims).await?;
+
+        let mut authorization_url =
+            Url::parse("openid4vp://").map_err(|e| Oid4vpVerifierError::Url(format!("{e:?}")))?;
+        authorization_url
+            .query_pairs_mut()
+            .append_pair("client_id", &client_id);
+        match &request_uri {
+            Some(request_uri) => authorization_url
+                .query_pairs_mut()
+                .append_pair("request_uri", request_uri.as_str()),
+            None => authorization_url
+                .query_pairs_mut()
+                .append_pair("request", &request_object),
+        };
+
+        self.lock_pending().await.insert(
+            state.clone(),
+            PendingRequest {
+                created_at,
+                nonce: nonce.clone(),
+                dcql_query: query,
+                request_object: request_uri.map(|_| request_object.clone()),
+                encryption_key,
+            },
+        );
+
+        Ok(Oid4vpVerifierRequest {
+            state,
+            nonce,
+            authorization_url: authorization_url.to_string(),
+            request_object,
+        })
+    }
+
+    /// Return the request object to serve at the `request_uri` of a request.
+    ///
+    /// A request object can only be fetched once.
+    pub async fn fetch_request_object(&self, state: String) -> Result<String, Oid4vpVerifierError> {
+        self.lock_pending()
+            .await
+            .get_mut(&state)
+            .ok_or(Oid4vpVerifierError::UnknownSession)?
+            .request_object
+            .take()
+            .ok_or(Oid4vpVerifierError::RequestObjectFetched)
+    }
+
+    /// Discard a pending request, e.g. when the user cancels it.
+    pub async fn cancel_request(&self, state: String) {
+        self.lock_pending().await.remove(&state);
+    }
+
+    /// Verify an authorization response, as posted by the wallet to the
+    /// `response_uri` in `application/x-www-form-urlencoded` form.
+    ///
+    /// The request is completed by its response: a second response to the
+    /// same request is rejected. Failing presentations are reported in the
+    /// result rather than as an error.
+    pub async fn verify_response(
+        &self,
+        body: String,
+    ) -> Result<Oid4vpVerifiedResponse, Oid4vpVerifierError> {
+        let parameters: HashMap<String, String> = url::form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect();
+
+        if let Some(error) = parameters.get("error") {
+            let description = parameters
+                .get("error_description")
+                .map(|d| format!(": {d}"))
+                .unwrap_or_default();
+            return Err(Oid4vpVerifierError::InvalidResponse(format!(
+                "the wallet returned `{error}`{description}"
+            )));
+        }
+
+        let (state, pending, vp_token) = match parameters.get("response") {
+            Some(jwe) => self.decrypt_response(jwe).await?,
+            None => {
+                let state = parameters
+                    .get("state")
+                    .ok_or_else(|| Oid4vpVerifierError::InvalidResponse("missing state".into()))?;
+                let encrypted = self
+                    .lock_pending()
+                    .await
+                    .get(state)
+                    .ok_or(Oid4vpVerifierError::UnknownSession)?
+                    .encryption_key
+                    .is_some();
+                if encrypted {
+                    return Err(Oid4vpVerifierError::InvalidResponse(
+                        "the response must be encrypted".into(),
+                    ));
+                }
+                let vp_token = parameters
+                    .get("vp_token")
+                    .ok_or_else(|| Oid4vpVerifierError::InvalidResponse("missing vp_token".into()))
+                    .and_then(|vp_token| {
+                        serde_json::from_str(vp_token)
+                            .map_err(|e| Oid4vpVerifierError::InvalidResponse(e.to_string()))
+                    })?;
+                (state.clone(), self.take_pending(state).await?, vp_token)
+            }
+        };
+
+        let Json::Object(vp_token) = vp_token else {
+            return Err(Oid4vpVerifierError::InvalidResponse(
+                "vp_token is not an object".into(),
+            ));
+        };
+
+        let client_id = self.client_id();
+        let response_uri = self.config.response_uri.to_string();
+        let jwk_thumbprint = pending
+            .encryption_key
+            .as_ref()
+            .map(|key| {
+                encryption_jwk(key).and_then(|jwk| {
+                    compute_jwk_thumbprint(&jwk)
+                        .map_err(|e| Oid4vpVerifierError::InvalidResponse(format!("{e:?}")))
+                })
+            })
+            .transpose()?;
+        let context = PresentationContext {
+            client_id: &client_id,
+            nonce: &pending.nonce,
+            issued_at: pending.created_at,
+            response_uri: &response_uri,
+            jwk_thumbprint,
+            trusted_roots: &self.config.trusted_roots,
+        };
+
+        let queries: HashMap<String, (&DcqlCredentialQuery, CredentialFormat)> = pending
+            .dcql_query
+            .credentials()
+            .iter()
+            .map(|query| {
+                let format = serde_json::to_value(query.format())
+                    .and_then(serde_json::from_value)
+                    .map_err(|e| Oid4vpVerifierError::InvalidQuery(e.to_string()))?;
+                Ok((query.id().to_string(), (query, format)))
+            })
+            .collect::<Result<_, Oid4vpVerifierError>>()?;
+
+        let mut presentations = Vec::new();
+        for (credential_query_id, items) in &vp_token {
+            let (query, format) = queries.get(credential_query_id).ok_or_else(|| {
+                Oid4vpVerifierError::InvalidResponse(format!(
+                    "unknown credential query `{credential_query_id}`"
+                ))
+            })?;
+
+            let items = match items {
+                Json::Array(items) => items.iter().collect(),
+                item => vec![item],
+            };
+            let too_many = items.len() > 1 && !allows_multiple(query);
+
+            for item in items {
+                let verification = if too_many {
+                    Err(format!(
+                        "credential query `{credential_query_id}` does not allow multiple credentials"
+                    ))
+                } else {
+                    verify_presentation(query, format, item, &context).await
+                };
+                let (claims, error) = match verification {
+                    Ok(claims) => (Some(claims.to_string()), None),
+                    Err(error) => (None, Some(error)),
+                };
+                presentations.push(Oid4vpVerifiedPresentation {
+                    credential_query_id: credential_query_id.clone(),
+                    format: format.clone(),
+                    verified: error.is_none(),
+                    claims,
+                    error,
+                });
+            }
+        }
+
+        let presented = presentations
+            .iter()
+            .filter(|p| p.verified)
+            .map(|p| p.credential_query_id.as_str())
+            .collect::<HashSet<_>>();
+        let missing_credential_queries =
+            missing_credential_queries(&pending.dcql_query, &presented);
+
+        Ok(Oid4vpVerifiedResponse {
+            state,
+            verified: !presentations.is_empty()
+                && presentations.iter().all(|p| p.verified)
+                && missing_credential_queries.is_empty(),
+            presentations,
+            missing_credential_queries,
+        })
+    }
+}
+
+impl Oid4vpVerifier {
+    /// Lock the pending requests, discarding the expired ones.
+    async fn lock_pending(&self) -> MutexGuard<'_, HashMap<String, PendingRequest>> {
+        let now = chrono::Utc::now().timestamp();
+        let mut pending = self.pending.lock().await;
+        pending.retain(|_, request| !request.is_expired(now));
+        pending
+    }
+
+    async fn sign_request_object(&self, claims: &Json) -> Result<String, Oid4vpVerifierError> {
+        let alg = self
+            .signer
+            .alg()
+            .map_err(|e| Oid4vpVerifierError::Signing(e.to_string()))?;
+
+        let mut header = self.config.client_id.header_parameters();
+        header.insert("alg".into(), alg.clone().into());
+        header.insert("typ".into(), REQUEST_OBJECT_TYPE.into());
+
+        let signing_input = format!(
+            "{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(Json::Object(header).to_string()),
+            BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+
+        let mut signature = self
+            .signer
+            .try_sign(signing_input.as_bytes().to_vec())
+            .await
+            .map_err(|e| Oid4vpVerifierError::Signing(e.to_string()))?;
+
+        // Foreign signers may return DER-encoded ECDSA signatures, while JWS
+        // expects them fixed-width.
+        if alg == "ES256" {
+            if let Ok(der) = p256::ecdsa::Signature::from_der(&signature) {
+                signature = der.to_bytes().to_vec();
+            }
+        }
+
+        Ok(format!(
+            "{signing_input}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    async fn take_pending(&self, state: &str) -> Result<PendingRequest, Oid4vpVerifierError> {
+        self.lock_pending()
+            .await
+            .remove(state)
+            .ok_or(Oid4vpVerifierError::UnknownSession)
+    }
+
+    /// Decrypt a `direct_post.jwt` response, returning its state, request and
+    /// `vp_token`.
+    async fn decrypt_response(
+        &self,
+        jwe: &str,
+    ) -> Result<(String, PendingRequest, Json), Oid4vpVerifierError> {
+        let decryption =
+            |e: &dyn std::fmt::Debug| Oid4vpVerifierError::Decryption(format!("{e:?}"));
+
+        // The request is looked up by its encryption key, and only completed
+        // once the response is decrypted, so that a forged response cannot
+        // discard it.
+        let kid = jwe
+            .split('.')
+            .next()
+            .and_then(|header| BASE64_URL_SAFE_NO_PAD.decode(header).ok())
+            .and_then(|header| serde_json::from_slice::<Json>(&header).ok())
+            .and_then(|header| header.get("kid")?.as_str().map(str::to_string))
+            .ok_or_else(|| Oid4vpVerifierError::Decryption("missing `kid` header".into()))?;
+
+        let (state, key) = self
+            .lock_pending()
+            .await
+            .iter()
+            .find(|(_, pending)| pending.encryption_key_id() == Some(kid.as_str()))
+            .and_then(|(state, pending)| Some((state.clone(), pending.encryption_key.clone()?)))
+            .ok_or(Oid4vpVerifierError::UnknownSession)?;
+
+        let key = serde_json::to_vec(&key)
+            .map_err(|e| decryption(&e))
+            .and_then(|key| josekit::jwk::Jwk::from_bytes(key).map_err(|e| decryption(&e)))?;
+        let decrypter: EcdhEsJweDecrypter<p256::NistP256> = josekit::jwe::ECDH_ES
+            .decrypter_from_jwk(&key)
+            .map_err(|e| decryption(&e))?;
+        let (payload, _) =
+            josekit::jwe::deserialize_compact(jwe, &decrypter).map_err(|e| decryption(&e))?;
+
+        let mut payload: Json = serde_json::from_slice(&payload)
+            .map_err(|e| Oid4vpVerifierError::InvalidResponse(e.to_string()))?;
+
+        if payload.get("state").and_then(Json::as_str) != Some(state.as_str()) {
+            return Err(Oid4vpVerifierError::InvalidResponse(
+                "the state does not match the request".into(),
+            ));
+        }
+
+        let vp_token = payload
+            .get_mut("vp_token")
+            .map(Json::take)
+            .ok_or_else(|| Oid4vpVerifierError::InvalidResponse("missing vp_token".into()))?;
+
+        let pending = self.take_pending(&state).await?;
+        Ok((state, pending, vp_token))
+    }
+}
+
+/// Public JWK of an ephemeral response encryption key.
+fn encryption_jwk(key: &JWK) -> Result<Json, Oid4vpVerifierError> {
+    let mut jwk = serde_json::to_value(key.to_public())
+        .map_err(|e| Oid4vpVerifierError::InvalidResponse(e.to_string()))?;
+    jwk["use"] = "enc".into();
+    jwk["alg"] = "ECDH-ES".into();
+    Ok(jwk)
+}
+
+/// The credential queries of required credential sets with no satisfied
+/// option, or all unanswered credential queries without credential sets.
+fn missing_credential_queries(query: &DcqlQuery, presented: &HashSet<&str>) -> Vec<String> {
+    match query.credential_sets() {
+        Some(credential_sets) => credential_sets
+            .iter()
+            .filter(|set| set.is_required())
+            .filter(|set| {
+                !set.options()
+                    .iter()
+                    .any(|option| option.iter().all(|id| presented.contains(id.as_str())))
+            })
+            .flat_map(|set| set.options().first().cloned().unwrap_or_default())
+            .filter(|id| !presented.contains(id.as_str()))
+            .collect(),
+        None => query
+            .credentials()
+            .iter()
+            .map(|credential| credential.id().to_string())
+            .filter(|id| !presented.contains(id.as_str()))
+            .collect(),
+    }
+}
+
+type DidVerificationParameters =
+    VerificationParameters<VerificationMethodDIDResolver<AnyDidMethod, AnyMethod>>;
+
+fn verification_parameters() -> DidVerificationParameters {
+    VerificationParameters::from_resolver(AnyDidMethod::default().into_vm_resolver())
+}
+
+/// Whether several credentials may be presented for a credential query.
+fn allows_multiple(query: &DcqlCredentialQuery) -> bool {
+    serde_json::to_value(query)
+        .ok()
+        .and_then(|query| query.get("multiple")?.as_bool())
+        .unwrap_or(false)
+}
+
+/// Verify a presentation and match it against its credential query,
+/// returning its disclosed claims.
+async fn verify_presentation(
+    query: &DcqlCredentialQuery,
+    format: &CredentialFormat,
+    item: &Json,
+    context: &PresentationContext<'_>,
+) -> Result<Json, String> {
+    let as_str = || {
+        item.as_str()
+            .ok_or_else(|| format!("a {format} presentation must be a string"))
+    };
+
+    match format {
+        CredentialFormat::MsoMdoc => {
+            let (doc_types, claims) = verify_mdoc(as_str()?, context).await?;
+            check_credential_query(query, &doc_types, &claims)?;
+            Ok(claims)
+        }
+        CredentialFormat::DcSdJwt => {
+            check_credential(query, verify_sd_jwt(as_str()?, context, true).await?)
+        }
+        CredentialFormat::VCDM2SdJwt => {
+            check_credential(query, verify_sd_jwt(as_str()?, context, false).await?)
+        }
+        CredentialFormat::JwtVcJson | CredentialFormat::JwtVcJsonLd => {
+            check_credentials(query, verify_jwt_vp(as_str()?, context).await?)
+        }
+        CredentialFormat::LdpVc => check_credentials(query, verify_ldp_vp(item, context).await?),
+        CredentialFormat::Cwt
+        | CredentialFormat::OpticalBarcodeCredential
+        | CredentialFormat::Other(_) => Err(format!("{format} cannot be presented over OID4VP")),
+    }
+}
+
+/// Match a presented credential against its credential query, returning its
+/// claims.
+fn check_credential(query: &DcqlCredentialQuery, claims: Json) -> Result<Json, String> {
+    check_credential_query(query, &credential_types(&claims), &claims)?;
+    Ok(claims)
+}
+
+/// Match the credentials of a W3C presentation against their credential
+/// query, returning them.
+fn check_credentials(query: &DcqlCredentialQuery, credentials: Json) -> Result<Json, String> {
+    let presented = credentials
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if presented.is_empty() {
+        return Err("the presentation does not contain any credential".into());
+    }
+
+    for credential in presented {
+        check_credential_query(query, &credential_types(credential), credential)?;
+    }
+    Ok(credentials)
+}
+
+/// The `vct` of an SD-JWT VC, or the `type` of a W3C credential.
+fn credential_types(claims: &Json) -> Vec<String> {
+    let types = match (claims.get("vct"), claims.get("type")) {
+        (Some(vct), _) => vec![vct],
+        (None, Some(Json::Array(types))) => types.iter().collect(),
+        (None, Some(credential_type)) => vec![credential_type],
+        (None, None) => vec![],
+    };
+
+    types
+        .into_iter()
+        .filter_map(|t| t.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Check that a presented credential is of a type requested by the credential
+/// query, and holds the requested claims with the requested values.
+fn check_credential_query(
+    query: &DcqlCredentialQuery,
+    types: &[String],
+    claims: &Json,
+) -> Result<(), String> {
+    let meta = query.meta();
+    let has_type = |expected: &Json| {
+        expected
+            .as_str()
+            .is_some_and(|expected| types.iter().any(|t| t == expected))
+    };
+
+    if let Some(doctype) = meta.get("doctype_value") {
+        if !has_type(doctype) {
+            return Err(format!(
+                "the credential is not of the requested doctype {doctype}"
+            ));
+        }
+    }
+
+    if let Some(vct_values) = meta.get("vct_values").and_then(Json::as_array) {
+        if !vct_values.iter().any(has_type) {
+            return Err("the credential is not of a requested vct".into());
+        }
+    }
+
+    // Each element of `type_values` is a set of types the credential must
+    // all have.
+    if let Some(type_values) = meta.get("type_values").and_then(Json::as_array) {
+        let matches = type_values.iter().any(|alternative| {
+            alternative
+                .as_array()
+                .is_some_and(|alternative| alternative.iter().all(has_type))
+        });
+        if !matches {
+            return Err("the credential is not of a requested type".into());
+        }
+    }
+
+    select_dcql_claim_set(query, |path| select_dcql_claim_values(claims, path))
+        .map(|_| ())
+        .ok_or_else(|| "the credential does not disclose the requested claims".into())
+}
+
+/// Verify an mdoc presentation, returning its doctypes and disclosed claims.
+///
+/// The issuer key is the leaf of the `x5chain` of the mobile security
+/// object, which must be issued by one of the trusted roots.
+async fn verify_mdoc(
+    presentation: &str,
+    context: &PresentationContext<'_>,
+) -> Result<(Vec<String>, Json), String> {
+    if context.trusted_roots.is_empty() {
+        return Err("no root is trusted for mdoc issuers".into());
+    }
+
+    let device_response = BASE64_URL_SAFE_NO_PAD
+        .decode(presentation)
+        .map_err(|e| format!("malformed device response: {e}"))?;
+
+    let handover = handover_from_components(
+        context.client_id,
+        context.nonce,
+        context.response_uri,
+        context.jwk_thumbprint.as_ref(),
+    )
+    .map_err(|e| format!("{e:#}"))?;
+    let session_transcript = isomdl::cbor::to_vec(&OID4VPSessionTranscript::new(handover))
+        .map_err(|e| format!("failed to encode the session transcript: {e}"))?;
+
+    let verification = validate_device_response(
+        device_response,
+        session_transcript,
+        vec![],
+        Some(context.trusted_roots.to_vec()),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !matches!(
+        verification.issuer_authentication,
+        AuthenticationStatus::Valid
+    ) {
+        return Err("invalid issuer authentication".into());
+    }
+    if !matches!(
+        verification.device_authentication,
+        AuthenticationStatus::Valid
+    ) {
+        return Err("invalid device authentication".into());
+    }
+
+    let claims = verification
+        .verified_response_as_json()
+        .map_err(|e| e.to_string())?;
+    Ok((verification.doc_types, claims))
+}
+
+async fn verify_sd_jwt(
+    presentation: &str,
+    context: &PresentationContext<'_>,
+    key_binding_required: bool,
+) -> Result<Json, String> {
+    let sd_jwt = SdJwt::new(presentation).map_err(|e| format!("invalid SD-JWT: {e:?}"))?;
+
+    // Issuers sign either with an `x5c` certificate chain, or a DID.
+    let issuer_jwt = presentation.split('~').next().unwrap_or_default();
+    let issuer_header = decode_jws_part(issuer_jwt, 0)?;
+    if issuer_header.get("x5c").is_some() {
+        verify_x5c_jwt(issuer_jwt, &issuer_header, context.trusted_roots)?;
+    } else {
+        sd_jwt
+            .decode_verify_concealed(&verification_parameters())
+            .await
+            .map_err(|e| format!("unable to verify the issuer signature: {e:?}"))?
+            .1
+            .map_err(|e| format!("invalid issuer signature: {e:?}"))?;
+    }
+
+    let claims = sd_jwt
+        .decode_reveal::<AnyClaims>()
+        .map_err(|e| format!("invalid disclosures: {e:?}"))
+        .and_then(|revealed| {
+            serde_json::to_value(revealed.claims()).map_err(|e| format!("{e:?}"))
+        })?;
+
+    // The key binding JWT follows the last `~`, and covers everything before.
+    let (disclosed, kb_jwt) = presentation
+        .rsplit_once('~')
+        .ok_or("invalid SD-JWT: missing `~` separator")?;
+
+    match (kb_jwt, claims.pointer("/cnf/jwk")) {
+        ("", None) if !key_binding_required => {}
+        ("", _) => return Err("missing key binding JWT".into()),
+        (_, None) => return Err("the credential is not bound to a key".into()),
+        (kb_jwt, Some(holder_key)) => {
+            let holder_key: JWK = serde_json::from_value(holder_key.clone())
+                .map_err(|e| format!("invalid holder key: {e}"))?;
+            let (header, payload) = verify_compact_jws(kb_jwt, &holder_key)?;
+
+            if header.get("typ").and_then(Json::as_str) != Some(KB_JWT_TYPE) {
+                return Err("invalid key binding JWT type".into());
+            }
+
+            let sd_hash = BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(format!("{disclosed}~")));
+            if payload.get("sd_hash").and_then(Json::as_str) != Some(sd_hash.as_str()) {
+                return Err("the key binding JWT does not match the disclosures".into());
+            }
+            check_binding(&payload, context)?;
+            check_issued_at(&payload, context)?;
+        }
+    }
+
+    Ok(claims)
+}
+
+async fn verify_jwt_vp(
+    presentation: &str,
+    context: &PresentationContext<'_>,
+) -> Result<Json, String> {
+    let params = verification_parameters();
+
+    let jws = Jws::new(presentation).map_err(|e| format!("invalid JWT: {e:?}"))?;
+    jws.verify_jwt(&params)
+        .await
+        .map_err(|e| format!("unable to verify the presentation: {e:?}"))?
+        .map_err(|e| format!("invalid presentation: {e:?}"))?;
+
+    let header = decode_jws_part(presentation, 0)?;
+    let payload = decode_jws_part(presentation, 1)?;
+    check_binding(&payload, context)?;
+
+    let holder = presentation_holder(payload.get("iss").or_else(|| payload.pointer("/vp/holder")))?;
+    check_signed_by_holder(holder, header.get("kid").and_then(Json::as_str))?;
+
+    let credentials = match payload.pointer("/vp/verifiableCredential") {
+        Some(Json::Array(credentials)) => credentials.iter().collect(),
+        Some(credential) => vec![credential],
+        None => vec![],
+    };
+
+    let mut claims = Vec::with_capacity(credentials.len());
+    for credential in credentials {
+        let credential = credential
+            .as_str()
+            .ok_or("the presented credentials must be JWTs")?;
+        Jws::new(credential)
+            .map_err(|e| format!("invalid credential: {e:?}"))?
+            .verify_jwt(&params)
+            .await
+            .map_err(|e| format!("unable to verify a credential: {e:?}"))?
+            .map_err(|e| format!("invalid credential: {e:?}"))?;
+
+        let credential = decode_jws_part(credential, 1)?;
+        let vc = credential
+            .get("vc")
+            .cloned()
+            .unwrap_or_else(|| credential.clone());
+        let mut subjects = credential_subjects(&vc);
+        subjects.extend(credential.get("sub").and_then(Json::as_str));
+        check_bound_to_holder(&subjects, holder)?;
+        claims.push(vc);
+    }
+
+    Ok(Json::Array(claims))
+}
+
+async fn verify_ldp_vp(
+    presentation: &Json,
+    context: &PresentationContext<'_>,
+) -> Result<Json, String> {
+    let params = verification_parameters();
+
+    let vp: DataIntegrity<AnyJsonPresentation, AnySuite> =
+        serde_json::from_value(presentation.clone())
+            .map_err(|e| format!("invalid presentation: {e}"))?;
+    vp.verify(&params)
+        .await
+        .map_err(|e| format!("unable to verify the presentation: {e:?}"))?
+        .map_err(|e| format!("invalid presentation: {e:?}"))?;
+
+    // The proofs are bound to the request by their challenge and domain.
+    let proofs = match presentation.get("proof") {
+        Some(Json::Array(proofs)) => proofs.iter().collect(),
+        Some(proof) => vec![proof],
+        None => vec![],
+    };
+    let bound = proofs.iter().any(|proof| {
+        let domain = match proof.get("domain") {
+            Some(Json::Array(domains)) => domains
+                .iter()
+                .any(|d| d.as_str() == Some(context.client_id)),
+            Some(domain) => domain.as_str() == Some(context.client_id),
+            None => false,
+        };
+        domain && proof.get("challenge").and_then(Json::as_str) == Some(context.nonce)
+    });
+    if !bound {
+        return Err("the presentation is not bound to the request".into());
+    }
+
+    let holder = presentation_holder(presentation.get("holder"))?;
+    for proof in &proofs {
+        check_signed_by_holder(
+            holder,
+            proof.get("verificationMethod").and_then(Json::as_str),
+        )?;
+    }
+
+    let credentials = match presentation.get("verifiableCredential") {
+        Some(Json::Array(credentials)) => credentials.clone(),
+        Some(credential) => vec![credential.clone()],
+        None => vec![],
+    };
+
+    for credential in &credentials {
+        let credential: DataIntegrity<AnyJsonCredential, AnySuite> =
+            serde_json::from_value(credential.clone())
+                .map_err(|e| format!("invalid credential: {e}"))?;
+        credential
+            .verify(&params)
+            .await
+            .map_err(|e| format!("unable to verify a credential: {e:?}"))?
+            .map_err(|e| format!("invalid credential: {e:?}"))?;
+    }
+    for credential in &credentials {
+        check_bound_to_holder(&credential_subjects(credential), holder)?;
+    }
+
+    Ok(Json::Array(credentials))
+}
+
+/// Check that a presentation is bound to the request, by its audience and
+/// nonce.
+fn check_binding(payload: &Json, context: &PresentationContext<'_>) -> Result<(), String> {
+    let audience = match payload.get("aud") {
+        Some(Json::Array(audiences)) => audiences
+            .iter()
+            .any(|a| a.as_str() == Some(context.client_id)),
+        Some(audience) => audience.as_str() == Some(context.client_id),
+        None => false,
+    };
+    if !audience {
+        return Err("the presentation is intended for another verifier".into());
+    }
+
+    if payload.get("nonce").and_then(Json::as_str) != Some(context.nonce) {
+        return Err("the presentation nonce does not match the request".into());
+    }
+
+    Ok(())
+}
+
+/// Check that a key binding JWT was issued for the request: after it was
+/// created, and not in the future.
+fn check_issued_at(payload: &Json, context: &PresentationContext<'_>) -> Result<(), String> {
+    let iat = payload
+        .get("iat")
+        .and_then(Json::as_i64)
+        .ok_or("the key binding JWT does not state when it was issued")?;
+
+    let now = chrono::Utc::now().timestamp();
+    if iat < context.issued_at - CLOCK_SKEW || iat > now + CLOCK_SKEW {
+        return Err("the key binding JWT was not issued for the request".into());
+    }
+    Ok(())
+}
+
+/// The DID of a DID URL.
+fn did_of(did_url: &str) -> &str {
+    did_url
+        .split_once('#')
+        .map(|(did, _)| did)
+        .unwrap_or(did_url)
+}
+
+/// The DID of the holder of a W3C presentation.
+fn presentation_holder(holder: Option<&Json>) -> Result<&str, String> {
+    let holder = match holder {
+        Some(Json::Array(holders)) if holders.len() == 1 => &holders[0],
+        Some(holder) => holder,
+        None => return Err("the presentation does not state its holder".into()),
+    };
+    match holder {
+        Json::Object(holder) => holder.get("id").and_then(Json::as_str),
+        holder => holder.as_str(),
+    }
+    .map(did_of)
+    .ok_or_else(|| "invalid presentation holder".into())
+}
+
+/// Check that a presentation is signed by a verification method of the DID
+/// of its holder.
+fn check_signed_by_holder(holder: &str, verification_method: Option<&str>) -> Result<(), String> {
+    if verification_method.map(did_of) != Some(holder) {
+        return Err("the presentation is not signed by its holder".into());
+    }
+    Ok(())
+}
+
+/// The `id` of the subjects of a W3C credential.
+fn credential_subjects(credential: &Json) -> Vec<&str> {
+    let subjects = match credential.get("credentialSubject") {
+        Some(Json::Array(subjects)) => subjects.iter().collect(),
+        Some(subject) => vec![subject],
+        None => vec![],
+    };
+
+    subjects
+        .into_iter()
+        .filter_map(|subject| subject.get("id")?.as_str())
+        .collect()
+}
+
+/// Check that a presented credential is about the holder of the
+/// presentation, so that it cannot be presented by anyone else.
+fn check_bound_to_holder(subjects: &[&str], holder: &str) -> Result<(), String> {
+    if !subjects.iter().any(|subject| did_of(subject) == holder) {
+        return Err("a credential is not bound to the holder of the presentation".into());
+    }
+    Ok(())
+}
+
+/// Verify a JWT signed with an `x5c` certificate chain anchored in the
+/// trusted roots.
+fn verify_x5c_jwt(jwt: &str, header: &Json, trusted_roots: &[String]) -> Result<(), String> {
+    let chain = x5c::decode_chain(header).map_err(|e| format!("{e:#}"))?;
+    x5c::verify_chain(&chain).map_err(|e| format!("{e:#}"))?;
+
+    let roots = trusted_roots
+        .iter()
+        .map(Certificate::from_pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid trusted root: {e}"))?;
+    if !x5c::is_anchored(&chain, &roots) {
+        return Err("the issuer certificate is not issued by a trusted root".into());
+    }
+
+    x5c::verify_jwt_signature(jwt, header, &chain[0])
+        .map_err(|e| format!("invalid issuer signature: {e:#}"))?;
+
+    let payload = decode_jws_part(jwt, 1)?;
+    check_issuer_name(&chain[0], payload.get("iss").and_then(Json::as_str))
+}
+
+/// The `iss` of an SD-JWT VC signed with an `x5c` chain, if it is a DNS name
+/// or an HTTPS URL, must be a subject alternative name of the leaf
+/// certificate (SD-JWT VC §3.5).
+fn check_issuer_name(leaf: &Certificate, iss: Option<&str>) -> Result<(), String> {
+    let Some(iss) = iss else {
+        return Ok(());
+    };
+    let dns_name = iss.strip_prefix("dns:");
+    if dns_name.is_none() && !iss.starts_with("https://") {
+        return Ok(());
+    }
+
+    let mut names = leaf
+        .tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .filter(|extension| extension.extn_id == SubjectAltName::OID)
+        .filter_map(|extension| SubjectAltName::from_der(extension.extn_value.as_bytes()).ok())
+        .flat_map(|names| names.0);
+    let matches = names.any(|name| match (name, dns_name) {
+        (GeneralName::DnsName(name), Some(dns_name)) => name.to_string() == dns_name,
+        (GeneralName::UniformResourceIdentifier(uri), None) => uri.to_string() == iss,
+        _ => false,
+    });
+
+    if !matches {
+        return Err(format!(
+            "the issuer '{iss}' is not a name of its certificate"
+        ));
+    }
+    Ok(())
+}
+
+fn decode_jws_part(jws: &str, index: usize) -> Result<Json, String> {
+    let part = jws.split('.').nth(index).ok_or("malformed JWS")?;
+    let bytes = BASE64_URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| format!("malformed JWS: {e}"))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("malformed JWS: {e}"))
+}
+
+/// Verify a compact JWS against a known key, returning its header and
+/// payload.
+fn verify_compact_jws(jws: &str, key: &JWK) -> Result<(Json, Json), String> {
+    let (signing_input, signature) = jws.rsplit_once('.').ok_or("malformed JWS")?;
+    let header = decode_jws_part(jws, 0)?;
+    let payload = decode_jws_part(jws, 1)?;
+
+    let alg: ssi::jwk::Algorithm = header
+        .get("alg")
+        .cloned()
+        .ok_or("missing `alg` header")
+        .and_then(|alg| serde_json::from_value(alg).map_err(|_| "unsupported `alg` header"))?;
+    let signature = BASE64_URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| format!("malformed JWS: {e}"))?;
+
+    ssi::claims::jws::verify_bytes(alg, signing_input.as_bytes(), key, &signature)
+        .map_err(|_| "invalid key binding signature".to_string())?;
+
+    Ok((header, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oid4vp::request_signer::ExampleRequestSigner;
+    use crate::oid4vp::x5c::test::{issue, root, x5c, ROOT_PEM};
+    use openid4vp::core::jwe::JweBuilder;
+    use p256::ecdsa::{signature::Signer, Signature};
+
+    const DCQL_QUERY: &str = r#"{
+        "credentials": [
+            {
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "meta": { "vct_values": ["urn:eudi:pid:1"] },
+                "claims": [{ "path": ["family_name"] }]
+            }
+        ]
+    }"#;
+
+    fn verifier(response_mode: Oid4vpVerifierResponseMode) -> (Arc<Oid4vpVerifier>, JWK) {
+        let jwk = JWK::generate_p256();
+        let verification_method = ssi::dids::DIDJWK::generate_url(&jwk.to_public()).to_string();
+
+        let verifier = Oid4vpVerifier::new(
+            Oid4vpVerifierConfig {
+                client_id: Oid4vpVerifierClientId::DecentralizedIdentifier {
+                    verification_method,
+                },
+                response_uri: "https://verifier.example/response".parse().unwrap(),
+                response_mode,
+                trusted_roots: vec![],
+            },
+            Arc::new(ExampleRequestSigner { jwk: jwk.clone() }),
+        );
+
+        (verifier, jwk)
+    }
+
+    #[tokio::test]
+    async fn test_create_request() {
+        let (verifier, jwk) = verifier(Oid4vpVerifierResponseMode::DirectPost);
+
+        let request = verifier
+            .create_request(
+                DCQL_QUERY.into(),
+                Some("https://verifier.example/request".parse().unwrap()),
+            )
+            .await
+            .unwrap();
+
+        assert!(request
+            .authorization_url
+            .contains("request_uri=https%3A%2F%2Fverifier.example%2Frequest"));
+
+        let (header, payload) = verify_compact_jws(&request.request_object, &jwk).unwrap();
+        assert_eq!(header["typ"], REQUEST_OBJECT_TYPE);
+        assert!(header["kid"].as_str().unwrap().starts_with("did:jwk:"));
+        assert_eq!(payload["client_id"], verifier.client_id());
+        assert_eq!(payload["nonce"], request.nonce.as_str());
+        assert_eq!(payload["response_mode"], "direct_post");
+        assert_eq!(payload["dcql_query"]["credentials"][0]["id"], "pid");
+
+        // The request object is served once.
+        let served = verifier
+            .fetch_request_object(request.state.clone())
+            .await
+            .unwrap();
+        assert_eq!(served, request.request_object);
+        assert!(matches!(
+            verifier.fetch_request_object(request.state).await,
+            Err(Oid4vpVerifierError::RequestObjectFetched)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pending_requests_expire() {
+        let (verifier, _) = verifier(Oid4vpVerifierResponseMode::DirectPost);
+
+        let request = verifier
+            .create_request(
+                DCQL_QUERY.into(),
+                Some("https://verifier.example/request".parse().unwrap()),
+            )
+            .await
+            .unwrap();
+        verifier
+            .pending
+            .lock()
+            .await
+            .get_mut(&request.state)
+            .unwrap()
+            .created_at -= PENDING_REQUEST_TTL + 1;
+
+        assert!(matches!(
+            verifier.fetch_request_object(request.state).await,
+            Err(Oid4vpVerifierError::UnknownSession)
+        ));
+        assert!(verifier.pending.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_encrypted_response() {
+        let (verifier, _) = verifier(Oid4vpVerifierResponseMode::DirectPostJwt);
+
+        let request = verifier
+            .create_request(DCQL_QUERY.into(), None)
+            .await
+            .unwrap();
+        let payload = decode_jws_part(&request.request_object, 1).unwrap();
+        let jwk = payload["client_metadata"]["jwks"]["keys"][0].clone();
+
+        // An unencrypted response is rejected.
+        let body = format!(
+            "vp_token=%7B%7D&state={}",
+            urlencoding::encode(&request.state)
+        );
+        assert!(matches!(
+            verifier.verify_response(body).await,
+            Err(Oid4vpVerifierError::InvalidResponse(_))
+        ));
+
+        let request = verifier
+            .create_request(DCQL_QUERY.into(), None)
+            .await
+            .unwrap();
+        let payload = decode_jws_part(&request.request_object, 1).unwrap();
+        let jwk_second = payload["client_metadata"]["jwks"]["keys"][0].clone();
+        assert_ne!(jwk, jwk_second);
+
+        let jwe = JweBuilder::new()
+            .payload(json!({
+                "vp_token": { "pid": ["not-an-sd-jwt"] },
+                "state": request.state,
+            }))
+            .recipient_key_json(&jwk_second)
+            .unwrap()
+            .alg("ECDH-ES")
+            .enc(RESPONSE_ENCRYPTION_ENC)
+            .kid(jwk_second["kid"].as_str().unwrap())
+            .build()
+            .unwrap();
+        let body = format!("response={}", urlencoding::encode(&jwe));
+
+        let response = verifier.verify_response(body.clone()).await.unwrap();
+        assert_eq!(response.state, request.state);
+        assert!(!response.verified);
+        assert_eq!(response.presentations.len(), 1);
+        assert_eq!(response.presentations[0].format, CredentialFormat::DcSdJwt);
+        assert!(response.presentations[0].error.is_some());
+        assert_eq!(response.missing_credential_queries, vec!["pid".to_string()]);
+
+        // The request is completed by its response.
+        assert!(matches!(
+            verifier.verify_response(body).await,
+            Err(Oid4vpVerifierError::UnknownSession)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_response_rejects_multiple_credentials() {
+        let (verifier, _) = verifier(Oid4vpVerifierResponseMode::DirectPost);
+
+        let request = verifier
+            .create_request(DCQL_QUERY.into(), None)
+            .await
+            .unwrap();
+        let body = format!(
+            "vp_token={}&state={}",
+            urlencoding::encode(&json!({ "pid": ["first", "second"] }).to_string()),
+            urlencoding::encode(&request.state)
+        );
+
+        let response = verifier.verify_response(body).await.unwrap();
+        assert!(!response.verified);
+        assert_eq!(response.presentations.len(), 2);
+        assert!(response
+            .presentations
+            .iter()
+            .all(|presentation| presentation
+                .error
+                .as_deref()
+                .is_some_and(|error| error.contains("does not allow multiple credentials"))));
+    }
+
+    fn context(trusted_roots: &[String]) -> PresentationContext<'_> {
+        PresentationContext {
+            client_id: "x509_san_dns:verifier.example",
+            nonce: "nonce",
+            issued_at: chrono::Utc::now().timestamp(),
+            response_uri: "https://verifier.example/response",
+            jwk_thumbprint: None,
+            trusted_roots,
+        }
+    }
+
+    #[test]
+    fn test_check_issued_at() {
+        let context = context(&[]);
+
+        let now = chrono::Utc::now().timestamp();
+        assert!(check_issued_at(&json!({ "iat": now }), &context).is_ok());
+        assert!(check_issued_at(&json!({}), &context).is_err());
+
+        // Issued before the request, or in the future.
+        let iat = context.issued_at - CLOCK_SKEW - 1;
+        assert!(check_issued_at(&json!({ "iat": iat }), &context).is_err());
+        let iat = now + CLOCK_SKEW + 60;
+        assert!(check_issued_at(&json!({ "iat": iat }), &context).is_err());
+    }
+
+    #[test]
+    fn test_check_holder_binding() {
+        let holder = presentation_holder(Some(&json!("did:example:holder"))).unwrap();
+        assert!(check_signed_by_holder(holder, Some("did:example:holder#key-1")).is_ok());
+        assert!(check_signed_by_holder(holder, Some("did:example:other#key-1")).is_err());
+        assert!(check_signed_by_holder(holder, None).is_err());
+
+        let holder = presentation_holder(Some(&json!([{ "id": "did:example:holder" }]))).unwrap();
+        assert_eq!(holder, "did:example:holder");
+        assert!(presentation_holder(None).is_err());
+
+        let credential = json!({ "credentialSubject": { "id": "did:example:holder" } });
+        assert!(check_bound_to_holder(&credential_subjects(&credential), holder).is_ok());
+        let credential = json!({ "credentialSubject": [{ "id": "did:example:other" }] });
+        assert!(check_bound_to_holder(&credential_subjects(&credential), holder).is_err());
+        let credential = json!({ "credentialSubject": { "name": "Doe" } });
+        assert!(check_bound_to_holder(&credential_subjects(&credential), holder).is_err());
+    }
+
+    #[test]
+    fn test_verify_x5c_issuer() {
+        let (root, root_key) = root();
+        let (certificate, key) = issue(&root, &root_key, "CN=Test Issuer", false);
+
+        let sign = |payload: Json| {
+            let header =
+                json!({ "alg": "ES256", "typ": "dc+sd-jwt", "x5c": x5c(&[certificate.clone()]) });
+            let signing_input = format!(
+                "{}.{}",
+                BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+                BASE64_URL_SAFE_NO_PAD.encode(payload.to_string())
+            );
+            let signature: Signature = key.sign(signing_input.as_bytes());
+            let jwt = format!(
+                "{signing_input}.{}",
+                BASE64_URL_SAFE_NO_PAD.encode(signature.to_bytes())
+            );
+            (jwt, header)
+        };
+
+        let roots = [ROOT_PEM.to_string()];
+        let (jwt, header) = sign(json!({ "vct": "urn:eudi:pid:1" }));
+        assert!(verify_x5c_jwt(&jwt, &header, &roots).is_ok());
+
+        // The chain must be anchored in a trusted root.
+        assert!(verify_x5c_jwt(&jwt, &header, &[]).is_err());
+
+        // A signature of another payload.
+        let (other, _) = sign(json!({ "vct": "urn:eudi:ehic:1" }));
+        let (signing_input, _) = jwt.rsplit_once('.').unwrap();
+        let (_, signature) = other.rsplit_once('.').unwrap();
+        let tampered = format!("{signing_input}.{signature}");
+        assert!(verify_x5c_jwt(&tampered, &header, &roots).is_err());
+
+        // An HTTPS issuer must be a name of the certificate.
+        let (jwt, header) =
+            sign(json!({ "vct": "urn:eudi:pid:1", "iss": "https://issuer.example" }));
+        assert!(verify_x5c_jwt(&jwt, &header, &roots).is_err());
+    }
+
+    fn credential_queries(query: Json) -> DcqlQuery {
+        serde_json::from_value(json!({ "credentials": query })).unwrap()
+    }
+
+    #[test]
+    fn test_check_credential_query_type() {
+        let query = credential_queries(json!([
+            {
+                "id": "mdl",
+                "format": "mso_mdoc",
+                "meta": { "doctype_value": "org.iso.18013.5.1.mDL" }
+            },
+            {
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "meta": { "vct_values": ["urn:eudi:pid:1"] }
+            },
+            {
+                "id": "degree",
+                "format": "ldp_vc",
+                "meta": {
+                    "type_values": [["VerifiableCredential", "UniversityDegreeCredential"]]
+                }
+            }
+        ]));
+        let (mdl, pid, degree) = (
+            &query.credentials()[0],
+            &query.credentials()[1],
+            &query.credentials()[2],
+        );
+
+        assert!(check_credential_query(mdl, &["org.iso.18013.5.1.mDL".into()], &json!({})).is_ok());
+        assert!(
+            check_credential_query(mdl, &["org.iso.23220.photoid.1".into()], &json!({})).is_err()
+        );
+
+        let claims = json!({ "vct": "urn:eudi:pid:1" });
+        assert!(check_credential(pid, claims).is_ok());
+        let claims = json!({ "vct": "urn:eudi:ehic:1" });
+        assert!(check_credential(pid, claims).is_err());
+
+        let credential = json!({ "type": ["VerifiableCredential", "UniversityDegreeCredential"] });
+        assert!(check_credentials(degree, json!([credential])).is_ok());
+        let credential = json!({ "type": ["VerifiableCredential", "EmployeeCredential"] });
+        assert!(check_credentials(degree, json!([credential])).is_err());
+        assert!(check_credentials(degree, json!([])).is_err());
+    }
+
+    #[test]
+    fn test_check_credential_query_claims() {
+        let query = credential_queries(json!([
+            {
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "meta": { "vct_values": ["urn:eudi:pid:1"] },
+                "claims": [
+                    { "path": ["family_name"] },
+                    { "path": ["address", "country"], "values": ["FR"] }
+                ]
+            },
+            {
+                "id": "mdl",
+                "format": "mso_mdoc",
+                "meta": { "doctype_value": "org.iso.18013.5.1.mDL" },
+                "claims": [
+                    { "id": "age", "path": ["org.iso.18013.5.1", "age_over_18"] },
+                    { "id": "birth_date", "path": ["org.iso.18013.5.1", "birth_date"] }
+                ],
+                "claim_sets": [["age"], ["birth_date"]]
+            }
+        ]));
+        let (pid, mdl) = (&query.credentials()[0], &query.credentials()[1]);
+
+        let claims = json!({
+            "vct": "urn:eudi:pid:1",
+            "family_name": "Doe",
+            "address": { "country": "FR" }
+        });
+        assert!(check_credential(pid, claims).is_ok());
+
+        // A missing claim.
+        let claims = json!({ "vct": "urn:eudi:pid:1", "address": { "country": "FR" } });
+        assert!(check_credential(pid, claims).is_err());
+
+        // A claim with another value than requested.
+        let claims = json!({
+            "vct": "urn:eudi:pid:1",
+            "family_name": "Doe",
+            "address": { "country": "DE" }
+        });
+        assert!(check_credential(pid, claims).is_err());
+
+        // Any claim set is enough.
+        let doc_types = ["org.iso.18013.5.1.mDL".to_string()];
+        let claims = json!({ "org.iso.18013.5.1": { "birth_date": "1990-01-01" } });
+        assert!(check_credential_query(mdl, &doc_types, &claims).is_ok());
+        let claims = json!({ "org.iso.18013.5.1": { "family_name": "Doe" } });
+        assert!(check_credential_query(mdl, &doc_types, &claims).is_err());
+    }
+}
@@ -1,13 +1,21 @@
 #![allow(deprecated)]
 
+use super::dc_api::build_response::Responder;
 use super::dynamic_credential::{
     DynamicCredentialOffer, DynamicCredentialProvider, PresentationBinding,
 };
 use super::error::OID4VPError;
 use super::presentation::{PresentationError, PresentationOptions, PresentationSigner};
-use crate::credential::{Credential, ParsedCredential, PresentableCredential};
+use super::request_uri::NegotiatedCapabilities;
+use super::response_encryption::ResponseEncrypter;
+use super::transaction_data::{parse_transaction_data, TransactionData};
+use super::trust::VerifierTrustResult;
+use super::verifier_attestation::{exceeds_entitlement, VerifierAttestation};
+use crate::credential::{
+    activity_log, Credential, ParsedCredential, ParsedCredentialInner, PresentableCredential,
+};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::{Arc, RwLock};
 
@@ -15,9 +23,13 @@ use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 use itertools::Itertools;
 use openid4vp::core::authorization_request::parameters::ResponseMode;
 use openid4vp::core::authorization_request::AuthorizationRequestObject;
-use openid4vp::core::dcql_query::DcqlQuery;
+use openid4vp::core::dcql_query::{DcqlCredentialClaimsQueryPath, DcqlQuery};
 use openid4vp::core::response::parameters::{VpToken, VpTokenItem};
-use openid4vp::core::response::{AuthorizationResponse, UnencodedAuthorizationResponse};
+use openid4vp::core::response::{
+    AuthorizationResponse, JwtAuthorizationResponse, UnencodedAuthorizationResponse,
+};
+use serde_json::json;
+use url::{form_urlencoded, Url};
 use uuid::Uuid;
 
 /// Type alias for mapping credential query ids to matching credentials
@@ -73,6 +85,14 @@ pub enum PermissionRequestError {
     #[error("Invalid Verification Method Identifier: {0}")]
     VerificationMethod(String),
 
+    /// The selected credentials do not satisfy a required credential set.
+    #[error("Selected credentials do not satisfy a required credential set, with options: {0}")]
+    UnsatisfiedCredentialSet(String),
+
+    /// No selected credential can authorize a transaction data item.
+    #[error("No selected credential can authorize the transaction data of type: {0}")]
+    UnboundTransactionData(String),
+
     #[error(transparent)]
     Presentation(#[from] PresentationError),
 }
@@ -131,6 +151,27 @@ impl RequestedField {
     }
 }
 
+/// Encode a DCQL claims path like the path of a [RequestedField].
+pub(crate) fn encode_claim_path(path: &[DcqlCredentialClaimsQueryPath]) -> String {
+    path.iter()
+        .filter_map(|component| match component {
+            DcqlCredentialClaimsQueryPath::String(s) => Some(s.clone()),
+            DcqlCredentialClaimsQueryPath::Integer(n) => Some(n.to_string()),
+            DcqlCredentialClaimsQueryPath::Null => None,
+        })
+        .map(|component| URL_SAFE.encode(component))
+        .join(",")
+}
+
+/// Decode the path of a [RequestedField] into the path recorded in the
+/// activity log, see [activity_log::ActivityLogEntry::get_fields].
+fn recorded_field_path(path: &str) -> String {
+    activity_log::field_path(
+        path.split(',')
+            .filter_map(|component| String::from_utf8(URL_SAFE.decode(component).ok()?).ok()),
+    )
+}
+
 /// Public methods for the RequestedField struct.
 #[uniffi::export]
 impl RequestedField {
@@ -212,6 +253,17 @@ pub struct CredentialRequirement {
     pub credentials: Vec<Arc<PresentableCredential>>,
 }
 
+/// A combination of credential queries satisfying the DCQL query, returned by
+/// [PermissionRequest::selection_options].
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct CredentialSelectionOption {
+    /// The credential queries to present a credential for.
+    pub credential_query_ids: Vec<String>,
+    /// The credential queries of the combination that are only part of
+    /// optional credential sets.
+    pub optional_credential_query_ids: Vec<String>,
+}
+
 #[derive(Clone, uniffi::Object)]
 pub struct PermissionRequest {
     pub(crate) dcql_query: DcqlQuery,
@@ -228,6 +280,16 @@ pub struct PermissionRequest {
     /// Map from a [`DynamicCredentialOffer::offer_id`] to the provider that
     /// produced it, used to mint the offer when building the response.
     pub(crate) offer_providers: HashMap<String, Arc<dyn DynamicCredentialProvider>>,
+    /// Origin of the verifier, for requests received over the Digital
+    /// Credentials API.
+    pub(crate) origin: Option<String>,
+    /// Trust in the verifier of the request, evaluated by the holder.
+    pub(crate) verifier_trust: VerifierTrustResult,
+    /// Attestations about the verifier, verified by the holder.
+    pub(crate) verifier_attestations: Vec<VerifierAttestation>,
+    /// Capabilities the request object was tailored for, when fetched with
+    /// `request_uri_method=post`.
+    pub(crate) negotiated_capabilities: Option<NegotiatedCapabilities>,
 }
 
 impl std::fmt::Debug for PermissionRequest {
@@ -238,6 +300,10 @@ impl std::fmt::Debug for PermissionRequest {
             .field("request", &self.request)
             .field("context_map", &self.context_map)
             .field("keystore", &self.keystore.as_ref().map(|_| "KeyStore"))
+            .field("origin", &self.origin)
+            .field("verifier_trust", &self.verifier_trust)
+            .field("verifier_attestations", &self.verifier_attestations)
+            .field("negotiated_capabilities", &self.negotiated_capabilities)
             .finish()
     }
 }
@@ -260,12 +326,17 @@ impl PermissionRequest {
             keystore,
             dynamic_offers: vec![],
             offer_providers: HashMap::new(),
+            origin: None,
+            verifier_trust: VerifierTrustResult::unverified(),
+            verifier_attestations: vec![],
+            negotiated_capabilities: None,
         })
     }
 
     /// Like [`PermissionRequest::new`], but additionally carries the dynamic
     /// credential offers surfaced for this request and the map from each
-    /// offer's id to the provider that can mint it.
+    /// offer's id to the provider that can mint it, and the trust in the
+    /// verifier of the request and its attestations.
     ///
     /// Crate-internal; the public surface is unchanged.
     #[allow(clippy::too_many_arguments)]
@@ -278,6 +349,8 @@ impl PermissionRequest {
         keystore: Option<Arc<dyn crate::crypto::KeyStore>>,
         dynamic_offers: Vec<DynamicCredentialOffer>,
         offer_providers: HashMap<String, Arc<dyn DynamicCredentialProvider>>,
+        verifier_trust: VerifierTrustResult,
+        verifier_attestations: Vec<VerifierAttestation>,
     ) -> Arc<Self> {
         Arc::new(Self {
             dcql_query,
@@ -288,6 +361,31 @@ impl PermissionRequest {
             keystore,
             dynamic_offers,
             offer_providers,
+            origin: None,
+            verifier_trust,
+            verifier_attestations,
+            negotiated_capabilities: None,
+        })
+    }
+
+    /// Bind the request to the origin of the verifier, for requests received
+    /// over the Digital Credentials API.
+    pub(crate) fn with_origin(self: Arc<Self>, origin: String) -> Arc<Self> {
+        Arc::new(Self {
+            origin: Some(origin),
+            ..(*self).clone()
+        })
+    }
+
+    /// Record the capabilities the request object was tailored for, when it
+    /// was fetched with `request_uri_method=post`.
+    pub(crate) fn with_negotiated_capabilities(
+        self: Arc<Self>,
+        negotiated_capabilities: NegotiatedCapabilities,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            negotiated_capabilities: Some(negotiated_capabilities),
+            ..(*self).clone()
         })
     }
 }
@@ -341,7 +439,62 @@ impl PermissionRequest {
         self.request.return_uri().domain().map(ToOwned::to_owned)
     }
 
+    /// Return the trust in the verifier of the request.
+    ///
+    /// The user interface should warn the holder when the verifier is not
+    /// trusted, and refuse to respond when the request is invalid.
+    pub fn verifier_trust(&self) -> VerifierTrustResult {
+        self.verifier_trust.clone()
+    }
+
+    /// Return the attestations about the verifier of the request, such as
+    /// registration certificates stating what it is entitled to request.
+    pub fn verifier_attestations(&self) -> Vec<VerifierAttestation> {
+        self.verifier_attestations.clone()
+    }
+
+    /// Return the capabilities negotiated with the verifier, when the request
+    /// object was fetched with `request_uri_method=post`.
+    pub fn negotiated_capabilities(&self) -> Option<NegotiatedCapabilities> {
+        self.negotiated_capabilities.clone()
+    }
+
+    /// Return whether the requested field exceeds the entitlement of the
+    /// verifier, as stated by its trusted attestations.
+    ///
+    /// The user interface should warn the holder about such fields. Without
+    /// any trusted attestation, no field exceeds the entitlement.
+    pub fn exceeds_entitlement(&self, field: &Arc<RequestedField>) -> Result<bool, OID4VPError> {
+        let Some(query) = self
+            .dcql_query
+            .credentials()
+            .iter()
+            .find(|query| query.id() == field.credential_query_id)
+        else {
+            return Ok(false);
+        };
+
+        for claim in query.claims().into_iter().flatten() {
+            if encode_claim_path(claim.path()) == field.path
+                && exceeds_entitlement(&self.verifier_attestations, query, claim.path())?
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Return the transaction data items of the request.
+    ///
+    /// The user interface should show them for consent, since presenting one
+    /// of the credentials they refer to authorizes the transactions.
+    pub fn transaction_data(&self) -> Result<Vec<TransactionData>, OID4VPError> {
+        Ok(parse_transaction_data(&self.request, &self.dcql_query)?)
+    }
+
     /// Construct a new permission response for the given credential.
+    ///
+    /// Fails if the verifier is [VerifierTrustResult::Invalid].
     pub async fn create_permission_response(
         &self,
         selected_credentials: Vec<Arc<PresentableCredential>>,
@@ -350,6 +503,8 @@ impl PermissionRequest {
     ) -> Result<Arc<PermissionResponse>, OID4VPError> {
         log::debug!("Creating Permission Response");
 
+        self.verifier_trust.ensure_answerable()?;
+
         // Ensure that the selected credentials are not empty.
         if selected_credentials.is_empty() {
             return Err(PermissionRequestError::InvalidSelectedCredential(
@@ -368,6 +523,14 @@ impl PermissionRequest {
             .into());
         }
 
+        self.validate_credential_sets(
+            selected_credentials
+                .iter()
+                .map(|credential| credential.credential_query_id.as_str())
+                .collect(),
+        )?;
+        self.validate_transaction_data(&selected_credentials)?;
+
         let (selected_credentials, vp_token_map) = self
             .build_stored_vp_token(selected_credentials, selected_fields, &response_options)
             .await?;
@@ -380,6 +543,7 @@ impl PermissionRequest {
             authorization_request: self.request.clone(),
             vp_token,
             options: response_options,
+            origin: self.origin.clone(),
         }))
     }
 
@@ -405,6 +569,8 @@ impl PermissionRequest {
     ) -> Result<Arc<PermissionResponse>, OID4VPError> {
         log::debug!("Creating Permission Response (with dynamic offers)");
 
+        self.verifier_trust.ensure_answerable()?;
+
         // At least one of stored credentials or dynamic offers must be selected.
         if selected_credentials.is_empty() && selected_offers.is_empty() {
             return Err(PermissionRequestError::InvalidSelectedCredential(
@@ -423,6 +589,19 @@ impl PermissionRequest {
             .into());
         }
 
+        self.validate_credential_sets(
+            selected_credentials
+                .iter()
+                .map(|credential| credential.credential_query_id.as_str())
+                .chain(
+                    selected_offers
+                        .iter()
+                        .map(|offer| offer.credential_query_id.as_str()),
+                )
+                .collect(),
+        )?;
+        self.validate_transaction_data(&selected_credentials)?;
+
         // Build the stored-credential vp_token exactly like the existing path.
         let (selected_credentials, mut vp_token_map) = self
             .build_stored_vp_token(selected_credentials, selected_fields, &response_options)
@@ -430,10 +609,10 @@ impl PermissionRequest {
 
         // Mint each selected dynamic offer, bound to this presentation. The
         // nonce/client_id are sourced identically to the stored response build.
-        let options = self.presentation_options(&response_options);
+        let options = self.presentation_options(&response_options)?;
         let binding = PresentationBinding {
             nonce: options.nonce().to_owned(),
-            client_id: options.audience().cloned().ok_or_else(|| {
+            client_id: options.audience().ok_or_else(|| {
                 OID4VPError::from(PermissionRequestError::CredentialPresentation(
                     "request is missing a client_id for the presentation binding".to_string(),
                 ))
@@ -471,6 +650,7 @@ impl PermissionRequest {
             authorization_request: self.request.clone(),
             vp_token,
             options: response_options,
+            origin: self.origin.clone(),
         }))
     }
 
@@ -628,6 +808,104 @@ impl PermissionRequest {
                 .collect()
         }
     }
+
+    /// Return the combinations of credential queries that satisfy the DCQL
+    /// query with the matching credentials and dynamic offers.
+    ///
+    /// Each combination picks one option of every required credential set, and
+    /// at most one option of every optional credential set. Combinations are
+    /// ranked by data minimization: fewest credentials first, then following
+    /// the verifier's order of preference of the options.
+    ///
+    /// An empty list means the request cannot be satisfied.
+    pub fn selection_options(&self) -> Vec<CredentialSelectionOption> {
+        let available = self
+            .credentials
+            .iter()
+            .map(|credential| credential.credential_query_id.as_str())
+            .chain(
+                self.dynamic_offers
+                    .iter()
+                    .map(|offer| offer.credential_query_id.as_str()),
+            )
+            .collect::<HashSet<_>>();
+
+        // The satisfiable options of each credential set, by index. Optional
+        // credential sets may also be left out, ranked after their options.
+        let Some(choices) = self
+            .credential_set_options()
+            .into_iter()
+            .map(|(options, required)| {
+                let mut choices = options
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, option)| option.iter().all(|id| available.contains(id.as_str())))
+                    .map(|(index, option)| (index, Some(option.clone()), required))
+                    .collect::<Vec<_>>();
+                if !required {
+                    choices.push((options.len(), None, required));
+                }
+                (!choices.is_empty()).then_some(choices)
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            return vec![];
+        };
+
+        let mut combinations = choices
+            .into_iter()
+            .multi_cartesian_product()
+            .map(|combination| {
+                let mut credential_query_ids: Vec<String> = Vec::new();
+                let mut optional_credential_query_ids: Vec<String> = Vec::new();
+                for (_, option, required) in &combination {
+                    for id in option.iter().flatten() {
+                        if !credential_query_ids.contains(id) {
+                            credential_query_ids.push(id.clone());
+                        }
+                        if !required && !optional_credential_query_ids.contains(id) {
+                            optional_credential_query_ids.push(id.clone());
+                        }
+                    }
+                }
+                // A query also part of a required credential set is not optional.
+                optional_credential_query_ids.retain(|id| {
+                    combination
+                        .iter()
+                        .filter(|(_, _, required)| *required)
+                        .all(|(_, option, _)| !option.iter().flatten().any(|other| other == id))
+                });
+
+                let rank = combination
+                    .iter()
+                    .map(|(index, _, _)| *index)
+                    .collect::<Vec<_>>();
+
+                (
+                    credential_query_ids.len(),
+                    rank,
+                    CredentialSelectionOption {
+                        credential_query_ids,
+                        optional_credential_query_ids,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        combinations.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+
+        let mut options: Vec<CredentialSelectionOption> = Vec::new();
+        for (_, _, option) in combinations {
+            let ids = option.credential_query_ids.iter().collect::<HashSet<_>>();
+            if !options
+                .iter()
+                .any(|other| other.credential_query_ids.iter().collect::<HashSet<_>>() == ids)
+            {
+                options.push(option);
+            }
+        }
+        options
+    }
 }
 
 impl PermissionRequest {
@@ -636,19 +914,93 @@ impl PermissionRequest {
         &self.dcql_query
     }
 
+    /// The options of each credential set of the DCQL query, with whether the
+    /// set is required. Without `credential_sets`, every credential query is
+    /// required.
+    fn credential_set_options(&self) -> Vec<(Vec<Vec<String>>, bool)> {
+        match self.dcql_query.credential_sets() {
+            Some(credential_sets) => credential_sets
+                .iter()
+                .map(|set| (set.options().to_vec(), set.is_required()))
+                .collect(),
+            None => self
+                .dcql_query
+                .credentials()
+                .iter()
+                .map(|query| (vec![vec![query.id().to_string()]], true))
+                .collect(),
+        }
+    }
+
+    /// Ensure that the selected credential queries satisfy every required
+    /// credential set.
+    fn validate_credential_sets(
+        &self,
+        selected_query_ids: HashSet<&str>,
+    ) -> Result<(), PermissionRequestError> {
+        for (options, _) in self
+            .credential_set_options()
+            .into_iter()
+            .filter(|(_, required)| *required)
+        {
+            let satisfied = options.iter().any(|option| {
+                option
+                    .iter()
+                    .all(|id| selected_query_ids.contains(id.as_str()))
+            });
+
+            if !satisfied {
+                return Err(PermissionRequestError::UnsatisfiedCredentialSet(
+                    options
+                        .iter()
+                        .map(|option| format!("[{}]", option.join(", ")))
+                        .join(" or "),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensure that every transaction data item is authorized by a selected
+    /// stored credential, of a format that binds transaction data.
+    ///
+    /// Only SD-JWT VCs bind them, in their KB-JWT: OID4VP defines no binding
+    /// for mdocs, and dynamic offers are minted by their provider.
+    fn validate_transaction_data(
+        &self,
+        selected_credentials: &[Arc<PresentableCredential>],
+    ) -> Result<(), OID4VPError> {
+        for item in self.transaction_data()? {
+            let bound = selected_credentials.iter().any(|credential| {
+                item.credential_ids
+                    .contains(&credential.credential_query_id)
+                    && matches!(credential.inner, ParsedCredentialInner::DcSdJwt(_))
+            });
+
+            if !bound {
+                return Err(PermissionRequestError::UnboundTransactionData(item.r#type).into());
+            }
+        }
+
+        Ok(())
+    }
+
     /// Build the `PresentationOptions` used when constructing a verifiable
     /// presentation for this request.
     fn presentation_options<'a>(
         &'a self,
         response_options: &'a ResponseOptions,
-    ) -> PresentationOptions<'a> {
-        PresentationOptions {
+    ) -> Result<PresentationOptions<'a>, OID4VPError> {
+        Ok(PresentationOptions {
             request: &self.request,
             signer: self.signer.clone(),
             context_map: self.context_map.clone(),
             response_options,
             keystore: self.keystore.clone(),
-        }
+            transaction_data: self.transaction_data()?,
+            origin: self.origin.clone(),
+        })
     }
 
     /// Apply the selected fields to the selected stored credentials and build
@@ -683,7 +1035,7 @@ impl PermissionRequest {
             .collect();
 
         // Set options for constructing a verifiable presentation.
-        let options = self.presentation_options(response_options);
+        let options = self.presentation_options(response_options)?;
 
         let mut vp_token_map: HashMap<String, Vec<VpTokenItem>> = HashMap::new();
 
@@ -734,6 +1086,27 @@ impl PermissionRequest {
     }
 }
 
+/// Response modes returning the response through a redirect of the user agent
+/// to the verifier, instead of an HTTP POST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RedirectResponseMode {
+    /// The response parameters are added to the fragment of the redirect URI.
+    Fragment,
+    /// The response parameters are added to the query of the redirect URI.
+    Query,
+}
+
+impl RedirectResponseMode {
+    /// Return the redirect response mode of the request, if any.
+    pub(crate) fn from_request(request: &AuthorizationRequestObject) -> Option<Self> {
+        match request.response_mode().to_string().as_str() {
+            "fragment" => Some(Self::Fragment),
+            "query" => Some(Self::Query),
+            _ => None,
+        }
+    }
+}
+
 /// Response options used to provide configurable interface
 /// for handling variations in the processing of the verifiable presentation
 /// payloads.
@@ -763,6 +1136,9 @@ pub struct PermissionResponse {
     pub authorization_request: AuthorizationRequestObject,
     pub vp_token: VpToken,
     pub options: ResponseOptions,
+    /// Origin of the verifier, for requests received over the Digital
+    /// Credentials API.
+    pub origin: Option<String>,
 }
 
 #[uniffi::export]
@@ -800,12 +1176,18 @@ impl PermissionResponse {
 
         // For DirectPostJwt response mode, build encrypted JWE per OID4VP 1.0 §8.3
         if matches!(response_mode, ResponseMode::DirectPostJwt) {
-            return openid4vp::core::jwe::build_encrypted_response(
-                &self.authorization_request,
-                &self.vp_token,
-                state.as_ref(),
-            )
-            .map_err(|e| OID4VPError::ResponseSubmission(format!("{e:?}")));
+            let vp_token = serde_json::to_value(&self.vp_token)
+                .map_err(|e| OID4VPError::Token(format!("{e:?}")))?;
+            let mut payload = json!({ "vp_token": vp_token });
+            if let Some(state) = state {
+                payload["state"] = json!(state.0);
+            }
+
+            let response =
+                ResponseEncrypter::from_request(&self.authorization_request)?.encrypt(&payload)?;
+            return Ok(AuthorizationResponse::Jwt(JwtAuthorizationResponse {
+                response,
+            }));
         }
 
         // Default: return unencoded response
@@ -816,4 +1198,68 @@ impl PermissionResponse {
 
         Ok(AuthorizationResponse::Unencoded(response))
     }
+
+    /// Return the URL redirecting the user agent to the verifier with the
+    /// response, for the `fragment` and `query` response modes.
+    pub(crate) fn redirect_url(&self, mode: RedirectResponseMode) -> Result<Url, OID4VPError> {
+        let vp_token = serde_json::to_string(&self.vp_token)
+            .map_err(|e| OID4VPError::Token(format!("{e:?}")))?;
+        let state = self
+            .authorization_request
+            .state()
+            .transpose()
+            .map_err(|e| OID4VPError::ResponseSubmission(format!("{e:?}")))?;
+
+        let mut parameters = form_urlencoded::Serializer::new(String::new());
+        parameters.append_pair("vp_token", &vp_token);
+        if let Some(state) = state {
+            parameters.append_pair("state", &state.0);
+        }
+        let parameters = parameters.finish();
+
+        let mut url = self.authorization_request.return_uri().clone();
+        match mode {
+            RedirectResponseMode::Fragment => url.set_fragment(Some(&parameters)),
+            RedirectResponseMode::Query => {
+                let query = match url.query() {
+                    Some(query) if !query.is_empty() => format!("{query}&{parameters}"),
+                    _ => parameters,
+                };
+                url.set_query(Some(&query))
+            }
+        }
+
+        Ok(url)
+    }
+
+    /// Return the response to hand back to the platform, for the `dc_api` and
+    /// `dc_api.jwt` response modes: a JSON object, or a JWE encrypted to the
+    /// verifier.
+    pub(crate) fn dc_api_response(&self) -> Result<String, OID4VPError> {
+        let vp_token = serde_json::to_value(&self.vp_token)
+            .map_err(|e| OID4VPError::Token(format!("{e:?}")))?;
+
+        Responder::new(&self.authorization_request)
+            .and_then(|responder| responder.response(vp_token))
+            .map_err(|e| OID4VPError::ResponseSubmission(format!("{e:#}")))
+    }
+
+    /// The IDs of the selected credentials, with the paths of their disclosed
+    /// fields.
+    pub(crate) fn shared_fields(&self) -> Vec<(Uuid, Vec<String>)> {
+        self.selected_credentials
+            .iter()
+            .map(|credential| {
+                (
+                    credential.as_parsed_credential().id(),
+                    credential
+                        .selected_fields
+                        .iter()
+                        .flatten()
+                        .map(|path| recorded_field_path(path))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
 }
@@ -2,13 +2,19 @@
 
 use crate::crypto::CryptoCurveUtils;
 
-use super::{error::OID4VPError, RequestedField, ResponseOptions};
+use super::{
+    error::OID4VPError,
+    transaction_data::{TransactionData, TransactionDataHashes},
+    RequestedField, ResponseOptions,
+};
 
 use std::{collections::HashMap, ops::Deref, str::FromStr, sync::Arc};
 
 use openid4vp::core::{
-    authorization_request::AuthorizationRequestObject, credential_format::ClaimFormatDesignation,
-    dcql_query::DcqlCredentialQuery, response::parameters::VpTokenItem,
+    authorization_request::AuthorizationRequestObject,
+    credential_format::ClaimFormatDesignation,
+    dcql_query::{DcqlCredentialClaimsQueryPath, DcqlCredentialQuery},
+    response::parameters::VpTokenItem,
 };
 use serde::Serialize;
 use ssi::{
@@ -121,6 +127,13 @@ pub trait CredentialPresentation {
             .collect()
     }
 
+    /// Return the values of the credential claim at a DCQL claims path.
+    fn dcql_claim_values(&self, path: &[DcqlCredentialClaimsQueryPath]) -> Vec<serde_json::Value> {
+        serde_json::to_value(self.credential())
+            .map(|credential| select_dcql_claim_values(&credential, path))
+            .unwrap_or_default()
+    }
+
     /// Return the credential as a verifiable presentation token item.
     #[allow(async_fn_in_trait)]
     async fn as_vp_token_item<'a>(
@@ -130,6 +143,39 @@ pub trait CredentialPresentation {
     ) -> Result<VpTokenItem, OID4VPError>;
 }
 
+/// Select the values at a DCQL claims path. A `null` segment selects all the
+/// elements of an array.
+pub(crate) fn select_dcql_claim_values(
+    value: &serde_json::Value,
+    path: &[DcqlCredentialClaimsQueryPath],
+) -> Vec<serde_json::Value> {
+    let Some((segment, rest)) = path.split_first() else {
+        return vec![value.clone()];
+    };
+
+    match segment {
+        DcqlCredentialClaimsQueryPath::String(key) => value
+            .as_object()
+            .and_then(|object| object.get(key))
+            .map(|value| select_dcql_claim_values(value, rest))
+            .unwrap_or_default(),
+        DcqlCredentialClaimsQueryPath::Integer(index) => value
+            .as_array()
+            .and_then(|array| array.get(*index))
+            .map(|value| select_dcql_claim_values(value, rest))
+            .unwrap_or_default(),
+        DcqlCredentialClaimsQueryPath::Null => value
+            .as_array()
+            .map(|array| {
+                array
+                    .iter()
+                    .flat_map(|value| select_dcql_claim_values(value, rest))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
 /// The `PresentationSigner` foreign callback interface to be implemented
 /// by the host environment, e.g. Kotlin or Swift.
 ///
@@ -192,6 +238,11 @@ pub struct PresentationOptions<'a> {
     pub(crate) response_options: &'a ResponseOptions,
     /// Optional KeyStore for mdoc credential signing
     pub(crate) keystore: Option<Arc<dyn crate::crypto::KeyStore>>,
+    /// Transaction data items to bind to the presentation.
+    pub(crate) transaction_data: Vec<TransactionData>,
+    /// Origin of the verifier, for requests received over the Digital
+    /// Credentials API.
+    pub(crate) origin: Option<String>,
 }
 
 impl std::fmt::Debug for PresentationOptions<'_> {
@@ -201,6 +252,8 @@ impl std::fmt::Debug for PresentationOptions<'_> {
             .field("context_map", &self.context_map)
             .field("response_options", &self.response_options)
             .field("keystore", &self.keystore.as_ref().map(|_| "KeyStore"))
+            .field("transaction_data", &self.transaction_data)
+            .field("origin", &self.origin)
             .finish()
     }
 }
@@ -275,8 +328,14 @@ impl PresentationOptions<'_> {
             .map_err(|e| PresentationError::VerificationMethod(format!("{e:?}")))
     }
 
-    pub fn audience(&self) -> Option<&String> {
-        self.request.client_id().map(|id| &id.0)
+    /// Return the audience of the presentation: the client_id of the request,
+    /// or the origin prefixed with `origin:` for requests received over the
+    /// Digital Credentials API (OID4VP 1.0 §A.4).
+    pub fn audience(&self) -> Option<String> {
+        match &self.origin {
+            Some(origin) => Some(format!("origin:{origin}")),
+            None => self.request.client_id().map(|id| id.0.clone()),
+        }
     }
 
     pub fn nonce(&self) -> &String {
@@ -287,6 +346,30 @@ impl PresentationOptions<'_> {
         self.signer.did()
     }
 
+    /// Return the options for presenting a credential matching the given
+    /// credential query, keeping only the transaction data it can authorize.
+    pub(crate) fn for_credential_query(&self, credential_query_id: &str) -> Self {
+        Self {
+            transaction_data: self
+                .transaction_data
+                .iter()
+                .filter(|item| {
+                    item.credential_ids
+                        .iter()
+                        .any(|id| id == credential_query_id)
+                })
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Return the hashes of the transaction data to bind to the presentation,
+    /// if any.
+    pub(crate) fn transaction_data_hashes(&self) -> Option<TransactionDataHashes> {
+        TransactionDataHashes::new(&self.transaction_data)
+    }
+
     pub fn subject(&self) -> String {
         self.signer.did()
     }
@@ -362,12 +445,9 @@ impl PresentationOptions<'_> {
         //
         // domain is the client_id of the request, in the example above.
         proof_options.challenge = Some(self.nonce().to_owned());
-        proof_options.domains = vec![self
-            .request
-            .client_id()
-            .ok_or_else(|| PresentationError::Context("request missing 'client_id'".to_string()))?
-            .0
-            .clone()];
+        proof_options.domains = vec![self.audience().ok_or_else(|| {
+            PresentationError::Context("request missing 'client_id'".to_string())
+        })?];
 
         if let AnyJsonPresentation::V1(_) = presentation {
             let iri_buf = IriRefBuf::new("https://w3id.org/security/data-integrity/v2".into())
@@ -1,3 +1,4 @@
+#[cfg(test)]
 use ssi::{claims::jws::JwsSigner, JWK};
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -21,30 +22,34 @@ pub trait RequestSignerInterface: Send + Sync + std::fmt::Debug {
     async fn try_sign(&self, payload: Vec<u8>) -> Result<Vec<u8>, RequestSignerError>;
 }
 
+/// A request signer holding its key in memory, for tests.
+#[cfg(test)]
 #[derive(Debug, Clone)]
 pub(crate) struct ExampleRequestSigner {
-    jwk: JWK,
+    pub(crate) jwk: JWK,
 }
 
+#[cfg(test)]
 impl Default for ExampleRequestSigner {
     fn default() -> Self {
         Self {
-            jwk: JWK::generate_ed25519().unwrap(),
+            jwk: JWK::generate_p256(),
         }
     }
 }
 
+#[cfg(test)]
 #[async_trait::async_trait]
 impl RequestSignerInterface for ExampleRequestSigner {
     fn alg(&self) -> Result<String, RequestSignerError> {
         self.jwk
-            .algorithm
+            .get_algorithm()
             .map(|alg| alg.to_string())
             .ok_or(RequestSignerError::UnsupportedAlgorithm)
     }
 
     fn jwk(&self) -> Result<String, RequestSignerError> {
-        serde_json::to_string(&self.jwk).map_err(|_| RequestSignerError::SigningError)
+        serde_json::to_string(&self.jwk.to_public()).map_err(|_| RequestSignerError::SigningError)
     }
 
     async fn try_sign(&self, payload: Vec<u8>) -> Result<Vec<u8>, RequestSignerError> {
@@ -0,0 +1,259 @@
+//! The `post` method of `request_uri` (OID4VP 1.0 §5.10).
+//!
+//! When a request passed by reference sets `request_uri_method=post`, the
+//! wallet POSTs its metadata and a fresh `wallet_nonce` to the `request_uri`,
+//! and the verifier answers with a request object tailored to the wallet
+//! capabilities. The request object must echo the `wallet_nonce`, binding it to
+//! this fetch and preventing its replay.
+
+use anyhow::{bail, Context, Result};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use openid4vp::core::{
+    authorization_request::{parameters::ResponseMode, AuthorizationRequestObject},
+    metadata::WalletMetadata,
+    object::{ParsingErrorContext, TypedParameter},
+    util::AsyncHttpClient,
+};
+use serde_json::Value as Json;
+use url::{form_urlencoded, Url};
+
+use super::{response_encryption::ResponseEncrypter, CLIENT_ID_PREFIXES};
+
+/// Media type of signed request objects (RFC 9101 §10.2).
+const REQUEST_OBJECT_MEDIA_TYPE: &str = "application/oauth-authz-req+jwt";
+
+/// The capabilities the request object was tailored for, once fetched with
+/// `request_uri_method=post`.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct NegotiatedCapabilities {
+    /// The `wallet_nonce` sent to the verifier, and echoed by the request
+    /// object.
+    pub wallet_nonce: String,
+    /// The client identifier prefix of the request, e.g. `x509_san_dns`.
+    /// `None` for pre-registered clients.
+    pub client_id_prefix: Option<String>,
+    /// The JWS algorithm of the request object, `none` when unsigned.
+    pub request_object_signing_alg: Option<String>,
+    /// The response mode of the request.
+    pub response_mode: String,
+    /// The formats of the requested credentials.
+    pub credential_formats: Vec<String>,
+    /// The content encryption of the response, for the encrypted response
+    /// modes.
+    pub response_encryption_enc: Option<String>,
+}
+
+impl NegotiatedCapabilities {
+    /// Describe the validated request object, fetched with the given nonce.
+    pub(crate) fn new(
+        request: &AuthorizationRequestObject,
+        request_jwt: &str,
+        wallet_nonce: String,
+    ) -> Self {
+        let client_id_prefix = request
+            .client_id()
+            .and_then(|client_id| {
+                let (prefix, _) = client_id.0.split_once(':')?;
+                Some(prefix.to_string())
+            })
+            .filter(|prefix| CLIENT_ID_PREFIXES.contains(&prefix.as_str()));
+
+        let request_object_signing_alg = request_jwt
+            .split('.')
+            .next()
+            .and_then(|header| BASE64_URL_SAFE_NO_PAD.decode(header).ok())
+            .and_then(|header| serde_json::from_slice::<Json>(&header).ok())
+            .and_then(|header| header.get("alg")?.as_str().map(str::to_string));
+
+        let mut credential_formats = vec![];
+        if let Some(Ok(dcql_query)) = request.dcql_query() {
+            for query in dcql_query.credentials() {
+                let format = serde_json::to_value(query.format())
+                    .ok()
+                    .and_then(|format| format.as_str().map(str::to_string));
+                if let Some(format) = format.filter(|f| !credential_formats.contains(f)) {
+                    credential_formats.push(format);
+                }
+            }
+        }
+
+        Self {
+            wallet_nonce,
+            client_id_prefix,
+            request_object_signing_alg,
+            response_mode: request.response_mode().to_string(),
+            credential_formats,
+            response_encryption_enc: matches!(
+                request.response_mode(),
+                ResponseMode::DirectPostJwt | ResponseMode::DcApiJwt
+            )
+            .then(|| ResponseEncrypter::from_request(request).ok())
+            .flatten()
+            .map(|encrypter| encrypter.enc().to_string()),
+        }
+    }
+}
+
+/// Return the `request_uri` of the request, if it must be fetched with the
+/// `post` method.
+pub(crate) fn post_request_uri(url: &Url) -> Option<String> {
+    let parameter = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    match parameter("request_uri_method") {
+        Some(method) if method == "post" => parameter("request_uri"),
+        _ => None,
+    }
+}
+
+/// Return a fresh `wallet_nonce`.
+pub(crate) fn generate_wallet_nonce() -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(rand::random::<[u8; 16]>())
+}
+
+/// Fetch the request object from the `request_uri`, sending the wallet
+/// metadata and nonce.
+pub(crate) async fn fetch_request_object<H: AsyncHttpClient>(
+    http_client: &H,
+    request_uri: &str,
+    wallet_metadata: &WalletMetadata,
+    wallet_nonce: &str,
+) -> Result<String> {
+    let wallet_metadata =
+        serde_json::to_string(wallet_metadata).context("failed to serialize wallet metadata")?;
+    let body = form_urlencoded::Serializer::new(String::new())
+        .append_pair("wallet_metadata", &wallet_metadata)
+        .append_pair("wallet_nonce", wallet_nonce)
+        .finish();
+
+    let request = http::Request::builder()
+        .method("POST")
+        .uri(request_uri)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("Accept", REQUEST_OBJECT_MEDIA_TYPE)
+        .body(body.into_bytes())
+        .context("failed to build request object request")?;
+
+    let response = http_client
+        .execute(request)
+        .await
+        .with_context(|| format!("failed to fetch the request object at {request_uri}"))?;
+
+    let status = response.status();
+    let body = String::from_utf8(response.into_body())
+        .context("failed to parse the request object as UTF-8")?;
+    if !status.is_success() {
+        bail!("request object request was unsuccessful (status: {status}): {body}")
+    }
+
+    Ok(body.trim().to_string())
+}
+
+/// Ensure that the request object echoes the `wallet_nonce` of the fetch.
+pub(crate) fn check_wallet_nonce(
+    request: &AuthorizationRequestObject,
+    wallet_nonce: &str,
+) -> Result<()> {
+    let WalletNonce(nonce) = request
+        .get::<WalletNonce>()
+        .parsing_error()
+        .context("the request object does not contain the wallet_nonce")?;
+
+    if nonce != wallet_nonce {
+        bail!("the wallet_nonce of the request object does not match")
+    }
+    Ok(())
+}
+
+/// The `wallet_nonce` request parameter.
+#[derive(Debug, Clone)]
+struct WalletNonce(String);
+
+impl TypedParameter for WalletNonce {
+    const KEY: &'static str = "wallet_nonce";
+}
+
+impl TryFrom<Json> for WalletNonce {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Json) -> Result<Self, Self::Error> {
+        Ok(Self(serde_json::from_value(value)?))
+    }
+}
+
+impl From<WalletNonce> for Json {
+    fn from(value: WalletNonce) -> Self {
+        Json::String(value.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn request(wallet_nonce: &str) -> AuthorizationRequestObject {
+        serde_json::from_value(json!({
+            "client_id": "x509_san_dns:verifier.example",
+            "response_uri": "https://verifier.example/callback",
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "nonce": "nonce",
+            "wallet_nonce": wallet_nonce,
+            "dcql_query": {
+                "credentials": [
+                    { "id": "pid", "format": "dc+sd-jwt" },
+                    { "id": "mdl", "format": "mso_mdoc" },
+                    { "id": "pid_2", "format": "dc+sd-jwt" }
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_post_request_uri() {
+        let url: Url = "openid4vp://?client_id=x509_san_dns%3Averifier.example&request_uri=https%3A%2F%2Fverifier.example%2Frequest&request_uri_method=post".parse().unwrap();
+        assert_eq!(
+            post_request_uri(&url).as_deref(),
+            Some("https://verifier.example/request")
+        );
+
+        let url: Url = "openid4vp://?client_id=x509_san_dns%3Averifier.example&request_uri=https%3A%2F%2Fverifier.example%2Frequest".parse().unwrap();
+        assert_eq!(post_request_uri(&url), None);
+    }
+
+    #[test]
+    fn test_check_wallet_nonce() {
+        let wallet_nonce = generate_wallet_nonce();
+        assert!(check_wallet_nonce(&request(&wallet_nonce), &wallet_nonce).is_ok());
+        assert!(check_wallet_nonce(&request("replayed"), &wallet_nonce).is_err());
+    }
+
+    #[test]
+    fn test_negotiated_capabilities() {
+        let header =
+            BASE64_URL_SAFE_NO_PAD.encode(r#"{"alg":"ES256","typ":"oauth-authz-req+jwt"}"#);
+        let capabilities = NegotiatedCapabilities::new(
+            &request("nonce"),
+            &format!("{header}.e30.c2ln"),
+            "nonce".into(),
+        );
+
+        assert_eq!(
+            capabilities.client_id_prefix.as_deref(),
+            Some("x509_san_dns")
+        );
+        assert_eq!(
+            capabilities.request_object_signing_alg.as_deref(),
+            Some("ES256")
+        );
+        assert_eq!(capabilities.response_mode, "direct_post");
+        assert_eq!(capabilities.credential_formats, ["dc+sd-jwt", "mso_mdoc"]);
+        assert_eq!(capabilities.response_encryption_enc, None);
+    }
+}
@@ -0,0 +1,546 @@
+//! Encryption of authorization responses (OID4VP 1.0 §8.3).
+//!
+//! The `direct_post.jwt` and `dc_api.jwt` response modes encrypt the response
+//! to one of the keys published in the verifier's `client_metadata`, as a
+//! compact JWE using ECDH-ES key agreement. The [ResponseEncrypter] negotiates
+//! the key and the content encryption from the verifier metadata, and is
+//! shared by every holder building encrypted responses.
+//!
+//! The key agreement and the JWE itself are left to josekit for P-256 keys.
+//! josekit does not support X25519, so for X25519 keys the agreement, the
+//! Concat KDF and the JWE are done here.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes128Gcm, Aes256Gcm, Nonce,
+};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use josekit::{
+    jwe::{alg::ecdh_es::EcdhEsJweEncrypter, JweHeader, ECDH_ES},
+    jwk::Jwk,
+    JoseError,
+};
+use openid4vp::core::{
+    authorization_request::{parameters::ResponseMode, AuthorizationRequestObject},
+    iso_18013_7::compute_jwk_thumbprint,
+    object::ParsingErrorContext,
+};
+use serde_json::{json, Value as Json};
+use sha2::{Digest, Sha256};
+
+/// The only supported key management algorithm: direct key agreement.
+pub const RESPONSE_ENCRYPTION_ALG: &str = "ECDH-ES";
+
+/// The supported content encryption algorithms, by order of preference when
+/// the verifier does not state its own.
+pub const RESPONSE_ENCRYPTION_ENC_VALUES: [&str; 2] = ["A128GCM", "A256GCM"];
+
+const IV_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum ResponseEncryptionError {
+    #[error("Malformed client metadata: {0}")]
+    MalformedClientMetadata(String),
+    #[error("The verifier did not provide any response encryption key")]
+    MissingEncryptionKey,
+    #[error("None of the verifier keys can encrypt the response: {0}")]
+    NoSuitableKey(String),
+    #[error("Unsupported response encryption algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("None of the content encryption algorithms of the verifier is supported: {0}")]
+    UnsupportedContentEncryption(String),
+    #[error("Failed to encrypt the response: {0}")]
+    Encryption(String),
+}
+
+/// A public key of the verifier, usable for ECDH-ES.
+#[derive(Debug, Clone)]
+struct RecipientKey {
+    agreement: KeyAgreement,
+    /// SHA-256 JWK Thumbprint (RFC 7638) of the key.
+    thumbprint: [u8; 32],
+}
+
+#[derive(Debug, Clone)]
+enum KeyAgreement {
+    P256(Jwk),
+    X25519(MontgomeryPoint),
+}
+
+impl RecipientKey {
+    /// Parse a JWK of the verifier, if it can be used to encrypt responses.
+    ///
+    /// Only P-256 and X25519 keys are supported.
+    fn from_jwk(jwk: &Json) -> Result<Self, String> {
+        if let Some(use_) = member(jwk, "use") {
+            if use_ != "enc" {
+                return Err(format!("`use` is `{use_}`"));
+            }
+        }
+        if let Some(alg) = member(jwk, "alg") {
+            if alg != RESPONSE_ENCRYPTION_ALG {
+                return Err(format!("unsupported `alg` `{alg}`"));
+            }
+        }
+
+        match (member(jwk, "kty"), member(jwk, "crv")) {
+            (Some("EC"), Some("P-256")) => {
+                let key = Jwk::from_bytes(jwk.to_string().as_bytes())
+                    .map_err(|e| format!("invalid key: {e}"))?;
+                p256_encrypter(&key).map_err(|e| format!("invalid key: {e}"))?;
+                Ok(Self {
+                    agreement: KeyAgreement::P256(key),
+                    thumbprint: compute_jwk_thumbprint(jwk)
+                        .map_err(|e| format!("invalid key: {e:?}"))?,
+                })
+            }
+            (Some("OKP"), Some("X25519")) => {
+                let x = member(jwk, "x").ok_or("missing `x`")?;
+                let bytes: [u8; 32] = BASE64_URL_SAFE_NO_PAD
+                    .decode(x)
+                    .map_err(|e| format!("invalid `x`: {e}"))?
+                    .try_into()
+                    .map_err(|_| "invalid X25519 key length".to_string())?;
+
+                // The members of the public key, in the lexicographic order
+                // of RFC 7638.
+                let public = json!({ "crv": "X25519", "kty": "OKP", "x": x });
+                Ok(Self {
+                    agreement: KeyAgreement::X25519(MontgomeryPoint(bytes)),
+                    thumbprint: Sha256::digest(public.to_string().as_bytes()).into(),
+                })
+            }
+            (kty, crv) => Err(format!(
+                "unsupported key type `{}` with curve `{}`",
+                kty.unwrap_or_default(),
+                crv.unwrap_or_default()
+            )),
+        }
+    }
+}
+
+fn p256_encrypter(jwk: &Jwk) -> Result<EcdhEsJweEncrypter<p256::NistP256>, JoseError> {
+    ECDH_ES.encrypter_from_jwk(jwk)
+}
+
+/// The encryption of a response, negotiated from the verifier metadata.
+#[derive(Debug, Clone)]
+pub struct ResponseEncrypter {
+    key: RecipientKey,
+    kid: Option<String>,
+    enc: String,
+    /// Agreement PartyUInfo and PartyVInfo, as used by ISO 18013-7 Annex B.
+    agreement_info: Option<(Vec<u8>, Vec<u8>)>,
+    typ: Option<String>,
+}
+
+impl ResponseEncrypter {
+    /// Negotiate the encryption of the response to an OID4VP 1.0 request.
+    ///
+    /// The key is the first of `client_metadata.jwks` usable for ECDH-ES, and
+    /// the content encryption the first of
+    /// `encrypted_response_enc_values_supported` which is supported.
+    pub(crate) fn from_request(
+        request: &AuthorizationRequestObject,
+    ) -> Result<Self, ResponseEncryptionError> {
+        let client_metadata = request.client_metadata().map_err(malformed)?;
+        let keys = match client_metadata.jwks() {
+            Some(jwks) => jwks_to_json(&jwks.map_err(malformed)?.keys)?,
+            None => return Err(ResponseEncryptionError::MissingEncryptionKey),
+        };
+        let enc_values = client_metadata
+            .encrypted_response_enc_values_supported()
+            .parsing_error()
+            .map_err(malformed)?
+            .0;
+
+        Self::negotiate(&keys, None, &enc_values)
+    }
+
+    /// Negotiate the encryption of a response to the given keys.
+    ///
+    /// `alg` is the algorithm required by the verifier, for the metadata
+    /// predating OID4VP 1.0 where it is not part of the keys. An empty
+    /// `enc_values` defaults to `A128GCM`.
+    pub(crate) fn negotiate(
+        keys: &[Json],
+        alg: Option<&str>,
+        enc_values: &[String],
+    ) -> Result<Self, ResponseEncryptionError> {
+        if let Some(alg) = alg.filter(|alg| *alg != RESPONSE_ENCRYPTION_ALG) {
+            return Err(ResponseEncryptionError::UnsupportedAlgorithm(alg.into()));
+        }
+
+        let enc = if enc_values.is_empty() {
+            RESPONSE_ENCRYPTION_ENC_VALUES[0].to_string()
+        } else {
+            enc_values
+                .iter()
+                .find(|enc| RESPONSE_ENCRYPTION_ENC_VALUES.contains(&enc.as_str()))
+                .cloned()
+                .ok_or_else(|| {
+                    ResponseEncryptionError::UnsupportedContentEncryption(enc_values.join(", "))
+                })?
+        };
+
+        if keys.is_empty() {
+            return Err(ResponseEncryptionError::MissingEncryptionKey);
+        }
+
+        let mut rejected = vec![];
+        for (index, jwk) in keys.iter().enumerate() {
+            let kid = member(jwk, "kid").map(ToString::to_string);
+            match RecipientKey::from_jwk(jwk) {
+                Ok(key) => {
+                    return Ok(Self {
+                        key,
+                        kid,
+                        enc,
+                        agreement_info: None,
+                        typ: None,
+                    })
+                }
+                Err(reason) => {
+                    let name = kid.unwrap_or_else(|| format!("#{index}"));
+                    tracing::debug!("skipping response encryption key {name}: {reason}");
+                    rejected.push(format!("key {name}: {reason}"));
+                }
+            }
+        }
+
+        Err(ResponseEncryptionError::NoSuitableKey(rejected.join("; ")))
+    }
+
+    /// Set the `apu` and `apv` header parameters, which are also bound to
+    /// the content encryption key.
+    pub(crate) fn with_agreement_info(self, apu: &str, apv: &str) -> Self {
+        Self {
+            agreement_info: Some((apu.as_bytes().to_vec(), apv.as_bytes().to_vec())),
+            ..self
+        }
+    }
+
+    /// Set the `typ` header parameter.
+    pub(crate) fn with_token_type(self, typ: &str) -> Self {
+        Self {
+            typ: Some(typ.to_string()),
+            ..self
+        }
+    }
+
+    /// The content encryption algorithm of the response.
+    pub(crate) fn enc(&self) -> &str {
+        &self.enc
+    }
+
+    /// SHA-256 JWK Thumbprint (RFC 7638) of the verifier key, bound to mdoc
+    /// presentations by their handover.
+    pub(crate) fn jwk_thumbprint(&self) -> [u8; 32] {
+        self.key.thumbprint
+    }
+
+    /// Encrypt the payload as a compact JWE.
+    pub(crate) fn encrypt(&self, payload: &Json) -> Result<String, ResponseEncryptionError> {
+        let plaintext = serde_json::to_vec(payload).map_err(encryption)?;
+
+        let jwk = match &self.key.agreement {
+            KeyAgreement::P256(jwk) => jwk,
+            KeyAgreement::X25519(key) => return self.encrypt_x25519(key, &plaintext),
+        };
+
+        let mut header = JweHeader::new();
+        header.set_algorithm(RESPONSE_ENCRYPTION_ALG);
+        header.set_content_encryption(&self.enc);
+        if let Some(kid) = &self.kid {
+            header.set_key_id(kid);
+        }
+        if let Some(typ) = &self.typ {
+            header.set_token_type(typ);
+        }
+        if let Some((apu, apv)) = &self.agreement_info {
+            header.set_agreement_partyuinfo(apu.as_slice());
+            header.set_agreement_partyvinfo(apv.as_slice());
+        }
+
+        let encrypter = p256_encrypter(jwk).map_err(encryption)?;
+        josekit::jwe::serialize_compact(&plaintext, &header, &encrypter).map_err(encryption)
+    }
+
+    /// Encrypt the payload to an X25519 key as a compact JWE (RFC 8037 §3.2).
+    fn encrypt_x25519(
+        &self,
+        key: &MontgomeryPoint,
+        plaintext: &[u8],
+    ) -> Result<String, ResponseEncryptionError> {
+        let ephemeral = rand::random::<[u8; 32]>();
+        let shared_secret = key.mul_clamped(ephemeral).to_bytes();
+        if shared_secret == [0; 32] {
+            return Err(ResponseEncryptionError::Encryption(
+                "the X25519 key of the verifier is of low order".into(),
+            ));
+        }
+        let epk = MontgomeryPoint::mul_base_clamped(ephemeral);
+
+        let mut header = json!({
+            "alg": RESPONSE_ENCRYPTION_ALG,
+            "enc": self.enc,
+            "epk": {
+                "kty": "OKP",
+                "crv": "X25519",
+                "x": BASE64_URL_SAFE_NO_PAD.encode(epk.to_bytes()),
+            },
+        });
+        if let Some(kid) = &self.kid {
+            header["kid"] = json!(kid);
+        }
+        if let Some(typ) = &self.typ {
+            header["typ"] = json!(typ);
+        }
+        let (apu, apv) = self.agreement_info.clone().unwrap_or_default();
+        if self.agreement_info.is_some() {
+            header["apu"] = json!(BASE64_URL_SAFE_NO_PAD.encode(&apu));
+            header["apv"] = json!(BASE64_URL_SAFE_NO_PAD.encode(&apv));
+        }
+        let protected = BASE64_URL_SAFE_NO_PAD.encode(header.to_string());
+
+        let cek = concat_kdf(&shared_secret, &self.enc, &apu, &apv);
+        let iv = rand::random::<[u8; IV_LEN]>();
+        let payload = Payload {
+            msg: plaintext,
+            aad: protected.as_bytes(),
+        };
+        let sealed = match cek.len() {
+            16 => Aes128Gcm::new_from_slice(&cek)
+                .map_err(encryption)?
+                .encrypt(Nonce::from_slice(&iv), payload),
+            _ => Aes256Gcm::new_from_slice(&cek)
+                .map_err(encryption)?
+                .encrypt(Nonce::from_slice(&iv), payload),
+        }
+        .map_err(encryption)?;
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+
+        Ok(format!(
+            "{protected}..{}.{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(iv),
+            BASE64_URL_SAFE_NO_PAD.encode(ciphertext),
+            BASE64_URL_SAFE_NO_PAD.encode(tag),
+        ))
+    }
+}
+
+/// Derive the content encryption key with the Concat KDF of RFC 7518 §4.6.2.
+///
+/// A single round of SHA-256 covers the key lengths of the supported `enc`.
+fn concat_kdf(shared_secret: &[u8], enc: &str, apu: &[u8], apv: &[u8]) -> Vec<u8> {
+    let key_len = if enc == "A128GCM" { 16 } else { 32 };
+
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(shared_secret);
+    for info in [enc.as_bytes(), apu, apv] {
+        hasher.update((info.len() as u32).to_be_bytes());
+        hasher.update(info);
+    }
+    hasher.update(((key_len * 8) as u32).to_be_bytes());
+
+    hasher.finalize()[..key_len].to_vec()
+}
+
+/// SHA-256 JWK Thumbprint of the key the response to the request is encrypted
+/// to, for the encrypted response modes.
+pub(crate) fn encryption_jwk_thumbprint(request: &AuthorizationRequestObject) -> Option<[u8; 32]> {
+    if !matches!(
+        request.response_mode(),
+        ResponseMode::DirectPostJwt | ResponseMode::DcApiJwt
+    ) {
+        return None;
+    }
+
+    ResponseEncrypter::from_request(request)
+        .ok()
+        .map(|encrypter| encrypter.jwk_thumbprint())
+}
+
+/// Convert the keys of a JWK set to JSON, whatever their representation.
+pub(crate) fn jwks_to_json<K: serde::Serialize>(
+    keys: &[K],
+) -> Result<Vec<Json>, ResponseEncryptionError> {
+    keys.iter()
+        .map(serde_json::to_value)
+        .collect::<Result<_, _>>()
+        .map_err(|e| ResponseEncryptionError::MalformedClientMetadata(e.to_string()))
+}
+
+fn member<'a>(jwk: &'a Json, name: &str) -> Option<&'a str> {
+    jwk.get(name).and_then(Json::as_str)
+}
+
+fn malformed(e: impl std::fmt::Display) -> ResponseEncryptionError {
+    ResponseEncryptionError::MalformedClientMetadata(format!("{e:#}"))
+}
+
+fn encryption(e: impl std::fmt::Display) -> ResponseEncryptionError {
+    ResponseEncryptionError::Encryption(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use josekit::jwe::alg::ecdh_es::EcdhEsJweDecrypter;
+
+    use super::*;
+
+    fn p256_key() -> (josekit::jwk::Jwk, Json) {
+        let secret = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let private = josekit::jwk::Jwk::from_bytes(secret.to_jwk_string().as_bytes()).unwrap();
+        let public = serde_json::from_str(&secret.public_key().to_jwk_string()).unwrap();
+        (private, public)
+    }
+
+    fn decode_header(jwe: &str) -> Json {
+        let header = jwe.split('.').next().unwrap();
+        serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(header).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_p256_round_trip() {
+        let (private, mut public) = p256_key();
+        public["kid"] = json!("enc-key");
+        let encrypter = ResponseEncrypter::negotiate(&[public], None, &["A256GCM".into()])
+            .unwrap()
+            .with_agreement_info("mdoc-nonce", "nonce");
+
+        let payload = json!({ "vp_token": { "pid": ["presentation"] }, "state": "state" });
+        let jwe = encrypter.encrypt(&payload).unwrap();
+
+        let header = decode_header(&jwe);
+        assert_eq!(header["enc"], "A256GCM");
+        assert_eq!(header["kid"], "enc-key");
+        assert_eq!(header["apv"], BASE64_URL_SAFE_NO_PAD.encode("nonce"));
+
+        let decrypter: EcdhEsJweDecrypter<p256::NistP256> =
+            josekit::jwe::ECDH_ES.decrypter_from_jwk(&private).unwrap();
+        let (decrypted, _) = josekit::jwe::deserialize_compact(&jwe, &decrypter).unwrap();
+        assert_eq!(serde_json::from_slice::<Json>(&decrypted).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_x25519_round_trip() {
+        let secret = rand::random::<[u8; 32]>();
+        let x = MontgomeryPoint::mul_base_clamped(secret).to_bytes();
+        let public = json!({
+            "kty": "OKP",
+            "crv": "X25519",
+            "use": "enc",
+            "kid": "x25519",
+            "x": BASE64_URL_SAFE_NO_PAD.encode(x),
+        });
+        let rsa = json!({ "kty": "RSA", "n": "AQAB", "e": "AQAB" });
+
+        for enc in RESPONSE_ENCRYPTION_ENC_VALUES {
+            let encrypter =
+                ResponseEncrypter::negotiate(&[rsa.clone(), public.clone()], None, &[enc.into()])
+                    .unwrap()
+                    .with_agreement_info("mdoc-nonce", "nonce");
+
+            let payload = json!({ "vp_token": { "pid": ["presentation"] }, "state": "state" });
+            let jwe = encrypter.encrypt(&payload).unwrap();
+
+            let header = decode_header(&jwe);
+            assert_eq!(header["alg"], "ECDH-ES");
+            assert_eq!(header["enc"], enc);
+            assert_eq!(header["kid"], "x25519");
+            assert_eq!(header["epk"]["crv"], "X25519");
+
+            // Decrypt as the verifier.
+            let epk: [u8; 32] = BASE64_URL_SAFE_NO_PAD
+                .decode(header["epk"]["x"].as_str().unwrap())
+                .unwrap()
+                .try_into()
+                .unwrap();
+            let shared_secret = MontgomeryPoint(epk).mul_clamped(secret).to_bytes();
+            let cek = concat_kdf(&shared_secret, enc, b"mdoc-nonce", b"nonce");
+
+            let parts = jwe.split('.').collect::<Vec<_>>();
+            assert_eq!(parts[1], "");
+            let decode = |part: &str| BASE64_URL_SAFE_NO_PAD.decode(part).unwrap();
+            let sealed = [decode(parts[3]), decode(parts[4])].concat();
+            let input = Payload {
+                msg: &sealed,
+                aad: parts[0].as_bytes(),
+            };
+            let iv = decode(parts[2]);
+            let decrypted = match enc {
+                "A128GCM" => Aes128Gcm::new_from_slice(&cek)
+                    .unwrap()
+                    .decrypt(Nonce::from_slice(&iv), input),
+                _ => Aes256Gcm::new_from_slice(&cek)
+                    .unwrap()
+                    .decrypt(Nonce::from_slice(&iv), input),
+            }
+            .unwrap();
+            assert_eq!(serde_json::from_slice::<Json>(&decrypted).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_concat_kdf() {
+        // RFC 7518 Appendix C.
+        let shared_secret = [
+            158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49,
+            110, 163, 218, 128, 106, 72, 246, 218, 167, 121, 140, 254, 144, 196,
+        ];
+        assert_eq!(
+            BASE64_URL_SAFE_NO_PAD.encode(concat_kdf(&shared_secret, "A128GCM", b"Alice", b"Bob")),
+            "VqqN6vgjbSBcIijNcacQGg"
+        );
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let (_, public) = p256_key();
+        let mut signing = public.clone();
+        signing["use"] = json!("sig");
+        let mut key_wrapping = public.clone();
+        key_wrapping["alg"] = json!("ECDH-ES+A128KW");
+        let rsa = json!({ "kty": "RSA", "n": "AQAB", "e": "AQAB" });
+
+        // Keys are selected by `use` and `alg`, and the first supported `enc`
+        // of the verifier wins.
+        let encrypter = ResponseEncrypter::negotiate(
+            &[signing.clone(), key_wrapping.clone(), public.clone()],
+            None,
+            &["A192GCM".into(), "A256GCM".into(), "A128GCM".into()],
+        )
+        .unwrap();
+        assert_eq!(
+            decode_header(&encrypter.encrypt(&json!({})).unwrap())["enc"],
+            "A256GCM"
+        );
+        assert_eq!(
+            encrypter.jwk_thumbprint(),
+            ResponseEncrypter::negotiate(&[public.clone()], None, &[])
+                .unwrap()
+                .jwk_thumbprint()
+        );
+
+        assert!(matches!(
+            ResponseEncrypter::negotiate(&[signing, key_wrapping, rsa], None, &[]),
+            Err(ResponseEncryptionError::NoSuitableKey(_))
+        ));
+        assert!(matches!(
+            ResponseEncrypter::negotiate(&[], None, &[]),
+            Err(ResponseEncryptionError::MissingEncryptionKey)
+        ));
+        assert!(matches!(
+            ResponseEncrypter::negotiate(&[public.clone()], None, &["A192GCM".into()]),
+            Err(ResponseEncryptionError::UnsupportedContentEncryption(_))
+        ));
+        assert!(matches!(
+            ResponseEncrypter::negotiate(&[public], Some("RSA-OAEP-256"), &[]),
+            Err(ResponseEncryptionError::UnsupportedAlgorithm(_))
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+//! Transaction data of OID4VP 1.0 requests (§5.1 and §8.4).
+//!
+//! Each item of the `transaction_data` request parameter describes a
+//! transaction the holder authorizes with the presentation, such as a payment
+//! or a qualified electronic signature. The items are bound to the
+//! presentation by signing their hashes with the holder key, in the KB-JWT of
+//! SD-JWT VCs. OID4VP does not define such a binding for mdocs, which cannot
+//! authorize transaction data.
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use openid4vp::core::{
+    authorization_request::AuthorizationRequestObject,
+    dcql_query::DcqlQuery,
+    object::{ParsingErrorContext, TypedParameter},
+};
+use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+
+/// The only supported hash algorithm, and the default one.
+pub const TRANSACTION_DATA_HASH_ALG: &str = "sha-256";
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum TransactionDataError {
+    #[error("Malformed transaction data: {0}")]
+    Malformed(String),
+    #[error("Transaction data refers to an unknown credential query: {0}")]
+    UnknownCredentialQuery(String),
+    #[error("None of the hash algorithms of the transaction data is supported: {0}")]
+    UnsupportedHashAlgorithm(String),
+}
+
+/// A transaction data item of the authorization request, to display to the
+/// holder for consent.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct TransactionData {
+    /// The item as received, a base64url-encoded JSON object.
+    pub encoded: String,
+    /// The type of the transaction, which defines its other parameters.
+    pub r#type: String,
+    /// The credential queries whose credentials can authorize the transaction.
+    pub credential_ids: Vec<String>,
+    /// The hash algorithms accepted by the verifier. Empty means `sha-256`.
+    pub hash_algorithms: Vec<String>,
+    /// The decoded JSON object, including the type-specific parameters.
+    pub payload: String,
+}
+
+impl TransactionData {
+    fn decode(encoded: String) -> Result<Self, TransactionDataError> {
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(encoded.trim_end_matches('='))
+            .map_err(|e| TransactionDataError::Malformed(e.to_string()))?;
+        let payload: Json = serde_json::from_slice(&bytes)
+            .map_err(|e| TransactionDataError::Malformed(e.to_string()))?;
+
+        let r#type = payload
+            .get("type")
+            .and_then(Json::as_str)
+            .ok_or_else(|| TransactionDataError::Malformed("missing `type`".into()))?
+            .to_string();
+
+        let credential_ids = payload
+            .get("credential_ids")
+            .and_then(Json::as_array)
+            .filter(|ids| !ids.is_empty())
+            .and_then(|ids| {
+                ids.iter()
+                    .map(|id| id.as_str().map(ToString::to_string))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| TransactionDataError::Malformed("missing `credential_ids`".into()))?;
+
+        let hash_algorithms = match payload.get("transaction_data_hashes_alg") {
+            None => vec![],
+            Some(algorithms) => algorithms
+                .as_array()
+                .and_then(|algorithms| {
+                    algorithms
+                        .iter()
+                        .map(|alg| alg.as_str().map(ToString::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| {
+                    TransactionDataError::Malformed("invalid `transaction_data_hashes_alg`".into())
+                })?,
+        };
+
+        if !hash_algorithms.is_empty()
+            && !hash_algorithms
+                .iter()
+                .any(|alg| alg == TRANSACTION_DATA_HASH_ALG)
+        {
+            return Err(TransactionDataError::UnsupportedHashAlgorithm(
+                hash_algorithms.join(", "),
+            ));
+        }
+
+        Ok(Self {
+            encoded,
+            r#type,
+            credential_ids,
+            hash_algorithms,
+            payload: payload.to_string(),
+        })
+    }
+
+    /// Base64url-encoded hash of the item, as received.
+    pub(crate) fn hash(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(self.encoded.as_bytes()))
+    }
+}
+
+/// Parse the transaction data of the request, ensuring that it only refers to
+/// credential queries of the DCQL query.
+pub(crate) fn parse_transaction_data(
+    request: &AuthorizationRequestObject,
+    dcql_query: &DcqlQuery,
+) -> Result<Vec<TransactionData>, TransactionDataError> {
+    let Some(parameter) = request.get::<TransactionDataParameter>() else {
+        return Ok(vec![]);
+    };
+    let TransactionDataParameter(items) = parameter
+        .parsing_error()
+        .map_err(|e| TransactionDataError::Malformed(e.to_string()))?;
+
+    items
+        .into_iter()
+        .map(|encoded| {
+            let item = TransactionData::decode(encoded)?;
+            if let Some(id) = item.credential_ids.iter().find(|id| {
+                !dcql_query
+                    .credentials()
+                    .iter()
+                    .any(|query| query.id() == id.as_str())
+            }) {
+                return Err(TransactionDataError::UnknownCredentialQuery(id.clone()));
+            }
+            Ok(item)
+        })
+        .collect()
+}
+
+/// The hashes binding transaction data items to a presentation.
+#[derive(Debug, Clone)]
+pub(crate) struct TransactionDataHashes {
+    pub(crate) hashes: Vec<String>,
+    /// Only set when the verifier specified the accepted hash algorithms.
+    pub(crate) alg: Option<String>,
+}
+
+impl TransactionDataHashes {
+    /// Return the hashes of the items, if any.
+    pub(crate) fn new(items: &[TransactionData]) -> Option<Self> {
+        if items.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            hashes: items.iter().map(TransactionData::hash).collect(),
+            alg: items
+                .iter()
+                .any(|item| !item.hash_algorithms.is_empty())
+                .then(|| TRANSACTION_DATA_HASH_ALG.to_string()),
+        })
+    }
+}
+
+/// The `transaction_data` request parameter.
+#[derive(Debug, Clone)]
+struct TransactionDataParameter(Vec<String>);
+
+impl TypedParameter for TransactionDataParameter {
+    const KEY: &'static str = "transaction_data";
+}
+
+impl TryFrom<Json> for TransactionDataParameter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Json) -> Result<Self, Self::Error> {
+        Ok(Self(serde_json::from_value(value)?))
+    }
+}
+
+impl From<TransactionDataParameter> for Json {
+    fn from(value: TransactionDataParameter) -> Self {
+        Json::from(value.0)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Return a request with a single credential query, of the given format,
+    /// and a `payment` transaction data item to authorize with its credential.
+    pub(crate) fn payment_request(
+        query_id: &str,
+        format: &str,
+    ) -> (AuthorizationRequestObject, TransactionData) {
+        let payload = json!({ "type": "payment", "credential_ids": [query_id] });
+        let item = TransactionData::decode(encode(payload)).unwrap();
+        let request = serde_json::from_value(json!({
+            "client_id": "redirect_uri:https://verifier.example/callback",
+            "response_uri": "https://verifier.example/callback",
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "nonce": "nonce",
+            "dcql_query": { "credentials": [{ "id": query_id, "format": format }] },
+            "transaction_data": [item.encoded],
+        }))
+        .unwrap();
+
+        (request, item)
+    }
+
+    fn encode(value: Json) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(value.to_string())
+    }
+
+    fn request(transaction_data: Vec<String>) -> (AuthorizationRequestObject, DcqlQuery) {
+        let dcql_query = json!({
+            "credentials": [{ "id": "pid", "format": "dc+sd-jwt" }]
+        });
+        let request = json!({
+            "client_id": "redirect_uri:https://verifier.example/callback",
+            "response_uri": "https://verifier.example/callback",
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "nonce": "nonce",
+            "dcql_query": dcql_query,
+            "transaction_data": transaction_data,
+        });
+
+        (
+            serde_json::from_value(request).unwrap(),
+            serde_json::from_value(dcql_query).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parse_transaction_data() {
+        let payment = encode(json!({
+            "type": "payment_data",
+            "credential_ids": ["pid"],
+            "transaction_data_hashes_alg": ["sha-384", "sha-256"],
+            "payee": "Merchant",
+            "amount": "23.50"
+        }));
+        let (request, dcql_query) = request(vec![payment.clone()]);
+
+        let items = parse_transaction_data(&request, &dcql_query).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].r#type, "payment_data");
+        assert_eq!(items[0].credential_ids, ["pid"]);
+        assert_eq!(items[0].encoded, payment);
+
+        let hashes = TransactionDataHashes::new(&items).unwrap();
+        assert_eq!(
+            hashes.hashes,
+            [BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(payment.as_bytes()))]
+        );
+        assert_eq!(hashes.alg.as_deref(), Some("sha-256"));
+    }
+
+    #[test]
+    fn test_reject_invalid_transaction_data() {
+        let (request, dcql_query) = request(vec![encode(json!({
+            "type": "payment_data",
+            "credential_ids": ["mdl"]
+        }))]);
+        assert!(matches!(
+            parse_transaction_data(&request, &dcql_query),
+            Err(TransactionDataError::UnknownCredentialQuery(id)) if id == "mdl"
+        ));
+
+        let (request, dcql_query) = request(vec![encode(json!({
+            "type": "payment_data",
+            "credential_ids": ["pid"],
+            "transaction_data_hashes_alg": ["sha-384"]
+        }))]);
+        assert!(matches!(
+            parse_transaction_data(&request, &dcql_query),
+            Err(TransactionDataError::UnsupportedHashAlgorithm(_))
+        ));
+
+        let (request, dcql_query) = request(vec![encode(json!({ "credential_ids": ["pid"] }))]);
+        assert!(matches!(
+            parse_transaction_data(&request, &dcql_query),
+            Err(TransactionDataError::Malformed(_))
+        ));
+    }
+}
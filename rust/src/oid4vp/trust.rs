@@ -0,0 +1,332 @@
+//! Trust in the verifiers of OID4VP requests.
+//!
+//! Verifying a request only establishes that it was issued by the holder of
+//! its client_id. The [VerifierTrustStore] tells whether that verifier is
+//! known: its X.509 certificate chains to a trusted reader or relying party
+//! root, or its DID is allowed. The outcome is reported to the user interface
+//! as a [VerifierTrustResult], for the consent screen to warn the user.
+
+use std::sync::{Arc, Mutex};
+
+use x509_cert::{der::DecodePem, Certificate};
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum VerifierTrustError {
+    #[error("Invalid root certificate: {0}")]
+    InvalidRootCertificate(String),
+    #[error("The request does not comply with the trust store: {0}")]
+    InvalidVerifier(String),
+}
+
+/// The trust in the verifier of a request.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum VerifierTrustResult {
+    /// The verifier is known to the trust store.
+    Trusted { reason: String },
+    /// The request is valid, but the verifier is not known to the trust store.
+    UntrustedButValid { reason: String },
+    /// The request does not comply with the trust store, and should not be
+    /// answered: no response is created for it.
+    Invalid { reason: String },
+}
+
+impl VerifierTrustResult {
+    /// The trust in the verifier of a request which was provided already
+    /// decoded, and therefore not verified.
+    pub(crate) fn unverified() -> Self {
+        Self::UntrustedButValid {
+            reason: "the request was not verified by the wallet".into(),
+        }
+    }
+
+    /// Return whether the verifier is known to the trust store.
+    pub(crate) fn is_trusted(&self) -> bool {
+        matches!(self, Self::Trusted { .. })
+    }
+
+    /// Return an error if the request should not be answered.
+    pub(crate) fn ensure_answerable(&self) -> Result<(), VerifierTrustError> {
+        match self {
+            Self::Invalid { reason } => Err(VerifierTrustError::InvalidVerifier(reason.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The verifiers trusted by the wallet.
+///
+/// An empty store trusts no verifier, and accepts every client_id prefix
+/// supported by the wallet.
+#[derive(Debug, Clone, Default, uniffi::Object)]
+pub struct VerifierTrustStore {
+    /// Roots of the reader and relying party certificates.
+    x509_roots: Vec<Certificate>,
+    /// Allowed DIDs of the `decentralized_identifier` prefix.
+    dids: Vec<String>,
+    /// Registered client_id prefixes. Empty means every prefix.
+    client_id_prefixes: Vec<String>,
+    /// Roots of the verifier attestations, such as registration certificates.
+    attestation_roots: Vec<Certificate>,
+}
+
+#[uniffi::export]
+impl VerifierTrustStore {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Trust the verifiers whose requests are signed with a certificate
+    /// issued by the given PEM-encoded root.
+    pub fn add_x509_root(
+        self: Arc<Self>,
+        certificate_pem: String,
+    ) -> Result<Arc<Self>, VerifierTrustError> {
+        let root = Certificate::from_pem(&certificate_pem)
+            .map_err(|e| VerifierTrustError::InvalidRootCertificate(e.to_string()))?;
+
+        let mut x509_roots = self.x509_roots.clone();
+        x509_roots.push(root);
+
+        Ok(Arc::new(Self {
+            x509_roots,
+            ..(*self).clone()
+        }))
+    }
+
+    /// Trust the verifier attestations issued by the given PEM-encoded root,
+    /// such as the registration certificates of a registrar.
+    pub fn add_attestation_root(
+        self: Arc<Self>,
+        certificate_pem: String,
+    ) -> Result<Arc<Self>, VerifierTrustError> {
+        let root = Certificate::from_pem(&certificate_pem)
+            .map_err(|e| VerifierTrustError::InvalidRootCertificate(e.to_string()))?;
+
+        let mut attestation_roots = self.attestation_roots.clone();
+        attestation_roots.push(root);
+
+        Ok(Arc::new(Self {
+            attestation_roots,
+            ..(*self).clone()
+        }))
+    }
+
+    /// Trust the verifier identified by the given DID.
+    pub fn add_did(self: Arc<Self>, did: String) -> Arc<Self> {
+        let mut dids = self.dids.clone();
+        dids.push(did);
+
+        Arc::new(Self {
+            dids,
+            ..(*self).clone()
+        })
+    }
+
+    /// Register a client_id prefix, e.g. `x509_san_dns`.
+    ///
+    /// Once a prefix is registered, requests using any other prefix are
+    /// reported as [VerifierTrustResult::Invalid].
+    pub fn add_client_id_prefix(self: Arc<Self>, prefix: String) -> Arc<Self> {
+        let mut client_id_prefixes = self.client_id_prefixes.clone();
+        client_id_prefixes.push(prefix);
+
+        Arc::new(Self {
+            client_id_prefixes,
+            ..(*self).clone()
+        })
+    }
+}
+
+impl VerifierTrustStore {
+    /// Return a store trusting the given DIDs.
+    pub(crate) fn from_dids(dids: Vec<String>) -> Self {
+        Self {
+            dids,
+            ..Self::default()
+        }
+    }
+
+    /// Return the DIDs allowed to sign requests, or `None` if any DID is.
+    pub(crate) fn allowed_dids(&self) -> Option<&[String]> {
+        match self.dids.as_slice() {
+            [] => None,
+            dids => Some(dids),
+        }
+    }
+
+    /// Return the roots of the verifier attestations.
+    pub(crate) fn attestation_roots(&self) -> &[Certificate] {
+        &self.attestation_roots
+    }
+
+    /// Evaluate a request of an X.509 client_id prefix.
+    ///
+    /// `validate` verifies the signature of the request with its certificate
+    /// chain, anchored in the given roots if any. Invalid signatures are
+    /// returned as errors, to reject the request.
+    pub(crate) fn evaluate_x509<F>(
+        &self,
+        prefix: &str,
+        validate: F,
+    ) -> anyhow::Result<VerifierTrustResult>
+    where
+        F: Fn(Option<&[Certificate]>) -> anyhow::Result<()>,
+    {
+        validate(None)?;
+
+        if let Some(invalid) = self.check_client_id_prefix(prefix) {
+            return Ok(invalid);
+        }
+
+        if !self.x509_roots.is_empty() && validate(Some(&self.x509_roots)).is_ok() {
+            return Ok(VerifierTrustResult::Trusted {
+                reason: "the request is signed with a certificate issued by a trusted root".into(),
+            });
+        }
+
+        Ok(VerifierTrustResult::UntrustedButValid {
+            reason:
+                "the request is signed with a certificate which was not issued by a trusted root"
+                    .into(),
+        })
+    }
+
+    /// Evaluate a signed request of a DID, once its signature is verified.
+    pub(crate) fn evaluate_did(&self, prefix: &str, did: &str) -> VerifierTrustResult {
+        if let Some(invalid) = self.check_client_id_prefix(prefix) {
+            return invalid;
+        }
+
+        if self.dids.iter().any(|trusted| trusted == did) {
+            VerifierTrustResult::Trusted {
+                reason: format!("{did} is a trusted DID"),
+            }
+        } else {
+            VerifierTrustResult::UntrustedButValid {
+                reason: format!("{did} is not a trusted DID"),
+            }
+        }
+    }
+
+    /// Evaluate a request whose client_id cannot be matched against the trust
+    /// store, such as `redirect_uri` or unsigned requests.
+    pub(crate) fn evaluate_unauthenticated(
+        &self,
+        prefix: &str,
+        reason: &str,
+    ) -> VerifierTrustResult {
+        self.check_client_id_prefix(prefix).unwrap_or_else(|| {
+            VerifierTrustResult::UntrustedButValid {
+                reason: reason.to_string(),
+            }
+        })
+    }
+
+    fn check_client_id_prefix(&self, prefix: &str) -> Option<VerifierTrustResult> {
+        if self.client_id_prefixes.is_empty() || self.client_id_prefixes.iter().any(|p| p == prefix)
+        {
+            return None;
+        }
+
+        Some(VerifierTrustResult::Invalid {
+            reason: format!("the client_id prefix '{prefix}' is not registered"),
+        })
+    }
+}
+
+/// Records the trust in the verifier while its request is being verified, for
+/// the request verifiers which cannot return it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VerifierTrustRecorder(Arc<Mutex<Option<VerifierTrustResult>>>);
+
+impl VerifierTrustRecorder {
+    pub(crate) fn record(&self, result: VerifierTrustResult) {
+        if let Ok(mut recorded) = self.0.lock() {
+            *recorded = Some(result);
+        }
+    }
+
+    /// Return the recorded result, or [VerifierTrustResult::unverified].
+    pub(crate) fn take(&self) -> VerifierTrustResult {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|mut recorded| recorded.take())
+            .unwrap_or_else(VerifierTrustResult::unverified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_did() {
+        let store = VerifierTrustStore::from_dids(vec!["did:web:verifier.example".into()]);
+
+        assert!(store
+            .evaluate_did("decentralized_identifier", "did:web:verifier.example")
+            .is_trusted());
+        assert!(matches!(
+            store.evaluate_did("decentralized_identifier", "did:web:other.example"),
+            VerifierTrustResult::UntrustedButValid { .. }
+        ));
+    }
+
+    #[test]
+    fn test_registered_client_id_prefixes() {
+        let store = VerifierTrustStore::new().add_client_id_prefix("x509_san_dns".into());
+
+        let invalid = store.evaluate_unauthenticated("redirect_uri", "unsigned request");
+        assert!(matches!(invalid, VerifierTrustResult::Invalid { .. }));
+        assert!(matches!(
+            invalid.ensure_answerable(),
+            Err(VerifierTrustError::InvalidVerifier(_))
+        ));
+
+        // The signature is checked before the prefix.
+        assert!(store
+            .evaluate_x509("x509_hash", |_| anyhow::bail!("invalid signature"))
+            .is_err());
+        assert!(matches!(
+            store.evaluate_x509("x509_hash", |_| Ok(())).unwrap(),
+            VerifierTrustResult::Invalid { .. }
+        ));
+        let valid = store.evaluate_x509("x509_san_dns", |_| Ok(())).unwrap();
+        assert!(matches!(
+            valid,
+            VerifierTrustResult::UntrustedButValid { .. }
+        ));
+        assert!(valid.ensure_answerable().is_ok());
+    }
+
+    #[test]
+    fn test_evaluate_x509_roots() {
+        let store = VerifierTrustStore {
+            x509_roots: crate::trusted_roots::trusted_roots().unwrap(),
+            ..Default::default()
+        };
+
+        // Only the roots of the store anchor the chain.
+        let trusted = store
+            .evaluate_x509("x509_san_dns", |roots| match roots {
+                Some(roots) if !roots.is_empty() => Ok(()),
+                Some(_) => anyhow::bail!("no root"),
+                None => Ok(()),
+            })
+            .unwrap();
+        assert!(trusted.is_trusted());
+
+        let untrusted = store
+            .evaluate_x509("x509_san_dns", |roots| match roots {
+                Some(_) => anyhow::bail!("untrusted chain"),
+                None => Ok(()),
+            })
+            .unwrap();
+        assert!(matches!(
+            untrusted,
+            VerifierTrustResult::UntrustedButValid { .. }
+        ));
+    }
+}
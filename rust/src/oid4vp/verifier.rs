@@ -8,6 +8,18 @@ pub enum Oid4vpVerifierError {
     HttpClient(String),
     #[error("Invalid URL: {0}")]
     Url(String),
+    #[error("Invalid DCQL query: {0}")]
+    InvalidQuery(String),
+    #[error("Failed to sign the authorization request: {0}")]
+    Signing(String),
+    #[error("No pending authorization request matches the response")]
+    UnknownSession,
+    #[error("The request object was already fetched")]
+    RequestObjectFetched,
+    #[error("Failed to decrypt the authorization response: {0}")]
+    Decryption(String),
+    #[error("Invalid authorization response: {0}")]
+    InvalidResponse(String),
 }
 
 #[derive(Debug, uniffi::Object)]
@@ -0,0 +1,583 @@
+//! Verifier attestations of OID4VP 1.0 requests (§5.1).
+//!
+//! The `verifier_info` request parameter carries attestations about the
+//! verifier, such as the registration certificates of the EUDI ARF, which
+//! state the credentials and claims the verifier is entitled to request. The
+//! attestations are verified against the attestation roots of the
+//! [VerifierTrustStore], and the requested claims exceeding the entitlement
+//! of the verifier are flagged to the user interface.
+
+use anyhow::{bail, Context};
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
+use openid4vp::core::{
+    authorization_request::AuthorizationRequestObject,
+    dcql_query::{DcqlCredentialClaimsQueryPath, DcqlCredentialQuery},
+    object::{ParsingErrorContext, TypedParameter},
+};
+use serde::Deserialize;
+use serde_json::Value as Json;
+
+use super::trust::{VerifierTrustResult, VerifierTrustStore};
+use super::{x5c, CLIENT_ID_PREFIXES};
+
+/// The format of JWT-encoded attestations, signed with an `x5c` chain.
+pub const VERIFIER_ATTESTATION_JWT_FORMAT: &str = "jwt";
+
+/// The tolerated clock skew, in seconds, when checking the time claims of an
+/// attestation.
+const CLOCK_SKEW: i64 = 60;
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum VerifierAttestationError {
+    #[error("Malformed verifier_info: {0}")]
+    Malformed(String),
+    #[error("Unable to compare the query with the entitlement of the verifier: {0}")]
+    Entitlement(String),
+}
+
+/// An attestation about the verifier of a request.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct VerifierAttestation {
+    /// The format of the attestation, e.g. `jwt`.
+    pub format: String,
+    /// The issuer of the attestation, such as a registrar.
+    pub issuer: Option<String>,
+    /// The verifier the attestation is about.
+    pub subject: Option<String>,
+    /// The credential queries the attestation applies to. Empty means all.
+    pub credential_ids: Vec<String>,
+    /// Whether the attestation is valid and issued by a trusted root.
+    pub trust: VerifierTrustResult,
+    /// The decoded claims of the attestation, as a JSON object.
+    pub payload: Option<String>,
+}
+
+impl VerifierAttestation {
+    fn verify(item: VerifierInfoItem, client_id: Option<&str>, store: &VerifierTrustStore) -> Self {
+        let mut attestation = Self {
+            format: item.format,
+            issuer: None,
+            subject: None,
+            credential_ids: item.credential_ids.unwrap_or_default(),
+            trust: VerifierTrustResult::unverified(),
+            payload: None,
+        };
+
+        if attestation.format != VERIFIER_ATTESTATION_JWT_FORMAT {
+            attestation.trust = VerifierTrustResult::UntrustedButValid {
+                reason: format!(
+                    "attestations of format '{}' are not supported",
+                    attestation.format
+                ),
+            };
+            return attestation;
+        }
+
+        let result = item
+            .data
+            .as_str()
+            .context("the attestation is not a JWT")
+            .and_then(|jwt| decode_jwt(jwt, store));
+
+        attestation.trust = match result {
+            Ok((claims, trust)) => {
+                attestation.issuer = claims.get("iss").and_then(Json::as_str).map(Into::into);
+                attestation.subject = claims.get("sub").and_then(Json::as_str).map(Into::into);
+                attestation.payload = Some(claims.to_string());
+
+                match check_subject(attestation.subject.as_deref(), client_id) {
+                    Ok(()) => trust,
+                    Err(e) => VerifierTrustResult::Invalid {
+                        reason: format!("{e:#}"),
+                    },
+                }
+            }
+            Err(e) => VerifierTrustResult::Invalid {
+                reason: format!("{e:#}"),
+            },
+        };
+
+        attestation
+    }
+
+    /// The credentials the verifier is entitled to request, if stated.
+    fn entitled_credentials(&self) -> Option<Vec<EntitledCredential>> {
+        let payload: Json = serde_json::from_str(self.payload.as_deref()?).ok()?;
+        serde_json::from_value(payload.get("credentials")?.clone()).ok()
+    }
+}
+
+/// Parse the attestations of the request, and verify them.
+///
+/// Attestations failing verification are reported as
+/// [VerifierTrustResult::Invalid], without rejecting the request.
+pub(crate) fn verify_verifier_attestations(
+    request: &AuthorizationRequestObject,
+    store: &VerifierTrustStore,
+) -> Result<Vec<VerifierAttestation>, VerifierAttestationError> {
+    let Some(parameter) = request.get::<VerifierInfo>() else {
+        return Ok(vec![]);
+    };
+    let VerifierInfo(items) = parameter
+        .parsing_error()
+        .map_err(|e| VerifierAttestationError::Malformed(format!("{e:#}")))?;
+
+    let client_id = request.client_id().map(|id| id.0.as_str());
+
+    Ok(items
+        .into_iter()
+        .map(|item| VerifierAttestation::verify(item, client_id, store))
+        .collect())
+}
+
+/// Return whether a claim of the credential query exceeds the entitlement of
+/// the verifier.
+///
+/// Only the trusted attestations stating the entitled credentials are taken
+/// into account: without any, the entitlement of the verifier is unknown and
+/// no claim exceeds it.
+pub(crate) fn exceeds_entitlement(
+    attestations: &[VerifierAttestation],
+    query: &DcqlCredentialQuery,
+    path: &[DcqlCredentialClaimsQueryPath],
+) -> Result<bool, VerifierAttestationError> {
+    let entitlements = attestations
+        .iter()
+        .filter(|attestation| attestation.trust.is_trusted())
+        .filter(|attestation| {
+            attestation.credential_ids.is_empty()
+                || attestation.credential_ids.iter().any(|id| id == query.id())
+        })
+        .filter_map(VerifierAttestation::entitled_credentials)
+        .flatten()
+        .collect::<Vec<_>>();
+
+    if entitlements.is_empty() {
+        return Ok(false);
+    }
+
+    let query = serde_json::to_value(query)
+        .map_err(|e| VerifierAttestationError::Entitlement(format!("{e:#}")))?;
+    let path = serde_json::to_value(path)
+        .map_err(|e| VerifierAttestationError::Entitlement(format!("{e:#}")))?;
+    let path = path.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    Ok(!entitlements
+        .iter()
+        .any(|credential| credential.entitles(&query, path)))
+}
+
+/// A credential the verifier is entitled to request, described like a DCQL
+/// credential query.
+#[derive(Debug, Deserialize)]
+struct EntitledCredential {
+    format: String,
+    #[serde(default)]
+    meta: Option<Json>,
+    /// Omitted when the verifier is entitled to all the claims.
+    #[serde(default)]
+    claims: Option<Vec<EntitledClaim>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntitledClaim {
+    path: Vec<Json>,
+}
+
+impl EntitledCredential {
+    fn entitles(&self, query: &Json, path: &[Json]) -> bool {
+        if query.get("format").and_then(Json::as_str) != Some(self.format.as_str()) {
+            return false;
+        }
+
+        if let (Some(entitled), Some(requested)) = (&self.meta, query.get("meta")) {
+            if !meta_overlaps(entitled, requested) {
+                return false;
+            }
+        }
+
+        let Some(claims) = &self.claims else {
+            return true;
+        };
+
+        // An entitled claim covers its nested claims. `null` matches any
+        // array element.
+        claims.iter().any(|claim| {
+            claim.path.len() <= path.len()
+                && claim.path.iter().zip(path).all(|(entitled, requested)| {
+                    entitled.is_null() || requested.is_null() || entitled == requested
+                })
+        })
+    }
+}
+
+/// Whether the credential types of the entitlement and of the query overlap.
+fn meta_overlaps(entitled: &Json, requested: &Json) -> bool {
+    let strings = |meta: &Json, key: &str| -> Option<Vec<String>> {
+        meta.get(key)?
+            .as_array()?
+            .iter()
+            .map(|value| value.as_str().map(ToString::to_string))
+            .collect()
+    };
+
+    if let (Some(entitled), Some(requested)) = (
+        strings(entitled, "vct_values"),
+        strings(requested, "vct_values"),
+    ) {
+        return entitled.iter().any(|vct| requested.contains(vct));
+    }
+
+    if let (Some(entitled), Some(requested)) = (
+        entitled.get("doctype_value"),
+        requested.get("doctype_value"),
+    ) {
+        return entitled == requested;
+    }
+
+    true
+}
+
+/// Decode and verify a JWT signed with an `x5c` certificate chain, returning
+/// its claims and whether the chain is anchored in the attestation roots.
+fn decode_jwt(
+    jwt: &str,
+    store: &VerifierTrustStore,
+) -> anyhow::Result<(Json, VerifierTrustResult)> {
+    let mut segments = jwt.split('.');
+    let (Some(header_b64), Some(claims_b64), Some(_), None) = (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) else {
+        bail!("the attestation is not a JWT");
+    };
+
+    let header: Json = decode_segment(header_b64).context("invalid JWT header")?;
+    let claims: Json = decode_segment(claims_b64).context("invalid JWT claims")?;
+
+    let chain = x5c::decode_chain(&header)?;
+    x5c::verify_jwt_signature(jwt, &header, &chain[0])?;
+    x5c::verify_chain(&chain)?;
+    check_time_claims(&claims)?;
+
+    let anchored = x5c::is_anchored(&chain, store.attestation_roots());
+
+    let trust = if anchored {
+        VerifierTrustResult::Trusted {
+            reason: "the attestation is issued by a trusted root".into(),
+        }
+    } else {
+        VerifierTrustResult::UntrustedButValid {
+            reason: "the attestation is not issued by a trusted root".into(),
+        }
+    };
+
+    Ok((claims, trust))
+}
+
+/// The attestation must not be expired, nor issued or valid only in the
+/// future.
+fn check_time_claims(claims: &Json) -> anyhow::Result<()> {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let claim = |name: &str| -> anyhow::Result<Option<i64>> {
+        claims
+            .get(name)
+            .map(|value| {
+                value
+                    .as_i64()
+                    .with_context(|| format!("invalid '{name}' claim"))
+            })
+            .transpose()
+    };
+
+    if claim("exp")?.is_some_and(|exp| exp < now - CLOCK_SKEW) {
+        bail!("the attestation is expired");
+    }
+    if claim("nbf")?.is_some_and(|nbf| nbf > now + CLOCK_SKEW) {
+        bail!("the attestation is not yet valid");
+    }
+    if claim("iat")?.is_some_and(|iat| iat > now + CLOCK_SKEW) {
+        bail!("the attestation is issued in the future");
+    }
+    Ok(())
+}
+
+fn decode_segment(segment: &str) -> anyhow::Result<Json> {
+    Ok(serde_json::from_slice(
+        &BASE64_URL_SAFE_NO_PAD.decode(segment.trim_end_matches('='))?,
+    )?)
+}
+
+/// The attestation must be about the verifier of the request, identified by
+/// its client_id, with or without its client identifier prefix.
+fn check_subject(subject: Option<&str>, client_id: Option<&str>) -> anyhow::Result<()> {
+    let subject = subject.context("the attestation does not state its subject")?;
+    let client_id = client_id.context("the request does not state its client_id")?;
+
+    // Identifiers without a registered prefix, such as pre-registered ones,
+    // may contain colons themselves.
+    let unprefixed = CLIENT_ID_PREFIXES
+        .iter()
+        .find_map(|prefix| client_id.strip_prefix(prefix)?.strip_prefix(':'))
+        .unwrap_or(client_id);
+    if subject != client_id && subject != unprefixed {
+        bail!("the attestation is about '{subject}', not '{client_id}'");
+    }
+    Ok(())
+}
+
+/// An item of the `verifier_info` request parameter.
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
+struct VerifierInfoItem {
+    format: String,
+    data: Json,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credential_ids: Option<Vec<String>>,
+}
+
+/// The `verifier_info` request parameter.
+#[derive(Debug, Clone)]
+struct VerifierInfo(Vec<VerifierInfoItem>);
+
+impl TypedParameter for VerifierInfo {
+    const KEY: &'static str = "verifier_info";
+}
+
+impl TryFrom<Json> for VerifierInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Json) -> Result<Self, Self::Error> {
+        Ok(Self(serde_json::from_value(value)?))
+    }
+}
+
+impl From<VerifierInfo> for Json {
+    fn from(value: VerifierInfo) -> Self {
+        // SAFETY: the items are always serializable.
+        serde_json::to_value(value.0).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use openid4vp::core::dcql_query::DcqlQuery;
+    use p256::ecdsa::{Signature, SigningKey};
+    use serde_json::json;
+    use signature::Signer;
+    use x509_cert::Certificate;
+
+    use super::*;
+    use crate::oid4vp::x5c::test::{issue, root, x5c, ROOT_PEM};
+
+    /// Return a registration certificate issued by the test root.
+    fn registration_certificate(claims: Json) -> String {
+        let (root, root_key) = root();
+        let (certificate, key) = issue(&root, &root_key, "CN=Test Registrar", false);
+        attestation_jwt(claims, &key, &[certificate])
+    }
+
+    /// Return an attestation signed with the key of the first certificate of
+    /// the chain.
+    fn attestation_jwt(claims: Json, key: &SigningKey, chain: &[Certificate]) -> String {
+        let header = json!({
+            "alg": "ES256",
+            "typ": "rc-wrp+jwt",
+            "x5c": x5c(chain),
+        });
+        let signing_input = format!(
+            "{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+            BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature: Signature = key.sign(signing_input.as_bytes());
+
+        format!(
+            "{signing_input}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    fn request_with(jwt: &str) -> (AuthorizationRequestObject, DcqlQuery) {
+        let dcql_query = json!({
+            "credentials": [{
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "meta": { "vct_values": ["urn:eudi:pid:1"] },
+                "claims": [
+                    { "path": ["given_name"] },
+                    { "path": ["address", "street_address"] },
+                    { "path": ["birthdate"] }
+                ]
+            }]
+        });
+        let request = json!({
+            "client_id": "x509_san_dns:verifier.example",
+            "response_uri": "https://verifier.example/callback",
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "nonce": "nonce",
+            "dcql_query": dcql_query,
+            "verifier_info": [{ "format": "jwt", "data": jwt, "credential_ids": ["pid"] }],
+        });
+
+        (
+            serde_json::from_value(request).unwrap(),
+            serde_json::from_value(dcql_query).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_verify_verifier_attestations() {
+        let jwt = registration_certificate(json!({
+            "iss": "https://registrar.example",
+            "sub": "verifier.example",
+            "credentials": [{
+                "format": "dc+sd-jwt",
+                "meta": { "vct_values": ["urn:eudi:pid:1"] },
+                "claims": [{ "path": ["given_name"] }, { "path": ["address"] }]
+            }]
+        }));
+        let (request, dcql_query) = request_with(&jwt);
+
+        let untrusted =
+            verify_verifier_attestations(&request, &VerifierTrustStore::default()).unwrap();
+        assert!(matches!(
+            untrusted[0].trust,
+            VerifierTrustResult::UntrustedButValid { .. }
+        ));
+
+        let store = VerifierTrustStore::new()
+            .add_attestation_root(ROOT_PEM.into())
+            .map(Arc::unwrap_or_clone)
+            .unwrap();
+        let attestations = verify_verifier_attestations(&request, &store).unwrap();
+        assert_eq!(attestations.len(), 1);
+        assert!(attestations[0].trust.is_trusted());
+        assert_eq!(
+            attestations[0].issuer.as_deref(),
+            Some("https://registrar.example")
+        );
+
+        let query = &dcql_query.credentials()[0];
+        let claims = query.claims().unwrap();
+        assert!(!exceeds_entitlement(&attestations, query, claims[0].path()).unwrap());
+        assert!(!exceeds_entitlement(&attestations, query, claims[1].path()).unwrap());
+        assert!(exceeds_entitlement(&attestations, query, claims[2].path()).unwrap());
+
+        // Untrusted attestations do not state any entitlement.
+        assert!(!exceeds_entitlement(&untrusted, query, claims[2].path()).unwrap());
+    }
+
+    #[test]
+    fn test_reject_invalid_verifier_attestations() {
+        let jwt = registration_certificate(json!({ "sub": "other.example" }));
+        let (request, _) = request_with(&jwt);
+        let attestations =
+            verify_verifier_attestations(&request, &VerifierTrustStore::default()).unwrap();
+        assert!(matches!(
+            attestations[0].trust,
+            VerifierTrustResult::Invalid { .. }
+        ));
+
+        let (header, _) = jwt.split_once('.').unwrap();
+        let tampered = format!(
+            "{header}.{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(json!({ "sub": "verifier.example" }).to_string()),
+            jwt.rsplit('.').next().unwrap()
+        );
+        let (request, _) = request_with(&tampered);
+        let attestations =
+            verify_verifier_attestations(&request, &VerifierTrustStore::default()).unwrap();
+        assert!(matches!(
+            attestations[0].trust,
+            VerifierTrustResult::Invalid { .. }
+        ));
+
+        // The attestation must state its subject.
+        let jwt = registration_certificate(json!({ "iss": "https://registrar.example" }));
+        let (request, _) = request_with(&jwt);
+        let attestations =
+            verify_verifier_attestations(&request, &VerifierTrustStore::default()).unwrap();
+        assert!(matches!(
+            attestations[0].trust,
+            VerifierTrustResult::Invalid { .. }
+        ));
+
+        // Nor may it be valid only in the future.
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        for claim in ["nbf", "iat"] {
+            let jwt = registration_certificate(json!({
+                "sub": "verifier.example",
+                claim: now + 60 * 60,
+            }));
+            let (request, _) = request_with(&jwt);
+            let attestations =
+                verify_verifier_attestations(&request, &VerifierTrustStore::default()).unwrap();
+            assert!(matches!(
+                attestations[0].trust,
+                VerifierTrustResult::Invalid { .. }
+            ));
+        }
+
+        // The attestation must not be signed with a CA key.
+        let (root, root_key) = root();
+        let (certificate, key) = issue(&root, &root_key, "CN=Test Registrar", true);
+        let jwt = attestation_jwt(json!({ "sub": "verifier.example" }), &key, &[certificate]);
+        let (request, _) = request_with(&jwt);
+        let attestations =
+            verify_verifier_attestations(&request, &VerifierTrustStore::default()).unwrap();
+        assert!(matches!(
+            attestations[0].trust,
+            VerifierTrustResult::Invalid { .. }
+        ));
+    }
+
+    #[test]
+    fn test_check_subject() {
+        let client_id = Some("x509_san_dns:verifier.example");
+        assert!(check_subject(Some("verifier.example"), client_id).is_ok());
+        assert!(check_subject(Some("x509_san_dns:verifier.example"), client_id).is_ok());
+        assert!(check_subject(Some("other.example"), client_id).is_err());
+
+        // Only registered prefixes are stripped.
+        let client_id = Some("https://verifier.example");
+        assert!(check_subject(Some("https://verifier.example"), client_id).is_ok());
+        assert!(check_subject(Some("//verifier.example"), client_id).is_err());
+    }
+
+    #[test]
+    fn test_reject_verifier_attestation_chains_without_ca() {
+        let (root, root_key) = root();
+        let store = VerifierTrustStore::new()
+            .add_attestation_root(ROOT_PEM.into())
+            .map(Arc::unwrap_or_clone)
+            .unwrap();
+        let claims = json!({ "sub": "verifier.example" });
+
+        // A certificate which is not a CA must not issue certificates.
+        let (registrar, registrar_key) = issue(&root, &root_key, "CN=Test Registrar", false);
+        let (certificate, key) = issue(&registrar, &registrar_key, "CN=Test Verifier", false);
+        let jwt = attestation_jwt(claims.clone(), &key, &[certificate, registrar]);
+        let (request, _) = request_with(&jwt);
+        let attestations = verify_verifier_attestations(&request, &store).unwrap();
+        assert!(matches!(
+            attestations[0].trust,
+            VerifierTrustResult::Invalid { .. }
+        ));
+
+        // The test root does not allow any intermediate CA below it.
+        let (registrar, registrar_key) = issue(&root, &root_key, "CN=Test Registrar", true);
+        let (certificate, key) = issue(&registrar, &registrar_key, "CN=Test Verifier", false);
+        let jwt = attestation_jwt(claims, &key, &[certificate, registrar]);
+        let (request, _) = request_with(&jwt);
+        let attestations = verify_verifier_attestations(&request, &store).unwrap();
+        assert!(matches!(
+            attestations[0].trust,
+            VerifierTrustResult::UntrustedButValid { .. }
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+//! X.509 certificate chains of JWTs, conveyed in their `x5c` header
+//! (RFC 7515 §4.1.6).
+
+use anyhow::{bail, Context};
+use base64::prelude::{Engine, BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD};
+use p256::{
+    ecdsa::{signature::Verifier, Signature, VerifyingKey},
+    pkcs8::DecodePublicKey,
+};
+use serde_json::Value as Json;
+use x509_cert::{
+    der::{oid::AssociatedOid, Decode, Encode},
+    ext::pkix::{BasicConstraints, KeyUsage, KeyUsages},
+    Certificate,
+};
+
+use crate::verifier::helpers::check_validity;
+
+/// Decode the certificate chain of a JWT header, leaf first.
+pub(crate) fn decode_chain(header: &Json) -> anyhow::Result<Vec<Certificate>> {
+    header
+        .get("x5c")
+        .and_then(Json::as_array)
+        .filter(|x5c| !x5c.is_empty())
+        .context("missing x5c header")?
+        .iter()
+        .map(|certificate| {
+            let der =
+                BASE64_STANDARD.decode(certificate.as_str().context("invalid x5c header")?)?;
+            Certificate::from_der(&der).context("invalid x5c certificate")
+        })
+        .collect()
+}
+
+/// Verify the signature of a compact JWT with the key of the leaf certificate
+/// of its chain.
+///
+/// The algorithm of the header must match the key: only ES256 with a P-256
+/// key is supported.
+pub(crate) fn verify_jwt_signature(
+    jwt: &str,
+    header: &Json,
+    leaf: &Certificate,
+) -> anyhow::Result<()> {
+    let key = match header.get("alg").and_then(Json::as_str) {
+        Some("ES256") => {
+            verifying_key(leaf).context("the certificate key does not match the ES256 algorithm")?
+        }
+        alg => bail!("unsupported signing algorithm: {alg:?}"),
+    };
+
+    let (signing_input, signature) = jwt.rsplit_once('.').context("malformed JWT")?;
+    let signature = BASE64_URL_SAFE_NO_PAD
+        .decode(signature)
+        .ok()
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+        .context("malformed JWT signature")?;
+    key.verify(signing_input.as_bytes(), &signature)
+        .context("invalid JWT signature")
+}
+
+/// Verify that every certificate of a chain is valid, and issued by the next
+/// one, and that the leaf is an end-entity certificate allowed to sign.
+pub(crate) fn verify_chain(chain: &[Certificate]) -> anyhow::Result<()> {
+    let leaf = chain.first().context("empty certificate chain")?;
+    check_leaf(leaf)?;
+
+    for certificate in chain {
+        check_validity(&certificate.tbs_certificate.validity)
+            .context("a certificate of the chain is expired or not yet valid")?;
+    }
+    // Below the issuer at index `n + 1` are `n` intermediate certificates,
+    // not counting the leaf.
+    for (intermediates, pair) in chain.windows(2).enumerate() {
+        verify_issued_by(&pair[0], &pair[1])?;
+        check_issuer(&pair[1], intermediates)?;
+    }
+    Ok(())
+}
+
+/// Return whether a verified chain ends with, or is issued by, one of the
+/// roots.
+pub(crate) fn is_anchored(chain: &[Certificate], roots: &[Certificate]) -> bool {
+    let Some(last) = chain.last() else {
+        return false;
+    };
+
+    roots.iter().any(|root| {
+        root.to_der().ok() == last.to_der().ok()
+            || (check_validity(&root.tbs_certificate.validity).is_ok()
+                && verify_issued_by(last, root).is_ok()
+                && check_issuer(root, chain.len() - 1).is_ok())
+    })
+}
+
+/// Return the P-256 key of a certificate.
+pub(crate) fn verifying_key(certificate: &Certificate) -> anyhow::Result<VerifyingKey> {
+    VerifyingKey::from_public_key_der(
+        &certificate
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()?,
+    )
+    .context("unsupported certificate key, expected P-256")
+}
+
+fn verify_issued_by(certificate: &Certificate, issuer: &Certificate) -> anyhow::Result<()> {
+    if certificate.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        bail!(
+            "certificate '{}' was not issued by '{}'",
+            certificate.tbs_certificate.subject,
+            issuer.tbs_certificate.subject
+        );
+    }
+
+    let signature = Signature::from_der(certificate.signature.raw_bytes())
+        .context("invalid certificate signature")?;
+    verifying_key(issuer)?
+        .verify(&certificate.tbs_certificate.to_der()?, &signature)
+        .with_context(|| {
+            format!(
+                "invalid signature of certificate '{}'",
+                certificate.tbs_certificate.subject
+            )
+        })
+}
+
+/// Ensure that the certificate is not a CA, and, if it states its key usage,
+/// that it is allowed to sign.
+fn check_leaf(leaf: &Certificate) -> anyhow::Result<()> {
+    let subject = &leaf.tbs_certificate.subject;
+    let (basic_constraints, key_usage) = constraints(leaf)?;
+
+    if basic_constraints.is_some_and(|constraints| constraints.ca) {
+        bail!("certificate '{subject}' is a CA, not an end-entity certificate");
+    }
+    if key_usage.is_some_and(|usage| !usage.0.contains(KeyUsages::DigitalSignature)) {
+        bail!("certificate '{subject}' is not allowed to sign");
+    }
+
+    Ok(())
+}
+
+/// Ensure that the certificate is a CA allowed to sign certificates, with the
+/// given number of intermediate certificates below it.
+fn check_issuer(issuer: &Certificate, intermediates: usize) -> anyhow::Result<()> {
+    let subject = &issuer.tbs_certificate.subject;
+    let (basic_constraints, key_usage) = constraints(issuer)?;
+
+    let Some(basic_constraints) = basic_constraints.filter(|constraints| constraints.ca) else {
+        bail!("certificate '{subject}' is not a CA");
+    };
+    if !key_usage.is_some_and(|usage| usage.0.contains(KeyUsages::KeyCertSign)) {
+        bail!("certificate '{subject}' is not allowed to sign certificates");
+    }
+    if let Some(path_len) = basic_constraints.path_len_constraint {
+        if intermediates > usize::from(path_len) {
+            bail!("the chain exceeds the path length constraint of '{subject}'");
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the basic constraints and key usage extensions of a certificate.
+fn constraints(
+    certificate: &Certificate,
+) -> anyhow::Result<(Option<BasicConstraints>, Option<KeyUsage>)> {
+    let mut basic_constraints = None;
+    let mut key_usage = None;
+    for extension in certificate.tbs_certificate.extensions.iter().flatten() {
+        match extension.extn_id {
+            BasicConstraints::OID => {
+                basic_constraints = Some(
+                    BasicConstraints::from_der(extension.extn_value.as_bytes())
+                        .context("unable to parse 'basic constraints' extension")?,
+                )
+            }
+            KeyUsage::OID => {
+                key_usage = Some(
+                    KeyUsage::from_der(extension.extn_value.as_bytes())
+                        .context("unable to parse 'key usage' extension")?,
+                )
+            }
+            _ => {}
+        }
+    }
+
+    Ok((basic_constraints, key_usage))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::time::Duration;
+
+    use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+    use signature::Signer;
+    use x509_cert::{
+        builder::{Builder, CertificateBuilder, Profile},
+        der::DecodePem,
+        spki::{SignatureBitStringEncoding, SubjectPublicKeyInfoOwned},
+        time::Validity,
+    };
+
+    use super::*;
+
+    pub(crate) const ROOT_PEM: &str = include_str!("../../tests/res/mdl/iaca-certificate.pem");
+    const ROOT_KEY_PEM: &str = include_str!("../../tests/res/mdl/iaca-key.pem");
+
+    /// Return the test root and its key.
+    pub(crate) fn root() -> (Certificate, SigningKey) {
+        (
+            Certificate::from_pem(ROOT_PEM).unwrap(),
+            SigningKey::from_pkcs8_pem(ROOT_KEY_PEM).unwrap(),
+        )
+    }
+
+    /// Issue a certificate for a new key, returning both. A CA certificate
+    /// may sign certificates.
+    pub(crate) fn issue(
+        issuer: &Certificate,
+        issuer_key: &SigningKey,
+        subject: &str,
+        ca: bool,
+    ) -> (Certificate, SigningKey) {
+        let key = SigningKey::random(&mut ssi::crypto::rand::thread_rng());
+
+        let mut builder = CertificateBuilder::new(
+            Profile::Manual {
+                issuer: Some(issuer.tbs_certificate.subject.clone()),
+            },
+            1u32.into(),
+            Validity::from_now(Duration::from_secs(60 * 60)).unwrap(),
+            subject.parse().unwrap(),
+            SubjectPublicKeyInfoOwned::from_key(*key.verifying_key()).unwrap(),
+            issuer_key,
+        )
+        .unwrap();
+        if ca {
+            builder
+                .add_extension(&BasicConstraints {
+                    ca: true,
+                    path_len_constraint: None,
+                })
+                .unwrap();
+            builder
+                .add_extension(&KeyUsage(KeyUsages::KeyCertSign.into()))
+                .unwrap();
+        }
+        let signature: Signature = issuer_key.sign(&builder.finalize().unwrap());
+        let certificate = builder
+            .assemble(signature.to_der().to_bitstring().unwrap())
+            .unwrap();
+
+        (certificate, key)
+    }
+
+    /// Return the `x5c` header of a chain.
+    pub(crate) fn x5c(chain: &[Certificate]) -> Json {
+        chain
+            .iter()
+            .map(|certificate| Json::String(BASE64_STANDARD.encode(certificate.to_der().unwrap())))
+            .collect()
+    }
+}
@@ -1,10 +1,69 @@
-pub use mobile_toolkit::storage_manager::{StorageManagerError, StorageManagerInterface};
+use crate::{Key, Value};
+
+pub use mobile_toolkit::storage_manager::{
+    StorageManagerError, StorageManagerInterface, StorageOperation,
+};
+
+/// Apply a batch of operations through the storage manager.
+///
+/// The batch is handed to [StorageManagerInterface::batch] first. If the
+/// storage manager does not support batches, the operations are applied one
+/// by one; should one of them fail, the keys already written are restored to
+/// their previous values on a best-effort basis before returning the error.
+pub async fn apply_batch(
+    storage: &dyn StorageManagerInterface,
+    operations: Vec<StorageOperation>,
+) -> Result<(), StorageManagerError> {
+    if operations.is_empty() {
+        return Ok(());
+    }
+
+    match storage.batch(operations.clone()).await {
+        Err(StorageManagerError::Unsupported) => (),
+        result => return result,
+    }
+
+    let mut applied: Vec<(Key, Option<Value>)> = Vec::with_capacity(operations.len());
+
+    for operation in operations {
+        let key = match &operation {
+            StorageOperation::Add { key, .. } | StorageOperation::Remove { key } => key.clone(),
+        };
+
+        let result = match storage.get(key.clone()).await {
+            Ok(previous) => {
+                applied.push((key, previous));
+                match operation {
+                    StorageOperation::Add { key, value } => storage.add(key, value).await,
+                    StorageOperation::Remove { key } => storage.remove(key).await,
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            for (key, previous) in applied.into_iter().rev() {
+                let restored = match previous {
+                    Some(value) => storage.add(key.clone(), value).await,
+                    None => storage.remove(key.clone()).await,
+                };
+                if let Err(restore_error) = restored {
+                    tracing::warn!("failed to roll back {key:?}: {restore_error}");
+                }
+            }
+
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
 
 #[cfg(test)]
 pub mod test {
     use async_trait::async_trait;
     use mobile_toolkit::{
-        storage_manager::{StorageManagerError, StorageManagerInterface},
+        storage_manager::{StorageManagerError, StorageManagerInterface, StorageOperation},
         Key, Value,
     };
 
@@ -58,6 +117,29 @@ pub mod test {
 
             Ok(())
         }
+
+        async fn batch(
+            &self,
+            operations: Vec<StorageOperation>,
+        ) -> Result<(), StorageManagerError> {
+            let mut inner = self
+                .0
+                .try_write()
+                .map_err(|_| StorageManagerError::InternalError)?;
+
+            for operation in operations {
+                match operation {
+                    StorageOperation::Add { key, value } => {
+                        inner.insert(key, value);
+                    }
+                    StorageOperation::Remove { key } => {
+                        inner.remove(&key);
+                    }
+                }
+            }
+
+            Ok(())
+        }
     }
 
     /// Dummy Storage Implementation for testing with support for user namespaces
@@ -104,5 +186,110 @@ pub mod test {
         async fn remove(&self, key: Key) -> Result<(), StorageManagerError> {
             self.inner.remove(self.namespaced_key(&key)).await
         }
+
+        async fn batch(
+            &self,
+            operations: Vec<StorageOperation>,
+        ) -> Result<(), StorageManagerError> {
+            let operations = operations
+                .into_iter()
+                .map(|operation| match operation {
+                    StorageOperation::Add { key, value } => StorageOperation::Add {
+                        key: self.namespaced_key(&key),
+                        value,
+                    },
+                    StorageOperation::Remove { key } => StorageOperation::Remove {
+                        key: self.namespaced_key(&key),
+                    },
+                })
+                .collect();
+
+            self.inner.batch(operations).await
+        }
+    }
+
+    /// Storage without batch support, failing writes to a single key.
+    #[derive(Debug, Default)]
+    struct FailingStorage {
+        inner: DummyStorage,
+        failing_key: Option<Key>,
+    }
+
+    #[async_trait]
+    impl StorageManagerInterface for FailingStorage {
+        async fn add(&self, key: Key, value: Value) -> Result<(), StorageManagerError> {
+            if self.failing_key.as_ref() == Some(&key) {
+                return Err(StorageManagerError::StorageFull);
+            }
+            self.inner.add(key, value).await
+        }
+
+        async fn get(&self, key: Key) -> Result<Option<Value>, StorageManagerError> {
+            self.inner.get(key).await
+        }
+
+        async fn list(&self) -> Result<Vec<Key>, StorageManagerError> {
+            self.inner.list().await
+        }
+
+        async fn remove(&self, key: Key) -> Result<(), StorageManagerError> {
+            self.inner.remove(key).await
+        }
+
+        async fn batch(
+            &self,
+            _operations: Vec<StorageOperation>,
+        ) -> Result<(), StorageManagerError> {
+            Err(StorageManagerError::Unsupported)
+        }
+    }
+
+    #[tokio::test]
+    async fn apply_batch_falls_back_and_rolls_back() {
+        let storage = FailingStorage {
+            failing_key: Some(Key("c".into())),
+            ..Default::default()
+        };
+        storage.add(Key("a".into()), Value(vec![1])).await.unwrap();
+        storage.add(Key("b".into()), Value(vec![2])).await.unwrap();
+
+        let result = super::apply_batch(
+            &storage,
+            vec![
+                StorageOperation::Add {
+                    key: Key("a".into()),
+                    value: Value(vec![10]),
+                },
+                StorageOperation::Remove {
+                    key: Key("b".into()),
+                },
+                StorageOperation::Add {
+                    key: Key("c".into()),
+                    value: Value(vec![30]),
+                },
+            ],
+        )
+        .await;
+
+        assert!(matches!(result, Err(StorageManagerError::StorageFull)));
+        assert_eq!(
+            storage.get(Key("a".into())).await.unwrap(),
+            Some(Value(vec![1]))
+        );
+        assert_eq!(
+            storage.get(Key("b".into())).await.unwrap(),
+            Some(Value(vec![2]))
+        );
+        assert_eq!(storage.get(Key("c".into())).await.unwrap(), None);
+
+        super::apply_batch(
+            &storage,
+            vec![StorageOperation::Remove {
+                key: Key("a".into()),
+            }],
+        )
+        .await
+        .unwrap();
+        assert_eq!(storage.get(Key("a".into())).await.unwrap(), None);
     }
 }
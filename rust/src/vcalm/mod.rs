@@ -1,12 +1,12 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use vcalm_rs::ports::StoredCredential;
 
-use crate::credential::ParsedCredential;
+use crate::credential::{activity_log, ParsedCredential};
 use crate::oid4vp::presentation::PresentationSigner;
 use crate::vcalm_adapters::{json_ld_body, SdkCredential, VcalmSignerAdapter};
-use crate::vdc_collection::VdcCollection;
+use crate::vdc_collection::{ActivityRecorder, VdcCollection};
 
 pub mod wire;
 
@@ -26,7 +26,12 @@ fn to_stored(credential: Arc<ParsedCredential>) -> StoredCredential<SdkCredentia
 }
 
 #[derive(uniffi::Object)]
-pub struct VcalmHolder(Arc<vcalm_rs::holder::VcalmHolder<SdkCredential>>);
+pub struct VcalmHolder {
+    inner: Arc<vcalm_rs::holder::VcalmHolder<SdkCredential>>,
+    activity_recorder: Arc<ActivityRecorder>,
+    /// The verifier domain of the current presentation request.
+    domain: Mutex<Option<String>>,
+}
 
 #[uniffi::export(async_runtime = "tokio")]
 impl VcalmHolder {
@@ -42,6 +47,7 @@ impl VcalmHolder {
             _ => crate::context::default_ld_json_context(),
         });
 
+        let activity_recorder = vdc_collection.clone().activity_recorder();
         let inner = vcalm_rs::holder::VcalmHolder::new_session(
             vdc_collection,
             trusted_dids,
@@ -50,12 +56,16 @@ impl VcalmHolder {
         )
         .await?;
 
-        Ok(Arc::new(Self(inner)))
+        Ok(Arc::new(Self {
+            inner,
+            activity_recorder,
+            domain: Mutex::new(None),
+        }))
     }
 
     /// Seed the QBE matcher with credentials from the host app's wallet packs.
     pub async fn provide_credentials(&self, credentials: Vec<Arc<ParsedCredential>>) {
-        self.0
+        self.inner
             .provide_credentials(credentials.into_iter().map(to_stored).collect())
             .await
     }
@@ -66,17 +76,18 @@ impl VcalmHolder {
         input: String,
         auth_header: Option<String>,
     ) -> Result<StepResult, VcalmError> {
-        Ok(self
-            .0
+        let step = self
+            .inner
             .clone()
             .start_exchange(input, auth_header)
             .await?
-            .into())
+            .into();
+        Ok(self.track(step))
     }
 
     pub async fn matched_credentials(&self) -> Result<Vec<VcalmMatchedCredentials>, VcalmError> {
         Ok(self
-            .0
+            .inner
             .matched_credentials()
             .await?
             .into_iter()
@@ -96,7 +107,7 @@ impl VcalmHolder {
 
     pub async fn requested_fields(&self) -> Result<Vec<VcalmRequestedField>, VcalmError> {
         Ok(self
-            .0
+            .inner
             .requested_fields()
             .await?
             .into_iter()
@@ -110,28 +121,59 @@ impl VcalmHolder {
         selected_credentials: Vec<Arc<ParsedCredential>>,
         allow_domain_mismatch: bool,
     ) -> Result<StepResult, VcalmError> {
-        Ok(self
-            .0
+        // Captured before submitting, as the request is consumed by it. Each
+        // credential discloses the fields requested by the queries it matches.
+        let requested_fields = self.requested_fields().await.unwrap_or_default();
+        let matched = self.matched_credentials().await.unwrap_or_default();
+        let shared = selected_credentials
+            .iter()
+            .map(|credential| {
+                let id = credential.id();
+                let query_indices = matched
+                    .iter()
+                    .filter(|m| m.credentials.iter().any(|c| c.credential.id() == id))
+                    .map(|m| m.query_index)
+                    .collect::<Vec<_>>();
+                let fields = requested_fields
+                    .iter()
+                    .filter(|field| query_indices.contains(&field.query_index))
+                    .map(|field| activity_log::field_path(field.path.split('.')))
+                    .collect();
+                (id, fields)
+            })
+            .collect();
+        let domain = self.domain.lock().unwrap().clone();
+
+        let step = self
+            .inner
             .clone()
             .submit_presentation(
                 selected_credentials.into_iter().map(to_stored).collect(),
                 allow_domain_mismatch,
             )
             .await?
-            .into())
+            .into();
+
+        self.activity_recorder
+            .record_shared(domain.as_deref(), None, shared)
+            .await;
+
+        Ok(self.track(step))
     }
 
     pub async fn accept_offer(self: Arc<Self>) -> Result<StepResult, VcalmError> {
-        Ok(self.0.clone().accept_offer().await?.into())
+        let step = self.inner.clone().accept_offer().await?.into();
+        Ok(self.track(step))
     }
 
     pub async fn reject_offer(self: Arc<Self>) -> Result<StepResult, VcalmError> {
-        Ok(self.0.clone().reject_offer().await?.into())
+        let step = self.inner.clone().reject_offer().await?.into();
+        Ok(self.track(step))
     }
 
     pub async fn offered_credentials(&self) -> Result<Vec<VcalmOfferedCredential>, VcalmError> {
         Ok(self
-            .0
+            .inner
             .offered_credentials()
             .await?
             .into_iter()
@@ -140,6 +182,22 @@ impl VcalmHolder {
     }
 }
 
+impl VcalmHolder {
+    /// Remember the verifier domain of the next presentation request, for
+    /// the activity log.
+    fn track(&self, step: StepResult) -> StepResult {
+        let vpr = match &step {
+            StepResult::Request { vpr } => Some(vpr),
+            StepResult::Offer { next_vpr, .. } => next_vpr.as_ref(),
+            _ => None,
+        };
+        if let Some(vpr) = vpr {
+            *self.domain.lock().unwrap() = vpr.domain.clone();
+        }
+        step
+    }
+}
+
 /// The credentials matching one QueryByExample query in the current VPR.
 #[derive(uniffi::Record)]
 pub struct VcalmMatchedCredentials {
@@ -0,0 +1,510 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use super::{
+    recorder, retention, CredentialIndexEntry, RetentionPolicy, VdcCollection, VdcCollectionError,
+    VdcCollectionEvent,
+};
+use crate::common::*;
+use crate::credential::{
+    activity_log::{self, ActivityLogEntry, KEY_PREFIX as ACTIVITY_LOG_KEY_PREFIX},
+    Credential,
+};
+use crate::storage_manager::StorageOperation;
+
+/// Current version of the backup archive format.
+const BACKUP_VERSION: u32 = 1;
+
+/// Number of PBKDF2-HMAC-SHA256 iterations used to derive the backup key.
+const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// Maximum number of PBKDF2 iterations accepted when importing an archive, so
+/// that a crafted header cannot stall the key derivation.
+const MAX_PBKDF2_ITERATIONS: u32 = 10 * PBKDF2_ITERATIONS;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Result of importing a wallet backup.
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct BackupImportReport {
+    /// Credentials that were imported into the collection.
+    pub imported: Vec<Uuid>,
+    /// Credentials that were skipped because they already exist in the
+    /// collection, either under the same ID or with an identical payload.
+    pub duplicates: Vec<Uuid>,
+    /// Imported credentials bound to a device key (`key_alias`). Device keys
+    /// are hardware-backed and never leave the original device, so these
+    /// credentials cannot be presented until they are re-issued.
+    pub missing_keys: Vec<Uuid>,
+    /// Number of activity log entries imported.
+    pub activity_log_entries: u32,
+}
+
+/// Unencrypted header of the archive. It is authenticated as associated data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BackupHeader {
+    version: u32,
+    #[serde(with = "serde_bytes")]
+    salt: Vec<u8>,
+    iterations: u32,
+}
+
+/// The archive as written to bytes.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupArchive {
+    header: BackupHeader,
+    #[serde(with = "serde_bytes")]
+    nonce: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    ciphertext: Vec<u8>,
+}
+
+/// The encrypted contents of the archive.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupContents {
+    /// Creation time, as a UNIX timestamp.
+    created_at: u64,
+    credentials: Vec<Credential>,
+    index: Vec<CredentialIndexEntry>,
+    activity_log: Vec<ActivityLogEntry>,
+    /// The retention policy, if one was set.
+    #[serde(default)]
+    retention_policy: Option<RetentionPolicy>,
+}
+
+#[uniffi::export]
+impl VdcCollection {
+    /// Export all credentials, their activity logs and their metadata, along
+    /// with the retention policy, as a single versioned archive, encrypted and
+    /// integrity-protected with a key derived from `passphrase`.
+    ///
+    /// Only the profile of this collection is exported: the other profiles
+    /// are exported from their own collection, see
+    /// [VdcCollection::with_profile]. The activity log hash chain is not
+    /// exported either, as the imported entries are linked to the chain of
+    /// the importing wallet, if enabled: its head and signed checkpoint only
+    /// vouch for the storage they were written to.
+    pub async fn export_backup(&self, passphrase: String) -> Result<Vec<u8>, VdcCollectionError> {
+        self.export_backup_with_iterations(passphrase, PBKDF2_ITERATIONS)
+            .await
+    }
+
+    /// Import an archive produced by [VdcCollection::export_backup].
+    ///
+    /// Credentials already present in the collection are skipped, and
+    /// everything else is written as a single atomic batch. The retention
+    /// policy is only imported if none is set in the collection.
+    ///
+    /// The activity log entries are linked anew to the hash chain of the
+    /// collection, if enabled, from the oldest.
+    pub async fn import_backup(
+        &self,
+        bytes: Vec<u8>,
+        passphrase: String,
+    ) -> Result<BackupImportReport, VdcCollectionError> {
+        self.import_backup_with_min_iterations(bytes, passphrase, PBKDF2_ITERATIONS)
+            .await
+    }
+}
+
+impl VdcCollection {
+    pub(crate) async fn import_backup_with_min_iterations(
+        &self,
+        bytes: Vec<u8>,
+        passphrase: String,
+        min_iterations: u32,
+    ) -> Result<BackupImportReport, VdcCollectionError> {
+        let archive: BackupArchive = serde_cbor::from_slice(&bytes)
+            .map_err(|e| VdcCollectionError::InvalidBackup(e.to_string()))?;
+
+        if archive.header.version != BACKUP_VERSION {
+            return Err(VdcCollectionError::InvalidBackup(format!(
+                "unsupported backup version: {}",
+                archive.header.version
+            )));
+        }
+
+        if archive.nonce.len() != NONCE_LEN {
+            return Err(VdcCollectionError::InvalidBackup(
+                "invalid nonce length".into(),
+            ));
+        }
+
+        // The iterations come from the untrusted archive, and are checked
+        // before deriving the key.
+        if !(min_iterations..=MAX_PBKDF2_ITERATIONS).contains(&archive.header.iterations) {
+            return Err(VdcCollectionError::InvalidBackup(format!(
+                "unsupported number of key derivation iterations: {}",
+                archive.header.iterations
+            )));
+        }
+
+        let cipher = backup_cipher(&passphrase, &archive.header)?;
+        let aad =
+            serde_cbor::to_vec(&archive.header).map_err(|_| VdcCollectionError::SerializeFailed)?;
+
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&archive.nonce),
+                Payload {
+                    msg: &archive.ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| VdcCollectionError::BackupDecryptionFailed)?;
+
+        let contents: BackupContents = serde_cbor::from_slice(&plaintext)
+            .map_err(|e| VdcCollectionError::InvalidBackup(e.to_string()))?;
+
+        // Make sure the index exists, so that imported metadata is persisted.
+        self.index_entries().await?;
+
+        // Identify existing credentials by ID and by payload.
+        let existing_ids: HashSet<Uuid> = self.all_entries().await?.into_iter().collect();
+        let mut existing_payloads = HashSet::new();
+        for id in existing_ids.iter() {
+            if let Ok(Some(credential)) = self.get(*id).await {
+                existing_payloads.insert(credential.payload);
+            }
+        }
+
+        let mut report = BackupImportReport::default();
+        let mut operations = Vec::new();
+        let mut index_entries = Vec::new();
+        let mut events = Vec::new();
+
+        for credential in contents.credentials {
+            if existing_ids.contains(&credential.id)
+                || !existing_payloads.insert(credential.payload.clone())
+            {
+                report.duplicates.push(credential.id);
+                continue;
+            }
+
+            operations.push(StorageOperation::Add {
+                key: Self::id_to_key(credential.id),
+                value: Value(super::record::encode(&credential)?),
+            });
+
+            let mut entry = CredentialIndexEntry::from_credential(&credential);
+            if let Some(backed_up) = contents.index.iter().find(|e| e.id == credential.id) {
+                entry.metadata = backed_up.metadata.clone();
+            }
+            index_entries.push(entry);
+
+            events.push(VdcCollectionEvent::Added {
+                id: credential.id,
+                format: credential.format.clone(),
+            });
+
+            if credential.key_alias.is_some() {
+                report.missing_keys.push(credential.id);
+            }
+            report.imported.push(credential.id);
+        }
+
+        let mut log_entries = contents
+            .activity_log
+            .into_iter()
+            .filter(|entry| report.imported.contains(&entry.credential_id()))
+            .collect::<Vec<_>>();
+        // The entries were chained in the source wallet, if at all.
+        log_entries.iter_mut().for_each(ActivityLogEntry::unchain);
+        log_entries.sort_by_key(|entry| (entry.timestamp(), entry.get_id()));
+        report.activity_log_entries = log_entries.len() as u32;
+
+        if let Some(policy) = contents.retention_policy {
+            if self.stored_retention_policy().await?.is_none() {
+                operations.push(retention::retention_policy_operation(&policy)?);
+            }
+        }
+
+        let _head = activity_log::lock_chain_head(&self.storage).await;
+        if !log_entries.is_empty() {
+            operations
+                .extend(recorder::entry_operations(self.storage.as_ref(), log_entries).await?);
+        }
+        events.extend(VdcCollectionEvent::logged_by(&operations));
+
+        self.commit(
+            operations,
+            |index| {
+                for entry in index_entries {
+                    index.entries.insert(entry.id, entry);
+                }
+            },
+            VdcCollectionError::StoreFailed,
+        )
+        .await?;
+
+        self.storage.notify(events);
+
+        Ok(report)
+    }
+
+    pub(crate) async fn export_backup_with_iterations(
+        &self,
+        passphrase: String,
+        iterations: u32,
+    ) -> Result<Vec<u8>, VdcCollectionError> {
+        let mut credentials = Vec::new();
+        for id in self.all_entries().await? {
+            if let Some(credential) = self.get(id).await? {
+                credentials.push(credential);
+            }
+        }
+
+        let index = self.index_entries().await?;
+
+        let activity_log = self.activity_log_entries().await?;
+        let retention_policy = self.stored_retention_policy().await?;
+
+        let contents = BackupContents {
+            created_at: chrono::Utc::now().timestamp() as u64,
+            credentials,
+            index,
+            activity_log,
+            retention_policy,
+        };
+        let plaintext =
+            serde_cbor::to_vec(&contents).map_err(|_| VdcCollectionError::SerializeFailed)?;
+
+        let header = BackupHeader {
+            version: BACKUP_VERSION,
+            salt: rand::random::<[u8; SALT_LEN]>().to_vec(),
+            iterations,
+        };
+        let aad = serde_cbor::to_vec(&header).map_err(|_| VdcCollectionError::SerializeFailed)?;
+
+        let cipher = backup_cipher(&passphrase, &header)?;
+        let nonce = rand::random::<[u8; NONCE_LEN]>();
+
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| VdcCollectionError::SerializeFailed)?;
+
+        serde_cbor::to_vec(&BackupArchive {
+            header,
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+        .map_err(|_| VdcCollectionError::SerializeFailed)
+    }
+
+    /// Load every activity log entry in the storage.
+    pub(super) async fn activity_log_entries(
+        &self,
+    ) -> Result<Vec<ActivityLogEntry>, VdcCollectionError> {
+        let keys = self
+            .storage
+            .list()
+            .await
+            .map_err(VdcCollectionError::LoadFailed)?
+            .into_iter()
+            .filter(|key| key.strip_prefix(ACTIVITY_LOG_KEY_PREFIX).is_some());
+
+        let mut entries = Vec::new();
+        for key in keys {
+            let value = self
+                .storage
+                .get(key)
+                .await
+                .map_err(VdcCollectionError::LoadFailed)?;
+
+            if let Some(entry) = value.and_then(|v| ActivityLogEntry::try_from(v).ok()) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Derive the archive cipher from the passphrase and the archive header.
+fn backup_cipher(passphrase: &str, header: &BackupHeader) -> Result<Aes256Gcm, VdcCollectionError> {
+    if passphrase.is_empty() {
+        return Err(VdcCollectionError::InvalidBackup(
+            "passphrase must not be empty".into(),
+        ));
+    }
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(
+        passphrase.as_bytes(),
+        &header.salt,
+        header.iterations,
+        key.as_mut_slice(),
+    );
+
+    Aes256Gcm::new_from_slice(key.as_slice()).map_err(|_| VdcCollectionError::SerializeFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        credential::{
+            activity_log::{ActivityLog, ActivityLogEntryType},
+            CredentialFormat,
+        },
+        crypto::{KeyAlias, KeyStore, RustTestKeyManager},
+        local_store::LocalStore,
+        storage_manager::StorageManagerInterface,
+    };
+
+    fn credential(payload: &str, key_alias: Option<KeyAlias>) -> Credential {
+        Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: payload.into(),
+            key_alias,
+        }
+    }
+
+    #[tokio::test]
+    async fn backup_roundtrip() {
+        let source_storage: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let source = VdcCollection::new(source_storage.clone());
+
+        let bound = credential("bound", Some(KeyAlias("device-key".into())));
+        let unbound = credential("unbound", None);
+        source.add(&bound).await.unwrap();
+        source.add(&unbound).await.unwrap();
+        source
+            .set_tags(unbound.id, vec!["favourite".into()])
+            .await
+            .unwrap();
+
+        let policy = RetentionPolicy {
+            purge_transcripts_after_days: Some(30),
+            ..Default::default()
+        };
+        source.set_retention_policy(policy.clone()).await.unwrap();
+
+        let log = ActivityLog::load(bound.id, source_storage).await.unwrap();
+        log.enable_chain().await.unwrap();
+        log.add(Arc::new(
+            ActivityLogEntry::new(
+                bound.id,
+                ActivityLogEntryType::Issued,
+                "Credential issued".into(),
+                "ACME Corp".into(),
+                None,
+                None,
+            )
+            .unwrap(),
+        ))
+        .await
+        .unwrap();
+
+        let backup = source
+            .export_backup_with_iterations("correct horse".into(), 1_000)
+            .await
+            .unwrap();
+
+        let target_storage: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let target = VdcCollection::new(target_storage.clone());
+        let target_log = target.activity_log(bound.id).await.unwrap();
+        target_log.enable_chain().await.unwrap();
+
+        assert!(matches!(
+            target
+                .import_backup_with_min_iterations(backup.clone(), "wrong horse".into(), 1_000)
+                .await,
+            Err(VdcCollectionError::BackupDecryptionFailed)
+        ));
+
+        let mut tampered = backup.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(target
+            .import_backup_with_min_iterations(tampered, "correct horse".into(), 1_000)
+            .await
+            .is_err());
+
+        // Pre-existing copy of the unbound credential under another ID.
+        let existing = credential("unbound", None);
+        target.add(&existing).await.unwrap();
+
+        let report = target
+            .import_backup_with_min_iterations(backup, "correct horse".into(), 1_000)
+            .await
+            .unwrap();
+
+        assert_eq!(report.imported, vec![bound.id]);
+        assert_eq!(report.duplicates, vec![unbound.id]);
+        assert_eq!(report.missing_keys, vec![bound.id]);
+        assert_eq!(report.activity_log_entries, 1);
+
+        assert_eq!(
+            target.get(bound.id).await.unwrap().unwrap().payload,
+            bound.payload
+        );
+        assert_eq!(target_log.entries(None).await.unwrap().len(), 1);
+        assert_eq!(target.retention_policy().await.unwrap(), policy);
+
+        // The imported entries are linked to the chain of the target.
+        let key_manager = RustTestKeyManager::default();
+        let alias = KeyAlias("activity_log".into());
+        key_manager
+            .generate_p256_signing_key(alias.clone())
+            .await
+            .unwrap();
+        let signer = key_manager.get_signing_key(alias).unwrap();
+        target_log.sign_chain_head(signer.clone()).await.unwrap();
+        let report = target
+            .verify_activity_log_chain(signer.jwk().unwrap())
+            .await
+            .unwrap();
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!(report.length, 1);
+    }
+
+    #[tokio::test]
+    async fn backup_iterations_bounds() {
+        let storage: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let collection = VdcCollection::new(storage);
+        collection.add(&credential("unbound", None)).await.unwrap();
+
+        let backup = collection
+            .export_backup_with_iterations("correct horse".into(), 1_000)
+            .await
+            .unwrap();
+
+        // Too few iterations to protect the passphrase.
+        assert!(matches!(
+            collection
+                .import_backup(backup.clone(), "correct horse".into())
+                .await,
+            Err(VdcCollectionError::InvalidBackup(_))
+        ));
+
+        // Too many iterations, rejected before deriving the key.
+        let mut archive: BackupArchive = serde_cbor::from_slice(&backup).unwrap();
+        archive.header.iterations = MAX_PBKDF2_ITERATIONS + 1;
+        let oversized = serde_cbor::to_vec(&archive).unwrap();
+        assert!(matches!(
+            collection
+                .import_backup_with_min_iterations(oversized, "correct horse".into(), 1_000)
+                .await,
+            Err(VdcCollectionError::InvalidBackup(_))
+        ));
+    }
+}
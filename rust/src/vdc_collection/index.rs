@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::common::*;
+use crate::credential::{Credential, CredentialFormat, ParsedCredentialInner};
+use crate::crypto::KeyAlias;
+
+/// Storage key of the secondary index, stored next to the `Credential.` keys.
+pub(crate) const INDEX_KEY: &str = "CredentialIndex";
+
+/// Indexed metadata for a single credential.
+///
+/// Entries are derived from the credential when it is added to the
+/// [VdcCollection](super::VdcCollection), so that lookups never need to
+/// deserialize credential payloads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct CredentialIndexEntry {
+    /// The local ID of the credential.
+    pub id: Uuid,
+    /// The format of the credential.
+    pub format: CredentialFormat,
+    /// The type of the credential (doctype, vct or VC type).
+    pub r#type: CredentialType,
+    /// The issuer identifier, if it could be determined from the credential.
+    pub issuer: Option<String>,
+    /// Start of the validity window, as a UNIX timestamp.
+    pub valid_from: Option<u64>,
+    /// End of the validity window, as a UNIX timestamp.
+    pub valid_until: Option<u64>,
+    /// The alias of the key bound to the credential.
+    pub key_alias: Option<KeyAlias>,
+    /// User-defined display metadata.
+    #[serde(default)]
+    pub metadata: CredentialMetadata,
+}
+
+/// User-defined display metadata of a credential, shared by all the apps of
+/// the wallet.
+///
+/// It is stored in the index, alongside the metadata derived from the
+/// credential, and is preserved when the credential is replaced.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[serde(default)]
+pub struct CredentialMetadata {
+    /// A name given to the credential by the user.
+    pub nickname: Option<String>,
+    /// Position of the credential when displayed, in ascending order.
+    pub order: Option<u32>,
+    /// Display colour, e.g. `#0055FF`.
+    pub color: Option<String>,
+    /// Whether the user marked the credential as a favourite.
+    pub favourite: bool,
+    /// Whether the user hid the credential.
+    pub hidden: bool,
+    /// User-defined tags.
+    pub tags: Vec<String>,
+}
+
+impl CredentialIndexEntry {
+    /// Build an index entry for a credential.
+    ///
+    /// Fields that require parsing the payload are left empty if the
+    /// credential cannot be parsed.
+    pub(crate) fn from_credential(credential: &Credential) -> Self {
+        let claims = credential
+            .try_into_parsed()
+            .ok()
+            .and_then(|parsed| summary_claims(&parsed.inner));
+
+        let (issuer, valid_from, valid_until) = match claims {
+            Some(claims) => (
+                issuer_from_claims(&claims),
+                timestamp_from_claims(&claims, VALID_FROM_CLAIMS),
+                timestamp_from_claims(&claims, VALID_UNTIL_CLAIMS),
+            ),
+            None => (None, None, None),
+        };
+
+        Self {
+            id: credential.id,
+            format: credential.format.clone(),
+            r#type: credential.r#type.clone(),
+            issuer,
+            valid_from,
+            valid_until,
+            key_alias: credential.key_alias.clone(),
+            metadata: CredentialMetadata::default(),
+        }
+    }
+}
+
+/// Options used to filter the credentials returned by
+/// [VdcCollection::query](super::VdcCollection::query).
+///
+/// All provided options must match for a credential to be returned.
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct CredentialFilter {
+    /// Only return credentials of this format.
+    pub format: Option<CredentialFormat>,
+    /// Only return credentials of this type.
+    pub r#type: Option<CredentialType>,
+    /// Only return credentials issued by this issuer.
+    pub issuer: Option<String>,
+    /// Only return credentials bound to this key alias.
+    pub key_alias: Option<KeyAlias>,
+    /// Only return credentials that are valid at this UNIX timestamp.
+    pub valid_at: Option<u64>,
+    /// Only return credentials that expire before this UNIX timestamp.
+    pub expires_before: Option<u64>,
+    /// Only return credentials carrying all of these tags.
+    pub tags: Vec<String>,
+    /// Only return credentials that are, or are not, favourites.
+    pub favourite: Option<bool>,
+    /// Only return credentials that are, or are not, hidden.
+    pub hidden: Option<bool>,
+}
+
+impl CredentialFilter {
+    /// Returns false when the entry should be filtered out.
+    pub(crate) fn matches(&self, entry: &CredentialIndexEntry) -> bool {
+        if self.format.as_ref().is_some_and(|f| *f != entry.format) {
+            return false;
+        }
+
+        if self.r#type.as_ref().is_some_and(|t| *t != entry.r#type) {
+            return false;
+        }
+
+        if self
+            .issuer
+            .as_ref()
+            .is_some_and(|issuer| entry.issuer.as_ref() != Some(issuer))
+        {
+            return false;
+        }
+
+        if self
+            .key_alias
+            .as_ref()
+            .is_some_and(|alias| entry.key_alias.as_ref() != Some(alias))
+        {
+            return false;
+        }
+
+        if let Some(at) = self.valid_at {
+            if entry.valid_from.is_some_and(|from| from > at)
+                || entry.valid_until.is_some_and(|until| until < at)
+            {
+                return false;
+            }
+        }
+
+        if let Some(before) = self.expires_before {
+            if entry.valid_until.is_none_or(|until| until >= before) {
+                return false;
+            }
+        }
+
+        if self
+            .favourite
+            .is_some_and(|favourite| favourite != entry.metadata.favourite)
+        {
+            return false;
+        }
+
+        if self
+            .hidden
+            .is_some_and(|hidden| hidden != entry.metadata.hidden)
+        {
+            return false;
+        }
+
+        self.tags
+            .iter()
+            .all(|tag| entry.metadata.tags.contains(tag))
+    }
+}
+
+/// The persisted form of the secondary index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CredentialIndex {
+    pub(crate) entries: BTreeMap<Uuid, CredentialIndexEntry>,
+}
+
+impl CredentialIndex {
+    pub(crate) fn from_slice(bytes: &[u8]) -> Option<Self> {
+        serde_cbor::from_slice(bytes).ok()
+    }
+
+    pub(crate) fn to_vec(&self) -> Option<Vec<u8>> {
+        serde_cbor::to_vec(self).ok()
+    }
+}
+
+/// Claim names, across formats, holding the start of the validity window.
+const VALID_FROM_CLAIMS: &[&str] = &["nbf", "validFrom", "issuanceDate", "Not Before"];
+
+/// Claim names, across formats, holding the end of the validity window.
+const VALID_UNTIL_CLAIMS: &[&str] = &["exp", "validUntil", "expirationDate", "Expires"];
+
+/// Return a JSON view of the credential claims that carry issuer and validity
+/// information.
+fn summary_claims(inner: &ParsedCredentialInner) -> Option<serde_json::Value> {
+    match inner {
+        ParsedCredentialInner::MsoMdoc(mdoc) => {
+            let validity = &mdoc.document().mso.validity_info;
+            Some(serde_json::json!({
+                "nbf": validity.valid_from.unix_timestamp(),
+                "exp": validity.valid_until.unix_timestamp(),
+            }))
+        }
+        ParsedCredentialInner::JwtVcJson(vc) | ParsedCredentialInner::JwtVcJsonLd(vc) => {
+            serde_json::from_str(&vc.jws_payload_as_json_encoded_utf8_string()).ok()
+        }
+        ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => sd_jwt.revealed_claims_as_json().ok(),
+        ParsedCredentialInner::DcSdJwt(sd_jwt) => sd_jwt.revealed_claims_as_json().ok(),
+        ParsedCredentialInner::LdpVc(vc) => {
+            serde_json::from_str(&vc.credential_as_json_encoded_utf8_string()).ok()
+        }
+        ParsedCredentialInner::Cwt(cwt) => cwt
+            .claims_json()
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        ParsedCredentialInner::OpticalBarcodeCredential(cred) => {
+            serde_json::from_str(&cred.raw_jsonld()).ok()
+        }
+    }
+}
+
+fn issuer_from_claims(claims: &serde_json::Value) -> Option<String> {
+    ["iss", "issuer", "Issuer"]
+        .iter()
+        .filter_map(|name| claims.get(name))
+        .find_map(|value| match value {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Object(o) => o.get("id").and_then(|id| id.as_str()).map(Into::into),
+            _ => None,
+        })
+}
+
+fn timestamp_from_claims(claims: &serde_json::Value, names: &[&str]) -> Option<u64> {
+    names
+        .iter()
+        .filter_map(|name| claims.get(name))
+        .find_map(|value| match value {
+            serde_json::Value::Number(n) => n.as_u64(),
+            serde_json::Value::String(s) => chrono::DateTime::parse_from_rfc3339(s)
+                .ok()
+                .and_then(|dt| u64::try_from(dt.timestamp()).ok())
+                .or_else(|| s.parse().ok()),
+            _ => None,
+        })
+}
@@ -0,0 +1,1107 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use crate::common::*;
+use crate::credential::{
+    activity_log::{self, ActivityLogEntry, KEY_PREFIX as ACTIVITY_LOG_KEY_PREFIX},
+    Credential,
+};
+use crate::storage_manager::*;
+
+use thiserror::Error;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+mod backup;
+mod index;
+mod observer;
+mod profile;
+mod record;
+mod recorder;
+mod retention;
+mod scope;
+
+pub use backup::*;
+pub use index::*;
+pub use observer::{VdcCollectionEvent, VdcCollectionObserver};
+pub use profile::Profile;
+pub(crate) use profile::ProfileStorage;
+pub use record::{CorruptedRecord, IntegrityReport};
+pub use recorder::{ActivityRecorder, UNKNOWN_ISSUER, UNKNOWN_VERIFIER};
+pub use retention::*;
+
+use record::DecodedRecord;
+
+/// Internal prefix for credential keys.
+const KEY_PREFIX: &str = "Credential.";
+
+#[derive(uniffi::Object)]
+/// Verifiable Digital Credential Collection
+///
+/// This is the main interface to credentials.
+#[derive(Debug)]
+pub struct VdcCollection {
+    /// The storage, scoped to the profile of the collection.
+    storage: Arc<ProfileStorage>,
+    /// The unscoped storage, holding every profile.
+    root: Arc<dyn StorageManagerInterface>,
+    profile: Option<Uuid>,
+    /// Whether activity log entries are recorded automatically.
+    record_activity: AtomicBool,
+}
+
+#[derive(Error, Debug, uniffi::Error)]
+pub enum VdcCollectionError {
+    /// Attempt to convert the credential to a serialized form suitable for writing to storage failed.
+    #[error("Failed to Serialize Value")]
+    SerializeFailed,
+
+    /// Attempting to convert the credential to a deserialized form suitable for runtime use failed.
+    #[error("Failed to Deserialize Value")]
+    DeserializeFailed,
+
+    /// Attempting to write the credential to storage failed.
+    #[error("Failed to Write to Storage")]
+    StoreFailed(StorageManagerError),
+
+    /// Attempting to read the credential from storage failed.
+    #[error("Failed to Read from Storage")]
+    LoadFailed(StorageManagerError),
+
+    /// Attempting to delete a credential from storage failed.
+    #[error("Failed to Delete from Storage")]
+    DeleteFailed(StorageManagerError),
+
+    /// The backup archive is malformed, or of an unsupported version.
+    #[error("Invalid Backup: {0}")]
+    InvalidBackup(String),
+
+    /// The backup archive could not be decrypted: either the passphrase is
+    /// wrong, or the archive was modified.
+    #[error("Failed to Decrypt Backup")]
+    BackupDecryptionFailed,
+
+    /// The activity log entry does not belong to the credential being stored.
+    #[error("Activity Log Entry Does Not Belong to Credential")]
+    ActivityLogEntryMismatch,
+
+    /// The requested credential does not exist in the collection.
+    #[error("Credential Not Found")]
+    NotFound,
+
+    /// The stored record was written by a newer version of the SDK.
+    #[error("Unsupported Record Version: {0}")]
+    UnsupportedRecordVersion(u32),
+
+    /// Upgrading the stored record to the current schema version failed.
+    #[error("Failed to Migrate Record: {0}")]
+    MigrationFailed(String),
+
+    /// The requested profile does not exist.
+    #[error("Profile Not Found")]
+    ProfileNotFound,
+
+    /// Writing activity log entries along with the credential failed.
+    #[error("Activity Log Error: {0}")]
+    ActivityLog(String),
+}
+
+#[uniffi::export]
+impl VdcCollection {
+    #[uniffi::constructor]
+    /// Create a new credential set.
+    pub fn new(engine: Arc<dyn StorageManagerInterface>) -> VdcCollection {
+        VdcCollection {
+            storage: Arc::new(ProfileStorage::new(engine.clone(), None)),
+            root: engine,
+            profile: None,
+            record_activity: AtomicBool::new(false),
+        }
+    }
+
+    /// Register an observer notified of every change made to the profile of
+    /// this collection, through any collection or activity log opened over
+    /// the same storage.
+    ///
+    /// Returns a handle to pass to [VdcCollection::unregister_observer].
+    pub fn register_observer(&self, observer: Arc<dyn VdcCollectionObserver>) -> u64 {
+        self.storage.register_observer(observer)
+    }
+
+    /// Unregister an observer. Returns false if the handle is unknown.
+    pub fn unregister_observer(&self, handle: u64) -> bool {
+        self.storage.scope().observers.unregister(handle)
+    }
+
+    /// Load the activity log of a credential of this collection.
+    ///
+    /// Unlike [ActivityLog::load](activity_log::ActivityLog::load), the
+    /// activity log shares the storage of the collection, so that their
+    /// writes are serialized even when the storage comes through the FFI.
+    pub async fn activity_log(
+        &self,
+        credential_id: Uuid,
+    ) -> Result<Arc<activity_log::ActivityLog>, VdcCollectionError> {
+        activity_log::ActivityLog::load_scoped(credential_id, self.storage.clone())
+            .await
+            .map(Arc::new)
+            .map_err(|e| VdcCollectionError::ActivityLog(e.to_string()))
+    }
+
+    /// Returns a page of the activity log entries of all the credentials of
+    /// this collection.
+    ///
+    /// Filtering, search and sorting are served from a wallet-wide index, so
+    /// only the entries of the returned page are deserialized.
+    pub async fn query_activity_log(
+        &self,
+        query: activity_log::ActivityLogQuery,
+    ) -> Result<activity_log::ActivityLogPage, activity_log::ActivityLogError> {
+        activity_log::query_entries(&self.storage, query).await
+    }
+
+    /// Aggregate the attributes disclosed in the `Shared` entries of all the
+    /// credentials of this collection: which verifiers received which
+    /// attributes, how often and when last, and the attributes ever disclosed
+    /// from each credential.
+    ///
+    /// The sort order and pagination options of the query are ignored.
+    pub async fn activity_log_disclosure_summary(
+        &self,
+        filter: activity_log::ActivityLogQuery,
+    ) -> Result<activity_log::DisclosureSummary, activity_log::ActivityLogError> {
+        activity_log::disclosure_summary(&self.storage, filter).await
+    }
+
+    /// Verify the hash chain of the activity log entries of all the
+    /// credentials of this collection, reporting the entries that were
+    /// edited, removed or inserted.
+    ///
+    /// The chain must have been signed with
+    /// [ActivityLog::sign_chain_head](activity_log::ActivityLog::sign_chain_head),
+    /// by the key of `public_jwk`. Only the entries and changes up to the
+    /// signed checkpoint are protected: those written afterwards are only as
+    /// trustworthy as the storage, until the chain is signed again.
+    ///
+    /// Entries removed or edited through the wallet, i.e. along with their
+    /// credential, with [ActivityLog::remove](activity_log::ActivityLog::remove),
+    /// [ActivityLog::set_hidden](activity_log::ActivityLog::set_hidden) or by
+    /// the retention policy, are recorded in the chain and not reported.
+    ///
+    /// The stored entries are read directly, rather than through the index,
+    /// so that entries inserted behind the wallet's back are verified too.
+    pub async fn verify_activity_log_chain(
+        &self,
+        public_jwk: String,
+    ) -> Result<activity_log::ActivityLogChainReport, activity_log::ActivityLogError> {
+        activity_log::verify_chain(&self.storage, public_jwk).await
+    }
+
+    /// Add a credential to the set.
+    ///
+    /// If activity recording is enabled, a new credential is stored along
+    /// with an `Issued` entry of its activity log.
+    pub async fn add(&self, credential: &Credential) -> Result<(), VdcCollectionError> {
+        let mut operations = Vec::new();
+        let mut _head = None;
+        if self.activity_recording() && self.index_entry(credential.id).await?.is_none() {
+            _head = Some(activity_log::lock_chain_head(&self.storage).await);
+            operations
+                .extend(recorder::issued_operations(self.storage.as_ref(), credential).await?);
+        }
+
+        self.add_with_operations(credential, operations).await
+    }
+
+    /// Add a credential to the set together with an entry of its activity
+    /// log, as a single atomic write.
+    pub async fn add_with_activity_log_entry(
+        &self,
+        credential: &Credential,
+        entry: Arc<ActivityLogEntry>,
+    ) -> Result<(), VdcCollectionError> {
+        if entry.credential_id() != credential.id {
+            return Err(VdcCollectionError::ActivityLogEntryMismatch);
+        }
+
+        let _head = activity_log::lock_chain_head(&self.storage).await;
+        let operations =
+            recorder::entry_operations(self.storage.as_ref(), vec![entry.as_ref().to_owned()])
+                .await?;
+
+        self.add_with_operations(credential, operations).await
+    }
+
+    /// Get a credential from the store.
+    ///
+    /// Records written with an older schema version are upgraded in place.
+    pub async fn get(&self, id: Uuid) -> Result<Option<Credential>, VdcCollectionError> {
+        Ok(self.load_record(id).await?.map(|record| record.credential))
+    }
+
+    /// Remove a credential from the store.
+    ///
+    /// The activity log entries of the credential are removed along with it,
+    /// as a single atomic write, and recorded as removed in the activity log
+    /// hash chain.
+    pub async fn delete(&self, id: Uuid) -> Result<(), VdcCollectionError> {
+        let log_prefix = format!("{ACTIVITY_LOG_KEY_PREFIX}{id}.");
+
+        let mut operations = vec![StorageOperation::Remove {
+            key: Self::id_to_key(id),
+        }];
+        let mut log_entries = Vec::new();
+        let log_keys = self
+            .storage
+            .list()
+            .await
+            .map_err(VdcCollectionError::LoadFailed)?
+            .into_iter()
+            .filter(|key| key.strip_prefix(&log_prefix).is_some());
+        for key in log_keys {
+            let value = self
+                .storage
+                .get(key.clone())
+                .await
+                .map_err(VdcCollectionError::LoadFailed)?;
+            if let Some(entry) = value.and_then(|v| ActivityLogEntry::try_from(v).ok()) {
+                log_entries.push(entry);
+            }
+            operations.push(StorageOperation::Remove { key });
+        }
+
+        let _head = activity_log::lock_chain_head(&self.storage).await;
+        operations.extend(recorder::purge_operations(self.storage.as_ref(), &log_entries).await?);
+
+        // Make sure the index exists, to know the format of the credential.
+        self.index_entries().await?;
+
+        let mut removed = None;
+        self.commit(
+            operations,
+            |index| {
+                removed = index.entries.remove(&id);
+            },
+            VdcCollectionError::DeleteFailed,
+        )
+        .await?;
+
+        if let Some(entry) = removed {
+            self.storage.notify([VdcCollectionEvent::Deleted {
+                id,
+                format: entry.format,
+            }]);
+        }
+
+        Ok(())
+    }
+
+    /// Get a list of all the credentials.
+    pub async fn all_entries(&self) -> Result<Vec<Uuid>, VdcCollectionError> {
+        self.storage
+            .list()
+            .await
+            .map(|list| list.iter().filter_map(Self::key_to_id).collect())
+            .map_err(VdcCollectionError::LoadFailed)
+    }
+
+    /// Get a list of all the credentials that match a specified type.
+    pub async fn all_entries_by_type(
+        &self,
+        ctype: &CredentialType,
+    ) -> Result<Vec<Uuid>, VdcCollectionError> {
+        self.query(CredentialFilter {
+            r#type: Some(ctype.clone()),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Get a list of all the credentials that match the filter, sorted by
+    /// their display order. Credentials without an order come last.
+    ///
+    /// The lookup is served from the secondary index, so credential payloads
+    /// are not deserialized.
+    pub async fn query(&self, filter: CredentialFilter) -> Result<Vec<Uuid>, VdcCollectionError> {
+        let mut entries: Vec<_> = self
+            .index_entries()
+            .await?
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .collect();
+        entries.sort_by_key(|entry| (entry.metadata.order.is_none(), entry.metadata.order));
+
+        Ok(entries.into_iter().map(|entry| entry.id).collect())
+    }
+
+    /// Get the indexed metadata of a credential.
+    pub async fn index_entry(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<CredentialIndexEntry>, VdcCollectionError> {
+        Ok(self
+            .index_entries()
+            .await?
+            .into_iter()
+            .find(|entry| entry.id == id))
+    }
+
+    /// Get the display metadata of a credential.
+    pub async fn metadata(&self, id: Uuid) -> Result<CredentialMetadata, VdcCollectionError> {
+        self.index_entry(id)
+            .await?
+            .map(|entry| entry.metadata)
+            .ok_or(VdcCollectionError::NotFound)
+    }
+
+    /// Replace the display metadata of a credential.
+    pub async fn set_metadata(
+        &self,
+        id: Uuid,
+        metadata: CredentialMetadata,
+    ) -> Result<(), VdcCollectionError> {
+        self.update_metadata(id, |existing| *existing = metadata)
+            .await
+    }
+
+    /// Replace the tags of a credential.
+    pub async fn set_tags(&self, id: Uuid, tags: Vec<String>) -> Result<(), VdcCollectionError> {
+        self.update_metadata(id, |metadata| metadata.tags = tags)
+            .await
+    }
+
+    /// Rebuild the secondary index from the stored credentials.
+    ///
+    /// This is done automatically when no index exists yet, but should be
+    /// called if the underlying storage was modified outside of the
+    /// collection. The display metadata of credentials that are still present,
+    /// including their tags, is preserved.
+    pub async fn rebuild_index(&self) -> Result<(), VdcCollectionError> {
+        let _guard = self.storage.scope().index_lock.lock().await;
+
+        let previous = self.load_index().await?.unwrap_or_default();
+        let mut index = CredentialIndex::default();
+
+        for id in self.all_entries().await? {
+            let credential = match self.get(id).await {
+                Ok(Some(credential)) => credential,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Skipping undecodable credential {id} while indexing: {e}");
+                    continue;
+                }
+            };
+
+            let mut entry = CredentialIndexEntry::from_credential(&credential);
+            if let Some(existing) = previous.entries.get(&id) {
+                entry.metadata = existing.metadata.clone();
+            }
+            index.entries.insert(id, entry);
+        }
+
+        self.store_index(&index).await
+    }
+
+    /// Check that every stored credential record can be decoded.
+    ///
+    /// Records of an older schema version are upgraded in place along the
+    /// way. Records that cannot be decoded are reported rather than skipped,
+    /// and are left untouched in the storage.
+    pub async fn verify_integrity(&self) -> Result<IntegrityReport, VdcCollectionError> {
+        let mut report = IntegrityReport::default();
+
+        for id in self.all_entries().await? {
+            match self.load_record(id).await {
+                Ok(Some(record)) => {
+                    if record.migrated {
+                        report.migrated.push(id);
+                    }
+                    report.valid.push(id);
+                }
+                Ok(None) => {}
+                Err(VdcCollectionError::LoadFailed(e)) => {
+                    return Err(VdcCollectionError::LoadFailed(e))
+                }
+                Err(e) => report.corrupted.push(CorruptedRecord {
+                    id,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Dump the contents of the credential set to the logger.
+    pub async fn dump(&self) {
+        match self.all_entries().await {
+            Ok(list) => {
+                for key in list {
+                    if let Ok(x) = self.get(key).await {
+                        info!("{:?}", x);
+                    }
+                }
+            }
+            Err(e) => info!("Unable to get list: {:?}", e),
+        }
+    }
+}
+
+impl VdcCollection {
+    /// Load and decode a stored credential record, writing it back if it was
+    /// migrated from an older schema version.
+    async fn load_record(&self, id: Uuid) -> Result<Option<DecodedRecord>, VdcCollectionError> {
+        let raw = match self.storage.get(Self::id_to_key(id)).await {
+            Ok(Some(x)) => x,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(VdcCollectionError::LoadFailed(e)),
+        };
+
+        let record = record::decode(&raw.0)?;
+
+        if record.migrated {
+            // A failed write-back is not fatal: the record is migrated again
+            // on the next load.
+            let val = record::encode(&record.credential)?;
+            if let Err(e) = self.storage.add(Self::id_to_key(id), Value(val)).await {
+                warn!("Failed to write back migrated credential {id}: {e}");
+            }
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Return all index entries, building the index if it does not exist yet.
+    async fn index_entries(&self) -> Result<Vec<CredentialIndexEntry>, VdcCollectionError> {
+        if let Some(index) = self.load_index().await? {
+            return Ok(index.entries.into_values().collect());
+        }
+
+        self.rebuild_index().await?;
+
+        Ok(self
+            .load_index()
+            .await?
+            .unwrap_or_default()
+            .entries
+            .into_values()
+            .collect())
+    }
+
+    /// Store a credential along with additional operations, atomically.
+    async fn add_with_operations(
+        &self,
+        credential: &Credential,
+        mut operations: Vec<StorageOperation>,
+    ) -> Result<(), VdcCollectionError> {
+        let val = record::encode(credential)?;
+
+        operations.insert(
+            0,
+            StorageOperation::Add {
+                key: Self::id_to_key(credential.id),
+                value: Value(val),
+            },
+        );
+
+        // Make sure the index exists, to tell additions from updates.
+        self.index_entries().await?;
+
+        let logged = VdcCollectionEvent::logged_by(&operations);
+        let entry = CredentialIndexEntry::from_credential(credential);
+        let mut existed = false;
+        self.commit(
+            operations,
+            |index| {
+                let metadata = match index.entries.get(&entry.id) {
+                    Some(existing) => {
+                        existed = true;
+                        existing.metadata.clone()
+                    }
+                    None => CredentialMetadata::default(),
+                };
+                index
+                    .entries
+                    .insert(entry.id, CredentialIndexEntry { metadata, ..entry });
+            },
+            VdcCollectionError::StoreFailed,
+        )
+        .await?;
+
+        let (id, format) = (credential.id, credential.format.clone());
+        let event = if existed {
+            VdcCollectionEvent::Updated { id, format }
+        } else {
+            VdcCollectionEvent::Added { id, format }
+        };
+        self.storage.notify(std::iter::once(event).chain(logged));
+
+        Ok(())
+    }
+
+    /// Update the display metadata of a credential with `f`.
+    async fn update_metadata(
+        &self,
+        id: Uuid,
+        f: impl FnOnce(&mut CredentialMetadata),
+    ) -> Result<(), VdcCollectionError> {
+        // Make sure the index exists before editing it.
+        self.index_entries().await?;
+
+        let mut format = None;
+        self.commit(
+            Vec::new(),
+            |index| {
+                if let Some(entry) = index.entries.get_mut(&id) {
+                    f(&mut entry.metadata);
+                    format = Some(entry.format.clone());
+                }
+            },
+            VdcCollectionError::StoreFailed,
+        )
+        .await?;
+
+        let format = format.ok_or(VdcCollectionError::NotFound)?;
+        self.storage
+            .notify([VdcCollectionEvent::Updated { id, format }]);
+
+        Ok(())
+    }
+
+    /// Atomically apply `operations` together with the update `f` of the
+    /// stored index, if one exists.
+    ///
+    /// A missing index is left missing, it will be rebuilt from the stored
+    /// credentials on the next lookup.
+    async fn commit(
+        &self,
+        mut operations: Vec<StorageOperation>,
+        f: impl FnOnce(&mut CredentialIndex),
+        map_err: fn(StorageManagerError) -> VdcCollectionError,
+    ) -> Result<(), VdcCollectionError> {
+        let _guard = self.storage.scope().index_lock.lock().await;
+
+        operations.extend(self.index_operation(f).await?);
+
+        apply_batch(self.storage.as_ref(), operations)
+            .await
+            .map_err(map_err)
+    }
+
+    /// The storage operation writing the update `f` of the stored index, if
+    /// one exists. The index lock must be held until it is written.
+    async fn index_operation(
+        &self,
+        f: impl FnOnce(&mut CredentialIndex),
+    ) -> Result<Option<StorageOperation>, VdcCollectionError> {
+        let Some(mut index) = self.load_index().await? else {
+            return Ok(None);
+        };
+        f(&mut index);
+
+        let val = index.to_vec().ok_or(VdcCollectionError::SerializeFailed)?;
+        Ok(Some(StorageOperation::Add {
+            key: Key(INDEX_KEY.into()),
+            value: Value(val),
+        }))
+    }
+
+    async fn load_index(&self) -> Result<Option<CredentialIndex>, VdcCollectionError> {
+        match self.storage.get(Key(INDEX_KEY.into())).await {
+            // An unreadable index is treated as missing, and rebuilt.
+            Ok(Some(raw)) => Ok(CredentialIndex::from_slice(&raw.0)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(VdcCollectionError::LoadFailed(e)),
+        }
+    }
+
+    async fn store_index(&self, index: &CredentialIndex) -> Result<(), VdcCollectionError> {
+        let val = index.to_vec().ok_or(VdcCollectionError::SerializeFailed)?;
+
+        self.storage
+            .add(Key(INDEX_KEY.into()), Value(val))
+            .await
+            .map_err(VdcCollectionError::StoreFailed)
+    }
+
+    /// Convert a UUID to a storage key.
+    fn id_to_key(id: Uuid) -> Key {
+        Key(format!("{KEY_PREFIX}{id}"))
+    }
+
+    /// Convert a string ref to a storage key.
+    ///
+    /// Returns `None` if it's not the right format.
+    fn key_to_id(key: &Key) -> Option<Uuid> {
+        key.strip_prefix(KEY_PREFIX)
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()
+            .ok()
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{credential::CredentialFormat, crypto::KeyAlias, local_store::*};
+
+    #[tokio::test]
+    async fn test_vdc() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi);
+        for id in vdc.all_entries().await.unwrap() {
+            vdc.delete(id).await.unwrap();
+        }
+        let payload_1: Vec<u8> = "Some random collection of bytes. ⚛".into();
+        let payload_2: Vec<u8> = "Some other random collection of bytes. 📯".into();
+        let payload_3: Vec<u8> = "Some third random collection of bytes. λ".into();
+
+        let credential_1 = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: payload_1.clone(),
+            key_alias: None,
+        };
+
+        let credential_2 = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: payload_2.clone(),
+            key_alias: None,
+        };
+
+        let credential_3 = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: payload_3.clone(),
+            key_alias: None,
+        };
+
+        vdc.add(&credential_1)
+            .await
+            .expect("Unable to add the first value.");
+
+        vdc.add(&credential_2)
+            .await
+            .expect("Unable to add the second value.");
+
+        vdc.add(&credential_3)
+            .await
+            .expect("Unable to add the third value.");
+
+        vdc.get(credential_2.id)
+            .await
+            .expect("Failed to get the second value");
+        vdc.get(credential_1.id)
+            .await
+            .expect("Failed to get the first value");
+        vdc.get(credential_3.id)
+            .await
+            .expect("Failed to get the third value");
+
+        assert!(vdc.all_entries().await.unwrap().len() == 3);
+
+        vdc.delete(credential_2.id)
+            .await
+            .expect("Failed to delete the second value.");
+
+        assert!(vdc.all_entries().await.unwrap().len() == 2);
+
+        vdc.delete(credential_1.id)
+            .await
+            .expect("Failed to delete the first value.");
+        vdc.delete(credential_3.id)
+            .await
+            .expect("Failed to delete the third value.");
+
+        assert!(vdc.all_entries().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_vdc_query() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+
+        let mdl = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: "not a real mdoc".into(),
+            key_alias: Some(KeyAlias("mdl-key".into())),
+        };
+
+        let other = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::DcSdJwt,
+            r#type: CredentialType("urn:example:pid".into()),
+            payload: "not a real sd-jwt".into(),
+            key_alias: None,
+        };
+
+        vdc.add(&mdl).await.unwrap();
+        vdc.add(&other).await.unwrap();
+
+        let by_format = vdc
+            .query(CredentialFilter {
+                format: Some(CredentialFormat::DcSdJwt),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(by_format, vec![other.id]);
+
+        let by_type = vdc
+            .all_entries_by_type(&CredentialType("org.iso.18013.5.1.mDL".into()))
+            .await
+            .unwrap();
+        assert_eq!(by_type, vec![mdl.id]);
+
+        let by_key = vdc
+            .query(CredentialFilter {
+                key_alias: Some(KeyAlias("mdl-key".into())),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(by_key, vec![mdl.id]);
+
+        vdc.set_tags(other.id, vec!["work".into()]).await.unwrap();
+        let tagged = vdc
+            .query(CredentialFilter {
+                tags: vec!["work".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(tagged, vec![other.id]);
+
+        // Re-adding a credential keeps its tags.
+        vdc.add(&other).await.unwrap();
+        assert_eq!(
+            vdc.metadata(other.id).await.unwrap().tags,
+            vec!["work".to_string()]
+        );
+
+        // A collection over the same storage sees the persisted index.
+        let reopened = VdcCollection::new(smi);
+        assert_eq!(
+            reopened
+                .query(CredentialFilter::default())
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        vdc.delete(other.id).await.unwrap();
+        assert!(vdc
+            .query(CredentialFilter {
+                tags: vec!["work".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            vdc.set_tags(other.id, vec![]).await,
+            Err(VdcCollectionError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_vdc_delete_cascades_activity_log() {
+        use crate::credential::activity_log::{ActivityLog, ActivityLogEntryType};
+
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+
+        let credential = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: "not a real mdoc".into(),
+            key_alias: None,
+        };
+        let entry = |credential_id| {
+            Arc::new(
+                ActivityLogEntry::new(
+                    credential_id,
+                    ActivityLogEntryType::Issued,
+                    "Credential issued".into(),
+                    "ACME Corp".into(),
+                    None,
+                    None,
+                )
+                .unwrap(),
+            )
+        };
+
+        assert!(matches!(
+            vdc.add_with_activity_log_entry(&credential, entry(Uuid::new_v4()))
+                .await,
+            Err(VdcCollectionError::ActivityLogEntryMismatch)
+        ));
+        assert!(vdc.get(credential.id).await.unwrap().is_none());
+
+        vdc.add_with_activity_log_entry(&credential, entry(credential.id))
+            .await
+            .unwrap();
+
+        let log = ActivityLog::load(credential.id, smi.clone()).await.unwrap();
+        log.add(entry(credential.id)).await.unwrap();
+        assert_eq!(log.entries(None).await.unwrap().len(), 2);
+
+        vdc.delete(credential.id).await.unwrap();
+
+        assert!(vdc.get(credential.id).await.unwrap().is_none());
+        assert!(smi
+            .list()
+            .await
+            .unwrap()
+            .iter()
+            .all(|key| key.strip_prefix(ACTIVITY_LOG_KEY_PREFIX).is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_vdc_migration_and_integrity() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+
+        let legacy = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: "not a real mdoc".into(),
+            key_alias: None,
+        };
+
+        // A record written before versioning was introduced: a bare credential.
+        smi.add(
+            VdcCollection::id_to_key(legacy.id),
+            Value(serde_cbor::to_vec(&legacy).unwrap()),
+        )
+        .await
+        .unwrap();
+
+        let corrupted = Uuid::new_v4();
+        smi.add(
+            VdcCollection::id_to_key(corrupted),
+            Value(b"not cbor".to_vec()),
+        )
+        .await
+        .unwrap();
+
+        let report = vdc.verify_integrity().await.unwrap();
+        assert_eq!(report.valid, vec![legacy.id]);
+        assert_eq!(report.migrated, vec![legacy.id]);
+        assert_eq!(report.corrupted.len(), 1);
+        assert_eq!(report.corrupted[0].id, corrupted);
+
+        // The legacy record was upgraded in place.
+        let raw = smi
+            .get(VdcCollection::id_to_key(legacy.id))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!record::decode(&raw.0).unwrap().migrated);
+        assert_eq!(
+            vdc.get(legacy.id).await.unwrap().unwrap().payload,
+            legacy.payload
+        );
+
+        let report = vdc.verify_integrity().await.unwrap();
+        assert!(report.migrated.is_empty());
+        assert_eq!(report.corrupted.len(), 1);
+
+        // Records from a newer schema version are reported, not rewritten.
+        let future = Uuid::new_v4();
+        let mut envelope = std::collections::BTreeMap::new();
+        envelope.insert("v", serde_cbor::Value::Integer(99));
+        envelope.insert("record", serde_cbor::Value::Null);
+        smi.add(
+            VdcCollection::id_to_key(future),
+            Value(serde_cbor::to_vec(&envelope).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            vdc.get(future).await,
+            Err(VdcCollectionError::UnsupportedRecordVersion(99))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_vdc_observers() {
+        #[derive(Debug, Default)]
+        struct Recorder(std::sync::Mutex<Vec<VdcCollectionEvent>>);
+
+        impl VdcCollectionObserver for Recorder {
+            fn on_change(&self, event: VdcCollectionEvent) {
+                self.0.lock().unwrap().push(event);
+            }
+        }
+
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+        let recorder = Arc::new(Recorder::default());
+        let handle = vdc.register_observer(recorder.clone());
+
+        let credential = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::DcSdJwt,
+            r#type: CredentialType("urn:example:pid".into()),
+            payload: "not a real sd-jwt".into(),
+            key_alias: None,
+        };
+        let (id, format) = (credential.id, credential.format.clone());
+
+        vdc.add(&credential).await.unwrap();
+        vdc.add(&credential).await.unwrap();
+        vdc.set_tags(id, vec!["work".into()]).await.unwrap();
+        vdc.delete(id).await.unwrap();
+        // Deleting a missing credential is not a change.
+        vdc.delete(id).await.unwrap();
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                VdcCollectionEvent::Added {
+                    id,
+                    format: format.clone()
+                },
+                VdcCollectionEvent::Updated {
+                    id,
+                    format: format.clone()
+                },
+                VdcCollectionEvent::Updated {
+                    id,
+                    format: format.clone()
+                },
+                VdcCollectionEvent::Deleted { id, format },
+            ]
+        );
+
+        assert!(vdc.unregister_observer(handle));
+        assert!(!vdc.unregister_observer(handle));
+        vdc.add(&credential).await.unwrap();
+        assert_eq!(recorder.0.lock().unwrap().len(), 4);
+
+        // Observers are notified of the changes made to their profile through
+        // any collection or activity log opened over the same storage.
+        let recorder = Arc::new(Recorder::default());
+        vdc.register_observer(recorder.clone());
+        let other = VdcCollection::new(smi.clone());
+        other.add(&credential).await.unwrap();
+        let entry = ActivityLogEntry::new(
+            id,
+            activity_log::ActivityLogEntryType::Shared,
+            "Shared with Bar".into(),
+            "The Bar".into(),
+            None,
+            None,
+        )
+        .unwrap();
+        let entry_id = entry.get_id();
+        let log = other.activity_log(id).await.unwrap();
+        log.add(Arc::new(entry)).await.unwrap();
+
+        let profile = vdc.create_profile("Work".into()).await.unwrap();
+        let work = vdc.with_profile(profile.id).await.unwrap();
+        work.add(&credential).await.unwrap();
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                VdcCollectionEvent::Updated {
+                    id,
+                    format: credential.format.clone()
+                },
+                VdcCollectionEvent::ActivityLogged {
+                    credential_id: id,
+                    entry_id
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_vdc_metadata() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi);
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let credential = Credential {
+                id: Uuid::new_v4(),
+                format: CredentialFormat::MsoMdoc,
+                r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+                payload: Uuid::new_v4().as_bytes().to_vec(),
+                key_alias: None,
+            };
+            vdc.add(&credential).await.unwrap();
+            ids.push(credential.id);
+        }
+
+        let metadata = CredentialMetadata {
+            nickname: Some("Driving licence".into()),
+            order: Some(1),
+            color: Some("#0055FF".into()),
+            favourite: true,
+            ..Default::default()
+        };
+        vdc.set_metadata(ids[2], metadata.clone()).await.unwrap();
+        vdc.set_metadata(
+            ids[1],
+            CredentialMetadata {
+                order: Some(0),
+                hidden: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(vdc.metadata(ids[2]).await.unwrap(), metadata);
+
+        // Results are sorted by display order, unordered credentials last.
+        assert_eq!(
+            vdc.query(CredentialFilter::default()).await.unwrap(),
+            vec![ids[1], ids[2], ids[0]]
+        );
+        assert_eq!(
+            vdc.query(CredentialFilter {
+                favourite: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap(),
+            vec![ids[2]]
+        );
+        assert_eq!(
+            vdc.query(CredentialFilter {
+                hidden: Some(false),
+                ..Default::default()
+            })
+            .await
+            .unwrap(),
+            vec![ids[2], ids[0]]
+        );
+
+        // Tags are part of the metadata.
+        vdc.set_tags(ids[2], vec!["id".into()]).await.unwrap();
+        assert_eq!(
+            vdc.metadata(ids[2]).await.unwrap(),
+            CredentialMetadata {
+                tags: vec!["id".into()],
+                ..metadata
+            }
+        );
+
+        assert!(matches!(
+            vdc.metadata(Uuid::new_v4()).await,
+            Err(VdcCollectionError::NotFound)
+        ));
+    }
+}
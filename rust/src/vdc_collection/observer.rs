@@ -0,0 +1,126 @@
+use std::fmt::Debug;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use uuid::Uuid;
+
+use crate::credential::{
+    activity_log::{ActivityLogEntry, KEY_PREFIX as ACTIVITY_LOG_KEY_PREFIX},
+    CredentialFormat,
+};
+use crate::storage_manager::StorageOperation;
+
+/// A change to the contents of a [VdcCollection](super::VdcCollection).
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum VdcCollectionEvent {
+    /// A new credential was stored.
+    Added { id: Uuid, format: CredentialFormat },
+    /// An existing credential, or its metadata, was replaced.
+    Updated { id: Uuid, format: CredentialFormat },
+    /// A credential was removed.
+    Deleted { id: Uuid, format: CredentialFormat },
+    /// An entry was added to the activity log of a credential.
+    ActivityLogged { credential_id: Uuid, entry_id: Uuid },
+}
+
+impl VdcCollectionEvent {
+    pub(crate) fn activity_logged(entry: &ActivityLogEntry) -> Self {
+        Self::ActivityLogged {
+            credential_id: entry.credential_id(),
+            entry_id: entry.get_id(),
+        }
+    }
+
+    /// The events of the activity log entries written by `operations`, which
+    /// must only write new entries.
+    pub(crate) fn logged_by(operations: &[StorageOperation]) -> Vec<Self> {
+        operations
+            .iter()
+            .filter_map(|operation| match operation {
+                StorageOperation::Add { key, value }
+                    if key.0.starts_with(ACTIVITY_LOG_KEY_PREFIX) =>
+                {
+                    ActivityLogEntry::try_from(value.clone()).ok()
+                }
+                _ => None,
+            })
+            .map(|entry| Self::activity_logged(&entry))
+            .collect()
+    }
+}
+
+/// Interface: VdcCollectionObserver
+///
+/// Receives the changes made to a profile of a storage, once they have been
+/// written, through any [VdcCollection](super::VdcCollection) or activity log
+/// opened over it. Changes made directly to the underlying storage are not
+/// observed.
+#[uniffi::export(with_foreign)]
+pub trait VdcCollectionObserver: Send + Sync + Debug {
+    /// Called after each change, on the task that made it. Implementations
+    /// should return quickly, e.g. by dispatching to the UI thread.
+    fn on_change(&self, event: VdcCollectionEvent);
+}
+
+/// An observer, along with the profile it observes.
+type Registration = (u64, Option<Uuid>, Arc<dyn VdcCollectionObserver>);
+
+/// The observers registered on the collections of a storage, by profile.
+#[derive(Debug, Default)]
+pub(crate) struct Observers {
+    next_id: AtomicU64,
+    observers: Mutex<Vec<Registration>>,
+}
+
+impl Observers {
+    pub(crate) fn register(
+        &self,
+        profile: Option<Uuid>,
+        observer: Arc<dyn VdcCollectionObserver>,
+    ) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.lock().push((id, profile, observer));
+        id
+    }
+
+    pub(crate) fn unregister(&self, id: u64) -> bool {
+        let mut observers = self.lock();
+        let len = observers.len();
+        observers.retain(|(observer_id, _, _)| *observer_id != id);
+        observers.len() != len
+    }
+
+    /// Notify the observers of `profile` of the events.
+    pub(crate) fn notify(
+        &self,
+        profile: Option<Uuid>,
+        events: impl IntoIterator<Item = VdcCollectionEvent>,
+    ) {
+        // Observers are called without holding the lock, so that they can
+        // register or unregister observers themselves.
+        let observers: Vec<_> = self
+            .lock()
+            .iter()
+            .filter(|(_, observed, _)| *observed == profile)
+            .map(|(_, _, observer)| observer.clone())
+            .collect();
+        if observers.is_empty() {
+            return;
+        }
+
+        for event in events {
+            for observer in observers.iter() {
+                observer.on_change(event.clone());
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Registration>> {
+        // A panicking observer must not disable notifications.
+        self.observers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
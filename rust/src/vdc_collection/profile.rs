@@ -0,0 +1,562 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    record, recorder, scope::StorageScope, CredentialIndexEntry, VdcCollection, VdcCollectionError,
+    VdcCollectionEvent, VdcCollectionObserver,
+};
+use crate::common::*;
+use crate::credential::activity_log::{
+    self, ActivityLogEntry, KEY_PREFIX as ACTIVITY_LOG_KEY_PREFIX,
+};
+use crate::storage_manager::*;
+
+/// Internal prefix of the keys stored in a profile.
+const PROFILE_KEY_PREFIX: &str = "Profile.";
+
+/// Storage key of the profile registry.
+const PROFILES_KEY: &str = "Profiles";
+
+/// A user profile, holding its own credentials and activity logs.
+///
+/// Credentials stored outside of any profile form the default profile, which
+/// is not listed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct Profile {
+    /// The ID of the profile, used to open it.
+    pub id: Uuid,
+    /// The display name of the profile.
+    pub name: String,
+}
+
+/// A [StorageManagerInterface] adapter scoping all keys under a profile.
+///
+/// The default profile (`None`) stores keys unchanged, but hides the keys of
+/// the other profiles when listing.
+#[derive(Debug)]
+pub(crate) struct ProfileStorage {
+    inner: Arc<dyn StorageManagerInterface>,
+    profile: Option<Uuid>,
+    prefix: Option<String>,
+    scope: Arc<StorageScope>,
+}
+
+impl ProfileStorage {
+    pub(crate) fn new(inner: Arc<dyn StorageManagerInterface>, profile: Option<Uuid>) -> Self {
+        Self {
+            scope: StorageScope::of(&inner),
+            inner,
+            profile,
+            prefix: profile.map(profile_prefix),
+        }
+    }
+
+    /// The state shared with everything opened over the same storage.
+    pub(crate) fn scope(&self) -> &StorageScope {
+        &self.scope
+    }
+
+    /// Register an observer of the changes made to the profile.
+    pub(crate) fn register_observer(&self, observer: Arc<dyn VdcCollectionObserver>) -> u64 {
+        self.scope.observers.register(self.profile, observer)
+    }
+
+    /// Notify the observers of the profile of changes written to it.
+    pub(crate) fn notify(&self, events: impl IntoIterator<Item = VdcCollectionEvent>) {
+        self.scope.observers.notify(self.profile, events)
+    }
+
+    fn scoped(&self, key: Key) -> Key {
+        match &self.prefix {
+            Some(prefix) => Key::with_prefix(prefix, &key.0),
+            None => key,
+        }
+    }
+
+    /// Scope operations to the profile, to be applied to the unscoped
+    /// storage, e.g. along with the operations of another profile.
+    fn scoped_operations(&self, operations: Vec<StorageOperation>) -> Vec<StorageOperation> {
+        operations
+            .into_iter()
+            .map(|operation| match operation {
+                StorageOperation::Add { key, value } => StorageOperation::Add {
+                    key: self.scoped(key),
+                    value,
+                },
+                StorageOperation::Remove { key } => StorageOperation::Remove {
+                    key: self.scoped(key),
+                },
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl StorageManagerInterface for ProfileStorage {
+    async fn add(&self, key: Key, value: Value) -> Result<(), StorageManagerError> {
+        self.inner.add(self.scoped(key), value).await
+    }
+
+    async fn get(&self, key: Key) -> Result<Option<Value>, StorageManagerError> {
+        self.inner.get(self.scoped(key)).await
+    }
+
+    async fn list(&self) -> Result<Vec<Key>, StorageManagerError> {
+        let keys = self.inner.list().await?.into_iter();
+
+        Ok(match &self.prefix {
+            Some(prefix) => keys
+                .filter_map(|key| key.strip_prefix(prefix).map(Key))
+                .collect(),
+            None => keys.filter(|key| !is_profile_key(key)).collect(),
+        })
+    }
+
+    async fn remove(&self, key: Key) -> Result<(), StorageManagerError> {
+        self.inner.remove(self.scoped(key)).await
+    }
+
+    async fn batch(&self, operations: Vec<StorageOperation>) -> Result<(), StorageManagerError> {
+        apply_batch(self.inner.as_ref(), self.scoped_operations(operations)).await
+    }
+}
+
+#[uniffi::export]
+impl VdcCollection {
+    /// The profile this collection is scoped to, or `None` for the default
+    /// profile.
+    pub fn profile_id(&self) -> Option<Uuid> {
+        self.profile
+    }
+
+    /// Open the collection of a profile, over the same storage.
+    ///
+    /// Observers are registered for a profile: those of this collection are
+    /// not notified of the changes made to the returned one.
+    pub async fn with_profile(
+        &self,
+        profile_id: Uuid,
+    ) -> Result<Arc<VdcCollection>, VdcCollectionError> {
+        self.find_profile(profile_id).await?;
+
+        Ok(Arc::new(self.view(Some(profile_id))))
+    }
+
+    /// List the profiles, excluding the default profile.
+    pub async fn list_profiles(&self) -> Result<Vec<Profile>, VdcCollectionError> {
+        self.load_profiles().await
+    }
+
+    /// Create a new, empty profile.
+    pub async fn create_profile(&self, name: String) -> Result<Profile, VdcCollectionError> {
+        let _guard = self.storage.scope().index_lock.lock().await;
+
+        let profile = Profile {
+            id: Uuid::new_v4(),
+            name,
+        };
+
+        let mut profiles = self.load_profiles().await?;
+        profiles.push(profile.clone());
+        self.root
+            .add(Key(PROFILES_KEY.into()), profiles_value(&profiles)?)
+            .await
+            .map_err(VdcCollectionError::StoreFailed)?;
+
+        Ok(profile)
+    }
+
+    /// Change the display name of a profile.
+    pub async fn rename_profile(
+        &self,
+        profile_id: Uuid,
+        name: String,
+    ) -> Result<(), VdcCollectionError> {
+        let _guard = self.storage.scope().index_lock.lock().await;
+
+        let mut profiles = self.load_profiles().await?;
+        let profile = profiles
+            .iter_mut()
+            .find(|profile| profile.id == profile_id)
+            .ok_or(VdcCollectionError::ProfileNotFound)?;
+        profile.name = name;
+
+        self.root
+            .add(Key(PROFILES_KEY.into()), profiles_value(&profiles)?)
+            .await
+            .map_err(VdcCollectionError::StoreFailed)
+    }
+
+    /// Delete a profile along with all of its credentials, activity logs and
+    /// metadata, as a single atomic write.
+    pub async fn wipe_profile(&self, profile_id: Uuid) -> Result<(), VdcCollectionError> {
+        let _guard = self.storage.scope().index_lock.lock().await;
+
+        let mut profiles = self.load_profiles().await?;
+        let len = profiles.len();
+        profiles.retain(|profile| profile.id != profile_id);
+        if profiles.len() == len {
+            return Err(VdcCollectionError::ProfileNotFound);
+        }
+
+        let prefix = profile_prefix(profile_id);
+        let mut operations: Vec<_> = self
+            .root
+            .list()
+            .await
+            .map_err(VdcCollectionError::LoadFailed)?
+            .into_iter()
+            .filter(|key| key.strip_prefix(&prefix).is_some())
+            .map(|key| StorageOperation::Remove { key })
+            .collect();
+        operations.push(StorageOperation::Add {
+            key: Key(PROFILES_KEY.into()),
+            value: profiles_value(&profiles)?,
+        });
+
+        apply_batch(self.root.as_ref(), operations)
+            .await
+            .map_err(VdcCollectionError::DeleteFailed)
+    }
+
+    /// Move a credential of this collection, along with its activity log and
+    /// metadata, to another profile (`None` for the default profile), as a
+    /// single atomic write.
+    ///
+    /// The activity log entries are linked to the hash chain of the other
+    /// profile, and recorded as moved in the chain of this one.
+    pub async fn move_credential(
+        &self,
+        credential_id: Uuid,
+        to_profile: Option<Uuid>,
+    ) -> Result<(), VdcCollectionError> {
+        if to_profile == self.profile {
+            return Ok(());
+        }
+
+        if let Some(profile_id) = to_profile {
+            self.find_profile(profile_id).await?;
+        }
+
+        let credential = self
+            .get(credential_id)
+            .await?
+            .ok_or(VdcCollectionError::NotFound)?;
+        let target = self.view(to_profile);
+
+        // Make sure both indexes exist, to move the metadata along.
+        self.index_entries().await?;
+        target.index_entries().await?;
+
+        let mut operations = vec![StorageOperation::Remove {
+            key: Self::id_to_key(credential_id),
+        }];
+        let mut target_operations = vec![StorageOperation::Add {
+            key: Self::id_to_key(credential_id),
+            value: Value(record::encode(&credential)?),
+        }];
+
+        let log_prefix = format!("{ACTIVITY_LOG_KEY_PREFIX}{credential_id}.");
+        let log_keys = self
+            .storage
+            .list()
+            .await
+            .map_err(VdcCollectionError::LoadFailed)?
+            .into_iter()
+            .filter(|key| key.strip_prefix(&log_prefix).is_some());
+        let mut log_entries = Vec::new();
+        for key in log_keys {
+            let Some(value) = self
+                .storage
+                .get(key.clone())
+                .await
+                .map_err(VdcCollectionError::LoadFailed)?
+            else {
+                continue;
+            };
+            match ActivityLogEntry::try_from(value.clone()) {
+                Ok(entry) => log_entries.push(entry),
+                // Undecodable entries are moved as they are.
+                Err(_) => target_operations.push(StorageOperation::Add {
+                    key: key.clone(),
+                    value,
+                }),
+            }
+            operations.push(StorageOperation::Remove { key });
+        }
+
+        // Both profiles share the locks of the storage.
+        let _head = activity_log::lock_chain_head(&self.storage).await;
+        operations.extend(recorder::move_operations(self.storage.as_ref(), &log_entries).await?);
+        if !log_entries.is_empty() {
+            let moved = log_entries
+                .into_iter()
+                .map(|mut entry| {
+                    entry.unchain();
+                    entry
+                })
+                .collect();
+            target_operations
+                .extend(recorder::entry_operations(target.storage.as_ref(), moved).await?);
+        }
+
+        let _guard = self.storage.scope().index_lock.lock().await;
+        let mut removed = None;
+        operations.extend(
+            self.index_operation(|index| removed = index.entries.remove(&credential_id))
+                .await?,
+        );
+        let entry = CredentialIndexEntry {
+            metadata: removed
+                .as_ref()
+                .map(|entry| entry.metadata.clone())
+                .unwrap_or_default(),
+            ..CredentialIndexEntry::from_credential(&credential)
+        };
+        target_operations.extend(
+            target
+                .index_operation(|index| {
+                    index.entries.insert(credential_id, entry);
+                })
+                .await?,
+        );
+
+        let mut events = vec![VdcCollectionEvent::Added {
+            id: credential_id,
+            format: credential.format.clone(),
+        }];
+        events.extend(VdcCollectionEvent::logged_by(&target_operations));
+
+        let mut operations = self.storage.scoped_operations(operations);
+        operations.extend(target.storage.scoped_operations(target_operations));
+        apply_batch(self.root.as_ref(), operations)
+            .await
+            .map_err(VdcCollectionError::StoreFailed)?;
+
+        if let Some(entry) = removed {
+            self.storage.notify([VdcCollectionEvent::Deleted {
+                id: credential_id,
+                format: entry.format,
+            }]);
+        }
+        target.storage.notify(events);
+
+        Ok(())
+    }
+}
+
+impl VdcCollection {
+    /// A collection scoped to `profile`, over the same storage.
+    fn view(&self, profile: Option<Uuid>) -> VdcCollection {
+        VdcCollection {
+            storage: Arc::new(ProfileStorage::new(self.root.clone(), profile)),
+            root: self.root.clone(),
+            profile,
+            record_activity: AtomicBool::new(self.activity_recording()),
+        }
+    }
+
+    async fn find_profile(&self, profile_id: Uuid) -> Result<Profile, VdcCollectionError> {
+        self.load_profiles()
+            .await?
+            .into_iter()
+            .find(|profile| profile.id == profile_id)
+            .ok_or(VdcCollectionError::ProfileNotFound)
+    }
+
+    async fn load_profiles(&self) -> Result<Vec<Profile>, VdcCollectionError> {
+        match self.root.get(Key(PROFILES_KEY.into())).await {
+            Ok(Some(raw)) => {
+                serde_cbor::from_slice(&raw.0).map_err(|_| VdcCollectionError::DeserializeFailed)
+            }
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(VdcCollectionError::LoadFailed(e)),
+        }
+    }
+}
+
+fn profile_prefix(profile_id: Uuid) -> String {
+    format!("{PROFILE_KEY_PREFIX}{profile_id}.")
+}
+
+/// Whether the key belongs to a profile other than the default one.
+fn is_profile_key(key: &Key) -> bool {
+    key.strip_prefix(PROFILE_KEY_PREFIX).is_some()
+}
+
+fn profiles_value(profiles: &[Profile]) -> Result<Value, VdcCollectionError> {
+    serde_cbor::to_vec(profiles)
+        .map(Value)
+        .map_err(|_| VdcCollectionError::SerializeFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        credential::{
+            activity_log::{ActivityLog, ActivityLogEntry, ActivityLogEntryType},
+            Credential, CredentialFormat,
+        },
+        local_store::LocalStore,
+    };
+
+    #[tokio::test]
+    async fn profiles_are_isolated() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+
+        let work = vdc.create_profile("Work".into()).await.unwrap();
+        vdc.rename_profile(work.id, "Office".into()).await.unwrap();
+        assert_eq!(
+            vdc.list_profiles().await.unwrap(),
+            vec![Profile {
+                id: work.id,
+                name: "Office".into()
+            }]
+        );
+
+        let office = vdc.with_profile(work.id).await.unwrap();
+        assert_eq!(office.profile_id(), Some(work.id));
+
+        let credential = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: "not a real mdoc".into(),
+            key_alias: None,
+        };
+        office.add(&credential).await.unwrap();
+        office
+            .set_tags(credential.id, vec!["badge".into()])
+            .await
+            .unwrap();
+
+        let log = ActivityLog::load_with_profile(credential.id, smi.clone(), work.id)
+            .await
+            .unwrap();
+        log.add(Arc::new(
+            ActivityLogEntry::new(
+                credential.id,
+                ActivityLogEntryType::Issued,
+                "Credential issued".into(),
+                "ACME Corp".into(),
+                None,
+                None,
+            )
+            .unwrap(),
+        ))
+        .await
+        .unwrap();
+
+        // The default profile does not see the credentials of other profiles.
+        assert!(vdc.all_entries().await.unwrap().is_empty());
+        assert!(ActivityLog::load(credential.id, smi.clone())
+            .await
+            .unwrap()
+            .entries(None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(office.all_entries().await.unwrap(), vec![credential.id]);
+
+        office.move_credential(credential.id, None).await.unwrap();
+        assert!(office.all_entries().await.unwrap().is_empty());
+        assert_eq!(vdc.all_entries().await.unwrap(), vec![credential.id]);
+        assert_eq!(
+            vdc.metadata(credential.id).await.unwrap().tags,
+            vec!["badge".to_string()]
+        );
+        assert_eq!(
+            ActivityLog::load(credential.id, smi.clone())
+                .await
+                .unwrap()
+                .entries(None)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        vdc.move_credential(credential.id, Some(work.id))
+            .await
+            .unwrap();
+        vdc.wipe_profile(work.id).await.unwrap();
+
+        assert!(vdc.list_profiles().await.unwrap().is_empty());
+        assert!(matches!(
+            vdc.with_profile(work.id).await,
+            Err(VdcCollectionError::ProfileNotFound)
+        ));
+        assert!(smi
+            .list()
+            .await
+            .unwrap()
+            .iter()
+            .all(|key| !is_profile_key(key)));
+    }
+
+    #[tokio::test]
+    async fn moved_activity_logs_are_rechained() {
+        use crate::crypto::{KeyAlias, KeyStore, RustTestKeyManager};
+
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+        vdc.set_activity_recording(true);
+        let work = vdc.create_profile("Work".into()).await.unwrap();
+        let office = vdc.with_profile(work.id).await.unwrap();
+
+        let credential = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: "not a real mdoc".into(),
+            key_alias: None,
+        };
+        let other = Credential {
+            id: Uuid::new_v4(),
+            ..credential.clone()
+        };
+
+        let home_log = vdc.activity_log(credential.id).await.unwrap();
+        let office_log = office.activity_log(credential.id).await.unwrap();
+        home_log.enable_chain().await.unwrap();
+        office_log.enable_chain().await.unwrap();
+
+        vdc.add(&credential).await.unwrap();
+        vdc.add(&other).await.unwrap();
+        office.add(&other).await.unwrap();
+
+        vdc.move_credential(credential.id, Some(work.id))
+            .await
+            .unwrap();
+
+        let moved = office_log.entries(None).await.unwrap();
+        assert_eq!(moved.len(), 1);
+        // Linked after the entry of the other credential of the profile.
+        assert_eq!(moved[0].get_chain_sequence(), Some(1));
+        assert!(home_log.entries(None).await.unwrap().is_empty());
+
+        let key_manager = RustTestKeyManager::default();
+        key_manager
+            .generate_p256_signing_key(KeyAlias("activity_log".into()))
+            .await
+            .unwrap();
+        let signer = key_manager
+            .get_signing_key(KeyAlias("activity_log".into()))
+            .unwrap();
+        for (collection, log) in [(&vdc, &home_log), (&office, &office_log)] {
+            log.sign_chain_head(signer.clone()).await.unwrap();
+            let report = collection
+                .verify_activity_log_chain(signer.jwk().unwrap())
+                .await
+                .unwrap();
+            assert!(report.valid, "{:?}", report.issues);
+        }
+        // The move is recorded in the chain of the default profile.
+        assert_eq!(home_log.chain_head().await.unwrap().unwrap().length, 3);
+    }
+}
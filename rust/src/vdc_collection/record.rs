@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::VdcCollectionError;
+use crate::credential::Credential;
+
+/// Current schema version of the persisted credential records.
+pub(crate) const RECORD_VERSION: u32 = 1;
+
+/// A migration upgrades a record from one schema version to the next.
+///
+/// Migrations operate on the raw CBOR value of the record, so that they can
+/// handle records that no longer deserialize into the current [Credential].
+type Migration = fn(serde_cbor::Value) -> Result<serde_cbor::Value, String>;
+
+/// Registered migrations, indexed by the version they upgrade from.
+///
+/// When bumping [RECORD_VERSION], append the migration from the previous
+/// version here.
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+/// Versioned envelope wrapping every persisted credential record.
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordEnvelope {
+    #[serde(rename = "v")]
+    version: u32,
+    record: serde_cbor::Value,
+}
+
+/// A credential record decoded from storage.
+pub(crate) struct DecodedRecord {
+    pub(crate) credential: Credential,
+    /// Whether the record was upgraded from an older schema version, and
+    /// should be written back.
+    pub(crate) migrated: bool,
+}
+
+/// A stored credential record that could not be decoded.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct CorruptedRecord {
+    /// The ID of the credential, taken from its storage key.
+    pub id: Uuid,
+    /// Why the record could not be decoded.
+    pub error: String,
+}
+
+/// Result of [VdcCollection::verify_integrity](super::VdcCollection::verify_integrity).
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct IntegrityReport {
+    /// Records that decoded successfully, including migrated ones.
+    pub valid: Vec<Uuid>,
+    /// Records that were upgraded to the current schema version.
+    pub migrated: Vec<Uuid>,
+    /// Records that could not be decoded.
+    pub corrupted: Vec<CorruptedRecord>,
+}
+
+/// Encode a credential as a record of the current schema version.
+pub(crate) fn encode(credential: &Credential) -> Result<Vec<u8>, VdcCollectionError> {
+    let record =
+        serde_cbor::value::to_value(credential).map_err(|_| VdcCollectionError::SerializeFailed)?;
+
+    serde_cbor::to_vec(&RecordEnvelope {
+        version: RECORD_VERSION,
+        record,
+    })
+    .map_err(|_| VdcCollectionError::SerializeFailed)
+}
+
+/// Decode a record, upgrading it to the current schema version if needed.
+pub(crate) fn decode(bytes: &[u8]) -> Result<DecodedRecord, VdcCollectionError> {
+    let value: serde_cbor::Value =
+        serde_cbor::from_slice(bytes).map_err(|_| VdcCollectionError::DeserializeFailed)?;
+
+    // Records written before the envelope was introduced are bare
+    // credentials, and are considered version 0.
+    let (mut version, mut record) = match serde_cbor::value::from_value(value.clone()) {
+        Ok(RecordEnvelope { version, record }) => (version, record),
+        Err(_) => (0, value),
+    };
+
+    if version > RECORD_VERSION {
+        return Err(VdcCollectionError::UnsupportedRecordVersion(version));
+    }
+
+    let migrated = version < RECORD_VERSION;
+
+    while version < RECORD_VERSION {
+        let migration = MIGRATIONS.get(version as usize).ok_or_else(|| {
+            VdcCollectionError::MigrationFailed(format!("no migration from v{version}"))
+        })?;
+
+        record = migration(record)
+            .map_err(|e| VdcCollectionError::MigrationFailed(format!("v{version}: {e}")))?;
+        version += 1;
+    }
+
+    let credential =
+        serde_cbor::value::from_value(record).map_err(|_| VdcCollectionError::DeserializeFailed)?;
+
+    Ok(DecodedRecord {
+        credential,
+        migrated,
+    })
+}
+
+/// Version 0 records are bare credentials, which version 1 wraps in the
+/// envelope unchanged.
+fn migrate_v0_to_v1(record: serde_cbor::Value) -> Result<serde_cbor::Value, String> {
+    match record {
+        serde_cbor::Value::Map(_) => Ok(record),
+        _ => Err("expected a map".into()),
+    }
+}
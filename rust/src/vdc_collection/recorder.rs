@@ -0,0 +1,285 @@
+use std::sync::{atomic::Ordering, Arc};
+
+use uuid::Uuid;
+
+use super::{CredentialIndexEntry, VdcCollection, VdcCollectionError, VdcCollectionEvent};
+use crate::credential::{
+    activity_log::{self, ActivityLogEntry, ActivityLogEntryType},
+    Credential,
+};
+use crate::storage_manager::*;
+
+/// The verifier recorded for presentations to a verifier which did not
+/// identify itself, e.g. a reader without a certificate common name.
+pub const UNKNOWN_VERIFIER: &str = "unknown verifier";
+
+/// The issuer recorded for credentials which do not state their issuer.
+pub const UNKNOWN_ISSUER: &str = "unknown issuer";
+
+/// Records the activity of the presentation and issuance flows in the
+/// activity logs of a [VdcCollection].
+///
+/// Recording is opt-in: nothing is written until it is enabled with
+/// [VdcCollection::set_activity_recording]. Flows built on a collection use
+/// its recorder automatically, the others accept one explicitly.
+#[derive(Debug, uniffi::Object)]
+pub struct ActivityRecorder {
+    collection: Arc<VdcCollection>,
+}
+
+#[uniffi::export]
+impl VdcCollection {
+    /// Enable or disable the automatic recording of activity log entries.
+    ///
+    /// When enabled, an `Issued` entry is written along with every new
+    /// credential added to the collection, and presentation flows write a
+    /// `Shared` entry for every credential they submit.
+    pub fn set_activity_recording(&self, enabled: bool) {
+        self.record_activity.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether activity log entries are recorded automatically.
+    pub fn activity_recording(&self) -> bool {
+        self.record_activity.load(Ordering::Relaxed)
+    }
+
+    /// Get a recorder writing to the activity logs of this collection, for
+    /// the flows that are not built on a collection.
+    pub fn activity_recorder(self: Arc<Self>) -> Arc<ActivityRecorder> {
+        Arc::new(ActivityRecorder { collection: self })
+    }
+}
+
+impl ActivityRecorder {
+    /// Record that credentials were presented to a verifier, identified by
+    /// its client ID or reader common name, or as [UNKNOWN_VERIFIER].
+    ///
+    /// `credentials` lists the presented credentials along with the paths of
+    /// their disclosed fields. Credentials that are not stored in the
+    /// collection, e.g. minted during the presentation, are skipped.
+    ///
+    /// The presentation has already been submitted at this point, so a
+    /// failure is logged rather than returned.
+    pub(crate) async fn record_shared(
+        &self,
+        verifier: Option<&str>,
+        response_url: Option<String>,
+        credentials: Vec<(Uuid, Vec<String>)>,
+    ) {
+        if !self.collection.activity_recording() {
+            return;
+        }
+
+        let verifier = verifier
+            .filter(|verifier| !verifier.is_empty())
+            .unwrap_or(UNKNOWN_VERIFIER);
+
+        if let Err(e) = self
+            .shared_operations(verifier, response_url, credentials)
+            .await
+        {
+            tracing::warn!("Failed to record the presentation to {verifier}: {e}");
+        }
+    }
+
+    async fn shared_operations(
+        &self,
+        verifier: &str,
+        response_url: Option<String>,
+        credentials: Vec<(Uuid, Vec<String>)>,
+    ) -> Result<(), VdcCollectionError> {
+        let mut entries = Vec::new();
+        for (credential_id, fields) in credentials {
+            if self.collection.index_entry(credential_id).await?.is_none() {
+                continue;
+            }
+
+            let entry = ActivityLogEntry::new(
+                credential_id,
+                ActivityLogEntryType::Shared,
+                format!("Credential shared with {verifier}"),
+                verifier.to_string(),
+                Some(fields),
+                response_url.clone(),
+            )
+            .map_err(|_| VdcCollectionError::SerializeFailed)?;
+            entries.push(entry);
+        }
+
+        let events = entries
+            .iter()
+            .map(VdcCollectionEvent::activity_logged)
+            .collect::<Vec<_>>();
+        {
+            let _head = activity_log::lock_chain_head(&self.collection.storage).await;
+            let operations = entry_operations(self.collection.storage.as_ref(), entries).await?;
+            apply_batch(self.collection.storage.as_ref(), operations)
+                .await
+                .map_err(VdcCollectionError::StoreFailed)?;
+        }
+
+        self.collection.storage.notify(events);
+        Ok(())
+    }
+}
+
+/// The storage operations writing the `Issued` entry of a new credential. The
+/// chain head must be locked until they are written.
+pub(super) async fn issued_operations(
+    storage: &dyn StorageManagerInterface,
+    credential: &Credential,
+) -> Result<Vec<StorageOperation>, VdcCollectionError> {
+    let issuer = CredentialIndexEntry::from_credential(credential)
+        .issuer
+        .filter(|issuer| !issuer.is_empty())
+        .unwrap_or_else(|| UNKNOWN_ISSUER.to_string());
+
+    let entry = ActivityLogEntry::new(
+        credential.id,
+        ActivityLogEntryType::Issued,
+        "Credential issued".into(),
+        issuer,
+        None,
+        None,
+    )
+    .map_err(|_| VdcCollectionError::SerializeFailed)?;
+
+    entry_operations(storage, vec![entry]).await
+}
+
+/// The storage operations writing entries, linked to the activity log hash
+/// chain when it is enabled, and indexing them. The chain head must be locked
+/// until they are written.
+pub(super) async fn entry_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: Vec<ActivityLogEntry>,
+) -> Result<Vec<StorageOperation>, VdcCollectionError> {
+    activity_log::entry_operations(storage, entries)
+        .await
+        .map_err(|e| VdcCollectionError::ActivityLog(e.to_string()))
+}
+
+/// The storage operations writing back edited entries, recording the edits in
+/// the activity log hash chain when it is enabled. The chain head must be
+/// locked until they are written.
+pub(super) async fn edit_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: Vec<ActivityLogEntry>,
+) -> Result<Vec<StorageOperation>, VdcCollectionError> {
+    activity_log::edit_operations(storage, entries)
+        .await
+        .map_err(|e| VdcCollectionError::ActivityLog(e.to_string()))
+}
+
+/// The storage operations recording the removal of entries in the activity
+/// log hash chain, when it is enabled, and from the index. The chain head must
+/// be locked until they are written.
+pub(super) async fn purge_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: &[ActivityLogEntry],
+) -> Result<Vec<StorageOperation>, VdcCollectionError> {
+    activity_log::purge_operations(storage, entries)
+        .await
+        .map_err(|e| VdcCollectionError::ActivityLog(e.to_string()))
+}
+
+/// The storage operations recording that entries were moved to another
+/// profile in the activity log hash chain, when it is enabled, and removing
+/// them from the index. The chain head must be locked until they are written.
+pub(super) async fn move_operations(
+    storage: &dyn StorageManagerInterface,
+    entries: &[ActivityLogEntry],
+) -> Result<Vec<StorageOperation>, VdcCollectionError> {
+    activity_log::move_operations(storage, entries)
+        .await
+        .map_err(|e| VdcCollectionError::ActivityLog(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::CredentialType,
+        credential::{activity_log::ActivityLog, CredentialFormat},
+        local_store::LocalStore,
+    };
+
+    #[tokio::test]
+    async fn activity_is_recorded_when_enabled() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = Arc::new(VdcCollection::new(smi.clone()));
+        let recorder = vdc.clone().activity_recorder();
+
+        let credential = |id| Credential {
+            id,
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: id.as_bytes().to_vec(),
+            key_alias: None,
+        };
+
+        // Nothing is recorded until enabled.
+        let silent = credential(Uuid::new_v4());
+        vdc.add(&silent).await.unwrap();
+        recorder
+            .record_shared(Some("Verifier"), None, vec![(silent.id, vec![])])
+            .await;
+        let log = ActivityLog::load(silent.id, smi.clone()).await.unwrap();
+        assert!(log.entries(None).await.unwrap().is_empty());
+
+        vdc.set_activity_recording(true);
+
+        let recorded = credential(Uuid::new_v4());
+        vdc.add(&recorded).await.unwrap();
+        // Updates are not issuances.
+        vdc.add(&recorded).await.unwrap();
+
+        let fields = vec!["$.family_name".to_string(), "$.birth_date".to_string()];
+        recorder
+            .record_shared(
+                Some("https://verifier.example"),
+                Some("https://verifier.example/response".into()),
+                vec![(recorded.id, fields.clone()), (Uuid::new_v4(), vec![])],
+            )
+            .await;
+
+        let log = ActivityLog::load(recorded.id, smi.clone()).await.unwrap();
+        let entries = log.entries(None).await.unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let shared = entries
+            .iter()
+            .find(|e| e.get_type() == ActivityLogEntryType::Shared)
+            .unwrap();
+        assert_eq!(shared.get_interaction_with(), "https://verifier.example");
+        assert_eq!(shared.get_fields(), fields);
+        assert_eq!(
+            shared.get_url().as_deref(),
+            Some("https://verifier.example/response")
+        );
+        // The test credential does not state its issuer.
+        assert!(entries
+            .iter()
+            .any(|e| e.get_type() == ActivityLogEntryType::Issued
+                && e.get_interaction_with() == UNKNOWN_ISSUER));
+
+        // Only stored credentials are recorded.
+        assert_eq!(vdc.activity_log_entries().await.unwrap().len(), 2);
+
+        // Anonymous verifiers are recorded as such.
+        recorder
+            .record_shared(None, None, vec![(recorded.id, vec![])])
+            .await;
+        recorder
+            .record_shared(Some(""), None, vec![(recorded.id, vec![])])
+            .await;
+        let entries = log.entries(None).await.unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|e| e.get_interaction_with() == UNKNOWN_VERIFIER)
+                .count(),
+            2
+        );
+    }
+}
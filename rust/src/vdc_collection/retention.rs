@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{recorder, VdcCollection, VdcCollectionError};
+use crate::common::*;
+use crate::credential::activity_log;
+use crate::storage_manager::*;
+
+/// Storage key of the retention policy, stored next to the `Credential.` keys.
+const RETENTION_POLICY_KEY: &str = "RetentionPolicy";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Data minimization rules enforced by
+/// [VdcCollection::apply_retention](super::VdcCollection::apply_retention).
+///
+/// Each rule is disabled when unset.
+///
+/// # Deletion guarantees
+///
+/// Deleted credentials and activity log entries are removed with
+/// [StorageManagerInterface::remove], and cleared transcripts are replaced by
+/// writing the entry back without them. Either way, the data is no longer
+/// readable through the SDK, but it is neither overwritten before removal nor
+/// wiped: whether it persists on the underlying medium, e.g. in the journals,
+/// free pages or backups of the host storage, is up to the storage manager.
+///
+/// When the activity log hash chain is enabled, purged entries and cleared
+/// transcripts are recorded in it, so that it remains verifiable. The
+/// records keep the identifiers and hashes of the entries, but none of their
+/// content.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct RetentionPolicy {
+    /// Delete credentials this many days after they expire, along with their
+    /// activity logs.
+    pub delete_expired_after_days: Option<u32>,
+    /// Delete activity log entries older than this many days.
+    pub purge_activity_log_after_days: Option<u32>,
+    /// Clear the presentation transcripts, i.e. the list of shared fields,
+    /// from activity log entries older than this many days. The entries
+    /// themselves are kept.
+    pub purge_transcripts_after_days: Option<u32>,
+}
+
+/// Result of applying the retention policy.
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct RetentionReport {
+    /// Expired credentials that were deleted.
+    pub deleted_credentials: Vec<Uuid>,
+    /// Number of activity log entries deleted, excluding those of the
+    /// deleted credentials.
+    pub purged_activity_log_entries: u32,
+    /// Number of activity log entries whose transcript was cleared.
+    pub purged_transcripts: u32,
+}
+
+#[uniffi::export]
+impl VdcCollection {
+    /// Get the retention policy of the collection.
+    pub async fn retention_policy(&self) -> Result<RetentionPolicy, VdcCollectionError> {
+        Ok(self.stored_retention_policy().await?.unwrap_or_default())
+    }
+
+    /// Replace the retention policy of the collection.
+    ///
+    /// The policy is persisted, but only enforced by
+    /// [VdcCollection::apply_retention].
+    pub async fn set_retention_policy(
+        &self,
+        policy: RetentionPolicy,
+    ) -> Result<(), VdcCollectionError> {
+        apply_batch(
+            self.storage.as_ref(),
+            vec![retention_policy_operation(&policy)?],
+        )
+        .await
+        .map_err(VdcCollectionError::StoreFailed)
+    }
+
+    /// Enforce the retention policy at `now`, a UNIX timestamp.
+    ///
+    /// Hosts are expected to call this periodically, e.g. on application
+    /// launch. See [RetentionPolicy] for what deletion guarantees.
+    pub async fn apply_retention(&self, now: u64) -> Result<RetentionReport, VdcCollectionError> {
+        let policy = self.retention_policy().await?;
+        let cutoff = |days: u32| now.saturating_sub(days as u64 * SECONDS_PER_DAY);
+
+        let mut report = RetentionReport::default();
+
+        if let Some(cutoff) = policy.delete_expired_after_days.map(cutoff) {
+            let expired = self
+                .index_entries()
+                .await?
+                .into_iter()
+                .filter(|entry| entry.valid_until.is_some_and(|until| until < cutoff));
+
+            for entry in expired {
+                self.delete(entry.id).await?;
+                report.deleted_credentials.push(entry.id);
+            }
+        }
+
+        let log_cutoff = policy.purge_activity_log_after_days.map(cutoff);
+        let transcript_cutoff = policy.purge_transcripts_after_days.map(cutoff);
+        if log_cutoff.is_none() && transcript_cutoff.is_none() {
+            return Ok(report);
+        }
+
+        let _head = activity_log::lock_chain_head(&self.storage).await;
+
+        let mut operations = Vec::new();
+        let mut purged = Vec::new();
+        let mut cleared = Vec::new();
+        for mut entry in self.activity_log_entries().await? {
+            if log_cutoff.is_some_and(|cutoff| entry.timestamp() < cutoff) {
+                operations.push(StorageOperation::Remove {
+                    key: entry.as_storage_key(),
+                });
+                purged.push(entry);
+                report.purged_activity_log_entries += 1;
+            } else if transcript_cutoff.is_some_and(|cutoff| entry.timestamp() < cutoff)
+                && entry.clear_fields()
+            {
+                cleared.push(entry);
+                report.purged_transcripts += 1;
+            }
+        }
+        operations.extend(recorder::purge_operations(self.storage.as_ref(), &purged).await?);
+        operations.extend(recorder::edit_operations(self.storage.as_ref(), cleared).await?);
+
+        apply_batch(self.storage.as_ref(), operations)
+            .await
+            .map_err(VdcCollectionError::DeleteFailed)?;
+
+        Ok(report)
+    }
+
+    /// The retention policy of the collection, if one was set.
+    pub(super) async fn stored_retention_policy(
+        &self,
+    ) -> Result<Option<RetentionPolicy>, VdcCollectionError> {
+        match self.storage.get(Key(RETENTION_POLICY_KEY.into())).await {
+            Ok(Some(raw)) => serde_cbor::from_slice(&raw.0)
+                .map(Some)
+                .map_err(|_| VdcCollectionError::DeserializeFailed),
+            Ok(None) => Ok(None),
+            Err(e) => Err(VdcCollectionError::LoadFailed(e)),
+        }
+    }
+}
+
+/// The storage operation replacing the retention policy.
+pub(super) fn retention_policy_operation(
+    policy: &RetentionPolicy,
+) -> Result<StorageOperation, VdcCollectionError> {
+    let val = serde_cbor::to_vec(policy).map_err(|_| VdcCollectionError::SerializeFailed)?;
+
+    Ok(StorageOperation::Add {
+        key: Key(RETENTION_POLICY_KEY.into()),
+        value: Value(val),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        credential::{
+            activity_log::{ActivityLog, ActivityLogEntry, ActivityLogEntryType},
+            Credential, CredentialFormat,
+        },
+        local_store::LocalStore,
+    };
+
+    #[tokio::test]
+    async fn retention_policy_is_enforced() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        let expired = add_credential(&vdc, Some(now - 10 * SECONDS_PER_DAY)).await;
+        let grace = add_credential(&vdc, Some(now - SECONDS_PER_DAY)).await;
+        let valid = add_credential(&vdc, None).await;
+
+        let log = ActivityLog::load(valid.id, smi.clone()).await.unwrap();
+        for fields in [None, Some(vec!["family_name".to_string()])] {
+            log.add(Arc::new(
+                ActivityLogEntry::new(
+                    valid.id,
+                    ActivityLogEntryType::Shared,
+                    "Credential shared".into(),
+                    "Verifier".into(),
+                    fields,
+                    None,
+                )
+                .unwrap(),
+            ))
+            .await
+            .unwrap();
+        }
+
+        // Nothing is enforced without a policy.
+        let report = vdc.apply_retention(now).await.unwrap();
+        assert!(report.deleted_credentials.is_empty());
+
+        vdc.set_retention_policy(RetentionPolicy {
+            delete_expired_after_days: Some(7),
+            purge_activity_log_after_days: Some(90),
+            purge_transcripts_after_days: Some(30),
+        })
+        .await
+        .unwrap();
+
+        let report = vdc.apply_retention(now).await.unwrap();
+        assert_eq!(report.deleted_credentials, vec![expired.id]);
+        assert_eq!(report.purged_transcripts, 0);
+        assert!(vdc.get(grace.id).await.unwrap().is_some());
+
+        let report = vdc
+            .apply_retention(now + 31 * SECONDS_PER_DAY)
+            .await
+            .unwrap();
+        assert_eq!(report.deleted_credentials, vec![grace.id]);
+        assert_eq!(report.purged_transcripts, 1);
+        assert_eq!(report.purged_activity_log_entries, 0);
+        assert!(vdc
+            .activity_log_entries()
+            .await
+            .unwrap()
+            .iter_mut()
+            .all(|entry| !entry.clear_fields()));
+
+        let report = vdc
+            .apply_retention(now + 91 * SECONDS_PER_DAY)
+            .await
+            .unwrap();
+        assert_eq!(report.purged_activity_log_entries, 2);
+        assert!(vdc.get(valid.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn retention_keeps_the_chain_verifiable() {
+        use crate::crypto::{KeyAlias, KeyStore, RustTestKeyManager};
+
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi.clone());
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        let expired = add_credential(&vdc, Some(now + SECONDS_PER_DAY)).await;
+        let valid = add_credential(&vdc, None).await;
+
+        let log = ActivityLog::load(valid.id, smi.clone()).await.unwrap();
+        log.enable_chain().await.unwrap();
+        for credential in [&expired, &valid, &valid] {
+            let log = ActivityLog::load(credential.id, smi.clone()).await.unwrap();
+            log.add(Arc::new(
+                ActivityLogEntry::new(
+                    credential.id,
+                    ActivityLogEntryType::Shared,
+                    "Credential shared".into(),
+                    "Verifier".into(),
+                    Some(vec!["family_name".to_string()]),
+                    None,
+                )
+                .unwrap(),
+            ))
+            .await
+            .unwrap();
+        }
+
+        let key_manager = RustTestKeyManager::default();
+        let alias = KeyAlias("activity_log".into());
+        key_manager
+            .generate_p256_signing_key(alias.clone())
+            .await
+            .unwrap();
+        let signer = key_manager.get_signing_key(alias).unwrap();
+        let public_jwk = signer.jwk().unwrap();
+        log.sign_chain_head(signer).await.unwrap();
+
+        vdc.set_retention_policy(RetentionPolicy {
+            delete_expired_after_days: Some(7),
+            purge_activity_log_after_days: Some(90),
+            purge_transcripts_after_days: Some(30),
+        })
+        .await
+        .unwrap();
+
+        // Transcripts are cleared, and the entries of the expired credential
+        // deleted along with it.
+        let report = vdc
+            .apply_retention(now + 31 * SECONDS_PER_DAY)
+            .await
+            .unwrap();
+        assert_eq!(report.deleted_credentials, vec![expired.id]);
+        assert_eq!(report.purged_transcripts, 2);
+        let report = vdc
+            .verify_activity_log_chain(public_jwk.clone())
+            .await
+            .unwrap();
+        assert!(report.valid, "{:?}", report.issues);
+
+        // The cleared transcripts are not kept, not even in the chain.
+        for key in smi.list().await.unwrap() {
+            let value = smi.get(key).await.unwrap().unwrap();
+            assert!(!String::from_utf8_lossy(&value.0).contains("family_name"));
+        }
+
+        // The remaining entries are purged.
+        let report = vdc
+            .apply_retention(now + 91 * SECONDS_PER_DAY)
+            .await
+            .unwrap();
+        assert_eq!(report.purged_activity_log_entries, 2);
+        let report = vdc.verify_activity_log_chain(public_jwk).await.unwrap();
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!(report.signed_length, 3);
+        // The removals and edits are chained after the signed entries.
+        assert_eq!(report.length, 8);
+    }
+
+    /// Store a credential, overriding its indexed expiry.
+    async fn add_credential(vdc: &VdcCollection, valid_until: Option<u64>) -> Credential {
+        let credential = Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType("org.iso.18013.5.1.mDL".into()),
+            payload: Uuid::new_v4().as_bytes().to_vec(),
+            key_alias: None,
+        };
+
+        vdc.add(&credential).await.unwrap();
+        vdc.commit(
+            Vec::new(),
+            |index| {
+                if let Some(entry) = index.entries.get_mut(&credential.id) {
+                    entry.valid_until = valid_until;
+                }
+            },
+            VdcCollectionError::StoreFailed,
+        )
+        .await
+        .unwrap();
+
+        credential
+    }
+}
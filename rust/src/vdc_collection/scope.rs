@@ -0,0 +1,67 @@
+use std::sync::{Arc, Mutex as StdMutex, Weak};
+
+use tokio::sync::Mutex;
+
+use super::observer::Observers;
+use crate::storage_manager::StorageManagerInterface;
+
+/// The scopes of the storages in use, by address.
+static SCOPES: StdMutex<Vec<(usize, Weak<StorageScope>)>> = StdMutex::new(Vec::new());
+
+/// State shared by the collections and activity logs opened over the same
+/// storage, across all of its profiles.
+///
+/// Storages are told apart by identity: the same foreign object passed
+/// through the FFI twice is seen as two storages, so collections and activity
+/// logs should be opened from one another, e.g. with
+/// [VdcCollection::activity_log](super::VdcCollection::activity_log).
+#[derive(Debug, Default)]
+pub(crate) struct StorageScope {
+    /// Serializes the updates of the activity log hash chains and indexes.
+    pub(crate) activity_log_lock: Mutex<()>,
+    /// Serializes the read-modify-write updates of the credential indexes
+    /// and of the profile registry. Taken after `activity_log_lock` when both
+    /// are needed.
+    pub(crate) index_lock: Mutex<()>,
+    /// The observers of the changes made to each profile.
+    pub(crate) observers: Observers,
+}
+
+impl StorageScope {
+    /// The scope of a storage, created on first use and dropped along with
+    /// the last collection or activity log using it.
+    pub(crate) fn of(storage: &Arc<dyn StorageManagerInterface>) -> Arc<Self> {
+        let address = Arc::as_ptr(storage) as *const () as usize;
+
+        let mut scopes = SCOPES.lock().unwrap_or_else(|e| e.into_inner());
+        scopes.retain(|(_, scope)| scope.strong_count() > 0);
+
+        if let Some(scope) = scopes
+            .iter()
+            .find(|(a, _)| *a == address)
+            .and_then(|(_, scope)| scope.upgrade())
+        {
+            return scope;
+        }
+
+        let scope = Arc::new(Self::default());
+        scopes.push((address, Arc::downgrade(&scope)));
+        scope
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage_manager::test::DummyStorage;
+
+    #[test]
+    fn test_storage_scope() {
+        let storage: Arc<dyn StorageManagerInterface> = Arc::new(DummyStorage::default());
+        let other: Arc<dyn StorageManagerInterface> = Arc::new(DummyStorage::default());
+
+        let scope = StorageScope::of(&storage);
+        assert!(Arc::ptr_eq(&scope, &StorageScope::of(&storage.clone())));
+        assert!(!Arc::ptr_eq(&scope, &StorageScope::of(&other)));
+    }
+}
@@ -19,6 +19,17 @@ public class MockStorageManager: StorageManagerInterface {
     public func remove(key: Key) async throws {
         storage.removeValue(forKey: key)
     }
+
+    public func batch(operations: [StorageOperation]) async throws {
+        for operation in operations {
+            switch operation {
+            case let .add(key, value):
+                storage[key] = value
+            case let .remove(key):
+                storage.removeValue(forKey: key)
+            }
+        }
+    }
 }
 
 var counter = DispatchGroup()